@@ -38,6 +38,7 @@ fn benchmark_buffers(c: &mut Criterion) {
                     config.sinks["out"].buffer = BufferConfig::Memory {
                         max_events: 100,
                         when_full: Default::default(),
+                        max_size: None,
                     };
 
                     let mut rt = runtime::Runtime::new().unwrap();
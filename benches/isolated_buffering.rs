@@ -4,14 +4,18 @@ use futures::{
     stream::StreamExt,
 };
 use futures01::{stream, AsyncSink, Poll, Sink, StartSend, Stream};
+use std::path::PathBuf;
 use tempfile::tempdir;
 use vector::{
-    buffers::disk::{leveldb_buffer, DiskBuffer},
+    buffers::disk::{segment_buffer, DiskBuffer},
     runtime,
     sinks::util::StreamSink,
     Event,
 };
 
+#[cfg(feature = "leveldb")]
+use vector::buffers::disk::leveldb_buffer;
+
 struct NullSink;
 
 impl Sink for NullSink {
@@ -33,62 +37,101 @@ fn benchmark_buffers(c: &mut Criterion) {
 
     let data_dir = tempdir().unwrap();
     let data_dir = data_dir.path().to_path_buf();
-    let data_dir2 = data_dir.clone();
-    let data_dir3 = data_dir.clone();
 
-    c.bench(
-        "buffers",
-        Benchmark::new("channels/futures01", move |b| {
-            b.iter_with_setup(
-                || {
-                    let rt = runtime::Runtime::new().unwrap();
+    let benchmark = Benchmark::new("channels/futures01", move |b| {
+        b.iter_with_setup(
+            || {
+                let rt = runtime::Runtime::new().unwrap();
 
-                    let (writer, reader) = futures01::sync::mpsc::channel(100);
-                    let writer = writer.sink_map_err(|e| panic!(e));
+                let (writer, reader) = futures01::sync::mpsc::channel(100);
+                let writer = writer.sink_map_err(|e| panic!(e));
 
-                    let read_loop = reader.for_each(move |_| Ok(()));
+                let read_loop = reader.for_each(move |_| Ok(()));
 
-                    (rt, writer, read_loop)
-                },
-                |(mut rt, writer, read_loop)| {
-                    let send = writer.send_all(random_events(line_size).take(num_lines as u64));
+                (rt, writer, read_loop)
+            },
+            |(mut rt, writer, read_loop)| {
+                let send = writer.send_all(random_events(line_size).take(num_lines as u64));
 
-                    let read_handle = rt.spawn_handle(read_loop.compat());
-                    let write_handle = rt.spawn_handle(send.compat());
+                let read_handle = rt.spawn_handle(read_loop.compat());
+                let write_handle = rt.spawn_handle(send.compat());
 
-                    let (writer, _stream) = rt.block_on_std(write_handle).unwrap().unwrap();
-                    drop(writer);
+                let (writer, _stream) = rt.block_on_std(write_handle).unwrap().unwrap();
+                drop(writer);
 
-                    rt.block_on_std(read_handle).unwrap().unwrap();
-                },
-            );
-        })
-        .with_function("channels/tokio", move |b| {
-            b.iter_with_setup(
-                || {
-                    let mut rt = runtime::Runtime::new().unwrap();
+                rt.block_on_std(read_handle).unwrap().unwrap();
+            },
+        );
+    })
+    .with_function("channels/tokio", move |b| {
+        b.iter_with_setup(
+            || {
+                let mut rt = runtime::Runtime::new().unwrap();
 
-                    let (writer, mut reader) = tokio::sync::mpsc::channel(100);
+                let (writer, mut reader) = tokio::sync::mpsc::channel(100);
 
-                    let read_handle =
-                        rt.spawn_handle(async move { while let Some(_) = reader.next().await {} });
+                let read_handle =
+                    rt.spawn_handle(async move { while let Some(_) = reader.next().await {} });
 
-                    (rt, writer, read_handle)
-                },
-                |(mut rt, mut writer, read_handle)| {
-                    let write_handle = rt.spawn_handle(async move {
-                        let mut stream = random_events(line_size).take(num_lines as u64).compat();
-                        while let Some(e) = stream.next().await {
-                            writer.send(e).await.unwrap();
-                        }
-                    });
-
-                    rt.block_on_std(write_handle).unwrap();
-                    rt.block_on_std(read_handle).unwrap();
-                },
-            );
-        })
-        .with_function("leveldb/writing", move |b| {
+                (rt, writer, read_handle)
+            },
+            |(mut rt, mut writer, read_handle)| {
+                let write_handle = rt.spawn_handle(async move {
+                    let mut stream = random_events(line_size).take(num_lines as u64).compat();
+                    while let Some(e) = stream.next().await {
+                        writer.send(e).await.unwrap();
+                    }
+                });
+
+                rt.block_on_std(write_handle).unwrap();
+                rt.block_on_std(read_handle).unwrap();
+            },
+        );
+    });
+
+    let benchmark = disk_buffer_functions::<segment_buffer::Buffer>(
+        benchmark,
+        "segment",
+        data_dir.join("segment"),
+        num_lines,
+        line_size,
+    );
+    #[cfg(feature = "leveldb")]
+    let benchmark = disk_buffer_functions::<leveldb_buffer::Buffer>(
+        benchmark,
+        "leveldb",
+        data_dir.join("leveldb"),
+        num_lines,
+        line_size,
+    );
+
+    c.bench(
+        "buffers",
+        benchmark
+            .sample_size(10)
+            .noise_threshold(0.05)
+            .throughput(Throughput::Bytes((num_lines * line_size) as u64)),
+    );
+}
+
+/// Adds the writing, reading and combined benchmarks of a disk buffer.
+fn disk_buffer_functions<B>(
+    benchmark: Benchmark,
+    name: &str,
+    data_dir: PathBuf,
+    num_lines: usize,
+    line_size: usize,
+) -> Benchmark
+where
+    B: DiskBuffer + 'static,
+    B::Writer: Send + 'static,
+    B::Reader: 'static,
+{
+    let data_dir2 = data_dir.clone();
+    let data_dir3 = data_dir.clone();
+
+    benchmark
+        .with_function(format!("{}/writing", name), move |b| {
             b.iter_with_setup(
                 || {
                     let rt = runtime::Runtime::new().unwrap();
@@ -101,8 +144,7 @@ fn benchmark_buffers(c: &mut Criterion) {
                     }
 
                     let plenty_of_room = num_lines * line_size * 2;
-                    let (writer, _reader, _acker) =
-                        leveldb_buffer::Buffer::build(path, plenty_of_room).unwrap();
+                    let (writer, _reader, _acker) = B::build(path, plenty_of_room).unwrap();
 
                     (rt, writer)
                 },
@@ -113,7 +155,7 @@ fn benchmark_buffers(c: &mut Criterion) {
                 },
             );
         })
-        .with_function("leveldb/reading", move |b| {
+        .with_function(format!("{}/reading", name), move |b| {
             b.iter_with_setup(
                 || {
                     let mut rt = runtime::Runtime::new().unwrap();
@@ -126,8 +168,7 @@ fn benchmark_buffers(c: &mut Criterion) {
                     }
 
                     let plenty_of_room = num_lines * line_size * 2;
-                    let (writer, reader, acker) =
-                        leveldb_buffer::Buffer::build(path, plenty_of_room).unwrap();
+                    let (writer, reader, acker) = B::build(path, plenty_of_room).unwrap();
 
                     let send = writer.send_all(random_events(line_size).take(num_lines as u64));
                     let write_handle = rt.spawn_handle(send.compat());
@@ -144,7 +185,7 @@ fn benchmark_buffers(c: &mut Criterion) {
                 },
             );
         })
-        .with_function("leveldb/both", move |b| {
+        .with_function(format!("{}/both", name), move |b| {
             b.iter_with_setup(
                 || {
                     let rt = runtime::Runtime::new().unwrap();
@@ -157,8 +198,7 @@ fn benchmark_buffers(c: &mut Criterion) {
                     }
 
                     let plenty_of_room = num_lines * line_size * 2;
-                    let (writer, reader, acker) =
-                        leveldb_buffer::Buffer::build(path, plenty_of_room).unwrap();
+                    let (writer, reader, acker) = B::build(path, plenty_of_room).unwrap();
 
                     let read_loop = StreamSink::new(NullSink, acker).send_all(reader);
 
@@ -175,10 +215,6 @@ fn benchmark_buffers(c: &mut Criterion) {
                 },
             );
         })
-        .sample_size(10)
-        .noise_threshold(0.05)
-        .throughput(Throughput::Bytes((num_lines * line_size) as u64)),
-    );
 }
 
 criterion_group!(buffers, benchmark_buffers);
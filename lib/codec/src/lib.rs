@@ -1,6 +1,12 @@
 #[macro_use]
 extern crate tracing;
 
+mod octet_counting;
+mod rfc6587;
+
+pub use octet_counting::OctetCountingCodec;
+pub use rfc6587::Rfc6587Codec;
+
 use bytes::{BufMut, Bytes, BytesMut};
 use std::{cmp, io, usize};
 use tokio_codec::{Decoder, Encoder};
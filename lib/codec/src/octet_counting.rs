@@ -0,0 +1,148 @@
+use bytes::{BufMut, Bytes, BytesMut};
+use std::{io, usize};
+use tokio_codec::{Decoder, Encoder};
+
+/// Longest length prefix accepted, `usize::MAX` has 20 digits.
+const MAX_PREFIX_LENGTH: usize = 20;
+
+/// Frames prefixed by their length in ASCII digits followed by a space, as
+/// described by the octet counting method of RFC 6587: `11 hello world`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct OctetCountingCodec {
+    max_length: usize,
+    /// Bytes left to skip from a frame longer than `max_length`.
+    discarding: usize,
+}
+
+impl OctetCountingCodec {
+    /// Returns an `OctetCountingCodec` accepting frames of any length.
+    pub fn new() -> Self {
+        OctetCountingCodec {
+            max_length: usize::MAX,
+            discarding: 0,
+        }
+    }
+
+    /// Returns an `OctetCountingCodec` with a maximum frame length limit.
+    pub fn new_with_max_length(max_length: usize) -> Self {
+        OctetCountingCodec {
+            max_length,
+            ..OctetCountingCodec::new()
+        }
+    }
+
+    /// Returns the maximum frame length when decoding.
+    pub fn max_length(&self) -> usize {
+        self.max_length
+    }
+}
+
+impl Default for OctetCountingCodec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Decoder for OctetCountingCodec {
+    type Item = Bytes;
+    type Error = io::Error;
+
+    fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<Bytes>, io::Error> {
+        loop {
+            if self.discarding > 0 {
+                let skipped = self.discarding.min(buf.len());
+                buf.advance(skipped);
+                self.discarding -= skipped;
+                if self.discarding > 0 {
+                    return Ok(None);
+                }
+            }
+
+            // Some senders terminate frames with a newline anyway.
+            let padding = buf
+                .iter()
+                .take_while(|&&b| b == b'\n' || b == b'\r')
+                .count();
+            buf.advance(padding);
+
+            let space = match buf
+                .iter()
+                .take(MAX_PREFIX_LENGTH + 1)
+                .position(|&b| b == b' ')
+            {
+                Some(space) => space,
+                None if buf.len() > MAX_PREFIX_LENGTH => return Err(invalid_prefix(buf)),
+                None => return Ok(None),
+            };
+
+            let length = std::str::from_utf8(&buf[..space])
+                .ok()
+                .filter(|prefix| prefix.bytes().all(|b| b.is_ascii_digit()))
+                .and_then(|prefix| prefix.parse::<usize>().ok())
+                .ok_or_else(|| invalid_prefix(buf))?;
+
+            if length > self.max_length {
+                warn!(
+                    message = "discarding frame larger than max_length",
+                    length = length,
+                    max_length = self.max_length,
+                    rate_limit_secs = 30
+                );
+                buf.advance(space + 1);
+                self.discarding = length;
+                continue;
+            }
+
+            if buf.len() < space + 1 + length {
+                buf.reserve(space + 1 + length - buf.len());
+                return Ok(None);
+            }
+
+            buf.advance(space + 1);
+            let frame = buf.split_to(length);
+
+            trace!(
+                message = "decoding the frame.",
+                bytes_proccesed = frame.len()
+            );
+
+            return Ok(Some(frame.freeze()));
+        }
+    }
+
+    fn decode_eof(&mut self, buf: &mut BytesMut) -> Result<Option<Bytes>, io::Error> {
+        let frame = self.decode(buf)?;
+        if frame.is_none() && !buf.is_empty() {
+            warn!(
+                message = "discarding incomplete frame at end of stream",
+                buf_len = buf.len()
+            );
+            buf.clear();
+        }
+        Ok(frame)
+    }
+}
+
+impl Encoder for OctetCountingCodec {
+    type Item = Bytes;
+    type Error = io::Error;
+
+    fn encode(&mut self, item: Bytes, buf: &mut BytesMut) -> Result<(), io::Error> {
+        let prefix = format!("{} ", item.len());
+        buf.reserve(prefix.len() + item.len());
+        buf.put(prefix.as_bytes());
+        buf.put(item);
+        Ok(())
+    }
+}
+
+fn invalid_prefix(buf: &BytesMut) -> io::Error {
+    let start = &buf[..buf.len().min(MAX_PREFIX_LENGTH)];
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!(
+            "expected an octet count, found {:?}",
+            String::from_utf8_lossy(start)
+        ),
+    )
+}
@@ -0,0 +1,106 @@
+use crate::{BytesDelimitedCodec, OctetCountingCodec};
+use bytes::{Bytes, BytesMut};
+use std::{io, usize};
+use tokio_codec::Decoder;
+
+/// Decodes syslog messages sent over a stream with either of the framing
+/// methods of RFC 6587: octet counting (`<len> <msg>`) or non-transparent
+/// framing, where messages are terminated by a newline.
+///
+/// Senders stick to one method for a whole connection, so it is detected on
+/// the first frame: syslog messages start with `<`, while octet counted
+/// frames start with a digit. A codec is meant to decode a single connection.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Rfc6587Codec {
+    max_length: usize,
+    framing: Option<Framing>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Framing {
+    OctetCounting(OctetCountingCodec),
+    NonTransparent(BytesDelimitedCodec),
+}
+
+impl Rfc6587Codec {
+    /// Returns a `Rfc6587Codec` accepting frames of any length.
+    pub fn new() -> Self {
+        Rfc6587Codec {
+            max_length: usize::MAX,
+            framing: None,
+        }
+    }
+
+    /// Returns a `Rfc6587Codec` with a maximum frame length limit.
+    pub fn new_with_max_length(max_length: usize) -> Self {
+        Rfc6587Codec {
+            max_length,
+            ..Rfc6587Codec::new()
+        }
+    }
+
+    /// Returns the maximum frame length when decoding.
+    pub fn max_length(&self) -> usize {
+        self.max_length
+    }
+
+    /// Returns whether the connection was found to use octet counting, once
+    /// the first frame was received.
+    pub fn is_octet_counting(&self) -> Option<bool> {
+        self.framing
+            .as_ref()
+            .map(|framing| matches!(framing, Framing::OctetCounting(_)))
+    }
+
+    fn framing(&mut self, buf: &BytesMut) -> Option<&mut Framing> {
+        if self.framing.is_none() {
+            let first = buf.iter().find(|b| !b.is_ascii_whitespace())?;
+            let octet_counting = first.is_ascii_digit();
+            trace!(
+                message = "detected framing.",
+                octet_counting = octet_counting
+            );
+            let framing = if octet_counting {
+                Framing::OctetCounting(OctetCountingCodec::new_with_max_length(self.max_length))
+            } else {
+                Framing::NonTransparent(BytesDelimitedCodec::new_with_max_length(
+                    b'\n',
+                    self.max_length,
+                ))
+            };
+            self.framing = Some(framing);
+        }
+        self.framing.as_mut()
+    }
+}
+
+impl Default for Rfc6587Codec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Decoder for Rfc6587Codec {
+    type Item = Bytes;
+    type Error = io::Error;
+
+    fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<Bytes>, io::Error> {
+        match self.framing(buf) {
+            Some(Framing::OctetCounting(codec)) => codec.decode(buf),
+            Some(Framing::NonTransparent(codec)) => codec.decode(buf),
+            None => Ok(None),
+        }
+    }
+
+    fn decode_eof(&mut self, buf: &mut BytesMut) -> Result<Option<Bytes>, io::Error> {
+        match self.framing(buf) {
+            Some(Framing::OctetCounting(codec)) => codec.decode_eof(buf),
+            Some(Framing::NonTransparent(codec)) => codec.decode_eof(buf),
+            None => {
+                // Nothing but whitespace was received.
+                buf.clear();
+                Ok(None)
+            }
+        }
+    }
+}
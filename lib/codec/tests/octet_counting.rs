@@ -0,0 +1,59 @@
+use bytes::{BufMut, BytesMut};
+use codec::OctetCountingCodec;
+use tokio_codec::{Decoder, Encoder};
+
+#[test]
+fn octet_counting_decode() {
+    let mut codec = OctetCountingCodec::new();
+    let buf = &mut BytesMut::new();
+    buf.put_slice(b"3 abc11 hello\nworld\n5 ab");
+
+    assert_eq!(Some("abc".into()), codec.decode(buf).unwrap());
+    assert_eq!(Some("hello\nworld".into()), codec.decode(buf).unwrap());
+    // Incomplete frames wait for more data.
+    assert_eq!(None, codec.decode(buf).unwrap());
+    buf.put_slice(b"cde");
+    assert_eq!(Some("abcde".into()), codec.decode(buf).unwrap());
+    assert!(buf.is_empty());
+}
+
+#[test]
+fn octet_counting_encode() {
+    let mut codec = OctetCountingCodec::new();
+
+    let mut buf = BytesMut::new();
+    codec.encode("abc".into(), &mut buf).unwrap();
+
+    assert_eq!(b"3 abc", &buf[..]);
+}
+
+#[test]
+fn octet_counting_max_length() {
+    let mut codec = OctetCountingCodec::new_with_max_length(4);
+    let buf = &mut BytesMut::new();
+    buf.put_slice(b"6 abcd");
+
+    // The long frame is skipped even though it did not fully arrive yet.
+    assert_eq!(None, codec.decode(buf).unwrap());
+    buf.put_slice(b"ef4 abcd");
+    assert_eq!(Some("abcd".into()), codec.decode(buf).unwrap());
+}
+
+#[test]
+fn octet_counting_invalid_prefix() {
+    let mut codec = OctetCountingCodec::new();
+    let buf = &mut BytesMut::new();
+    buf.put_slice(b"<13>Jan 1 00:00:00 host app: message\n");
+
+    assert!(codec.decode(buf).is_err());
+}
+
+#[test]
+fn octet_counting_decode_eof_drops_partial_frame() {
+    let mut codec = OctetCountingCodec::new();
+    let buf = &mut BytesMut::new();
+    buf.put_slice(b"10 abc");
+
+    assert_eq!(None, codec.decode_eof(buf).unwrap());
+    assert!(buf.is_empty());
+}
@@ -0,0 +1,59 @@
+use bytes::{BufMut, BytesMut};
+use codec::Rfc6587Codec;
+use tokio_codec::Decoder;
+
+#[test]
+fn rfc6587_detects_octet_counting() {
+    let mut codec = Rfc6587Codec::new();
+    let buf = &mut BytesMut::new();
+    assert_eq!(None, codec.decode(buf).unwrap());
+    assert_eq!(None, codec.is_octet_counting());
+
+    buf.put_slice(b"10 <13>hello\n10 <13>wor");
+    assert_eq!(Some("<13>hello\n".into()), codec.decode(buf).unwrap());
+    assert_eq!(Some(true), codec.is_octet_counting());
+    assert_eq!(None, codec.decode(buf).unwrap());
+    buf.put_slice(b"ld\n");
+    assert_eq!(Some("<13>world\n".into()), codec.decode(buf).unwrap());
+    assert!(buf.is_empty());
+}
+
+#[test]
+fn rfc6587_detects_non_transparent_framing() {
+    let mut codec = Rfc6587Codec::new();
+    let buf = &mut BytesMut::new();
+    buf.put_slice(b"<13>hello\n<13>3 world\n<13>partial");
+
+    assert_eq!(Some("<13>hello".into()), codec.decode(buf).unwrap());
+    assert_eq!(Some(false), codec.is_octet_counting());
+    // A digit later on doesn't switch framing.
+    assert_eq!(Some("<13>3 world".into()), codec.decode(buf).unwrap());
+    assert_eq!(None, codec.decode(buf).unwrap());
+    assert_eq!(Some("<13>partial".into()), codec.decode_eof(buf).unwrap());
+}
+
+#[test]
+fn rfc6587_max_length() {
+    let mut codec = Rfc6587Codec::new_with_max_length(6);
+    let buf = &mut BytesMut::new();
+    buf.put_slice(b"<13>too long\n<13>ok\n");
+
+    assert_eq!(None, codec.decode(buf).unwrap());
+    assert_eq!(Some("<13>ok".into()), codec.decode(buf).unwrap());
+
+    let mut codec = Rfc6587Codec::new_with_max_length(6);
+    let buf = &mut BytesMut::new();
+    buf.put_slice(b"12 <13>too long6 <13>ok");
+
+    assert_eq!(Some("<13>ok".into()), codec.decode(buf).unwrap());
+}
+
+#[test]
+fn rfc6587_eof_with_only_whitespace() {
+    let mut codec = Rfc6587Codec::new();
+    let buf = &mut BytesMut::new();
+    buf.put_slice(b"\r\n");
+
+    assert_eq!(None, codec.decode_eof(buf).unwrap());
+    assert!(buf.is_empty());
+}
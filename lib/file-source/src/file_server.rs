@@ -13,6 +13,7 @@ use std::collections::{HashMap, HashSet};
 use std::fs::{self, File};
 use std::io::{self, Read, Seek, Write};
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 use std::time;
 use tokio::time::delay_for;
 use tracing::field;
@@ -42,6 +43,36 @@ where
     pub glob_minimum_cooldown: time::Duration,
     pub fingerprinter: Fingerprinter,
     pub oldest_first: bool,
+    /// When set, checkpoints only advance to the positions recorded here
+    /// instead of following the reads.
+    pub acknowledged_checkpoints: Option<AcknowledgedCheckpoints>,
+}
+
+/// A line read from a watched file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Line {
+    pub text: Bytes,
+    pub filename: String,
+    pub file_id: FileFingerprint,
+    /// Position in the file right after this line.
+    pub offset: FilePosition,
+}
+
+/// File positions whose lines have been delivered, to be picked up by the
+/// `FileServer` the next time it writes its checkpoints.
+#[derive(Clone, Debug, Default)]
+pub struct AcknowledgedCheckpoints(Arc<Mutex<HashMap<FileFingerprint, FilePosition>>>);
+
+impl AcknowledgedCheckpoints {
+    pub fn update(&self, file_id: FileFingerprint, offset: FilePosition) {
+        self.0.lock().unwrap().insert(file_id, offset);
+    }
+
+    fn drain_into(&self, checkpointer: &mut Checkpointer) {
+        for (file_id, offset) in self.0.lock().unwrap().drain() {
+            checkpointer.set_checkpoint(file_id, offset);
+        }
+    }
 }
 
 /// `FileServer` as Source
@@ -63,7 +94,7 @@ where
 {
     pub fn run(
         self,
-        mut chans: impl Sink<(Bytes, String), Error = ()> + Unpin,
+        mut chans: impl Sink<Line, Error = ()> + Unpin,
         mut shutdown: impl Future + Unpin,
     ) {
         let mut line_buffer = Vec::new();
@@ -123,6 +154,10 @@ where
                 // Schedule the next glob time.
                 next_glob_time = now_time.checked_add(self.glob_minimum_cooldown).unwrap();
 
+                if let Some(acknowledged) = &self.acknowledged_checkpoints {
+                    acknowledged.drain_into(&mut checkpointer);
+                }
+
                 // Write any stored checkpoints (uses glob to find old checkpoints).
                 checkpointer
                     .write_checkpoints()
@@ -207,10 +242,16 @@ where
                         bytes_read += sz;
 
                         if !line_buffer.is_empty() {
-                            lines.push((
-                                line_buffer.clone().into(),
-                                watcher.path.to_str().expect("not a valid path").to_owned(),
-                            ));
+                            lines.push(Line {
+                                text: line_buffer.clone().into(),
+                                filename: watcher
+                                    .path
+                                    .to_str()
+                                    .expect("not a valid path")
+                                    .to_owned(),
+                                file_id,
+                                offset: watcher.get_file_position(),
+                            });
                             line_buffer.clear();
                         }
                     } else {
@@ -223,7 +264,9 @@ where
                 }
                 if bytes_read > 0 {
                     global_bytes_read = global_bytes_read.saturating_add(bytes_read);
-                    checkpointer.set_checkpoint(file_id, watcher.get_file_position());
+                    if self.acknowledged_checkpoints.is_none() {
+                        checkpointer.set_checkpoint(file_id, watcher.get_file_position());
+                    }
                 }
                 // Do not move on to newer files if we are behind on an older file
                 if self.oldest_first && maxed_out_reading_single_file {
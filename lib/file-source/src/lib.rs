@@ -8,10 +8,10 @@ mod file_watcher;
 mod metadata_ext;
 pub mod paths_provider;
 
-pub use self::file_server::{FileServer, Fingerprinter};
+pub use self::file_server::{AcknowledgedCheckpoints, FileServer, Fingerprinter, Line};
 
-type FileFingerprint = u64;
-type FilePosition = u64;
+pub type FileFingerprint = u64;
+pub type FilePosition = u64;
 
 #[cfg(test)]
 mod test {
@@ -0,0 +1,317 @@
+//! Optional HTTP API for observing and controlling a running Vector.
+
+mod tap;
+
+use crate::{
+    runtime::Runtime,
+    topology::{
+        component::{Component, ComponentKind, HealthStatus},
+        fanout::ControlChannel,
+        RunningTopology,
+    },
+};
+use futures01::{future, Future};
+use hyper::{
+    header::HeaderValue, service::service_fn, Body, Method, Request, Response, StatusCode,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::{Arc, RwLock},
+};
+use stream_cancel::{Trigger, Tripwire};
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Options {
+    pub enabled: bool,
+    pub address: SocketAddr,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            address: default_address(),
+        }
+    }
+}
+
+pub fn default_address() -> SocketAddr {
+    SocketAddr::from(([127, 0, 0, 1], 8686))
+}
+
+/// View of the running topology served by the API.
+#[derive(Default)]
+struct State {
+    components: Vec<Component>,
+    outputs: HashMap<String, ControlChannel>,
+}
+
+/// Shared by all connections. It's refreshed by whoever owns the topology
+/// every time the topology changes.
+type Shared = Arc<RwLock<State>>;
+
+/// Handle to the API server. Dropping it shuts the server down.
+pub struct Server {
+    state: Shared,
+    _shutdown: Trigger,
+}
+
+impl Server {
+    pub fn start(options: &Options, rt: &mut Runtime) -> crate::Result<Self> {
+        let state = Shared::default();
+
+        let shared = Arc::clone(&state);
+        let new_service = move || {
+            let state = Arc::clone(&shared);
+            service_fn(move |req| {
+                info_span!(
+                    "api_server",
+                    method = ?req.method(),
+                    path = ?req.uri().path(),
+                )
+                .in_scope(|| handle(req, &state))
+            })
+        };
+
+        let (trigger, tripwire) = Tripwire::new();
+        let server = hyper::Server::try_bind(&options.address)?
+            .serve(new_service)
+            .with_graceful_shutdown(tripwire)
+            .map_err(|error| error!(message = "API server error.", %error));
+        rt.spawn(server);
+
+        info!(message = "API server listening.", address = %options.address);
+
+        Ok(Self {
+            state,
+            _shutdown: trigger,
+        })
+    }
+
+    pub fn update(&self, topology: &RunningTopology) {
+        *self.state.write().unwrap() = State {
+            components: topology.components(),
+            outputs: topology.outputs().clone(),
+        };
+    }
+}
+
+#[derive(Serialize)]
+struct ComponentView<'a> {
+    name: &'a str,
+    kind: ComponentKind,
+    #[serde(rename = "type")]
+    component_type: &'a str,
+    inputs: &'a [String],
+    healthcheck: HealthStatus,
+    received_events: u64,
+    received_bytes: u64,
+    sent_events: u64,
+    sent_bytes: u64,
+    errors: u64,
+    discarded_events: u64,
+    buffered_events: u64,
+    buffer_capacity: Option<u64>,
+}
+
+impl<'a> From<&'a Component> for ComponentView<'a> {
+    fn from(component: &'a Component) -> Self {
+        Self {
+            name: &component.name,
+            kind: component.kind,
+            component_type: component.component_type,
+            inputs: &component.inputs,
+            healthcheck: component.stats.health(),
+            received_events: component.stats.received_events(),
+            received_bytes: component.stats.received_bytes(),
+            sent_events: component.stats.sent_events(),
+            sent_bytes: component.stats.sent_bytes(),
+            errors: component.stats.errors(),
+            discarded_events: component.stats.discarded_events(),
+            buffered_events: component.stats.buffered_events(),
+            buffer_capacity: component.stats.buffer_capacity(),
+        }
+    }
+}
+
+fn handle(
+    req: Request<Body>,
+    state: &Shared,
+) -> impl Future<Item = Response<Body>, Error = hyper::Error> + Send {
+    let response = match (req.method(), req.uri().path()) {
+        (&Method::GET, "/health") => {
+            json_response(StatusCode::OK, &serde_json::json!({ "ok": true }))
+        }
+        (&Method::GET, "/components") => {
+            let state = state.read().unwrap();
+            let views = state
+                .components
+                .iter()
+                .map(ComponentView::from)
+                .collect::<Vec<_>>();
+            json_response(StatusCode::OK, &views)
+        }
+        (&Method::GET, "/tap") => tap(req.uri().query().unwrap_or(""), state),
+        (&Method::POST, "/reload") => reload(),
+        _ => {
+            let mut response = Response::new(Body::empty());
+            *response.status_mut() = StatusCode::NOT_FOUND;
+            response
+        }
+    };
+
+    debug!(message = "Request complete.", status = ?response.status());
+    future::ok(response)
+}
+
+/// Streams the events coming out of the requested outputs for as long as the
+/// client stays connected.
+fn tap(query: &str, state: &Shared) -> Response<Body> {
+    let request = match tap::Request::parse(query) {
+        Ok(request) => request,
+        Err(error) => {
+            return json_response(
+                StatusCode::BAD_REQUEST,
+                &serde_json::json!({ "ok": false, "error": error }),
+            )
+        }
+    };
+
+    match request.attach(&state.read().unwrap().outputs) {
+        Some(events) => {
+            let mut response = Response::new(tap::body(events));
+            response.headers_mut().insert(
+                "Content-Type",
+                HeaderValue::from_static("application/x-ndjson"),
+            );
+            response
+        }
+        None => json_response(
+            StatusCode::NOT_FOUND,
+            &serde_json::json!({ "ok": false, "error": "No output matches." }),
+        ),
+    }
+}
+
+/// Triggers the same reload as `SIGHUP` does.
+#[cfg(unix)]
+fn reload() -> Response<Body> {
+    crate::topology::config::watcher::raise_sighup();
+    json_response(StatusCode::ACCEPTED, &serde_json::json!({ "ok": true }))
+}
+
+#[cfg(windows)]
+fn reload() -> Response<Body> {
+    json_response(
+        StatusCode::NOT_IMPLEMENTED,
+        &serde_json::json!({ "ok": false, "error": "Reloading isn't supported on Windows." }),
+    )
+}
+
+fn json_response(status: StatusCode, body: &impl Serialize) -> Response<Body> {
+    let mut response = Response::new(Body::from(
+        serde_json::to_vec(body).expect("JSON serialization can't fail"),
+    ));
+    *response.status_mut() = status;
+    response
+        .headers_mut()
+        .insert("Content-Type", HeaderValue::from_static("application/json"));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{topology::component::ComponentStats, Event};
+    use futures01::Stream;
+
+    fn request(method: Method, path: &str, state: &Shared) -> (StatusCode, Vec<u8>) {
+        let req = Request::builder()
+            .method(method)
+            .uri(path)
+            .body(Body::empty())
+            .unwrap();
+        let response = handle(req, state).wait().unwrap();
+        let status = response.status();
+        let body = response.into_body().concat2().wait().unwrap();
+        (status, body.to_vec())
+    }
+
+    #[test]
+    fn health() {
+        let state = Shared::default();
+        let (status, body) = request(Method::GET, "/health", &state);
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, br#"{"ok":true}"#.to_vec());
+    }
+
+    #[test]
+    fn lists_components() {
+        let event = Event::from("hello");
+        let stats = Arc::new(
+            ComponentStats::new("out", ComponentKind::Sink, "console")
+                .with_buffer_capacity(Some(500)),
+        );
+        stats.record_received(&event);
+        stats.record_received(&event);
+        stats.record_sent(&event);
+        stats.record_errors(1);
+        stats.record_discarded(3);
+        stats.set_health(HealthStatus::Passed);
+
+        let state = Shared::default();
+        state.write().unwrap().components.push(Component {
+            name: "out".into(),
+            kind: ComponentKind::Sink,
+            component_type: "console",
+            inputs: vec!["in".into()],
+            stats,
+        });
+
+        let (status, body) = request(Method::GET, "/components", &state);
+
+        assert_eq!(status, StatusCode::OK);
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!([{
+                "name": "out",
+                "kind": "sink",
+                "type": "console",
+                "inputs": ["in"],
+                "healthcheck": "passed",
+                "received_events": 2,
+                "received_bytes": 2 * event.size_of(),
+                "sent_events": 1,
+                "sent_bytes": event.size_of(),
+                "errors": 1,
+                "discarded_events": 3,
+                "buffered_events": 1,
+                "buffer_capacity": 500,
+            }])
+        );
+    }
+
+    #[test]
+    fn unknown_path() {
+        let state = Shared::default();
+        let (status, _) = request(Method::GET, "/nope", &state);
+
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn tap_without_outputs() {
+        let state = Shared::default();
+
+        let (status, _) = request(Method::GET, "/tap?outputs=in", &state);
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (status, _) = request(Method::GET, "/tap?format=yaml", &state);
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,240 @@
+//! Taps copy the events flowing out of running components to API clients.
+
+use crate::{
+    event::{self, Event},
+    topology::fanout::{ControlChannel, ControlMessage},
+};
+use futures01::{sync::mpsc, Async, AsyncSink, Poll, Sink, StartSend, Stream};
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+pub const DEFAULT_LIMIT: usize = 100;
+
+static NEXT_TAP_ID: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Json,
+    Text,
+}
+
+/// What a client asked to tap, parsed from the query string.
+#[derive(Debug, PartialEq)]
+pub struct Request {
+    patterns: Vec<glob::Pattern>,
+    format: Format,
+    limit: usize,
+}
+
+impl Request {
+    /// Parses `outputs=<glob>,...&format=json|text&limit=<n>`. Every output
+    /// is tapped when no pattern is given.
+    pub fn parse(query: &str) -> Result<Self, String> {
+        let mut patterns = Vec::new();
+        let mut format = Format::Json;
+        let mut limit = DEFAULT_LIMIT;
+
+        for (key, value) in url::form_urlencoded::parse(query.as_bytes()) {
+            match key.as_ref() {
+                "outputs" => {
+                    for pattern in value.split(',').filter(|pattern| !pattern.is_empty()) {
+                        patterns.push(glob::Pattern::new(pattern).map_err(|error| {
+                            format!("Invalid pattern {:?}: {}", pattern, error)
+                        })?);
+                    }
+                }
+                "format" => {
+                    format = match value.as_ref() {
+                        "json" => Format::Json,
+                        "text" => Format::Text,
+                        other => return Err(format!("Unknown format {:?}.", other)),
+                    }
+                }
+                "limit" => {
+                    limit = value
+                        .parse()
+                        .ok()
+                        .filter(|limit| *limit > 0)
+                        .ok_or_else(|| format!("Invalid limit {:?}.", value))?;
+                }
+                other => return Err(format!("Unknown parameter {:?}.", other)),
+            }
+        }
+
+        Ok(Self {
+            patterns,
+            format,
+            limit,
+        })
+    }
+
+    fn matches(&self, output: &str) -> bool {
+        self.patterns.is_empty() || self.patterns.iter().any(|pattern| pattern.matches(output))
+    }
+
+    /// Attaches a tap to every matching output. The returned stream yields the
+    /// encoded events, one per line, and ends once all taps are detached.
+    pub fn attach(
+        &self,
+        outputs: &HashMap<String, ControlChannel>,
+    ) -> Option<mpsc::Receiver<String>> {
+        let (tx, rx) = mpsc::channel(self.limit);
+        let id = NEXT_TAP_ID.fetch_add(1, Ordering::Relaxed);
+
+        let mut attached = false;
+        for (output, control) in outputs {
+            if !self.matches(output) {
+                continue;
+            }
+
+            let name = format!("_tap_{}", id);
+            let tap = TapSink {
+                output: output.clone(),
+                name: name.clone(),
+                format: self.format,
+                tx: tx.clone(),
+                control: control.clone(),
+                detached: false,
+            };
+            if control
+                .unbounded_send(ControlMessage::Add(name, Box::new(tap)))
+                .is_ok()
+            {
+                attached = true;
+            }
+        }
+
+        if attached {
+            Some(rx)
+        } else {
+            None
+        }
+    }
+}
+
+/// Consumer added to the fanout of a tapped output. It never holds back the
+/// pipeline: events are skipped while the client is lagging, and the tap
+/// detaches itself once the client is gone.
+struct TapSink {
+    output: String,
+    name: String,
+    format: Format,
+    tx: mpsc::Sender<String>,
+    control: ControlChannel,
+    detached: bool,
+}
+
+impl Sink for TapSink {
+    type SinkItem = Event;
+    type SinkError = ();
+
+    fn start_send(&mut self, event: Self::SinkItem) -> StartSend<Self::SinkItem, Self::SinkError> {
+        if !self.detached {
+            if let Err(error) = self.tx.try_send(encode(&self.output, self.format, &event)) {
+                if error.is_disconnected() {
+                    self.detached = true;
+                    let _ = self
+                        .control
+                        .unbounded_send(ControlMessage::Remove(self.name.clone()));
+                }
+            }
+        }
+
+        Ok(AsyncSink::Ready)
+    }
+
+    fn poll_complete(&mut self) -> Poll<(), Self::SinkError> {
+        Ok(Async::Ready(()))
+    }
+}
+
+fn encode(output: &str, format: Format, event: &Event) -> String {
+    let mut line = match (format, event) {
+        (Format::Json, Event::Log(log)) => {
+            serde_json::json!({ "output": output, "event": log }).to_string()
+        }
+        (Format::Json, Event::Metric(metric)) => {
+            serde_json::json!({ "output": output, "event": metric }).to_string()
+        }
+        (Format::Text, Event::Log(log)) => format!(
+            "{}: {}",
+            output,
+            log.get(&event::log_schema().message_key())
+                .map(|message| message.to_string_lossy())
+                .unwrap_or_default()
+        ),
+        (Format::Text, Event::Metric(metric)) => format!(
+            "{}: {}",
+            output,
+            serde_json::to_string(metric).unwrap_or_default()
+        ),
+    };
+    line.push('\n');
+    line
+}
+
+/// Turns the tapped events into an HTTP response body.
+pub fn body(rx: mpsc::Receiver<String>) -> hyper::Body {
+    hyper::Body::wrap_stream(
+        rx.map_err(|()| std::io::Error::new(std::io::ErrorKind::Other, "tap closed")),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::topology::fanout::Fanout;
+    use futures01::Future;
+
+    #[test]
+    fn parses_request() {
+        let request = Request::parse("outputs=in*,parse.errors&format=text&limit=5").unwrap();
+
+        assert!(request.matches("input"));
+        assert!(request.matches("parse.errors"));
+        assert!(!request.matches("parse"));
+        assert_eq!(request.format, Format::Text);
+        assert_eq!(request.limit, 5);
+
+        assert!(Request::parse("").unwrap().matches("anything"));
+        assert!(Request::parse("limit=0").is_err());
+        assert!(Request::parse("format=yaml").is_err());
+        assert!(Request::parse("colour=red").is_err());
+    }
+
+    #[test]
+    fn taps_without_backpressure() {
+        crate::test_util::trace_init();
+        let mut rt = crate::runtime::Runtime::new().unwrap();
+
+        let (fanout, control) = Fanout::new();
+        let mut outputs = HashMap::new();
+        outputs.insert("in".to_string(), control.clone());
+        outputs.insert("other".to_string(), control);
+
+        let request = Request::parse("outputs=in&limit=1").unwrap();
+        let rx = request.attach(&outputs).unwrap();
+
+        let events = vec![Event::from("one"), Event::from("two"), Event::from("three")];
+        let fanout = rt
+            .block_on(fanout.send_all(futures01::stream::iter_ok(events)))
+            .unwrap()
+            .0;
+
+        drop(fanout);
+        // The pipeline went on while the tap was full, so the client got less.
+        let lines = rt.block_on(rx.collect()).unwrap();
+        assert!(lines.len() < 3);
+        let line: serde_json::Value = serde_json::from_str(&lines[0]).unwrap();
+        assert_eq!(line["output"], "in");
+        assert_eq!(line["event"]["message"], "one");
+    }
+
+    #[test]
+    fn nothing_to_tap() {
+        let request = Request::parse("outputs=in").unwrap();
+        assert!(request.attach(&HashMap::new()).is_none());
+    }
+}
@@ -16,7 +16,7 @@ use std::{
     collections::VecDeque,
     convert::TryInto,
     mem::size_of,
-    path::PathBuf,
+    path::{Path, PathBuf},
     sync::{
         atomic::{AtomicUsize, Ordering},
         Arc, Mutex,
@@ -248,6 +248,17 @@ impl Reader {
     }
 }
 
+/// Reads the events left in a LevelDB buffer, in the order they were written,
+/// as their encoded payloads.
+pub(super) fn read_pending(path: &Path) -> Result<Vec<Vec<u8>>, Error> {
+    let db: Database<Key> =
+        Database::open(path, Options::new()).with_context(|| DataDirOpenError {
+            data_dir: path.parent().expect("always a parent"),
+        })?;
+    let values = db.value_iter(ReadOptions::new()).collect();
+    Ok(values)
+}
+
 pub struct Buffer;
 
 impl super::DiskBuffer for Buffer {
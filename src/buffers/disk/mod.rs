@@ -1,12 +1,12 @@
-#![cfg(feature = "leveldb")]
-
 use crate::event::Event;
 use futures01::{Async, AsyncSink, Sink, Stream};
 use snafu::Snafu;
 use std::io;
 use std::path::{Path, PathBuf};
 
+#[cfg(feature = "leveldb")]
 pub mod leveldb_buffer;
+pub mod segment_buffer;
 
 #[derive(Debug, Snafu)]
 pub enum Error {
@@ -19,11 +19,19 @@ pub enum Error {
         data_dir: PathBuf,
         source: std::io::Error,
     },
+    #[cfg(feature = "leveldb")]
     #[snafu(display("Unable to open data_dir {:?}", data_dir))]
     DataDirOpenError {
         data_dir: PathBuf,
         source: leveldb::database::error::Error,
     },
+    #[snafu(display("Unable to access buffer directory {:?}: {}", path, source))]
+    BufferDirIoError {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[snafu(display("The buffer directory {:?} holds a LevelDB buffer left by a previous version of vector. To migrate it, run a vector build with the `leveldb-plain` or `leveldb-cmake` feature (included in the default features of this release) once with the same data_dir and sink name, or remove the directory to discard its events", path))]
+    LevelDbBufferFound { path: PathBuf },
 }
 
 pub trait DiskBuffer {
@@ -38,7 +46,13 @@ pub trait DiskBuffer {
 
 #[derive(Clone)]
 pub struct Writer {
-    inner: leveldb_buffer::Writer,
+    inner: segment_buffer::Writer,
+}
+
+impl Writer {
+    pub fn recovered_events(&self) -> usize {
+        self.inner.recovered_events()
+    }
 }
 
 impl Sink for Writer {
@@ -95,6 +109,127 @@ pub fn open(
             }
         })?;
 
-    let (writer, reader, acker) = leveldb_buffer::Buffer::build(path, max_size)?;
+    if is_leveldb_buffer(&path) {
+        migrate_leveldb_buffer(&path)?;
+    }
+
+    let (writer, reader, acker) = segment_buffer::Buffer::build(path, max_size)?;
     Ok((Writer { inner: writer }, Box::new(reader), acker))
 }
+
+/// LevelDB always keeps a `CURRENT` file pointing at its manifest.
+fn is_leveldb_buffer(path: &Path) -> bool {
+    path.join("CURRENT").is_file()
+}
+
+#[cfg_attr(not(feature = "leveldb"), allow(unused))]
+fn is_leveldb_file(path: &Path) -> bool {
+    let name = match path.file_name().and_then(|name| name.to_str()) {
+        Some(name) => name,
+        None => return false,
+    };
+    let extension = path.extension().and_then(|extension| extension.to_str());
+    ["CURRENT", "LOCK", "LOG", "LOG.old"].contains(&name)
+        || name.starts_with("MANIFEST-")
+        || extension.map_or(false, |extension| {
+            ["ldb", "log", "sst"].contains(&extension)
+        })
+}
+
+/// Moves the events of a LevelDB buffer in front of the segment files, then
+/// removes the LevelDB files. The events land in a single segment all at
+/// once, and are only imported while there are no segments yet, so an
+/// interrupted migration is either started over or only left to clean up.
+#[cfg(feature = "leveldb")]
+fn migrate_leveldb_buffer(path: &Path) -> Result<(), Error> {
+    use snafu::ResultExt;
+
+    let segments = segment_buffer::list_segments(path).context(BufferDirIoError { path })?;
+    if segments.is_empty() {
+        let payloads = leveldb_buffer::read_pending(path)?;
+        info!(
+            message = "Migrating LevelDB disk buffer.",
+            ?path,
+            events = payloads.len()
+        );
+        segment_buffer::import(path, payloads).context(BufferDirIoError { path })?;
+    }
+
+    for entry in std::fs::read_dir(path).context(BufferDirIoError { path })? {
+        let file = entry.context(BufferDirIoError { path })?.path();
+        if is_leveldb_file(&file) {
+            std::fs::remove_file(&file).context(BufferDirIoError { path: &file })?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "leveldb"))]
+fn migrate_leveldb_buffer(path: &Path) -> Result<(), Error> {
+    Err(Error::LevelDbBufferFound { path: path.into() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn recognizes_leveldb_files() {
+        for name in &[
+            "CURRENT",
+            "LOCK",
+            "LOG",
+            "MANIFEST-000002",
+            "000003.log",
+            "000005.ldb",
+        ] {
+            assert!(is_leveldb_file(Path::new(name)), "{}", name);
+        }
+        for name in &["checkpoint", "00000000000000000000.seg"] {
+            assert!(!is_leveldb_file(Path::new(name)), "{}", name);
+        }
+    }
+
+    #[cfg(not(feature = "leveldb"))]
+    #[test]
+    fn refuses_leveldb_buffer() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("sink_buffer");
+        std::fs::create_dir(&path).unwrap();
+        std::fs::write(path.join("CURRENT"), "MANIFEST-000002\n").unwrap();
+
+        match open(dir.path(), "sink_buffer", 1_000_000) {
+            Err(Error::LevelDbBufferFound { .. }) => (),
+            Err(error) => panic!("unexpected error: {}", error),
+            Ok(_) => panic!("LevelDB buffer was ignored"),
+        }
+    }
+
+    #[cfg(feature = "leveldb")]
+    #[test]
+    fn migrates_leveldb_buffer() {
+        use crate::runtime::Runtime;
+        use futures::compat::Future01CompatExt;
+        use futures01::{stream, Future};
+
+        let dir = tempdir().unwrap();
+        let input = (0..10)
+            .map(|i| Event::from(format!("line {}", i)))
+            .collect::<Vec<_>>();
+
+        let mut rt = Runtime::new().unwrap();
+        let (writer, reader, _acker) =
+            leveldb_buffer::Buffer::build(dir.path().join("sink_buffer"), 1_000_000).unwrap();
+        rt.block_on(writer.send_all(stream::iter_ok(input.clone())))
+            .unwrap();
+        drop(reader);
+
+        let (writer, reader, _acker) = open(dir.path(), "sink_buffer", 1_000_000).unwrap();
+        drop(writer);
+        assert!(!is_leveldb_buffer(&dir.path().join("sink_buffer")));
+
+        let handle = rt.spawn_handle(reader.collect().compat());
+        assert_eq!(rt.block_on_std(handle).unwrap().unwrap(), input);
+    }
+}
@@ -0,0 +1,930 @@
+//! A disk buffer implemented on top of plain, append-only segment files.
+//!
+//! Every event is serialized with the same protobuf encoding used by the
+//! other disk buffers and written as a record with the following layout:
+//!
+//! ```text
+//! +----------------+----------------+-----------------------+
+//! | length (u32be) | crc32 (u32be)  | payload (length bytes) |
+//! +----------------+----------------+-----------------------+
+//! ```
+//!
+//! Records are appended to the active segment until it reaches the segment
+//! size limit, at which point a new segment is started. Segments are only ever
+//! deleted once every record in them has been acknowledged, and the position
+//! of the last acknowledged record is persisted to a checkpoint file so that a
+//! restart does not replay already delivered events. The checkpoint is
+//! replaced atomically and at most once per `CHECKPOINT_INTERVAL`, a crash
+//! can only replay the events acknowledged since it was last written.
+//!
+//! Sealed segments that contain a record with a bad checksum, or a truncated
+//! record left behind by a crash, are skipped from that point on.
+
+use super::{BufferDirIoError, Error};
+use crate::{
+    buffers::Acker,
+    event::{proto, Event},
+};
+use futures01::{
+    task::{self, AtomicTask, Task},
+    Async, AsyncSink, Poll, Sink, Stream,
+};
+use prost::Message;
+use snafu::{ResultExt, Snafu};
+use std::{
+    collections::{BTreeMap, VecDeque},
+    convert::TryInto,
+    fs::{self, File, OpenOptions},
+    io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+const SEGMENT_EXTENSION: &str = "seg";
+const CHECKPOINT_FILE: &str = "checkpoint";
+const CHECKPOINT_TEMP_FILE: &str = "checkpoint.tmp";
+const IMPORT_TEMP_FILE: &str = "import.tmp";
+const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(1);
+const RECORD_HEADER_SIZE: usize = 8;
+const MAX_SEGMENT_SIZE: usize = 16 * 1024 * 1024;
+const MIN_SEGMENT_SIZE: usize = 64 * 1024;
+const READ_BATCH_SIZE: usize = 100;
+
+#[derive(Debug, Snafu)]
+enum RecordError {
+    #[snafu(display("Record length {} exceeds the maximum segment size", length))]
+    InvalidLength { length: usize },
+    #[snafu(display("Record checksum mismatch, expected {:#x} got {:#x}", expected, actual))]
+    ChecksumMismatch { expected: u32, actual: u32 },
+    #[snafu(display("Unable to read segment: {}", source))]
+    ReadFailed { source: io::Error },
+}
+
+fn segment_path(dir: &Path, id: u64) -> PathBuf {
+    dir.join(format!("{:020}.{}", id, SEGMENT_EXTENSION))
+}
+
+pub(super) fn list_segments(dir: &Path) -> io::Result<Vec<u64>> {
+    let mut ids = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().and_then(|ext| ext.to_str()) == Some(SEGMENT_EXTENSION))
+        .filter_map(|path| path.file_stem()?.to_str()?.parse::<u64>().ok())
+        .collect::<Vec<_>>();
+    ids.sort();
+    Ok(ids)
+}
+
+/// Reads into `buf` until it is full or the end of the file is reached,
+/// returning the number of bytes read.
+fn read_full(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut read = 0;
+    while read < buf.len() {
+        match reader.read(&mut buf[read..]) {
+            Ok(0) => break,
+            Ok(n) => read += n,
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+            Err(error) => return Err(error),
+        }
+    }
+    Ok(read)
+}
+
+fn parse_header(header: &[u8; RECORD_HEADER_SIZE]) -> (usize, u32) {
+    let length = u32::from_be_bytes(header[..4].try_into().unwrap()) as usize;
+    let checksum = u32::from_be_bytes(header[4..].try_into().unwrap());
+    (length, checksum)
+}
+
+/// Counts the records found in a segment, starting at `offset`, and sums
+/// their payload sizes. Scanning stops at the first truncated or implausible
+/// header, the reader will skip the rest of the segment when it gets there.
+fn scan_segment(path: &Path, offset: u64) -> io::Result<(usize, usize)> {
+    let mut file = BufReader::new(File::open(path)?);
+    let len = file.get_ref().metadata()?.len();
+    let mut position = file.seek(SeekFrom::Start(offset))?;
+    let mut total = 0;
+    let mut records = 0;
+
+    let mut header = [0; RECORD_HEADER_SIZE];
+    while read_full(&mut file, &mut header)? == RECORD_HEADER_SIZE {
+        let (length, _) = parse_header(&header);
+        position += (RECORD_HEADER_SIZE + length) as u64;
+        if length > MAX_SEGMENT_SIZE || position > len {
+            break;
+        }
+        total += length;
+        records += 1;
+        file.seek(SeekFrom::Start(position))?;
+    }
+
+    Ok((total, records))
+}
+
+#[cfg(unix)]
+fn sync_dir(dir: &Path) -> io::Result<()> {
+    File::open(dir)?.sync_all()
+}
+
+// Directories can't be opened as files there, renames are durable once the
+// file itself is synced.
+#[cfg(not(unix))]
+fn sync_dir(_dir: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+/// Writes events left by another buffer into a new segment of `dir`, in
+/// order, ahead of anything written through `Buffer::build`. The segment is
+/// written aside and renamed into place once synced, so that a crash leaves
+/// either all of the events or none of them.
+pub(super) fn import(dir: &Path, payloads: Vec<Vec<u8>>) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    let segment = list_segments(dir)?
+        .last()
+        .map(|segment| segment + 1)
+        .unwrap_or(0);
+
+    let temp = dir.join(IMPORT_TEMP_FILE);
+    let mut file = BufWriter::new(File::create(&temp)?);
+    for payload in payloads {
+        file.write_all(&(payload.len() as u32).to_be_bytes())?;
+        file.write_all(&crc32fast::hash(&payload).to_be_bytes())?;
+        file.write_all(&payload)?;
+    }
+    file.into_inner()?.sync_all()?;
+    fs::rename(&temp, segment_path(dir, segment))?;
+    sync_dir(dir)
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Checkpoint {
+    segment: u64,
+    offset: u64,
+}
+
+impl Checkpoint {
+    fn read(dir: &Path) -> Option<Self> {
+        let bytes = fs::read(dir.join(CHECKPOINT_FILE)).ok()?;
+        if bytes.len() != 20 || crc32fast::hash(&bytes[..16]).to_be_bytes() != bytes[16..] {
+            warn!(message = "Ignoring invalid disk buffer checkpoint.", ?dir);
+            return None;
+        }
+        Some(Self {
+            segment: u64::from_be_bytes(bytes[..8].try_into().unwrap()),
+            offset: u64::from_be_bytes(bytes[8..16].try_into().unwrap()),
+        })
+    }
+
+    /// Replaces the checkpoint file through a rename, so that a crash leaves
+    /// either the previous checkpoint or this one behind.
+    fn write(&self, dir: &Path) -> io::Result<()> {
+        let mut bytes = Vec::with_capacity(20);
+        bytes.extend_from_slice(&self.segment.to_be_bytes());
+        bytes.extend_from_slice(&self.offset.to_be_bytes());
+        let checksum = crc32fast::hash(&bytes);
+        bytes.extend_from_slice(&checksum.to_be_bytes());
+
+        let temp = dir.join(CHECKPOINT_TEMP_FILE);
+        let mut file = File::create(&temp)?;
+        file.write_all(&bytes)?;
+        file.sync_all()?;
+        fs::rename(&temp, dir.join(CHECKPOINT_FILE))?;
+        sync_dir(dir)
+    }
+}
+
+struct WriteState {
+    segment: u64,
+    file: BufWriter<File>,
+    size: usize,
+    dirty: bool,
+    /// Payload bytes written to each segment that has not been deleted yet.
+    payloads: BTreeMap<u64, usize>,
+}
+
+struct Shared {
+    dir: PathBuf,
+    segment_size: usize,
+    state: Mutex<WriteState>,
+    /// Id of the segment currently being written to. A segment with a lower
+    /// id is sealed and will not receive any more records.
+    write_segment: AtomicU64,
+    /// Records left unacknowledged by a previous run.
+    recovered: usize,
+}
+
+impl Shared {
+    fn rotate(&self, state: &mut WriteState) -> io::Result<()> {
+        state.file.flush()?;
+
+        let segment = state.segment + 1;
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(segment_path(&self.dir, segment))?;
+
+        state.segment = segment;
+        state.file = BufWriter::new(file);
+        state.size = 0;
+        state.dirty = false;
+        state.payloads.insert(segment, 0);
+        self.write_segment.store(segment, Ordering::Release);
+        Ok(())
+    }
+}
+
+pub struct Writer {
+    shared: Arc<Shared>,
+    write_notifier: Arc<AtomicTask>,
+    blocked_write_tasks: Arc<Mutex<Vec<Task>>>,
+    max_size: usize,
+    current_size: Arc<AtomicUsize>,
+}
+
+impl Clone for Writer {
+    fn clone(&self) -> Self {
+        Self {
+            shared: Arc::clone(&self.shared),
+            write_notifier: Arc::clone(&self.write_notifier),
+            blocked_write_tasks: Arc::clone(&self.blocked_write_tasks),
+            max_size: self.max_size,
+            current_size: Arc::clone(&self.current_size),
+        }
+    }
+}
+
+impl Sink for Writer {
+    type SinkItem = Event;
+    type SinkError = ();
+
+    fn start_send(
+        &mut self,
+        event: Self::SinkItem,
+    ) -> Result<AsyncSink<Self::SinkItem>, Self::SinkError> {
+        let mut value = vec![];
+        proto::EventWrapper::from(event).encode(&mut value).unwrap(); // This will not error when writing to a Vec
+        let event_size = value.len();
+
+        if self.current_size.fetch_add(event_size, Ordering::Relaxed) + (event_size / 2)
+            > self.max_size
+        {
+            self.blocked_write_tasks
+                .lock()
+                .unwrap()
+                .push(task::current());
+
+            self.current_size.fetch_sub(event_size, Ordering::Relaxed);
+
+            self.poll_complete()?;
+
+            let event = proto::EventWrapper::decode(value).unwrap().into();
+            return Ok(AsyncSink::NotReady(event));
+        }
+
+        let mut header = [0; RECORD_HEADER_SIZE];
+        header[..4].copy_from_slice(&(event_size as u32).to_be_bytes());
+        header[4..].copy_from_slice(&crc32fast::hash(&value).to_be_bytes());
+        let record_size = RECORD_HEADER_SIZE + event_size;
+
+        let mut state = self.shared.state.lock().unwrap();
+        let result = if state.size > 0 && state.size + record_size > self.shared.segment_size {
+            self.shared.rotate(&mut state)
+        } else {
+            Ok(())
+        }
+        .and_then(|_| {
+            state.file.write_all(&header)?;
+            state.file.write_all(&value)
+        });
+
+        match result {
+            Ok(()) => {
+                let segment = state.segment;
+                *state.payloads.entry(segment).or_insert(0) += event_size;
+                state.size += record_size;
+                state.dirty = true;
+                Ok(AsyncSink::Ready)
+            }
+            Err(error) => {
+                error!(message = "Error writing to disk buffer.", %error);
+                self.current_size.fetch_sub(event_size, Ordering::Relaxed);
+                Err(())
+            }
+        }
+    }
+
+    fn poll_complete(&mut self) -> Result<Async<()>, Self::SinkError> {
+        // This only hands the data over to the OS and doesn't need to be wrapped
+        // with `blocking`. Records are checksummed, so a torn write caused by a
+        // machine crash is detected and skipped by the reader.
+        if self.flush().is_err() {
+            return Err(());
+        }
+
+        Ok(Async::Ready(()))
+    }
+}
+
+impl Writer {
+    /// Number of events that were already in the buffer when it was opened.
+    pub fn recovered_events(&self) -> usize {
+        self.shared.recovered
+    }
+
+    fn flush(&self) -> io::Result<()> {
+        let mut state = self.shared.state.lock().unwrap();
+        if state.dirty {
+            state.file.flush().map_err(|error| {
+                error!(message = "Error flushing disk buffer.", %error);
+                error
+            })?;
+            state.dirty = false;
+            self.write_notifier.notify();
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Writer {
+    fn drop(&mut self) {
+        let _ = self.flush();
+
+        // We need to wake up the reader so it can return None if there are no more writers
+        self.write_notifier.notify();
+    }
+}
+
+struct Record {
+    segment: u64,
+    end_offset: u64,
+    payload: Vec<u8>,
+}
+
+pub struct Reader {
+    shared: Arc<Shared>,
+    segment: u64,
+    file: Option<BufReader<File>>,
+    offset: u64,
+    /// Payload bytes consumed from the current segment.
+    consumed: usize,
+    write_notifier: Arc<AtomicTask>,
+    blocked_write_tasks: Arc<Mutex<Vec<Task>>>,
+    current_size: Arc<AtomicUsize>,
+    ack_counter: Arc<AtomicUsize>,
+    /// Segment, end offset and payload size of every record handed out but
+    /// not yet acknowledged.
+    unacked: VecDeque<(u64, u64, usize)>,
+    /// Segments that have been read to the end but may still hold unacked records.
+    finished_segments: VecDeque<u64>,
+    buffer: VecDeque<Record>,
+    /// Position of the last acknowledged record, not written to disk yet.
+    checkpoint: Option<Checkpoint>,
+    checkpointed_at: Instant,
+}
+
+impl Stream for Reader {
+    type Item = Event;
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
+        self.delete_acked();
+        if self.checkpoint.is_some() && self.checkpointed_at.elapsed() >= CHECKPOINT_INTERVAL {
+            tokio::task::block_in_place(|| self.write_checkpoint());
+        }
+
+        // If there's nothing left to read, we return NotReady and rely on Writer
+        // using write_notifier to wake this task up after the next flush.
+        self.write_notifier.register();
+
+        if self.buffer.is_empty() {
+            // This will usually complete instantly, but in the case of a large queue (or a fresh launch of
+            // the app), this will have to go to disk.
+            let records = tokio::task::block_in_place(|| self.read_batch());
+            self.buffer = records;
+        }
+
+        if let Some(record) = self.buffer.pop_front() {
+            self.unacked
+                .push_back((record.segment, record.end_offset, record.payload.len()));
+
+            match proto::EventWrapper::decode(record.payload) {
+                Ok(event) => {
+                    let event = Event::from(event);
+                    Ok(Async::Ready(Some(event)))
+                }
+                Err(err) => {
+                    error!("Error deserializing proto: {:?}", err);
+                    debug_assert!(false);
+                    self.poll()
+                }
+            }
+        } else if Arc::strong_count(&self.shared) == 1 {
+            // There are no writers left
+            Ok(Async::Ready(None))
+        } else {
+            Ok(Async::NotReady)
+        }
+    }
+}
+
+impl Drop for Reader {
+    fn drop(&mut self) {
+        self.delete_acked();
+        self.write_checkpoint();
+    }
+}
+
+impl Reader {
+    fn read_batch(&mut self) -> VecDeque<Record> {
+        let mut records = VecDeque::new();
+
+        while records.len() < READ_BATCH_SIZE {
+            // This has to be loaded before reading, a segment that was sealed at
+            // this point is guaranteed to be fully flushed.
+            let write_segment = self.shared.write_segment.load(Ordering::Acquire);
+            let sealed = self.segment < write_segment;
+
+            match self.read_record() {
+                Ok(Some(record)) => records.push_back(record),
+                Ok(None) if sealed => self.next_segment(),
+                Ok(None) => break,
+                Err(error) if sealed => {
+                    error!(
+                        message = "Skipping corrupted disk buffer segment.",
+                        segment = self.segment,
+                        offset = self.offset,
+                        %error,
+                    );
+                    self.next_segment();
+                }
+                Err(error) => {
+                    // The writer is still appending to this segment. Seal it so
+                    // the rest of it is skipped on the next pass.
+                    error!(
+                        message = "Corrupted record in active disk buffer segment.",
+                        segment = self.segment,
+                        offset = self.offset,
+                        %error,
+                    );
+                    self.file = None;
+                    let mut state = self.shared.state.lock().unwrap();
+                    if state.segment == self.segment {
+                        if let Err(error) = self.shared.rotate(&mut state) {
+                            error!(message = "Unable to start new disk buffer segment.", %error);
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+
+        records
+    }
+
+    fn read_record(&mut self) -> Result<Option<Record>, RecordError> {
+        if self.file.is_none() {
+            let path = segment_path(&self.shared.dir, self.segment);
+            let mut file = match File::open(&path) {
+                Ok(file) => BufReader::new(file),
+                Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(None),
+                Err(error) => return Err(error).context(ReadFailed),
+            };
+            file.seek(SeekFrom::Start(self.offset))
+                .context(ReadFailed)?;
+            self.file = Some(file);
+        }
+        let file = self.file.as_mut().unwrap();
+
+        let mut header = [0; RECORD_HEADER_SIZE];
+        let read = read_full(file, &mut header).context(ReadFailed)?;
+        if read < RECORD_HEADER_SIZE {
+            // Either the end of the segment or a partially flushed record.
+            file.seek(SeekFrom::Start(self.offset))
+                .context(ReadFailed)?;
+            return Ok(None);
+        }
+
+        let (length, expected) = parse_header(&header);
+        if length > MAX_SEGMENT_SIZE {
+            return Err(RecordError::InvalidLength { length });
+        }
+
+        let mut payload = vec![0; length];
+        let read = read_full(file, &mut payload).context(ReadFailed)?;
+        if read < length {
+            file.seek(SeekFrom::Start(self.offset))
+                .context(ReadFailed)?;
+            return Ok(None);
+        }
+
+        let actual = crc32fast::hash(&payload);
+        if actual != expected {
+            return Err(RecordError::ChecksumMismatch { expected, actual });
+        }
+
+        self.offset += (RECORD_HEADER_SIZE + length) as u64;
+        self.consumed += length;
+
+        Ok(Some(Record {
+            segment: self.segment,
+            end_offset: self.offset,
+            payload,
+        }))
+    }
+
+    /// Moves on to the next segment, releasing whatever was left unread in the
+    /// current one.
+    fn next_segment(&mut self) {
+        let written = self
+            .shared
+            .state
+            .lock()
+            .unwrap()
+            .payloads
+            .get(&self.segment)
+            .copied()
+            .unwrap_or(0);
+        let skipped = written.saturating_sub(self.consumed);
+        if skipped > 0 {
+            warn!(
+                message = "Discarded unreadable data from disk buffer segment.",
+                segment = self.segment,
+                bytes = skipped,
+            );
+            self.current_size.fetch_sub(skipped, Ordering::Relaxed);
+        }
+
+        self.finished_segments.push_back(self.segment);
+        self.segment += 1;
+        self.file = None;
+        self.offset = 0;
+        self.consumed = 0;
+
+        self.delete_finished_segments();
+    }
+
+    fn delete_acked(&mut self) {
+        let num_to_delete = self.ack_counter.swap(0, Ordering::Relaxed);
+
+        if num_to_delete > 0 {
+            assert!(
+                num_to_delete <= self.unacked.len(),
+                "Tried to ack beyond read offset"
+            );
+
+            let mut size_deleted = 0;
+            for (segment, offset, size) in self.unacked.drain(..num_to_delete) {
+                size_deleted += size;
+                self.checkpoint = Some(Checkpoint { segment, offset });
+            }
+            self.current_size.fetch_sub(size_deleted, Ordering::Relaxed);
+
+            self.delete_finished_segments();
+        }
+
+        for task in self.blocked_write_tasks.lock().unwrap().drain(..) {
+            task.notify();
+        }
+    }
+
+    fn write_checkpoint(&mut self) {
+        if let Some(checkpoint) = self.checkpoint.take() {
+            if let Err(error) = checkpoint.write(&self.shared.dir) {
+                error!(message = "Unable to write disk buffer checkpoint.", %error);
+                self.checkpoint = Some(checkpoint);
+            }
+            self.checkpointed_at = Instant::now();
+        }
+    }
+
+    fn delete_finished_segments(&mut self) {
+        let oldest_live = self
+            .unacked
+            .front()
+            .map(|(segment, _, _)| *segment)
+            .unwrap_or(self.segment);
+
+        while let Some(&segment) = self.finished_segments.front() {
+            if segment >= oldest_live {
+                break;
+            }
+            self.finished_segments.pop_front();
+
+            self.shared.state.lock().unwrap().payloads.remove(&segment);
+            let path = segment_path(&self.shared.dir, segment);
+            if let Err(error) = fs::remove_file(&path) {
+                if error.kind() != io::ErrorKind::NotFound {
+                    error!(message = "Unable to delete disk buffer segment.", ?path, %error);
+                }
+            }
+        }
+    }
+}
+
+pub struct Buffer;
+
+impl super::DiskBuffer for Buffer {
+    type Writer = Writer;
+    type Reader = Reader;
+
+    fn build(path: PathBuf, max_size: usize) -> Result<(Self::Writer, Self::Reader, Acker), Error> {
+        fs::create_dir_all(&path).context(BufferDirIoError { path: &path })?;
+
+        let segments = list_segments(&path).context(BufferDirIoError { path: &path })?;
+        let checkpoint = Checkpoint::read(&path);
+
+        // Segments before the checkpoint have been fully acknowledged.
+        let mut payloads = BTreeMap::new();
+        let mut recovered = 0;
+        for &segment in &segments {
+            let segment_file = segment_path(&path, segment);
+            match checkpoint {
+                Some(checkpoint) if segment < checkpoint.segment => {
+                    fs::remove_file(&segment_file).context(BufferDirIoError {
+                        path: &segment_file,
+                    })?;
+                }
+                _ => {
+                    let offset = match checkpoint {
+                        Some(checkpoint) if checkpoint.segment == segment => checkpoint.offset,
+                        _ => 0,
+                    };
+                    let (payload, records) =
+                        scan_segment(&segment_file, offset).context(BufferDirIoError {
+                            path: &segment_file,
+                        })?;
+                    payloads.insert(segment, payload);
+                    recovered += records;
+                }
+            }
+        }
+
+        // Always start writing into a fresh segment, so a record torn by a
+        // crash can't end up in the middle of a segment.
+        let write_segment = segments
+            .last()
+            .copied()
+            .into_iter()
+            .chain(checkpoint.map(|checkpoint| checkpoint.segment))
+            .max()
+            .map(|segment| segment + 1)
+            .unwrap_or(0);
+        let (read_segment, read_offset) = match payloads.keys().next() {
+            Some(&segment) => match checkpoint {
+                Some(checkpoint) if checkpoint.segment == segment => (segment, checkpoint.offset),
+                _ => (segment, 0),
+            },
+            None => (write_segment, 0),
+        };
+
+        let segment_file = segment_path(&path, write_segment);
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&segment_file)
+            .context(BufferDirIoError {
+                path: &segment_file,
+            })?;
+
+        let initial_size = payloads.values().sum();
+        let current_size = Arc::new(AtomicUsize::new(initial_size));
+        payloads.insert(write_segment, 0);
+
+        let segment_size = (max_size / 4).max(MIN_SEGMENT_SIZE).min(MAX_SEGMENT_SIZE);
+        let shared = Arc::new(Shared {
+            dir: path,
+            segment_size,
+            state: Mutex::new(WriteState {
+                segment: write_segment,
+                file: BufWriter::new(file),
+                size: 0,
+                dirty: false,
+                payloads,
+            }),
+            write_segment: AtomicU64::new(write_segment),
+            recovered,
+        });
+
+        let write_notifier = Arc::new(AtomicTask::new());
+
+        let blocked_write_tasks = Arc::new(Mutex::new(Vec::new()));
+
+        let ack_counter = Arc::new(AtomicUsize::new(0));
+        let acker = Acker::Disk(Arc::clone(&ack_counter), Arc::clone(&write_notifier));
+
+        let writer = Writer {
+            shared: Arc::clone(&shared),
+            write_notifier: Arc::clone(&write_notifier),
+            blocked_write_tasks: Arc::clone(&blocked_write_tasks),
+            max_size,
+            current_size: Arc::clone(&current_size),
+        };
+
+        let reader = Reader {
+            shared,
+            segment: read_segment,
+            file: None,
+            offset: read_offset,
+            consumed: 0,
+            write_notifier,
+            blocked_write_tasks,
+            current_size,
+            ack_counter,
+            unacked: VecDeque::new(),
+            finished_segments: VecDeque::new(),
+            buffer: VecDeque::new(),
+            checkpoint: None,
+            checkpointed_at: Instant::now(),
+        };
+
+        Ok((writer, reader, acker))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{buffers::disk::DiskBuffer, runtime::Runtime, test_util::runtime};
+    use futures::compat::Future01CompatExt;
+    use futures01::{stream, Future};
+    use tempfile::tempdir;
+
+    fn events(count: usize) -> Vec<Event> {
+        (0..count)
+            .map(|i| Event::from(format!("line {}", i)))
+            .collect()
+    }
+
+    fn write_all(writer: Writer, events: Vec<Event>) {
+        let mut rt = runtime();
+        rt.block_on(writer.send_all(stream::iter_ok(events)))
+            .unwrap();
+    }
+
+    // The reader uses `block_in_place`, so it has to be polled from a worker thread.
+    fn read(reader: Reader, acker: Option<Acker>, count: usize) -> Vec<Event> {
+        let mut rt = Runtime::new().unwrap();
+        let read = reader
+            .take(count as u64)
+            .map(move |event| {
+                if let Some(acker) = &acker {
+                    acker.ack(1);
+                }
+                event
+            })
+            .collect();
+        let handle = rt.spawn_handle(read.compat());
+        rt.block_on_std(handle).unwrap().unwrap()
+    }
+
+    #[test]
+    fn segment_buffer_round_trip() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("buffer");
+        let input = events(1000);
+
+        let (writer, reader, acker) = Buffer::build(path, 1_000_000).unwrap();
+        write_all(writer, input.clone());
+
+        assert_eq!(read(reader, Some(acker), input.len()), input);
+    }
+
+    #[test]
+    fn segment_buffer_rotates_segments() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("buffer");
+        let input = (0..250)
+            .map(|_| Event::from("x".repeat(1_000)))
+            .collect::<Vec<_>>();
+
+        let (writer, reader, acker) = Buffer::build(path.clone(), 400_000).unwrap();
+        write_all(writer, input.clone());
+        assert!(list_segments(&path).unwrap().len() > 1);
+
+        assert_eq!(read(reader, Some(acker), input.len()), input);
+        // Only the segment that was active last remains.
+        assert_eq!(list_segments(&path).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn segment_buffer_resumes_from_checkpoint() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("buffer");
+        let input = events(10);
+
+        let (writer, reader, acker) = Buffer::build(path.clone(), 1_000_000).unwrap();
+        write_all(writer, input.clone());
+        assert_eq!(read(reader, Some(acker), 4), &input[..4]);
+
+        assert!(path.join(CHECKPOINT_FILE).exists());
+        assert!(!path.join(CHECKPOINT_TEMP_FILE).exists());
+
+        let (writer, reader, _acker) = Buffer::build(path, 1_000_000).unwrap();
+        drop(writer);
+        assert_eq!(read(reader, None, usize::max_value()), &input[4..]);
+    }
+
+    #[test]
+    fn segment_buffer_reads_imported_events_first() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("buffer");
+        let input = events(10);
+
+        let payloads = input[..5]
+            .iter()
+            .map(|event| {
+                let mut payload = vec![];
+                proto::EventWrapper::from(event.clone())
+                    .encode(&mut payload)
+                    .unwrap();
+                payload
+            })
+            .collect();
+        import(&path, payloads).unwrap();
+
+        let (writer, reader, _acker) = Buffer::build(path, 1_000_000).unwrap();
+        write_all(writer, input[5..].to_vec());
+
+        assert_eq!(read(reader, None, usize::max_value()), input);
+    }
+
+    #[test]
+    fn segment_buffer_import_replaces_interrupted_one() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("buffer");
+        fs::create_dir(&path).unwrap();
+        // Left by a crash halfway through a previous import.
+        fs::write(path.join(IMPORT_TEMP_FILE), b"\x00\x00\x01").unwrap();
+        assert!(list_segments(&path).unwrap().is_empty());
+
+        let input = events(3);
+        let payloads = input
+            .iter()
+            .map(|event| {
+                let mut payload = vec![];
+                proto::EventWrapper::from(event.clone())
+                    .encode(&mut payload)
+                    .unwrap();
+                payload
+            })
+            .collect();
+        import(&path, payloads).unwrap();
+
+        assert!(!path.join(IMPORT_TEMP_FILE).exists());
+        assert_eq!(list_segments(&path).unwrap(), vec![0]);
+
+        let (_writer, reader, _acker) = Buffer::build(path, 1_000_000).unwrap();
+        assert_eq!(read(reader, None, input.len()), input);
+    }
+
+    #[test]
+    fn segment_buffer_skips_corrupted_segment() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("buffer");
+
+        let (writer, reader, _acker) = Buffer::build(path.clone(), 1_000_000).unwrap();
+        write_all(writer, events(10));
+        drop(reader);
+
+        // Flip a byte in the payload of the first record.
+        let first = segment_path(&path, 0);
+        let mut bytes = fs::read(&first).unwrap();
+        bytes[RECORD_HEADER_SIZE + 1] ^= 0xff;
+        fs::write(&first, bytes).unwrap();
+
+        let (writer, reader, _acker) = Buffer::build(path, 1_000_000).unwrap();
+        let input = events(5);
+        write_all(writer, input.clone());
+
+        assert_eq!(read(reader, None, usize::max_value()), input);
+    }
+
+    #[test]
+    fn segment_buffer_skips_truncated_record() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("buffer");
+
+        let input = events(10);
+        let (writer, reader, _acker) = Buffer::build(path.clone(), 1_000_000).unwrap();
+        write_all(writer, input.clone());
+        drop(reader);
+
+        let first = segment_path(&path, 0);
+        let len = fs::metadata(&first).unwrap().len();
+        OpenOptions::new()
+            .write(true)
+            .open(&first)
+            .unwrap()
+            .set_len(len - 3)
+            .unwrap();
+
+        let (writer, reader, _acker) = Buffer::build(path, 1_000_000).unwrap();
+        drop(writer);
+
+        assert_eq!(read(reader, None, usize::max_value()), &input[..9]);
+    }
+}
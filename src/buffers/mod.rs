@@ -1,14 +1,18 @@
-use crate::Event;
+use crate::{
+    event::finalization::{EventFinalizers, EventStatus},
+    Event,
+};
 use futures01::{sync::mpsc, task::AtomicTask, AsyncSink, Poll, Sink, StartSend, Stream};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
 use std::path::PathBuf;
 use std::sync::{
     atomic::{AtomicUsize, Ordering},
-    Arc,
+    Arc, Mutex,
 };
 
-#[cfg(feature = "leveldb")]
 pub mod disk;
+pub mod overflow;
 
 #[derive(Deserialize, Serialize, Debug)]
 #[serde(tag = "type")]
@@ -19,8 +23,11 @@ pub enum BufferConfig {
         max_events: usize,
         #[serde(default)]
         when_full: WhenFull,
+        /// Size of the disk buffer that receives events once `max_events` is
+        /// reached, only used with `when_full = "overflow"`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max_size: Option<usize>,
     },
-    #[cfg(feature = "leveldb")]
     Disk {
         max_size: usize,
         #[serde(default)]
@@ -33,6 +40,7 @@ impl Default for BufferConfig {
         BufferConfig::Memory {
             max_events: BufferConfig::memory_max_events(),
             when_full: Default::default(),
+            max_size: None,
         }
     }
 }
@@ -42,6 +50,7 @@ impl Default for BufferConfig {
 pub enum WhenFull {
     Block,
     DropNewest,
+    Overflow,
 }
 
 impl Default for WhenFull {
@@ -52,33 +61,112 @@ impl Default for WhenFull {
 
 pub enum BufferInputCloner {
     Memory(mpsc::Sender<Event>, WhenFull),
-    #[cfg(feature = "leveldb")]
     Disk(disk::Writer, WhenFull),
+    Overflow(overflow::Writer),
+    /// Reports what happens to the events written to the inner buffer.
+    Observed(Box<BufferInputCloner>, Observer),
+}
+
+/// Told about the events going through a buffer, to keep count of them.
+pub trait BufferObserver: Send + Sync {
+    /// An event of `byte_size` entered the buffer.
+    fn accepted(&self, byte_size: usize);
+
+    /// An event was dropped because the buffer is full.
+    fn shed(&self);
+
+    /// The sink reading the buffer got `count` events rejected downstream.
+    fn rejected(&self, count: usize);
 }
 
+pub type Observer = Arc<dyn BufferObserver>;
+
 impl BufferInputCloner {
     pub fn get(&self) -> Box<dyn Sink<SinkItem = Event, SinkError = ()> + Send> {
+        self.get_observed(None)
+    }
+
+    fn get_observed(
+        &self,
+        observer: Option<&Observer>,
+    ) -> Box<dyn Sink<SinkItem = Event, SinkError = ()> + Send> {
         match self {
             BufferInputCloner::Memory(tx, when_full) => {
-                let inner = tx.clone().sink_map_err(|e| error!("sender error: {:?}", e));
+                let inner = observed(
+                    tx.clone().sink_map_err(|e| error!("sender error: {:?}", e)),
+                    observer,
+                    *when_full,
+                );
                 if when_full == &WhenFull::DropNewest {
                     Box::new(DropWhenFull { inner })
                 } else {
-                    Box::new(inner)
+                    inner
                 }
             }
 
-            #[cfg(feature = "leveldb")]
             BufferInputCloner::Disk(writer, when_full) => {
+                let inner = observed(writer.clone(), observer, *when_full);
                 if when_full == &WhenFull::DropNewest {
-                    Box::new(DropWhenFull {
-                        inner: writer.clone(),
-                    })
+                    Box::new(DropWhenFull { inner })
                 } else {
-                    Box::new(writer.clone())
+                    inner
                 }
             }
+
+            BufferInputCloner::Overflow(writer) => {
+                observed(writer.clone(), observer, WhenFull::Overflow)
+            }
+
+            BufferInputCloner::Observed(inner, observer) => inner.get_observed(Some(observer)),
+        }
+    }
+}
+
+// Goes below `DropWhenFull`, where a full buffer is told apart from an
+// accepted event.
+fn observed<S>(
+    inner: S,
+    observer: Option<&Observer>,
+    when_full: WhenFull,
+) -> Box<dyn Sink<SinkItem = Event, SinkError = ()> + Send>
+where
+    S: Sink<SinkItem = Event, SinkError = ()> + Send + 'static,
+{
+    match observer {
+        Some(observer) => Box::new(Observe {
+            inner,
+            observer: Arc::clone(observer),
+            when_full,
+        }),
+        None => Box::new(inner),
+    }
+}
+
+struct Observe<S> {
+    inner: S,
+    observer: Observer,
+    when_full: WhenFull,
+}
+
+impl<S: Sink<SinkItem = Event>> Sink for Observe<S> {
+    type SinkItem = Event;
+    type SinkError = S::SinkError;
+
+    fn start_send(&mut self, event: Event) -> StartSend<Event, Self::SinkError> {
+        let size = event.size_of();
+        let result = self.inner.start_send(event);
+        match result {
+            Ok(AsyncSink::Ready) => self.observer.accepted(size),
+            Ok(AsyncSink::NotReady(_)) if self.when_full == WhenFull::DropNewest => {
+                self.observer.shed()
+            }
+            _ => (),
         }
+        result
+    }
+
+    fn poll_complete(&mut self) -> Poll<(), Self::SinkError> {
+        self.inner.poll_complete()
     }
 }
 
@@ -88,7 +176,23 @@ impl BufferConfig {
         500
     }
 
-    #[cfg_attr(not(feature = "leveldb"), allow(unused))]
+    /// How many events the buffer holds at most, when it's bounded that way.
+    pub fn max_events(&self) -> Option<usize> {
+        match self {
+            BufferConfig::Memory {
+                max_events,
+                when_full: WhenFull::Block,
+                ..
+            }
+            | BufferConfig::Memory {
+                max_events,
+                when_full: WhenFull::DropNewest,
+                ..
+            } => Some(*max_events),
+            _ => None,
+        }
+    }
+
     pub fn build(
         &self,
         data_dir: &Option<PathBuf>,
@@ -102,9 +206,26 @@ impl BufferConfig {
         String,
     > {
         match &self {
+            BufferConfig::Memory {
+                max_events,
+                when_full: WhenFull::Overflow,
+                max_size,
+            } => {
+                let max_size = max_size.ok_or_else(|| {
+                    "Must set max_size to overflow the memory buffer to disk.".to_string()
+                })?;
+                let disk = open_disk(data_dir, sink_name, max_size)?;
+
+                let (tx, rx, acker) = overflow::open(*max_events, disk);
+                let tx = BufferInputCloner::Overflow(tx);
+                let rx = Box::new(rx);
+                Ok((tx, rx, acker))
+            }
+
             BufferConfig::Memory {
                 max_events,
                 when_full,
+                ..
             } => {
                 let (tx, rx) = mpsc::channel(*max_events);
                 let tx = BufferInputCloner::Memory(tx, *when_full);
@@ -112,30 +233,51 @@ impl BufferConfig {
                 Ok((tx, rx, Acker::Null))
             }
 
-            #[cfg(feature = "leveldb")]
+            BufferConfig::Disk {
+                when_full: WhenFull::Overflow,
+                ..
+            } => Err("The disk buffer can't overflow, use a memory buffer instead.".to_string()),
+
             BufferConfig::Disk {
                 max_size,
                 when_full,
             } => {
-                let data_dir = data_dir
-                    .as_ref()
-                    .ok_or_else(|| "Must set data_dir to use on-disk buffering.".to_string())?;
-                let buffer_dir = format!("{}_buffer", sink_name);
-
-                let (tx, rx, acker) = disk::open(&data_dir, buffer_dir.as_ref(), *max_size)
-                    .map_err(|err| err.to_string())?;
+                let (tx, rx, acker) = open_disk(data_dir, sink_name, *max_size)?;
                 let tx = BufferInputCloner::Disk(tx, *when_full);
-                let rx = Box::new(rx);
                 Ok((tx, rx, acker))
             }
         }
     }
 }
 
+fn open_disk(
+    data_dir: &Option<PathBuf>,
+    sink_name: &str,
+    max_size: usize,
+) -> Result<
+    (
+        disk::Writer,
+        Box<dyn Stream<Item = Event, Error = ()> + Send>,
+        Acker,
+    ),
+    String,
+> {
+    let data_dir = data_dir
+        .as_ref()
+        .ok_or_else(|| "Must set data_dir to use on-disk buffering.".to_string())?;
+    let buffer_dir = format!("{}_buffer", sink_name);
+
+    disk::open(&data_dir, buffer_dir.as_ref(), max_size).map_err(|err| err.to_string())
+}
+
 #[derive(Debug, Clone)]
 pub enum Acker {
     Disk(Arc<AtomicUsize>, Arc<AtomicTask>),
     Null,
+    /// Marks the finalizers of acked events as delivered before passing the
+    /// ack on to the buffer, and routes rejected events to the dead-letter
+    /// output if the sink has one.
+    Finalizing(Box<Acker>, PendingFinalizers),
 }
 
 impl Acker {
@@ -146,6 +288,12 @@ impl Acker {
     // This is primary used by the on-disk buffer to know which events are okay to
     // delete from disk.
     pub fn ack(&self, num: usize) {
+        self.ack_with(num, EventStatus::Delivered)
+    }
+
+    /// Like `ack`, for the next `num` events ending up with `status`. The sink
+    /// is done with them either way, only their sources are told apart.
+    pub fn ack_with(&self, num: usize, status: EventStatus) {
         // Only ack items if the amount to ack is larger than zero.
         if num > 0 {
             match self {
@@ -154,10 +302,44 @@ impl Acker {
                     counter.fetch_add(num, Ordering::Relaxed);
                     notifier.notify();
                 }
+                Acker::Finalizing(inner, pending) => {
+                    pending.finalize(num, status);
+                    inner.ack(num);
+                }
             }
         }
     }
 
+    /// Like `ack`, for the next `num` events being permanently rejected by the
+    /// downstream service. They are done with as far as the buffer is
+    /// concerned.
+    pub fn reject(&self, num: usize, reason: &str) {
+        if num > 0 {
+            if let Acker::Finalizing(inner, pending) = self {
+                pending.reject(num, reason);
+                inner.ack(num);
+            } else {
+                self.ack(num);
+            }
+        }
+    }
+
+    /// Wraps this acker so that acking events also finalizes them. Each event
+    /// entering the sink must be pushed onto the returned queue, in order.
+    /// The observer is told how many events get rejected.
+    pub fn finalizing(
+        self,
+        dead_letter: Option<DeadLetter>,
+        observer: Option<Observer>,
+    ) -> (Self, PendingFinalizers) {
+        let pending = PendingFinalizers(Arc::new(PendingInner {
+            queue: Default::default(),
+            dead_letter,
+            observer,
+        }));
+        (Acker::Finalizing(Box::new(self), pending.clone()), pending)
+    }
+
     pub fn new_for_testing() -> (Self, Arc<AtomicUsize>) {
         let ack_counter = Arc::new(AtomicUsize::new(0));
         let notifier = Arc::new(AtomicTask::new());
@@ -167,6 +349,113 @@ impl Acker {
     }
 }
 
+/// Finalizers of the events read by a sink and not acked yet, in stream order,
+/// along with a copy of the events themselves when the sink has a dead-letter
+/// output.
+///
+/// Whatever is left once the last handle goes away, because the sink stopped
+/// or failed, is reported as errored.
+#[derive(Clone, Debug)]
+pub struct PendingFinalizers(Arc<PendingInner>);
+
+#[derive(Derivative)]
+#[derivative(Debug)]
+struct PendingInner {
+    queue: Mutex<VecDeque<(EventFinalizers, Option<Event>)>>,
+    dead_letter: Option<DeadLetter>,
+    #[derivative(Debug = "ignore")]
+    observer: Option<Observer>,
+}
+
+impl PendingFinalizers {
+    /// Takes the finalizers off an event entering the sink.
+    pub fn push(&self, event: &mut Event) {
+        let finalizers = event.take_finalizers();
+        let copy = self.0.dead_letter.as_ref().map(|_| event.clone());
+        self.0.queue.lock().unwrap().push_back((finalizers, copy));
+    }
+
+    fn finalize(&self, num: usize, status: EventStatus) {
+        let mut queue = self.0.queue.lock().unwrap();
+        let num = num.min(queue.len());
+        for (finalizers, _) in queue.drain(..num) {
+            finalizers.update_status(status);
+        }
+    }
+
+    fn reject(&self, num: usize, reason: &str) {
+        let mut queue = self.0.queue.lock().unwrap();
+        let num = num.min(queue.len());
+        for (finalizers, event) in queue.drain(..num) {
+            finalizers.update_status(EventStatus::Failed);
+            if let (Some(dead_letter), Some(event)) = (&self.0.dead_letter, event) {
+                dead_letter.send(event, reason);
+            }
+        }
+        if let Some(observer) = &self.0.observer {
+            observer.rejected(num);
+        }
+    }
+}
+
+impl Drop for PendingInner {
+    fn drop(&mut self) {
+        if let Ok(queue) = self.queue.get_mut() {
+            for (finalizers, _) in queue.drain(..) {
+                finalizers.update_status(EventStatus::Errored);
+            }
+        }
+    }
+}
+
+/// How many rejected events can wait for the dead-letter output to take them
+/// before new ones get dropped.
+const DEAD_LETTER_CAPACITY: usize = 1024;
+
+/// Sends the events a sink's downstream service rejected to the sink's
+/// dead-letter output, tagged with the sink name and the rejection reason.
+#[derive(Debug)]
+pub struct DeadLetter {
+    sink: String,
+    tx: Mutex<mpsc::Sender<Event>>,
+}
+
+impl DeadLetter {
+    pub fn new(sink: &str) -> (Self, mpsc::Receiver<Event>) {
+        let (tx, rx) = mpsc::channel(DEAD_LETTER_CAPACITY);
+        let dead_letter = Self {
+            sink: sink.to_owned(),
+            tx: Mutex::new(tx),
+        };
+        (dead_letter, rx)
+    }
+
+    fn send(&self, mut event: Event, reason: &str) {
+        match &mut event {
+            Event::Log(log) => {
+                log.insert("error", reason);
+                log.insert("sink", self.sink.clone());
+            }
+            Event::Metric(metric) => {
+                let tags = metric.tags.get_or_insert_with(BTreeMap::new);
+                tags.insert("error".into(), reason.into());
+                tags.insert("sink".into(), self.sink.clone());
+            }
+        }
+        if let Err(error) = self.tx.lock().unwrap().try_send(event) {
+            if error.is_full() {
+                warn!(
+                    message = "Dead-letter output is full; dropping rejected event.",
+                    sink = %self.sink,
+                    rate_limit_secs = 10
+                );
+            } else {
+                debug!(message = "Dead-letter output closed; dropping rejected event.");
+            }
+        }
+    }
+}
+
 pub struct DropWhenFull<S> {
     inner: S,
 }
@@ -195,10 +484,21 @@ impl<S: Sink> Sink for DropWhenFull<S> {
 
 #[cfg(test)]
 mod test {
-    use super::{Acker, BufferConfig, DropWhenFull, WhenFull};
-    use crate::test_util::block_on;
-    use futures01::{future, sync::mpsc, task::AtomicTask, Async, AsyncSink, Sink, Stream};
-    use std::sync::{atomic::AtomicUsize, Arc};
+    use super::{
+        Acker, BufferConfig, BufferInputCloner, BufferObserver, DeadLetter, DropWhenFull, WhenFull,
+        DEAD_LETTER_CAPACITY,
+    };
+    use crate::{
+        event::finalization::{BatchNotifier, BatchStatus, BatchStatusReceiver, EventStatus},
+        test_util::block_on,
+        Event,
+    };
+    use futures01::{future, sync::mpsc, task::AtomicTask, Async, AsyncSink, Future, Sink, Stream};
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+    use string_cache::DefaultAtom as Atom;
     use tokio01_test::task::MockTask;
 
     #[test]
@@ -223,6 +523,52 @@ mod test {
         .unwrap();
     }
 
+    #[derive(Default)]
+    struct CountingObserver {
+        accepted: AtomicUsize,
+        shed: AtomicUsize,
+        rejected: AtomicUsize,
+    }
+
+    impl BufferObserver for CountingObserver {
+        fn accepted(&self, _byte_size: usize) {
+            self.accepted.fetch_add(1, Ordering::Relaxed);
+        }
+
+        fn shed(&self) {
+            self.shed.fetch_add(1, Ordering::Relaxed);
+        }
+
+        fn rejected(&self, count: usize) {
+            self.rejected.fetch_add(count, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn observer_counts_shed_events() {
+        let (tx, mut rx) = mpsc::channel(0);
+        let observer = Arc::new(CountingObserver::default());
+        let cloner = BufferInputCloner::Observed(
+            Box::new(BufferInputCloner::Memory(tx, WhenFull::DropNewest)),
+            observer.clone(),
+        );
+
+        block_on::<_, _, ()>(future::lazy(|| {
+            let mut tx = cloner.get();
+            for _ in 0..3 {
+                assert_eq!(tx.start_send(Event::from("x")), Ok(AsyncSink::Ready));
+            }
+
+            assert!(rx.poll().unwrap().is_ready());
+            assert_eq!(rx.poll(), Ok(Async::NotReady));
+            future::ok(())
+        }))
+        .unwrap();
+
+        assert_eq!(observer.accepted.load(Ordering::Relaxed), 1);
+        assert_eq!(observer.shed.load(Ordering::Relaxed), 2);
+    }
+
     #[test]
     fn ack_with_none() {
         let counter = Arc::new(AtomicUsize::new(0));
@@ -240,6 +586,90 @@ mod test {
         assert!(mock.is_notified());
     }
 
+    fn event_with_batch() -> (Event, BatchStatusReceiver) {
+        let (batch, receiver) = BatchNotifier::new_with_receiver();
+        let mut event = Event::from("message");
+        event.as_mut_log().add_batch_notifier(batch);
+        (event, receiver)
+    }
+
+    #[test]
+    fn finalizing_acker() {
+        let (acker, pending) = Acker::Null.finalizing(None, None);
+
+        let (mut delivered, delivered_rx) = event_with_batch();
+        pending.push(&mut delivered);
+        let (mut errored, errored_rx) = event_with_batch();
+        pending.push(&mut errored);
+        drop((delivered, errored));
+
+        acker.ack(1);
+        drop(acker);
+        drop(pending);
+
+        assert_eq!(delivered_rx.wait(), Ok(BatchStatus::Delivered));
+        assert_eq!(errored_rx.wait(), Ok(BatchStatus::Errored));
+    }
+
+    #[test]
+    fn finalizing_acker_with_status() {
+        let (acker, pending) = Acker::Null.finalizing(None, None);
+
+        let (mut errored, errored_rx) = event_with_batch();
+        pending.push(&mut errored);
+        let (mut delivered, delivered_rx) = event_with_batch();
+        pending.push(&mut delivered);
+        drop((errored, delivered));
+
+        acker.ack_with(1, EventStatus::Errored);
+        acker.ack(1);
+
+        assert_eq!(errored_rx.wait(), Ok(BatchStatus::Errored));
+        assert_eq!(delivered_rx.wait(), Ok(BatchStatus::Delivered));
+    }
+
+    #[test]
+    fn rejected_events_go_to_dead_letter() {
+        let (dead_letter, dead_letter_rx) = DeadLetter::new("out");
+        let observer = Arc::new(CountingObserver::default());
+        let (acker, pending) = Acker::Null.finalizing(Some(dead_letter), Some(observer.clone()));
+
+        let (mut rejected, rejected_rx) = event_with_batch();
+        pending.push(&mut rejected);
+        drop(rejected);
+
+        acker.reject(1, "mapping error");
+        drop(acker);
+        drop(pending);
+
+        assert_eq!(rejected_rx.wait(), Ok(BatchStatus::Failed));
+        let events = dead_letter_rx.collect().wait().unwrap();
+        assert_eq!(events.len(), 1);
+        let log = events[0].as_log();
+        assert_eq!(log[&Atom::from("message")], "message".into());
+        assert_eq!(log[&Atom::from("error")], "mapping error".into());
+        assert_eq!(log[&Atom::from("sink")], "out".into());
+        assert_eq!(observer.rejected.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn full_dead_letter_drops_rejected_events() {
+        let (dead_letter, dead_letter_rx) = DeadLetter::new("out");
+        let (acker, pending) = Acker::Null.finalizing(Some(dead_letter), None);
+
+        let total = DEAD_LETTER_CAPACITY + 10;
+        for _ in 0..total {
+            pending.push(&mut Event::from("message"));
+        }
+        acker.reject(total, "mapping error");
+        drop(acker);
+        drop(pending);
+
+        // The channel holds one more event than its capacity for its sender.
+        let events = dead_letter_rx.collect().wait().unwrap();
+        assert_eq!(events.len(), DEAD_LETTER_CAPACITY + 1);
+    }
+
     #[test]
     fn config_default_values() {
         fn check(source: &str, config: BufferConfig) {
@@ -254,6 +684,7 @@ mod test {
             BufferConfig::Memory {
                 max_events: 500,
                 when_full: WhenFull::Block,
+                max_size: None,
             },
         );
 
@@ -265,6 +696,7 @@ mod test {
             BufferConfig::Memory {
                 max_events: 100,
                 when_full: WhenFull::Block,
+                max_size: None,
             },
         );
 
@@ -276,10 +708,23 @@ mod test {
             BufferConfig::Memory {
                 max_events: 500,
                 when_full: WhenFull::DropNewest,
+                max_size: None,
+            },
+        );
+
+        check(
+            r#"
+          type = "memory"
+          when_full = "overflow"
+          max_size = 1024
+          "#,
+            BufferConfig::Memory {
+                max_events: 500,
+                when_full: WhenFull::Overflow,
+                max_size: Some(1024),
             },
         );
 
-        #[cfg(feature = "leveldb")]
         check(
             r#"
           type = "disk"
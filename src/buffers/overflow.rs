@@ -0,0 +1,309 @@
+//! A memory buffer that spills over to a disk buffer.
+//!
+//! Events go into the in-memory channel for as long as it has room. Once it is
+//! full, writers switch to the disk buffer and keep writing there until the
+//! reader has drained everything that was spilled, so that events from the
+//! same writer are always read back in the order they were written.
+
+use super::{disk, Acker};
+use crate::Event;
+use futures01::{sync::mpsc, task::AtomicTask, Async, AsyncSink, Poll, Sink, StartSend, Stream};
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+/// Number of events that were written to disk and not read back yet,
+/// including the ones left on disk by a previous run. Records the disk buffer
+/// skips as corrupted are never read back, which keeps writers on disk:
+/// slower, but still in order.
+#[derive(Clone)]
+struct Spilled(Arc<AtomicUsize>);
+
+impl Spilled {
+    fn new(recovered: usize) -> Self {
+        Self(Arc::new(AtomicUsize::new(recovered)))
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0.load(Ordering::Acquire) == 0
+    }
+
+    fn increment(&self) {
+        self.0.fetch_add(1, Ordering::AcqRel);
+    }
+
+    fn decrement(&self) {
+        let previous = self.0.fetch_sub(1, Ordering::AcqRel);
+        debug_assert!(previous > 0, "Read more events from disk than were written");
+    }
+}
+
+#[derive(Clone)]
+pub struct Writer {
+    memory: mpsc::Sender<Event>,
+    disk: disk::Writer,
+    spilled: Spilled,
+}
+
+impl Sink for Writer {
+    type SinkItem = Event;
+    type SinkError = ();
+
+    fn start_send(&mut self, event: Self::SinkItem) -> StartSend<Self::SinkItem, Self::SinkError> {
+        let event = if self.spilled.is_empty() {
+            match self
+                .memory
+                .start_send(event)
+                .map_err(|e| error!("sender error: {:?}", e))?
+            {
+                AsyncSink::Ready => return Ok(AsyncSink::Ready),
+                AsyncSink::NotReady(event) => {
+                    trace!(message = "Memory buffer full; overflowing to disk.");
+                    event
+                }
+            }
+        } else {
+            event
+        };
+
+        let result = self.disk.start_send(event)?;
+        if result.is_ready() {
+            self.spilled.increment();
+        }
+        Ok(result)
+    }
+
+    fn poll_complete(&mut self) -> Poll<(), Self::SinkError> {
+        let memory = self
+            .memory
+            .poll_complete()
+            .map_err(|e| error!("sender error: {:?}", e))?;
+        let disk = self.disk.poll_complete()?;
+
+        if memory.is_ready() && disk.is_ready() {
+            Ok(Async::Ready(()))
+        } else {
+            Ok(Async::NotReady)
+        }
+    }
+}
+
+pub struct Reader {
+    memory: Option<mpsc::Receiver<Event>>,
+    disk: Option<Box<dyn Stream<Item = Event, Error = ()> + Send>>,
+    disk_acker: Acker,
+    spilled: Spilled,
+    ack_counter: Arc<AtomicUsize>,
+    notifier: Arc<AtomicTask>,
+    /// Whether each event handed out but not yet acked came from disk.
+    unacked: VecDeque<bool>,
+}
+
+impl Stream for Reader {
+    type Item = Event;
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
+        self.forward_acks();
+        self.notifier.register();
+
+        // Anything in memory was written before whatever is currently on disk,
+        // since writers only go back to memory once the disk has been drained.
+        if let Some(memory) = &mut self.memory {
+            match memory.poll()? {
+                Async::Ready(Some(event)) => {
+                    self.unacked.push_back(false);
+                    return Ok(Async::Ready(Some(event)));
+                }
+                Async::Ready(None) => self.memory = None,
+                Async::NotReady => {}
+            }
+        }
+
+        if let Some(disk) = &mut self.disk {
+            match disk.poll()? {
+                Async::Ready(Some(event)) => {
+                    self.spilled.decrement();
+                    self.unacked.push_back(true);
+                    return Ok(Async::Ready(Some(event)));
+                }
+                Async::Ready(None) => self.disk = None,
+                Async::NotReady => {}
+            }
+        }
+
+        if self.memory.is_none() && self.disk.is_none() {
+            Ok(Async::Ready(None))
+        } else {
+            Ok(Async::NotReady)
+        }
+    }
+}
+
+impl Reader {
+    /// Translates acks for the combined stream into acks for the disk buffer.
+    fn forward_acks(&mut self) {
+        let num = self.ack_counter.swap(0, Ordering::Relaxed);
+        if num > 0 {
+            assert!(num <= self.unacked.len(), "Tried to ack beyond read offset");
+            let from_disk = self.unacked.drain(..num).filter(|disk| *disk).count();
+            self.disk_acker.ack(from_disk);
+        }
+    }
+}
+
+impl Drop for Reader {
+    fn drop(&mut self) {
+        self.forward_acks();
+    }
+}
+
+pub fn open(
+    max_events: usize,
+    disk: (
+        disk::Writer,
+        Box<dyn Stream<Item = Event, Error = ()> + Send>,
+        Acker,
+    ),
+) -> (Writer, Reader, Acker) {
+    let (disk_tx, disk_rx, disk_acker) = disk;
+    let (memory_tx, memory_rx) = mpsc::channel(max_events);
+    let spilled = Spilled::new(disk_tx.recovered_events());
+
+    let ack_counter = Arc::new(AtomicUsize::new(0));
+    let notifier = Arc::new(AtomicTask::new());
+    let acker = Acker::Disk(Arc::clone(&ack_counter), Arc::clone(&notifier));
+
+    let writer = Writer {
+        memory: memory_tx,
+        disk: disk_tx,
+        spilled: spilled.clone(),
+    };
+    let reader = Reader {
+        memory: Some(memory_rx),
+        disk: Some(disk_rx),
+        disk_acker,
+        spilled,
+        ack_counter,
+        notifier,
+        unacked: VecDeque::new(),
+    };
+
+    (writer, reader, acker)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{runtime::Runtime, test_util::runtime};
+    use futures::compat::Future01CompatExt;
+    use futures01::{future, stream, try_ready, Future};
+    use tempfile::tempdir;
+
+    fn events(count: usize) -> Vec<Event> {
+        (0..count)
+            .map(|i| Event::from(format!("line {}", i)))
+            .collect()
+    }
+
+    #[test]
+    fn overflow_preserves_order() {
+        let dir = tempdir().unwrap();
+        let disk = disk::open(dir.path(), "overflow", 1_000_000).unwrap();
+        let (writer, reader, acker) = open(10, disk);
+        let input = events(100);
+
+        // Nothing is reading yet, so everything past the first events has to
+        // go to disk.
+        let mut rt = runtime();
+        rt.block_on(writer.send_all(stream::iter_ok(input.clone())))
+            .unwrap();
+        assert!(std::fs::read_dir(dir.path().join("overflow"))
+            .unwrap()
+            .any(|entry| entry.unwrap().metadata().unwrap().len() > 0));
+
+        // The disk reader uses `block_in_place`, so it has to run on a worker thread.
+        let mut rt = Runtime::new().unwrap();
+        let read = reader
+            .map(move |event| {
+                acker.ack(1);
+                event
+            })
+            .collect();
+        let handle = rt.spawn_handle(read.compat());
+        let output = rt.block_on_std(handle).unwrap().unwrap();
+
+        assert_eq!(output, input);
+    }
+
+    #[test]
+    fn overflow_returns_to_memory_once_drained() {
+        let dir = tempdir().unwrap();
+        let disk = disk::open(dir.path(), "overflow", 1_000_000).unwrap();
+        let (writer, reader, _acker) = open(10, disk);
+
+        let mut rt = runtime();
+        let (writer, _) = rt
+            .block_on(writer.send_all(stream::iter_ok(events(20))))
+            .unwrap();
+        assert!(!writer.spilled.is_empty());
+
+        // Reads everything back, keeping the reader open for the next writes.
+        let mut reader = Some(reader);
+        let mut read = 0;
+        let drain = future::poll_fn(move || {
+            while read < 20 {
+                try_ready!(reader.as_mut().unwrap().poll());
+                read += 1;
+            }
+            Ok::<_, ()>(Async::Ready(reader.take().unwrap()))
+        });
+        let mut read_rt = Runtime::new().unwrap();
+        let handle = read_rt.spawn_handle(drain.compat());
+        let _reader = read_rt.block_on_std(handle).unwrap().unwrap();
+        assert!(writer.spilled.is_empty());
+
+        let (writer, _) = rt
+            .block_on(writer.send_all(stream::iter_ok(events(5))))
+            .unwrap();
+        assert!(writer.spilled.is_empty());
+    }
+
+    #[test]
+    fn overflow_counts_events_left_on_disk() {
+        let dir = tempdir().unwrap();
+        let input = events(30);
+
+        let disk = disk::open(dir.path(), "overflow", 1_000_000).unwrap();
+        let (writer, reader, _acker) = open(10, disk);
+        let mut rt = runtime();
+        rt.block_on(writer.send_all(stream::iter_ok(input[..20].to_vec())))
+            .unwrap();
+        drop(reader);
+
+        // The events that were in memory are gone, the ones left on disk are
+        // read back before anything written after the restart.
+        let disk = disk::open(dir.path(), "overflow", 1_000_000).unwrap();
+        let (writer, reader, acker) = open(10, disk);
+        assert!(!writer.spilled.is_empty());
+        rt.block_on(writer.send_all(stream::iter_ok(input[20..].to_vec())))
+            .unwrap();
+
+        let mut read_rt = Runtime::new().unwrap();
+        let read = reader
+            .map(move |event| {
+                acker.ack(1);
+                event
+            })
+            .collect();
+        let handle = read_rt.spawn_handle(read.compat());
+        let output = read_rt.block_on_std(handle).unwrap().unwrap();
+
+        assert!(output.len() > 10);
+        assert_eq!(output, &input[input.len() - output.len()..]);
+    }
+}
@@ -0,0 +1,250 @@
+//! Codecs turn a frame into an event.
+
+use crate::event::{self, Event, Value};
+use bytes::Bytes;
+use chrono::{DateTime, Datelike, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use snafu::{ResultExt, Snafu};
+use std::str;
+use syslog_loose::{IncompleteDate, Message, ProcId, Protocol};
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Derivative)]
+#[serde(rename_all = "snake_case")]
+#[derivative(Default)]
+pub enum Codec {
+    /// The frame is the message.
+    #[derivative(Default)]
+    Bytes,
+    /// The frame is a JSON object, its fields become the fields of the event.
+    Json,
+    /// The frame is a syslog message, either RFC 5424 or RFC 3164.
+    Syslog,
+    /// The frame is a list of `key=value` pairs.
+    Logfmt,
+}
+
+#[derive(Debug, Snafu)]
+pub enum ParseError {
+    #[snafu(display("Frame is not valid UTF-8: {}", source))]
+    InvalidUtf8 { source: str::Utf8Error },
+    #[snafu(display("Invalid JSON: {}", source))]
+    InvalidJson { source: serde_json::Error },
+    #[snafu(display("Expected a JSON object, got {}", kind))]
+    NotAnObject { kind: &'static str },
+    #[snafu(display("No key=value pair found"))]
+    NoPairs,
+}
+
+impl Codec {
+    pub fn name(self) -> &'static str {
+        match self {
+            Codec::Bytes => "bytes",
+            Codec::Json => "json",
+            Codec::Syslog => "syslog",
+            Codec::Logfmt => "logfmt",
+        }
+    }
+
+    pub fn decode(self, frame: Bytes) -> Result<Event, ParseError> {
+        match self {
+            Codec::Bytes => Ok(Event::from(frame)),
+            Codec::Json => decode_json(&frame),
+            Codec::Syslog => decode_syslog(&frame),
+            Codec::Logfmt => decode_logfmt(&frame),
+        }
+    }
+}
+
+fn decode_json(frame: &[u8]) -> Result<Event, ParseError> {
+    let fields = match serde_json::from_slice(frame).context(InvalidJson)? {
+        JsonValue::Object(fields) => fields,
+        other => {
+            return Err(ParseError::NotAnObject {
+                kind: json_kind(&other),
+            })
+        }
+    };
+
+    let mut event = Event::new_empty_log();
+    let log = event.as_mut_log();
+    for (key, value) in fields {
+        log.insert(key, value);
+    }
+    log.try_insert(event::log_schema().timestamp_key(), Utc::now());
+
+    Ok(event)
+}
+
+fn json_kind(value: &JsonValue) -> &'static str {
+    match value {
+        JsonValue::Object(_) => "an object",
+        JsonValue::Array(_) => "an array",
+        JsonValue::String(_) => "a string",
+        JsonValue::Number(_) => "a number",
+        JsonValue::Bool(_) => "a boolean",
+        JsonValue::Null => "null",
+    }
+}
+
+fn decode_syslog(frame: &[u8]) -> Result<Event, ParseError> {
+    let line = str::from_utf8(frame).context(InvalidUtf8)?;
+    let parsed = syslog_loose::parse_message_with_year(line.trim(), resolve_year);
+
+    let mut event = Event::from(parsed.msg);
+    let log = event.as_mut_log();
+    if let Some(host) = parsed.hostname {
+        log.insert(event::log_schema().host_key().clone(), host);
+    }
+    if let Some(timestamp) = parsed.timestamp {
+        log.insert(
+            event::log_schema().timestamp_key().clone(),
+            DateTime::<Utc>::from(timestamp),
+        );
+    }
+    insert_fields_from_syslog(&mut event, parsed);
+
+    Ok(event)
+}
+
+/// Resolves the year of syslog messages that don't include it. Messages from
+/// December received in January are from the previous year, the others are
+/// from the current year.
+pub(crate) fn resolve_year((month, _date, _hour, _min, _sec): IncompleteDate) -> i32 {
+    let now = Utc::now();
+    if now.month() == 1 && month == 12 {
+        now.year() - 1
+    } else {
+        now.year()
+    }
+}
+
+/// Adds the header fields and structured data of a syslog message, besides
+/// the message, host and timestamp.
+pub(crate) fn insert_fields_from_syslog(event: &mut Event, parsed: Message<&str>) {
+    let log = event.as_mut_log();
+
+    if let Some(severity) = parsed.severity {
+        log.insert("severity", severity.as_str());
+    }
+    if let Some(facility) = parsed.facility {
+        log.insert("facility", facility.as_str());
+    }
+    if let Protocol::RFC5424(version) = parsed.protocol {
+        log.insert("version", version as i64);
+    }
+    if let Some(app_name) = parsed.appname {
+        log.insert("appname", app_name);
+    }
+    if let Some(msg_id) = parsed.msgid {
+        log.insert("msgid", msg_id);
+    }
+    if let Some(procid) = parsed.procid {
+        let value: Value = match procid {
+            ProcId::PID(pid) => pid.into(),
+            ProcId::Name(name) => name.into(),
+        };
+        log.insert("procid", value);
+    }
+
+    for element in parsed.structured_data.iter() {
+        for (name, value) in element.params.iter() {
+            let key = format!("{}.{}", element.id, name);
+            log.insert(key, value.clone());
+        }
+    }
+}
+
+fn decode_logfmt(frame: &[u8]) -> Result<Event, ParseError> {
+    let line = str::from_utf8(frame).context(InvalidUtf8)?;
+
+    let mut event = Event::new_empty_log();
+    let log = event.as_mut_log();
+    // Words without a value are not logfmt data.
+    for logfmt::Pair { key, val } in logfmt::parse(line) {
+        if let Some(val) = val {
+            log.insert(key, val);
+        }
+    }
+    if log.is_empty() {
+        return Err(ParseError::NoPairs);
+    }
+    log.try_insert(event::log_schema().timestamp_key(), Utc::now());
+
+    Ok(event)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn decode(codec: Codec, frame: &str) -> Result<event::LogEvent, ParseError> {
+        codec.decode(frame.into()).map(Event::into_log)
+    }
+
+    #[test]
+    fn decodes_bytes() {
+        let log = decode(Codec::Bytes, "{\"not\": \"parsed\"}").unwrap();
+        assert_eq!(
+            log[&event::log_schema().message_key()],
+            "{\"not\": \"parsed\"}".into()
+        );
+        assert!(log.contains(&event::log_schema().timestamp_key()));
+    }
+
+    #[test]
+    fn decodes_json() {
+        let log = decode(Codec::Json, r#"{"message": "hi", "nested": {"n": 1}}"#).unwrap();
+        assert_eq!(log[&"message".into()], "hi".into());
+        assert_eq!(log[&"nested.n".into()], 1.into());
+        assert!(log.contains(&event::log_schema().timestamp_key()));
+
+        assert!(matches!(
+            decode(Codec::Json, "[1, 2]"),
+            Err(ParseError::NotAnObject { kind: "an array" })
+        ));
+        assert!(matches!(
+            decode(Codec::Json, "{"),
+            Err(ParseError::InvalidJson { .. })
+        ));
+    }
+
+    #[test]
+    fn decodes_syslog() {
+        let log = decode(
+            Codec::Syslog,
+            r#"<13>1 2020-03-13T20:45:38.119Z dynamicwireless.name non 2426 ID931 [exampleSDID@32473 iut="3"] Try to override"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            log[&event::log_schema().message_key()],
+            "Try to override".into()
+        );
+        assert_eq!(
+            log[&event::log_schema().host_key()],
+            "dynamicwireless.name".into()
+        );
+        assert_eq!(log[&"appname".into()], "non".into());
+        assert_eq!(log[&"procid".into()], 2426.into());
+        assert_eq!(log[&"exampleSDID@32473.iut".into()], "3".into());
+        assert_eq!(
+            log[&event::log_schema().timestamp_key()],
+            Utc.ymd(2020, 3, 13).and_hms_milli(20, 45, 38, 119).into()
+        );
+    }
+
+    #[test]
+    fn decodes_logfmt() {
+        let log = decode(Codec::Logfmt, "level=info msg=\"hello world\" took=3ms").unwrap();
+        assert_eq!(log[&"level".into()], "info".into());
+        assert_eq!(log[&"msg".into()], "hello world".into());
+        assert_eq!(log[&"took".into()], "3ms".into());
+
+        assert!(matches!(
+            decode(Codec::Logfmt, "just some words"),
+            Err(ParseError::NoPairs)
+        ));
+    }
+}
@@ -0,0 +1,133 @@
+//! Framing splits a stream of bytes into the frames handed to a codec.
+
+use bytes::{Bytes, BytesMut};
+use codec::{BytesDelimitedCodec, OctetCountingCodec};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::io;
+use tokio01::codec::{Decoder, LengthDelimitedCodec};
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum FramingConfig {
+    /// The whole input is one frame: a datagram, a request body, a message
+    /// or, for streams, everything received until the end of the stream.
+    Bytes,
+    NewlineDelimited {
+        max_length: Option<usize>,
+    },
+    CharacterDelimited {
+        #[serde(deserialize_with = "de_delimiter", serialize_with = "ser_delimiter")]
+        delimiter: u8,
+        max_length: Option<usize>,
+    },
+    /// Frames prefixed by their length as a 4 bytes big endian integer.
+    LengthDelimited {
+        max_length: Option<usize>,
+    },
+    /// Frames prefixed by their length in ASCII digits and a space, see
+    /// RFC 6587.
+    OctetCounting {
+        max_length: Option<usize>,
+    },
+}
+
+impl FramingConfig {
+    pub fn framer(&self) -> Framer {
+        match *self {
+            FramingConfig::Bytes => Framer::Bytes,
+            FramingConfig::NewlineDelimited { max_length } => Framer::NewlineDelimited(
+                BytesDelimitedCodec::new_with_max_length(b'\n', unlimited(max_length)),
+            ),
+            FramingConfig::CharacterDelimited {
+                delimiter,
+                max_length,
+            } => Framer::Delimited(BytesDelimitedCodec::new_with_max_length(
+                delimiter,
+                unlimited(max_length),
+            )),
+            FramingConfig::LengthDelimited { max_length } => {
+                let mut builder = LengthDelimitedCodec::builder();
+                if let Some(max_length) = max_length {
+                    builder.max_frame_length(max_length);
+                }
+                Framer::LengthDelimited(builder.new_codec())
+            }
+            FramingConfig::OctetCounting { max_length } => Framer::OctetCounting(
+                OctetCountingCodec::new_with_max_length(unlimited(max_length)),
+            ),
+        }
+    }
+}
+
+fn unlimited(max_length: Option<usize>) -> usize {
+    max_length.unwrap_or(usize::MAX)
+}
+
+fn de_delimiter<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u8, D::Error> {
+    let delimiter = String::deserialize(deserializer)?;
+    let mut chars = delimiter.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if c.is_ascii() => Ok(c as u8),
+        _ => Err(de::Error::custom(format!(
+            "delimiter must be a single ASCII character, got {:?}",
+            delimiter
+        ))),
+    }
+}
+
+fn ser_delimiter<S: Serializer>(delimiter: &u8, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_char(*delimiter as char)
+}
+
+/// Lines ending with CRLF lose their carriage return, like with
+/// `LinesCodec`.
+fn without_carriage_return(frame: Bytes) -> Bytes {
+    if frame.ends_with(b"\r") {
+        frame.slice_to(frame.len() - 1)
+    } else {
+        frame
+    }
+}
+
+#[derive(Debug)]
+pub enum Framer {
+    Bytes,
+    NewlineDelimited(BytesDelimitedCodec),
+    Delimited(BytesDelimitedCodec),
+    LengthDelimited(LengthDelimitedCodec),
+    OctetCounting(OctetCountingCodec),
+}
+
+impl Decoder for Framer {
+    type Item = Bytes;
+    type Error = io::Error;
+
+    fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<Bytes>, io::Error> {
+        match self {
+            Framer::Bytes => Ok(None),
+            Framer::NewlineDelimited(codec) => codec
+                .decode(buf)
+                .map(|frame| frame.map(without_carriage_return)),
+            Framer::Delimited(codec) => codec.decode(buf),
+            Framer::LengthDelimited(codec) => {
+                codec.decode(buf).map(|frame| frame.map(BytesMut::freeze))
+            }
+            Framer::OctetCounting(codec) => codec.decode(buf),
+        }
+    }
+
+    fn decode_eof(&mut self, buf: &mut BytesMut) -> Result<Option<Bytes>, io::Error> {
+        match self {
+            Framer::Bytes if buf.is_empty() => Ok(None),
+            Framer::Bytes => Ok(Some(buf.take().freeze())),
+            Framer::NewlineDelimited(codec) => codec
+                .decode_eof(buf)
+                .map(|frame| frame.map(without_carriage_return)),
+            Framer::Delimited(codec) => codec.decode_eof(buf),
+            Framer::LengthDelimited(codec) => codec
+                .decode_eof(buf)
+                .map(|frame| frame.map(BytesMut::freeze)),
+            Framer::OctetCounting(codec) => codec.decode_eof(buf),
+        }
+    }
+}
@@ -0,0 +1,304 @@
+//! Decoding of the bytes received by sources into events, in two steps:
+//! framing splits the input into frames, then a codec parses each frame.
+//!
+//! Sources add a `decoding` option to their configuration and pick the
+//! framing they would use without it, so that it only needs to be set to
+//! change the defaults.
+
+mod codecs;
+mod framing;
+
+pub(crate) use self::codecs::{insert_fields_from_syslog, resolve_year};
+pub use self::{
+    codecs::{Codec, ParseError},
+    framing::{Framer, FramingConfig},
+};
+
+use crate::{emit, event::Event, internal_events::DecoderParseFailed};
+use bytes::{Bytes, BytesMut};
+use futures01::{try_ready, Async, Poll, Stream};
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
+use std::io;
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct DecodingConfig {
+    pub framing: Option<FramingConfig>,
+    #[serde(default)]
+    pub codec: Codec,
+}
+
+impl DecodingConfig {
+    pub fn decoder(&self, default_framing: FramingConfig) -> Decoder {
+        let framing = self.framing.as_ref().unwrap_or(&default_framing);
+        Decoder::new(framing.framer(), self.codec)
+    }
+}
+
+#[derive(Debug, Snafu)]
+pub enum DecodeError {
+    #[snafu(display("Invalid frame: {}", source))]
+    Framing { source: io::Error },
+    #[snafu(display("Unable to parse frame as {}: {}", codec, source))]
+    Parsing {
+        codec: &'static str,
+        source: ParseError,
+    },
+}
+
+/// Decodes events along with the size of the frame they were parsed from.
+///
+/// As a `tokio` decoder, frames that can't be parsed are reported and
+/// skipped, while framing errors end the stream.
+#[derive(Debug)]
+pub struct Decoder {
+    framer: Framer,
+    codec: Codec,
+}
+
+impl Decoder {
+    pub fn new(framer: Framer, codec: Codec) -> Self {
+        Self { framer, codec }
+    }
+
+    /// Blank frames carry no data for structured codecs, such as the empty
+    /// lines in between JSON objects.
+    fn skips(&self, frame: &[u8]) -> bool {
+        self.codec != Codec::Bytes && frame.iter().all(u8::is_ascii_whitespace)
+    }
+
+    /// Decodes a frame split by the source itself. Frames that can't be
+    /// parsed are reported and skipped.
+    pub fn decode_frame(&self, frame: Bytes) -> Option<(Event, usize)> {
+        if self.skips(&frame) {
+            return None;
+        }
+
+        let byte_size = frame.len();
+        match self.codec.decode(frame) {
+            Ok(event) => Some((event, byte_size)),
+            Err(error) => {
+                emit!(DecoderParseFailed {
+                    codec: self.codec.name(),
+                    error,
+                });
+                None
+            }
+        }
+    }
+
+    /// Decodes a whole payload, such as the body of a request, and fails on
+    /// the first frame that can't be decoded.
+    pub fn decode_all(&mut self, mut buf: BytesMut) -> Result<Vec<Event>, DecodeError> {
+        let mut events = Vec::new();
+        while let Some(frame) = self.framer.decode_eof(&mut buf).context(Framing)? {
+            if self.skips(&frame) {
+                continue;
+            }
+            let event = self.codec.decode(frame).context(Parsing {
+                codec: self.codec.name(),
+            })?;
+            events.push(event);
+        }
+        Ok(events)
+    }
+}
+
+impl tokio01::codec::Decoder for Decoder {
+    type Item = (Event, usize);
+    type Error = io::Error;
+
+    fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<Self::Item>, io::Error> {
+        while let Some(frame) = self.framer.decode(buf)? {
+            if let Some(decoded) = self.decode_frame(frame) {
+                return Ok(Some(decoded));
+            }
+        }
+        Ok(None)
+    }
+
+    fn decode_eof(&mut self, buf: &mut BytesMut) -> Result<Option<Self::Item>, io::Error> {
+        while let Some(frame) = self.framer.decode_eof(buf)? {
+            if let Some(decoded) = self.decode_frame(frame) {
+                return Ok(Some(decoded));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Decodes a stream of chunks of bytes, for inputs that aren't `AsyncRead`.
+pub struct DecodedStream<S> {
+    chunks: S,
+    decoder: Decoder,
+    buffer: BytesMut,
+    done: bool,
+}
+
+impl<S> DecodedStream<S> {
+    pub fn new(chunks: S, decoder: Decoder) -> Self {
+        Self {
+            chunks,
+            decoder,
+            buffer: BytesMut::new(),
+            done: false,
+        }
+    }
+}
+
+impl<S> Stream for DecodedStream<S>
+where
+    S: Stream<Item = Bytes, Error = io::Error>,
+{
+    type Item = (Event, usize);
+    type Error = io::Error;
+
+    fn poll(&mut self) -> Poll<Option<Self::Item>, io::Error> {
+        use tokio01::codec::Decoder as _;
+
+        loop {
+            if self.done {
+                return self.decoder.decode_eof(&mut self.buffer).map(Async::Ready);
+            }
+
+            if let Some(decoded) = self.decoder.decode(&mut self.buffer)? {
+                return Ok(Async::Ready(Some(decoded)));
+            }
+
+            match try_ready!(self.chunks.poll()) {
+                Some(chunk) => self.buffer.extend_from_slice(&chunk),
+                None => self.done = true,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event;
+    use futures01::{stream, Future};
+
+    fn config(toml: &str) -> DecodingConfig {
+        toml::from_str(toml).unwrap()
+    }
+
+    fn messages(events: Vec<Event>) -> Vec<String> {
+        events
+            .into_iter()
+            .map(|event| event.as_log()[&event::log_schema().message_key()].to_string_lossy())
+            .collect()
+    }
+
+    #[test]
+    fn parses_config() {
+        assert_eq!(config(""), DecodingConfig::default());
+        assert_eq!(
+            config(
+                r#"
+                codec = "json"
+                framing = { method = "character_delimited", delimiter = "\t" }
+                "#
+            ),
+            DecodingConfig {
+                framing: Some(FramingConfig::CharacterDelimited {
+                    delimiter: b'\t',
+                    max_length: None,
+                }),
+                codec: Codec::Json,
+            }
+        );
+
+        assert!(toml::from_str::<DecodingConfig>(
+            r#"
+            framing = { method = "character_delimited", delimiter = "->" }
+            "#
+        )
+        .is_err());
+        assert!(toml::from_str::<DecodingConfig>(r#"codec = "xml""#).is_err());
+    }
+
+    #[test]
+    fn defaults_to_source_framing() {
+        let mut decoder = DecodingConfig::default().decoder(FramingConfig::Bytes);
+        let events = decoder.decode_all("one\ntwo".into()).unwrap();
+        assert_eq!(messages(events), vec!["one\ntwo"]);
+
+        let mut decoder =
+            config(r#"framing = { method = "newline_delimited" }"#).decoder(FramingConfig::Bytes);
+        let events = decoder.decode_all("one\ntwo".into()).unwrap();
+        assert_eq!(messages(events), vec!["one", "two"]);
+    }
+
+    #[test]
+    fn newline_framing_strips_carriage_returns() {
+        let mut decoder =
+            DecodingConfig::default().decoder(FramingConfig::NewlineDelimited { max_length: None });
+        let events = decoder
+            .decode_all("one\r\ntwo\n\r\nthree\r".into())
+            .unwrap();
+        assert_eq!(messages(events), vec!["one", "two", "", "three"]);
+
+        let mut decoder =
+            config(r#"framing = { method = "character_delimited", delimiter = "\n" }"#)
+                .decoder(FramingConfig::Bytes);
+        let events = decoder.decode_all("one\r\ntwo".into()).unwrap();
+        assert_eq!(messages(events), vec!["one\r", "two"]);
+    }
+
+    #[test]
+    fn decodes_length_and_octet_counted_frames() {
+        let mut decoder =
+            config(r#"framing = { method = "length_delimited" }"#).decoder(FramingConfig::Bytes);
+        let events = decoder
+            .decode_all(BytesMut::from(&b"\0\0\0\x03one\0\0\0\x03two"[..]))
+            .unwrap();
+        assert_eq!(messages(events), vec!["one", "two"]);
+
+        let mut decoder =
+            config(r#"framing = { method = "octet_counting" }"#).decoder(FramingConfig::Bytes);
+        let events = decoder.decode_all("3 one7 two\nsix".into()).unwrap();
+        assert_eq!(messages(events), vec!["one", "two\nsix"]);
+    }
+
+    #[test]
+    fn decode_all_fails_on_invalid_frames() {
+        let mut decoder = config(
+            r#"
+            codec = "json"
+            framing = { method = "newline_delimited" }
+            "#,
+        )
+        .decoder(FramingConfig::Bytes);
+
+        let events = decoder
+            .decode_all("{\"a\": 1}\n\n{\"a\": 2}\n".into())
+            .unwrap();
+        assert_eq!(events.len(), 2);
+
+        assert!(matches!(
+            decoder.decode_all("{\"a\": 1}\nnot json\n".into()),
+            Err(DecodeError::Parsing { codec: "json", .. })
+        ));
+    }
+
+    #[test]
+    fn streams_skip_invalid_frames() {
+        let decoder = config(r#"codec = "json""#)
+            .decoder(FramingConfig::NewlineDelimited { max_length: None });
+        let chunks = vec!["{\"a\":", " 1}\nnot json\n{\"a\"", ": 2}"]
+            .into_iter()
+            .map(Bytes::from);
+
+        let decoded = DecodedStream::new(stream::iter_ok(chunks), decoder)
+            .collect()
+            .wait()
+            .unwrap();
+
+        assert_eq!(decoded.len(), 2);
+        assert_eq!(decoded[0].0.as_log()[&"a".into()], 1.into());
+        assert_eq!(decoded[0].1, 8);
+        assert_eq!(decoded[1].0.as_log()[&"a".into()], 2.into());
+    }
+}
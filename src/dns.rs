@@ -25,6 +25,7 @@ pub type ResolverFuture = Box<dyn Future<Item = LookupIp, Error = DnsError> + Se
 #[derive(Debug, Clone)]
 pub struct Resolver {
     inner: AsyncResolver,
+    pinned: Option<IpAddr>,
 }
 
 pub enum LookupIp {
@@ -81,10 +82,26 @@ impl Resolver {
 
         exec.spawn(bg_task);
 
-        Ok(Self { inner })
+        Ok(Self {
+            inner,
+            pinned: None,
+        })
+    }
+
+    /// Returns a resolver answering every lookup with `ip`, so that a client
+    /// connects to that address while still using the host name for TLS.
+    pub fn pinned(&self, ip: IpAddr) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            pinned: Some(ip),
+        }
     }
 
     pub fn lookup_ip(&self, name: impl AsRef<str>) -> ResolverFuture {
+        if let Some(ip) = self.pinned {
+            return Box::new(future::ok(LookupIp::Single(Some(ip))));
+        }
+
         if let Ok(ip) = IpAddr::from_str(name.as_ref()) {
             return Box::new(future::ok(LookupIp::Single(Some(ip))));
         }
@@ -421,4 +438,17 @@ mod tests {
             Some(IpAddr::from_str("2001:0db8:85a3:0000:0000:8a2e:0370:7334").unwrap())
         );
     }
+
+    #[test]
+    fn resolve_pinned() {
+        let mut rt = runtime();
+        let resolver = Resolver::new(Vec::new(), rt.executor())
+            .unwrap()
+            .pinned(IpAddr::from_str("10.45.12.34").unwrap());
+
+        let mut res = rt.block_on(resolver.lookup_ip("vector.test")).unwrap();
+
+        assert_eq!(res.next(), Some(IpAddr::from_str("10.45.12.34").unwrap()));
+        assert_eq!(res.next(), None);
+    }
 }
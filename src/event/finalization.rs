@@ -0,0 +1,268 @@
+//! End-to-end delivery tracking.
+//!
+//! A source that wants to know whether its events made it out of the pipeline
+//! creates a `BatchNotifier` and attaches it to each event it produces. Every
+//! copy of such an event (one per connected sink) shares the same
+//! `EventFinalizer`, and the batch notifier resolves its `BatchStatusReceiver`
+//! once all the copies of all the events in the batch are gone.
+//!
+//! The topology takes the finalizers off events as they enter a sink and
+//! releases them when the sink acks the events, see `buffers::Acker`. Events
+//! written to a disk buffer are considered delivered once they are on disk.
+//!
+//! Only log events carry finalizers. Metric events count as delivered as soon
+//! as a source hands them over, which is why sources that only emit metrics
+//! can't enable acknowledgements, see `SourceConfig::acknowledgements`.
+
+use futures01::{sync::oneshot, Async, Future, Poll};
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicU8, Ordering},
+        Arc,
+    },
+};
+
+/// The delivery status of a single event, or of one copy of it.
+///
+/// When copies of an event end up with different statuses, the greatest one
+/// wins.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[repr(u8)]
+pub enum EventStatus {
+    /// The event was discarded without being sent anywhere, for example by a
+    /// filter or a sink that doesn't accept its type.
+    Dropped,
+    /// The event was delivered by a sink.
+    Delivered,
+    /// Delivery failed, sending the event again may succeed.
+    Errored,
+    /// The event was permanently rejected by the downstream service.
+    Failed,
+}
+
+impl EventStatus {
+    fn from_u8(value: u8) -> Self {
+        match value {
+            0 => EventStatus::Dropped,
+            1 => EventStatus::Delivered,
+            2 => EventStatus::Errored,
+            _ => EventStatus::Failed,
+        }
+    }
+
+    /// Raises `status` to `self` if `self` is the greater of the two.
+    fn merge_into(self, status: &AtomicU8) {
+        let mut current = status.load(Ordering::Acquire);
+        while current < self as u8 {
+            let previous = status.compare_and_swap(current, self as u8, Ordering::AcqRel);
+            if previous == current {
+                break;
+            }
+            current = previous;
+        }
+    }
+}
+
+/// The outcome of a whole batch of events.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BatchStatus {
+    /// Every event was either delivered or intentionally dropped.
+    Delivered,
+    /// At least one event failed to be delivered.
+    Errored,
+    /// At least one event was permanently rejected.
+    Failed,
+}
+
+impl From<EventStatus> for BatchStatus {
+    fn from(status: EventStatus) -> Self {
+        match status {
+            EventStatus::Dropped | EventStatus::Delivered => BatchStatus::Delivered,
+            EventStatus::Errored => BatchStatus::Errored,
+            EventStatus::Failed => BatchStatus::Failed,
+        }
+    }
+}
+
+/// Reports the status of a batch of events back to the source that created
+/// it, once the last event of the batch has been finalized.
+pub struct BatchNotifier {
+    status: AtomicU8,
+    notifier: Option<oneshot::Sender<BatchStatus>>,
+}
+
+impl BatchNotifier {
+    pub fn new_with_receiver() -> (Arc<Self>, BatchStatusReceiver) {
+        let (tx, rx) = oneshot::channel();
+        let notifier = Self {
+            status: AtomicU8::new(EventStatus::Dropped as u8),
+            notifier: Some(tx),
+        };
+        (Arc::new(notifier), BatchStatusReceiver(rx))
+    }
+
+    fn update_status(&self, status: EventStatus) {
+        status.merge_into(&self.status);
+    }
+}
+
+impl Drop for BatchNotifier {
+    fn drop(&mut self) {
+        if let Some(notifier) = self.notifier.take() {
+            let status = EventStatus::from_u8(self.status.load(Ordering::Acquire));
+            // The source may not be interested anymore.
+            let _ = notifier.send(status.into());
+        }
+    }
+}
+
+impl fmt::Debug for BatchNotifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BatchNotifier")
+            .field(
+                "status",
+                &EventStatus::from_u8(self.status.load(Ordering::Acquire)),
+            )
+            .finish()
+    }
+}
+
+/// Resolves to the status of a batch once all of its events are finalized.
+#[derive(Debug)]
+pub struct BatchStatusReceiver(oneshot::Receiver<BatchStatus>);
+
+impl Future for BatchStatusReceiver {
+    type Item = BatchStatus;
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        match self.0.poll() {
+            Ok(Async::Ready(status)) => Ok(Async::Ready(status)),
+            Ok(Async::NotReady) => Ok(Async::NotReady),
+            // The notifier always sends on drop, this only happens on panics.
+            Err(oneshot::Canceled) => Ok(Async::Ready(BatchStatus::Errored)),
+        }
+    }
+}
+
+/// The delivery status of one event, shared by all of its copies. The status
+/// is passed on to the batch when the last copy goes away.
+#[derive(Debug)]
+pub struct EventFinalizer {
+    status: AtomicU8,
+    batch: Arc<BatchNotifier>,
+}
+
+impl EventFinalizer {
+    pub fn new(batch: Arc<BatchNotifier>) -> Self {
+        Self {
+            status: AtomicU8::new(EventStatus::Dropped as u8),
+            batch,
+        }
+    }
+
+    pub fn update_status(&self, status: EventStatus) {
+        status.merge_into(&self.status);
+    }
+}
+
+impl Drop for EventFinalizer {
+    fn drop(&mut self) {
+        self.batch
+            .update_status(EventStatus::from_u8(self.status.load(Ordering::Acquire)));
+    }
+}
+
+/// The finalizers attached to an event. An event built out of several others
+/// carries the finalizers of all of them.
+#[derive(Clone, Default)]
+pub struct EventFinalizers(Vec<Arc<EventFinalizer>>);
+
+impl EventFinalizers {
+    pub fn new(finalizer: EventFinalizer) -> Self {
+        Self(vec![Arc::new(finalizer)])
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn merge(&mut self, other: Self) {
+        self.0.extend(other.0);
+    }
+
+    pub fn update_status(&self, status: EventStatus) {
+        for finalizer in &self.0 {
+            finalizer.update_status(status);
+        }
+    }
+}
+
+impl fmt::Debug for EventFinalizers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("EventFinalizers")
+            .field(&self.0.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tokio01_test::task::MockTask;
+
+    fn finalizers(batch: &Arc<BatchNotifier>) -> EventFinalizers {
+        EventFinalizers::new(EventFinalizer::new(Arc::clone(batch)))
+    }
+
+    #[test]
+    fn batch_resolves_after_last_event() {
+        let (batch, mut receiver) = BatchNotifier::new_with_receiver();
+        let first = finalizers(&batch);
+        let second = finalizers(&batch);
+        drop(batch);
+
+        let mut mock = MockTask::new();
+
+        first.update_status(EventStatus::Delivered);
+        drop(first);
+        assert_eq!(mock.enter(|| receiver.poll()), Ok(Async::NotReady));
+
+        second.update_status(EventStatus::Delivered);
+        drop(second);
+        assert!(mock.is_notified());
+        assert_eq!(
+            mock.enter(|| receiver.poll()),
+            Ok(Async::Ready(BatchStatus::Delivered))
+        );
+    }
+
+    #[test]
+    fn copies_report_worst_status() {
+        let (batch, receiver) = BatchNotifier::new_with_receiver();
+        let original = finalizers(&batch);
+        let copy = original.clone();
+        drop(batch);
+
+        original.update_status(EventStatus::Errored);
+        copy.update_status(EventStatus::Delivered);
+        drop(original);
+        drop(copy);
+
+        assert_eq!(receiver.wait(), Ok(BatchStatus::Errored));
+    }
+
+    #[test]
+    fn dropped_events_count_as_delivered() {
+        let (batch, receiver) = BatchNotifier::new_with_receiver();
+        drop(finalizers(&batch));
+        drop(batch);
+
+        assert_eq!(receiver.wait(), Ok(BatchStatus::Delivered));
+    }
+}
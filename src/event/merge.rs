@@ -15,6 +15,8 @@ pub fn merge_log_event(current: &mut LogEvent, mut incoming: LogEvent, merge_fie
             Some(current_val) => merge_value(current_val, incoming_val),
         }
     }
+
+    current.add_finalizers(incoming.take_finalizers());
 }
 
 /// Merges `incoming` value into `current` value.
@@ -1,16 +1,18 @@
 use self::proto::{event_wrapper::Event as EventProto, metric::Value as MetricProto, Log};
 use bytes::Bytes;
 use chrono::{DateTime, SecondsFormat, TimeZone, Utc};
+use finalization::{BatchNotifier, EventFinalizer, EventFinalizers};
 use getset::{Getters, Setters};
 use lazy_static::lazy_static;
 use metric::{MetricKind, MetricValue};
 use once_cell::sync::OnceCell;
 use serde::{Deserialize, Serialize, Serializer};
 use serde_json::Value as JsonValue;
-use std::{collections::BTreeMap, iter::FromIterator};
+use std::{collections::BTreeMap, iter::FromIterator, sync::Arc};
 use string_cache::DefaultAtom as Atom;
 
 pub mod discriminant;
+pub mod finalization;
 pub mod merge;
 pub mod merge_state;
 pub mod metric;
@@ -43,9 +45,12 @@ pub enum Event {
     Metric(Metric),
 }
 
-#[derive(PartialEq, Debug, Clone)]
+#[derive(Debug, Clone, Derivative)]
+#[derivative(PartialEq)]
 pub struct LogEvent {
     fields: BTreeMap<String, Value>,
+    #[derivative(PartialEq = "ignore")]
+    finalizers: EventFinalizers,
 }
 
 impl Event {
@@ -94,15 +99,58 @@ impl Event {
             _ => panic!("failed type coercion, {:?} is not a metric", self),
         }
     }
+
+    /// Takes the delivery finalizers off the event. Metrics don't carry any.
+    pub fn take_finalizers(&mut self) -> EventFinalizers {
+        match self {
+            Event::Log(log) => log.take_finalizers(),
+            Event::Metric(_) => EventFinalizers::default(),
+        }
+    }
+
+    /// Rough number of bytes of data carried by the event: field names and
+    /// values for logs, name and tags for metrics. Fixed size values count
+    /// as eight bytes.
+    pub fn size_of(&self) -> usize {
+        match self {
+            Event::Log(log) => log
+                .all_fields()
+                .map(|(name, value)| name.len() + value.size_of())
+                .sum(),
+            Event::Metric(metric) => {
+                let tags = metric.tags.as_ref().map_or(0, |tags| {
+                    tags.iter()
+                        .map(|(name, value)| name.len() + value.len())
+                        .sum()
+                });
+                metric.name.len() + tags + 8
+            }
+        }
+    }
 }
 
 impl LogEvent {
     pub fn new() -> Self {
         Self {
             fields: BTreeMap::new(),
+            finalizers: Default::default(),
         }
     }
 
+    /// Tracks the delivery of this event as part of the given batch.
+    pub fn add_batch_notifier(&mut self, batch: Arc<BatchNotifier>) {
+        self.finalizers
+            .merge(EventFinalizers::new(EventFinalizer::new(batch)));
+    }
+
+    pub fn add_finalizers(&mut self, finalizers: EventFinalizers) {
+        self.finalizers.merge(finalizers);
+    }
+
+    pub fn take_finalizers(&mut self) -> EventFinalizers {
+        std::mem::take(&mut self.finalizers)
+    }
+
     pub fn get(&self, key: &Atom) -> Option<&Value> {
         util::log::get(&self.fields, key)
     }
@@ -409,6 +457,19 @@ impl From<JsonValue> for Value {
 }
 
 impl Value {
+    fn size_of(&self) -> usize {
+        match self {
+            Value::Bytes(bytes) => bytes.len(),
+            Value::Null => 0,
+            Value::Map(map) => map
+                .iter()
+                .map(|(name, value)| name.len() + value.size_of())
+                .sum(),
+            Value::Array(array) => array.iter().map(Value::size_of).sum(),
+            _ => 8,
+        }
+    }
+
     // TODO: return Cow
     pub fn to_string_lossy(&self) -> String {
         match self {
@@ -509,7 +570,10 @@ impl From<proto::EventWrapper> for Event {
                     .filter_map(|(k, v)| decode_value(v).map(|value| (k, value)))
                     .collect::<BTreeMap<_, _>>();
 
-                Event::Log(LogEvent { fields })
+                Event::Log(LogEvent {
+                    fields,
+                    finalizers: Default::default(),
+                })
             }
             EventProto::Metric(proto) => {
                 let kind = match proto.kind() {
@@ -603,7 +667,7 @@ fn encode_array(items: Vec<Value>) -> proto::ValueArray {
 impl From<Event> for proto::EventWrapper {
     fn from(event: Event) -> Self {
         match event {
-            Event::Log(LogEvent { fields }) => {
+            Event::Log(LogEvent { fields, .. }) => {
                 let fields = fields
                     .into_iter()
                     .map(|(k, v)| (k.to_string(), encode_value(v)))
@@ -700,9 +764,7 @@ impl From<Event> for Vec<u8> {
 
 impl From<Bytes> for Event {
     fn from(message: Bytes) -> Self {
-        let mut event = Event::Log(LogEvent {
-            fields: BTreeMap::new(),
-        });
+        let mut event = Event::Log(LogEvent::new());
 
         event
             .as_mut_log()
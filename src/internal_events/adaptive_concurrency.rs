@@ -0,0 +1,30 @@
+use super::InternalEvent;
+use metrics::gauge;
+use std::time::Duration;
+
+#[derive(Debug)]
+pub struct AdaptiveConcurrencyLimitChanged<'a> {
+    pub component_name: &'a str,
+    pub limit: usize,
+    pub back_pressure: bool,
+    pub current_rtt: Duration,
+}
+
+impl InternalEvent for AdaptiveConcurrencyLimitChanged<'_> {
+    fn emit_logs(&self) {
+        trace!(
+            message = "changed request concurrency.",
+            component = %self.component_name,
+            limit = %self.limit,
+            back_pressure = %self.back_pressure,
+            current_rtt = ?self.current_rtt,
+        );
+    }
+
+    fn emit_metrics(&self) {
+        gauge!("adaptive_concurrency_limit", self.limit as i64,
+            "component_name" => self.component_name.to_owned(),
+            "component_kind" => "sink",
+        );
+    }
+}
@@ -0,0 +1,98 @@
+use super::InternalEvent;
+use metrics::{counter, Label};
+
+/// Tags shared by the metrics every component reports through the topology.
+#[derive(Debug, Clone, Copy)]
+pub struct ComponentId<'a> {
+    pub name: &'a str,
+    /// The `component_name`, `component_kind` and `component_type` labels,
+    /// built once per component.
+    pub labels: &'a [Label],
+}
+
+pub fn component_labels(
+    name: &str,
+    kind: &'static str,
+    component_type: &'static str,
+) -> Vec<Label> {
+    vec![
+        Label::new("component_name", name.to_owned()),
+        Label::new("component_kind", kind),
+        Label::new("component_type", component_type),
+    ]
+}
+
+#[derive(Debug)]
+pub struct ComponentEventReceived<'a> {
+    pub component: ComponentId<'a>,
+    pub byte_size: usize,
+}
+
+impl InternalEvent for ComponentEventReceived<'_> {
+    fn emit_metrics(&self) {
+        counter!("events_received", 1, self.component.labels.to_vec());
+        counter!(
+            "bytes_received",
+            self.byte_size as u64,
+            self.component.labels.to_vec()
+        );
+    }
+}
+
+#[derive(Debug)]
+pub struct ComponentEventSent<'a> {
+    pub component: ComponentId<'a>,
+    pub byte_size: usize,
+}
+
+impl InternalEvent for ComponentEventSent<'_> {
+    fn emit_metrics(&self) {
+        counter!("events_sent", 1, self.component.labels.to_vec());
+        counter!(
+            "bytes_sent",
+            self.byte_size as u64,
+            self.component.labels.to_vec()
+        );
+    }
+}
+
+#[derive(Debug)]
+pub struct ComponentProcessingErrors<'a> {
+    pub component: ComponentId<'a>,
+    pub count: usize,
+}
+
+impl InternalEvent for ComponentProcessingErrors<'_> {
+    fn emit_metrics(&self) {
+        counter!(
+            "processing_errors",
+            self.count as u64,
+            self.component.labels.to_vec()
+        );
+    }
+}
+
+#[derive(Debug)]
+pub struct ComponentEventsDiscarded<'a> {
+    pub component: ComponentId<'a>,
+    pub count: usize,
+}
+
+impl InternalEvent for ComponentEventsDiscarded<'_> {
+    fn emit_logs(&self) {
+        debug!(
+            message = "Discarded events.",
+            component = %self.component.name,
+            count = %self.count,
+            rate_limit_secs = 10,
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!(
+            "events_discarded",
+            self.count as u64,
+            self.component.labels.to_vec()
+        );
+    }
+}
@@ -0,0 +1,28 @@
+use super::InternalEvent;
+use crate::decoding::ParseError;
+use metrics::counter;
+
+#[derive(Debug)]
+pub struct DecoderParseFailed {
+    pub codec: &'static str,
+    pub error: ParseError,
+}
+
+impl InternalEvent for DecoderParseFailed {
+    fn emit_logs(&self) {
+        warn!(
+            message = "failed to parse frame.",
+            codec = self.codec,
+            error = %self.error,
+            rate_limit_secs = 30
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!("processing_error", 1,
+            "component_kind" => "source",
+            "codec" => self.codec,
+            "error_type" => "parse_failed",
+        );
+    }
+}
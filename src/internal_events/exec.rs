@@ -0,0 +1,79 @@
+use super::InternalEvent;
+use metrics::counter;
+use std::time::Duration;
+
+#[derive(Debug)]
+pub struct ExecEventReceived<'a> {
+    pub command: &'a str,
+    pub byte_size: usize,
+}
+
+impl InternalEvent for ExecEventReceived<'_> {
+    fn emit_logs(&self) {
+        trace!(message = "received one event.", command = %self.command);
+    }
+
+    fn emit_metrics(&self) {
+        counter!(
+            "events_processed", 1,
+            "component_kind" => "source",
+            "component_type" => "exec",
+        );
+        counter!(
+            "bytes_processed", self.byte_size as u64,
+            "component_kind" => "source",
+            "component_type" => "exec",
+        );
+    }
+}
+
+#[derive(Debug)]
+pub struct ExecCommandExited<'a> {
+    pub command: &'a str,
+    pub exit_status: Option<i32>,
+    pub duration: Duration,
+}
+
+impl InternalEvent for ExecCommandExited<'_> {
+    fn emit_logs(&self) {
+        debug!(
+            message = "command exited.",
+            command = %self.command,
+            exit_status = ?self.exit_status,
+            elapsed_millis = %self.duration.as_millis(),
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!(
+            "processes_exited", 1,
+            "component_kind" => "source",
+            "component_type" => "exec",
+        );
+    }
+}
+
+#[derive(Debug)]
+pub struct ExecFailed<'a> {
+    pub command: &'a str,
+    pub error: std::io::Error,
+}
+
+impl InternalEvent for ExecFailed<'_> {
+    fn emit_logs(&self) {
+        error!(
+            message = "unable to run command.",
+            command = %self.command,
+            error = %self.error,
+            rate_limit_secs = 30,
+        );
+    }
+
+    fn emit_metrics(&self) {
+        counter!(
+            "processing_errors", 1,
+            "component_kind" => "source",
+            "component_type" => "exec",
+        );
+    }
+}
@@ -1,5 +1,10 @@
+mod adaptive_concurrency;
 mod blackhole;
+mod component;
+mod decoding;
 mod elasticsearch;
+#[cfg(feature = "sources-exec")]
+mod exec;
 mod file;
 #[cfg(feature = "transforms-lua")]
 mod lua;
@@ -12,8 +17,13 @@ mod udp;
 mod unix;
 mod vector;
 
+pub use self::adaptive_concurrency::*;
 pub use self::blackhole::*;
+pub use self::component::*;
+pub use self::decoding::*;
 pub use self::elasticsearch::*;
+#[cfg(feature = "sources-exec")]
+pub use self::exec::*;
 pub use self::file::*;
 #[cfg(feature = "transforms-lua")]
 pub use self::lua::*;
@@ -1,5 +1,5 @@
 use super::InternalEvent;
-use crate::sources::prometheus::parser::ParserError;
+use crate::sources::prometheus::{file_sd::FileSdError, parser::ParserError};
 use metrics::counter;
 
 #[derive(Debug)]
@@ -50,3 +50,40 @@ impl InternalEvent for PrometheusHttpError {
         );
     }
 }
+
+#[derive(Debug)]
+pub struct PrometheusHttpResponseError {
+    pub code: hyper::StatusCode,
+    pub url: String,
+}
+
+impl InternalEvent for PrometheusHttpResponseError {
+    fn emit_logs(&self) {
+        error!(message = "http request returned an error", url = %self.url, code = %self.code);
+    }
+
+    fn emit_metrics(&self) {
+        counter!("http_error_response", 1,
+            "component_kind" => "source",
+            "component_type" => "prometheus",
+        );
+    }
+}
+
+#[derive(Debug)]
+pub struct PrometheusFileSdError {
+    pub error: FileSdError,
+}
+
+impl InternalEvent for PrometheusFileSdError {
+    fn emit_logs(&self) {
+        error!(message = "could not read target files, keeping the previous targets", error = %self.error);
+    }
+
+    fn emit_metrics(&self) {
+        counter!("file_sd_errors", 1,
+            "component_kind" => "source",
+            "component_type" => "prometheus",
+        );
+    }
+}
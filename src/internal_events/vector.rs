@@ -1,6 +1,8 @@
 use super::InternalEvent;
+use crate::dns::DnsError;
 use metrics::counter;
 use prost::DecodeError;
+use std::net::SocketAddr;
 
 #[derive(Debug)]
 pub struct VectorEventSent {
@@ -64,3 +66,54 @@ impl InternalEvent for VectorProtoDecodeError {
         );
     }
 }
+
+#[derive(Debug)]
+pub struct VectorPeerEjected {
+    pub address: SocketAddr,
+    pub reason: String,
+}
+
+impl InternalEvent for VectorPeerEjected {
+    fn emit_logs(&self) {
+        warn!(message = "peer ejected.", address = %self.address, reason = %self.reason);
+    }
+
+    fn emit_metrics(&self) {
+        counter!(
+            "peer_ejections", 1,
+            "component_kind" => "sink",
+            "component_type" => "vector",
+        );
+    }
+}
+
+#[derive(Debug)]
+pub struct VectorPeerRestored {
+    pub address: SocketAddr,
+}
+
+impl InternalEvent for VectorPeerRestored {
+    fn emit_logs(&self) {
+        info!(message = "peer restored.", address = %self.address);
+    }
+}
+
+#[derive(Debug)]
+pub struct VectorPeerResolveFailed<'a> {
+    pub host: &'a str,
+    pub error: DnsError,
+}
+
+impl<'a> InternalEvent for VectorPeerResolveFailed<'a> {
+    fn emit_logs(&self) {
+        warn!(message = "unable to resolve peers, keeping the previous ones.", host = %self.host, error = %self.error);
+    }
+
+    fn emit_metrics(&self) {
+        counter!(
+            "dns_resolve_errors", 1,
+            "component_kind" => "sink",
+            "component_type" => "vector",
+        );
+    }
+}
@@ -9,9 +9,11 @@ extern crate derivative;
 #[global_allocator]
 static ALLOC: jemallocator::Jemalloc = jemallocator::Jemalloc;
 
+pub mod api;
 pub mod buffers;
 pub mod conditions;
 pub mod config_paths;
+pub mod decoding;
 pub mod dns;
 pub mod event;
 pub mod expiring_hash_map;
@@ -22,6 +24,8 @@ pub mod internal_events;
 pub mod kafka;
 pub mod list;
 pub mod metrics;
+#[cfg(any(feature = "sources-prometheus", feature = "sinks-prometheus"))]
+pub mod prometheus;
 pub mod region;
 pub mod runtime;
 pub mod serde;
@@ -29,14 +33,18 @@ pub mod shutdown;
 pub mod sinks;
 pub mod sources;
 pub mod stream;
+pub mod tap;
 pub mod template;
 pub mod test_util;
 pub mod tls;
+pub mod top;
 pub mod topology;
 pub mod trace;
 pub mod transforms;
 pub mod types;
 pub mod unit_test;
+#[cfg(any(feature = "sources-vector", feature = "sinks-vector"))]
+pub mod vector_protocol;
 
 pub use event::Event;
 
@@ -11,7 +11,10 @@ use structopt::{clap::AppSettings, StructOpt};
 #[cfg(unix)]
 use tokio_signal::unix::{Signal, SIGHUP, SIGINT, SIGQUIT, SIGTERM};
 use topology::Config;
-use vector::{config_paths, event, generate, list, metrics, runtime, topology, trace, unit_test};
+use vector::{
+    api, config_paths, event, generate, list, metrics, runtime, tap, top, topology, trace,
+    unit_test,
+};
 
 #[derive(StructOpt, Debug)]
 #[structopt(rename_all = "kebab-case")]
@@ -86,6 +89,12 @@ enum SubCommand {
     /// Run Vector config unit tests, then exit. This command is experimental and therefore subject to change.
     /// For guidance on how to write unit tests check out: https://vector.dev/docs/setup/guides/unit-testing/
     Test(unit_test::Opts),
+
+    /// Print the events flowing out of components of a running Vector, fetched from its API.
+    Tap(tap::Opts),
+
+    /// Show live throughput, errors and buffer usage of each component of a running Vector.
+    Top(top::Opts),
 }
 
 #[derive(StructOpt, Debug)]
@@ -211,6 +220,8 @@ fn main() {
             SubCommand::List(l) => list::cmd(&l),
             SubCommand::Test(t) => unit_test::cmd(&t),
             SubCommand::Generate(g) => generate::cmd(&g),
+            SubCommand::Tap(t) => tap::cmd(&t),
+            SubCommand::Top(t) => top::cmd(&t),
         })
     });
 
@@ -285,6 +296,8 @@ fn main() {
         std::process::exit(exitcode::OK);
     }
 
+    let api_options = config.global.api.clone();
+
     let result = topology::start_validated(config, diff, pieces, &mut rt, opts.require_healthy);
     let (topology, mut graceful_crash) = result.unwrap_or_else(|| {
         std::process::exit(exitcode::CONFIG);
@@ -295,6 +308,19 @@ fn main() {
         std::process::exit(exitcode::OK);
     }
 
+    // Kept around until shutdown, only unix can reload the topology it reports on.
+    #[cfg_attr(windows, allow(unused_variables))]
+    let api_server = if api_options.enabled {
+        let server = api::Server::start(&api_options, &mut rt).unwrap_or_else(|error| {
+            error!(message = "Unable to start the API server.", %error);
+            std::process::exit(exitcode::OSERR);
+        });
+        server.update(&topology);
+        Some(server)
+    } else {
+        None
+    };
+
     #[cfg(unix)]
     {
         let mut topology = topology;
@@ -343,6 +369,9 @@ fn main() {
                     // Trigger graceful shutdown for what remains of the topology
                     Err(()) => break SIGINT,
                 }
+                if let Some(api_server) = &api_server {
+                    api_server.update(&topology);
+                }
             } else {
                 error!("Reload aborted.");
             }
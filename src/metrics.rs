@@ -16,3 +16,10 @@ pub fn init() -> crate::Result<()> {
 
     Ok(())
 }
+
+/// Installs the metrics system once for all the tests of the process.
+#[cfg(test)]
+pub fn init_test() {
+    static INIT: std::sync::Once = std::sync::Once::new();
+    INIT.call_once(|| init().unwrap());
+}
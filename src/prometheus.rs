@@ -0,0 +1,8 @@
+//! The remote write protocol shared by the Prometheus sources and sinks.
+
+pub mod proto {
+    include!(concat!(env!("OUT_DIR"), "/prometheus.rs"));
+}
+
+/// The label holding the name of a time series.
+pub const METRIC_NAME_LABEL: &str = "__name__";
@@ -67,6 +67,10 @@ impl SinkConfig for CloudWatchMetricsSinkConfig {
     fn sink_type(&self) -> &'static str {
         "aws_cloudwatch_metrics"
     }
+
+    fn supports_dead_letter(&self) -> bool {
+        true
+    }
 }
 
 impl CloudWatchMetricsSvc {
@@ -91,7 +95,7 @@ impl CloudWatchMetricsSvc {
                 cloudwatch_metrics,
                 MetricBuffer::new(),
                 batch,
-                cx.acker(),
+                &cx,
             )
             .sink_map_err(|e| error!("CloudwatchMetrics sink error: {}", e));
 
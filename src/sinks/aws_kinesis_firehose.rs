@@ -78,6 +78,10 @@ impl SinkConfig for KinesisFirehoseSinkConfig {
     fn sink_type(&self) -> &'static str {
         "aws_kinesis_firehose"
     }
+
+    fn supports_dead_letter(&self) -> bool {
+        true
+    }
 }
 
 impl KinesisFirehoseService {
@@ -98,13 +102,7 @@ impl KinesisFirehoseService {
         let kinesis = KinesisFirehoseService { client, config };
 
         let sink = request
-            .batch_sink(
-                KinesisFirehoseRetryLogic,
-                kinesis,
-                Vec::new(),
-                batch,
-                cx.acker(),
-            )
+            .batch_sink(KinesisFirehoseRetryLogic, kinesis, Vec::new(), batch, &cx)
             .sink_map_err(|e| error!("Fatal kinesis firehose sink error: {}", e))
             .with_flat_map(move |e| iter_ok(encode_event(e, &encoding)));
 
@@ -81,6 +81,10 @@ impl SinkConfig for KinesisSinkConfig {
     fn sink_type(&self) -> &'static str {
         "aws_kinesis_streams"
     }
+
+    fn supports_dead_letter(&self) -> bool {
+        true
+    }
 }
 
 impl KinesisService {
@@ -102,7 +106,7 @@ impl KinesisService {
         let kinesis = KinesisService { client, config };
 
         let sink = request
-            .batch_sink(KinesisRetryLogic, kinesis, Vec::new(), batch, cx.acker())
+            .batch_sink(KinesisRetryLogic, kinesis, Vec::new(), batch, &cx)
             .sink_map_err(|e| error!("Fatal kinesis streams sink error: {}", e))
             .with_flat_map(move |e| iter_ok(encode_event(e, &partition_key_field, &encoding)));
 
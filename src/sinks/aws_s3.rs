@@ -1,11 +1,11 @@
 use crate::{
     dns::Resolver,
-    event::{self, Event},
+    event::Event,
     region::RegionOrEndpoint,
     serde::to_string,
     sinks::util::{
-        encoding::{EncodingConfigWithDefault, EncodingConfiguration},
-        retries::RetryLogic,
+        encoding::{EncodingConfigWithDefault, EncodingConfiguration, StandardEncodings},
+        retries::{rejections, RetryLogic},
         rusoto, BatchBytesConfig, Buffer, PartitionBatchSink, PartitionBuffer,
         PartitionInnerBuffer, ServiceBuilderExt, TowerRequestConfig,
     },
@@ -50,7 +50,7 @@ pub struct S3SinkConfig {
         skip_serializing_if = "crate::serde::skip_serializing_if_default",
         default
     )]
-    pub encoding: EncodingConfigWithDefault<Encoding>,
+    pub encoding: EncodingConfigWithDefault<StandardEncodings>,
     pub compression: Compression,
     #[serde(default)]
     pub batch: BatchBytesConfig,
@@ -115,15 +115,6 @@ lazy_static! {
     };
 }
 
-#[derive(Deserialize, Serialize, Debug, Eq, PartialEq, Clone, Derivative)]
-#[serde(rename_all = "snake_case")]
-#[derivative(Default)]
-pub enum Encoding {
-    #[derivative(Default)]
-    Text,
-    Ndjson,
-}
-
 #[derive(Deserialize, Serialize, Debug, Clone, Derivative)]
 #[serde(rename_all = "snake_case")]
 #[derivative(Default)]
@@ -153,6 +144,10 @@ impl SinkConfig for S3SinkConfig {
     fn sink_type(&self) -> &'static str {
         "aws_s3"
     }
+
+    fn supports_dead_letter(&self) -> bool {
+        true
+    }
 }
 
 #[derive(Debug, Snafu)]
@@ -206,12 +201,13 @@ impl S3Sink {
                     options.clone(),
                 )
             })
-            .settings(request, S3RetryLogic)
+            .settings(request, S3RetryLogic, cx.name())
             .service(s3);
 
         let buffer = PartitionBuffer::new(Buffer::new(compression));
 
         let sink = PartitionBatchSink::new(svc, buffer, batch, cx.acker())
+            .with_rejections(rejections(S3RetryLogic))
             .with_flat_map(move |e| iter_ok(encode_event(e, &key_prefix, &encoding)))
             .sink_map_err(|error| error!("Sink failed to flush: {}", error));
 
@@ -380,9 +376,9 @@ impl RetryLogic for S3RetryLogic {
 }
 
 fn encode_event(
-    mut event: Event,
+    event: Event,
     key_prefix: &Template,
-    encoding: &EncodingConfigWithDefault<Encoding>,
+    encoding: &EncodingConfigWithDefault<StandardEncodings>,
 ) -> Option<PartitionInnerBuffer<Vec<u8>, Bytes>> {
     let key = key_prefix
         .render_string(&event)
@@ -395,25 +391,16 @@ fn encode_event(
         })
         .ok()?;
 
-    encoding.apply_rules(&mut event);
-
-    let log = event.into_log();
-    let bytes = match encoding.codec() {
-        Encoding::Ndjson => serde_json::to_vec(&log)
-            .map(|mut b| {
-                b.push(b'\n');
-                b
-            })
-            .expect("Failed to encode event as json, this is a bug!"),
-        Encoding::Text => {
-            let mut bytes = log
-                .get(&event::log_schema().message_key())
-                .map(|v| v.as_bytes().to_vec())
-                .unwrap_or_default();
-            bytes.push(b'\n');
-            bytes
-        }
-    };
+    let bytes = encoding
+        .encode_frame(event)
+        .map_err(|error| {
+            warn!(
+                message = "Unable to encode event. Dropping event.",
+                %error,
+                rate_limit_secs = 30,
+            );
+        })
+        .ok()?;
 
     Some(PartitionInnerBuffer::new(bytes, key.into()))
 }
@@ -432,7 +419,7 @@ mod tests {
         let bytes = encode_event(
             message.clone().into(),
             &batch_time_format,
-            &Encoding::Text.into(),
+            &StandardEncodings::Text.into(),
         )
         .unwrap();
 
@@ -448,7 +435,8 @@ mod tests {
         event.as_mut_log().insert("key", "value");
 
         let batch_time_format = Template::from("date=%F");
-        let bytes = encode_event(event, &batch_time_format, &Encoding::Ndjson.into()).unwrap();
+        let bytes =
+            encode_event(event, &batch_time_format, &StandardEncodings::Ndjson.into()).unwrap();
 
         let (bytes, _) = bytes.into_parts();
         let map: BTreeMap<String, String> = serde_json::from_slice(&bytes[..]).unwrap();
@@ -466,7 +454,7 @@ mod tests {
         let key_prefix = Template::from("{{ key }}");
 
         let encoding_config = EncodingConfigWithDefault {
-            codec: Encoding::Ndjson,
+            codec: StandardEncodings::Ndjson,
             except_fields: Some(vec!["key".into()]),
             ..Default::default()
         };
@@ -2,10 +2,9 @@ use crate::{
     event::{log_schema, Event},
     sinks::util::{
         self,
-        encoding::{EncodingConfig, EncodingConfiguration},
+        encoding::{EncodingConfig, EncodingConfiguration, StandardEncodings},
         tcp::{tcp_healthcheck, TcpSink},
         uri::UriSerde,
-        Encoding,
     },
     tls::{MaybeTlsSettings, TlsConfig},
     topology::config::{DataType, SinkConfig, SinkContext, SinkDescription},
@@ -19,7 +18,7 @@ use serde::{Deserialize, Serialize};
 pub struct DatadogLogsConfig {
     endpoint: Option<UriSerde>,
     api_key: String,
-    encoding: EncodingConfig<Encoding>,
+    encoding: EncodingConfig<StandardEncodings>,
     tls: Option<TlsConfig>,
 }
 
@@ -75,7 +74,7 @@ impl SinkConfig for DatadogLogsConfig {
 fn encode_event(
     mut event: Event,
     mut api_key: Bytes,
-    encoding: &EncodingConfig<Encoding>,
+    encoding: &EncodingConfig<StandardEncodings>,
 ) -> Option<Bytes> {
     encoding.apply_rules(&mut event);
 
@@ -126,6 +126,10 @@ impl SinkConfig for ElasticSearchConfig {
     fn sink_type(&self) -> &'static str {
         "elasticsearch"
     }
+
+    fn supports_dead_letter(&self) -> bool {
+        true
+    }
 }
 
 #[derive(Debug)]
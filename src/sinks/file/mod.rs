@@ -1,8 +1,8 @@
 use crate::expiring_hash_map::ExpiringHashMap;
 use crate::{
-    event::{self, Event},
+    event::Event,
     sinks::util::{
-        encoding::{EncodingConfigWithDefault, EncodingConfiguration},
+        encoding::{EncodingConfigWithDefault, EncodingConfiguration, StandardEncodings},
         StreamSink,
     },
     template::Template,
@@ -33,26 +33,13 @@ pub struct FileSinkConfig {
         default,
         skip_serializing_if = "crate::serde::skip_serializing_if_default"
     )]
-    pub encoding: EncodingConfigWithDefault<Encoding>,
+    pub encoding: EncodingConfigWithDefault<StandardEncodings>,
 }
 
 inventory::submit! {
     SinkDescription::new_without_default::<FileSinkConfig>("file")
 }
 
-#[derive(Deserialize, Serialize, Debug, Eq, PartialEq, Clone)]
-#[serde(rename_all = "snake_case")]
-pub enum Encoding {
-    Text,
-    Ndjson,
-}
-
-impl Default for Encoding {
-    fn default() -> Self {
-        Encoding::Text
-    }
-}
-
 #[typetag::serde(name = "file")]
 impl SinkConfig for FileSinkConfig {
     fn build(&self, mut cx: SinkContext) -> crate::Result<(super::RouterSink, super::Healthcheck)> {
@@ -74,7 +61,7 @@ impl SinkConfig for FileSinkConfig {
 #[derive(Debug)]
 pub struct FileSink {
     path: Template,
-    encoding: EncodingConfigWithDefault<Encoding>,
+    encoding: EncodingConfigWithDefault<StandardEncodings>,
     idle_timeout: Duration,
     files: ExpiringHashMap<Bytes, File>,
 }
@@ -206,25 +193,14 @@ async fn open_file(path: impl AsRef<std::path::Path>) -> std::io::Result<File> {
         .await
 }
 
-pub fn encode_event(encoding: &EncodingConfigWithDefault<Encoding>, mut event: Event) -> Vec<u8> {
-    encoding.apply_rules(&mut event);
-    let log = event.into_log();
-    match encoding.codec() {
-        Encoding::Ndjson => serde_json::to_vec(&log).expect("Unable to encode event as JSON."),
-        Encoding::Text => log
-            .get(&event::log_schema().message_key())
-            .map(|v| v.to_string_lossy().into_bytes())
-            .unwrap_or_default(),
-    }
-}
-
 async fn write_event_to_file(
     file: &mut File,
     event: Event,
-    encoding: &EncodingConfigWithDefault<Encoding>,
+    encoding: &EncodingConfigWithDefault<StandardEncodings>,
 ) -> Result<(), std::io::Error> {
-    let mut buf = encode_event(encoding, event);
-    buf.push(b'\n');
+    let buf = encoding
+        .encode_frame(event)
+        .map_err(|error| std::io::Error::new(std::io::ErrorKind::Other, error))?;
     file.write_all(&buf[..]).await
 }
 
@@ -259,7 +235,7 @@ mod tests {
         let config = FileSinkConfig {
             path: template.clone().into(),
             idle_timeout_secs: None,
-            encoding: Encoding::Text.into(),
+            encoding: StandardEncodings::Text.into(),
         };
 
         let mut sink = FileSink::new(&config);
@@ -292,7 +268,7 @@ mod tests {
         let config = FileSinkConfig {
             path: template.clone().into(),
             idle_timeout_secs: None,
-            encoding: Encoding::Text.into(),
+            encoding: StandardEncodings::Text.into(),
         };
 
         let mut sink = FileSink::new(&config);
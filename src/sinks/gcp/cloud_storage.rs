@@ -6,7 +6,7 @@ use crate::{
         util::{
             encoding::{EncodingConfig, EncodingConfiguration},
             http::{HttpClient, HttpClientFuture},
-            retries::{RetryAction, RetryLogic},
+            retries::{rejections, RetryAction, RetryLogic},
             BatchBytesConfig, Buffer, PartitionBuffer, PartitionInnerBuffer, ServiceBuilderExt,
             TowerRequestConfig,
         },
@@ -185,6 +185,10 @@ impl SinkConfig for GcsSinkConfig {
     fn sink_type(&self) -> &'static str {
         NAME
     }
+
+    fn supports_dead_letter(&self) -> bool {
+        true
+    }
 }
 
 #[derive(Debug, Snafu)]
@@ -234,12 +238,13 @@ impl GcsSink {
 
         let svc = ServiceBuilder::new()
             .map(move |req| RequestWrapper::new(req, settings.clone()))
-            .settings(request, GcsRetryLogic)
+            .settings(request, GcsRetryLogic, cx.name())
             .service(self);
 
         let buffer = PartitionBuffer::new(Buffer::new(compression));
 
         let sink = crate::sinks::util::PartitionBatchSink::new(svc, buffer, batch, cx.acker())
+            .with_rejections(rejections(GcsRetryLogic))
             .sink_map_err(|e| error!("Fatal gcs sink error: {}", e))
             .with_flat_map(move |e| iter_ok(encode_event(e, &key_prefix, &encoding)));
 
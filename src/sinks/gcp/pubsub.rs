@@ -90,6 +90,10 @@ impl SinkConfig for PubsubConfig {
     fn sink_type(&self) -> &'static str {
         "gcp_pubsub"
     }
+
+    fn supports_dead_letter(&self) -> bool {
+        true
+    }
 }
 
 struct PubsubSink {
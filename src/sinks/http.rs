@@ -2,7 +2,7 @@ use crate::{
     dns::Resolver,
     event::{self, Event},
     sinks::util::{
-        encoding::{EncodingConfig, EncodingConfiguration},
+        encoding::{EncodingConfig, EncodingConfiguration, StandardEncodings},
         http::{Auth, BatchedHttpSink, HttpClient, HttpSink},
         BatchBytesConfig, Buffer, Compression, TowerRequestConfig, UriSerde,
     },
@@ -44,7 +44,7 @@ pub struct HttpSinkConfig {
     pub auth: Option<Auth>,
     pub headers: Option<IndexMap<String, String>>,
     pub compression: Option<Compression>,
-    pub encoding: EncodingConfig<Encoding>,
+    pub encoding: EncodingConfig<StandardEncodings>,
     #[serde(default)]
     pub batch: BatchBytesConfig,
     #[serde(default)]
@@ -53,7 +53,7 @@ pub struct HttpSinkConfig {
 }
 
 #[cfg(test)]
-fn default_config(e: Encoding) -> HttpSinkConfig {
+fn default_config(e: StandardEncodings) -> HttpSinkConfig {
     HttpSinkConfig {
         uri: Default::default(),
         method: Default::default(),
@@ -86,14 +86,6 @@ pub enum HttpMethod {
     Put,
 }
 
-#[derive(Deserialize, Serialize, Debug, Eq, PartialEq, Clone)]
-#[serde(rename_all = "snake_case")]
-pub enum Encoding {
-    Text,
-    Ndjson,
-    Json,
-}
-
 inventory::submit! {
     SinkDescription::new_without_default::<HttpSinkConfig>("http")
 }
@@ -143,49 +135,44 @@ impl SinkConfig for HttpSinkConfig {
     fn sink_type(&self) -> &'static str {
         "http"
     }
+
+    fn supports_dead_letter(&self) -> bool {
+        true
+    }
 }
 
 impl HttpSink for HttpSinkConfig {
     type Input = Vec<u8>;
     type Output = Vec<u8>;
 
-    fn encode_event(&self, mut event: Event) -> Option<Self::Input> {
-        self.encoding.apply_rules(&mut event);
-        let event = event.into_log();
-
-        let body = match &self.encoding.codec() {
-            Encoding::Text => {
-                if let Some(v) = event.get(&event::log_schema().message_key()) {
-                    let mut b = v.to_string_lossy().into_bytes();
-                    b.push(b'\n');
-                    b
-                } else {
-                    warn!(
-                        message = "Event missing the message key; Dropping event.",
-                        rate_limit_secs = 30,
-                    );
-                    return None;
-                }
-            }
-
-            Encoding::Ndjson => {
-                let mut b = serde_json::to_vec(&event)
-                    .map_err(|e| panic!("Unable to encode into JSON: {}", e))
-                    .ok()?;
-                b.push(b'\n');
-                b
-            }
+    fn encode_event(&self, event: Event) -> Option<Self::Input> {
+        if self.encoding.codec() == &StandardEncodings::Text
+            && !event.as_log().contains(&event::log_schema().message_key())
+        {
+            warn!(
+                message = "Event missing the message key; Dropping event.",
+                rate_limit_secs = 30,
+            );
+            return None;
+        }
 
-            Encoding::Json => {
-                let mut b = serde_json::to_vec(&event)
-                    .map_err(|e| panic!("Unable to encode into JSON: {}", e))
-                    .ok()?;
+        // JSON events are put together into an array by `build_request`.
+        let body = match self.encoding.codec() {
+            StandardEncodings::Json => self.encoding.encode(event).map(|mut b| {
                 b.push(b',');
                 b
-            }
+            }),
+            _ => self.encoding.encode_frame(event),
         };
 
-        Some(body)
+        body.map_err(|error| {
+            warn!(
+                message = "Unable to encode event; Dropping event.",
+                %error,
+                rate_limit_secs = 30,
+            )
+        })
+        .ok()
     }
 
     fn build_request(&self, mut body: Self::Output) -> http::Request<Vec<u8>> {
@@ -201,16 +188,12 @@ impl HttpSink for HttpSinkConfig {
         let uri: Uri = self.uri.clone().into();
         builder.uri(uri);
 
-        match self.encoding.codec() {
-            Encoding::Text => builder.header("Content-Type", "text/plain"),
-            Encoding::Ndjson => builder.header("Content-Type", "application/x-ndjson"),
-            Encoding::Json => {
-                body.insert(0, b'[');
-                body.pop(); // remove trailing comma from last record
-                body.push(b']');
-                builder.header("Content-Type", "application/json")
-            }
-        };
+        if let StandardEncodings::Json = self.encoding.codec() {
+            body.insert(0, b'[');
+            body.pop(); // remove trailing comma from last record
+            body.push(b']');
+        }
+        builder.header("Content-Type", self.encoding.codec().content_type());
 
         if let Some(Compression::Gzip) = &self.compression {
             builder.header("Content-Encoding", "gzip");
@@ -317,10 +300,10 @@ mod tests {
 
     #[test]
     fn http_encode_event_text() {
-        let encoding = EncodingConfig::from(Encoding::Text);
+        let encoding = EncodingConfig::from(StandardEncodings::Text);
         let event = Event::from("hello world");
 
-        let mut config = default_config(Encoding::Text);
+        let mut config = default_config(StandardEncodings::Text);
         config.encoding = encoding.clone();
         let bytes = config.encode_event(event).unwrap();
 
@@ -329,10 +312,10 @@ mod tests {
 
     #[test]
     fn http_encode_event_json() {
-        let encoding = EncodingConfig::from(Encoding::Ndjson);
+        let encoding = EncodingConfig::from(StandardEncodings::Ndjson);
         let event = Event::from("hello world");
 
-        let mut config = default_config(Encoding::Json);
+        let mut config = default_config(StandardEncodings::Json);
         config.encoding = encoding.clone();
         let bytes = config.encode_event(event).unwrap();
 
@@ -167,6 +167,10 @@ impl SinkConfig for InfluxDBConfig {
     fn sink_type(&self) -> &'static str {
         "influxdb_metrics"
     }
+
+    fn supports_dead_letter(&self) -> bool {
+        true
+    }
 }
 
 impl InfluxDBSvc {
@@ -204,7 +208,7 @@ impl InfluxDBSvc {
                 influxdb_http_service,
                 MetricBuffer::new(),
                 batch,
-                cx.acker(),
+                &cx,
             )
             .sink_map_err(|e| error!("Fatal influxdb sink error: {}", e));
 
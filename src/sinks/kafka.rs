@@ -1,9 +1,11 @@
 use crate::{
     buffers::Acker,
-    event::{self, Event},
+    event::{finalization::EventStatus, Event, Value},
     kafka::{KafkaCompression, KafkaTlsConfig},
     serde::to_string,
-    sinks::util::encoding::{EncodingConfig, EncodingConfigWithDefault, EncodingConfiguration},
+    sinks::util::encoding::{
+        EncodingConfig, EncodingConfigWithDefault, EncodingConfiguration, StandardEncodings,
+    },
     template::Template,
     topology::config::{DataType, SinkConfig, SinkContext, SinkDescription},
 };
@@ -13,11 +15,15 @@ use futures01::{
 };
 use rdkafka::{
     consumer::{BaseConsumer, Consumer},
+    error::KafkaError,
+    message::OwnedHeaders,
     producer::{DeliveryFuture, FutureProducer, FutureRecord},
+    types::RDKafkaError,
 };
 use serde::{Deserialize, Serialize};
 use snafu::{ResultExt, Snafu};
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::time::Duration;
 use string_cache::DefaultAtom as Atom;
 
@@ -32,9 +38,13 @@ enum BuildError {
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct KafkaSinkConfig {
     bootstrap_servers: String,
-    topic: String,
+    topic: Template,
     key_field: Option<Atom>,
-    encoding: EncodingConfigWithDefault<Encoding>,
+    #[serde(default)]
+    header_fields: Vec<Atom>,
+    #[serde(default)]
+    partitioner: KafkaPartitioner,
+    encoding: EncodingConfigWithDefault<StandardEncodings>,
     compression: Option<KafkaCompression>,
     tls: Option<KafkaTlsConfig>,
     #[serde(default = "default_socket_timeout_ms")]
@@ -44,6 +54,21 @@ pub struct KafkaSinkConfig {
     librdkafka_options: Option<HashMap<String, String>>,
 }
 
+#[derive(Clone, Debug, Derivative, Deserialize, Serialize, PartialEq)]
+#[derivative(Default)]
+#[serde(deny_unknown_fields, rename_all = "snake_case", tag = "strategy")]
+pub enum KafkaPartitioner {
+    /// Left to librdkafka, which hashes the key or picks a random partition
+    /// for messages without one.
+    #[derivative(Default)]
+    KeyHash,
+    Random,
+    /// Falls back to the key hash for events without an integer in `field`.
+    Field {
+        field: Atom,
+    },
+}
+
 fn default_socket_timeout_ms() -> u64 {
     60000 // default in librdkafka
 }
@@ -52,26 +77,19 @@ fn default_message_timeout_ms() -> u64 {
     300000 // default in librdkafka
 }
 
-#[derive(Clone, Copy, Debug, Derivative, Deserialize, Serialize, Eq, PartialEq)]
-#[derivative(Default)]
-#[serde(rename_all = "snake_case")]
-pub enum Encoding {
-    #[derivative(Default)]
-    Text,
-    Json,
-}
-
 pub struct KafkaSink {
     producer: FutureProducer,
     topic: Template,
     key_field: Option<Atom>,
-    encoding: EncodingConfig<Encoding>,
+    header_fields: Vec<Atom>,
+    partitioner: KafkaPartitioner,
+    encoding: EncodingConfig<StandardEncodings>,
     in_flight: FuturesUnordered<MetadataFuture<Compat<DeliveryFuture>, usize>>,
 
     acker: Acker,
     seq_head: usize,
     seq_tail: usize,
-    pending_acks: HashSet<usize>,
+    pending_acks: HashMap<usize, EventStatus>,
 }
 
 inventory::submit! {
@@ -108,6 +126,9 @@ impl KafkaSinkConfig {
         );
         client_config.set("socket.timeout.ms", &self.socket_timeout_ms.to_string());
         client_config.set("message.timeout.ms", &self.message_timeout_ms.to_string());
+        if self.partitioner == KafkaPartitioner::Random {
+            client_config.set("partitioner", "random");
+        }
         if let Some(ref librdkafka_options) = self.librdkafka_options {
             for (key, value) in librdkafka_options.iter() {
                 client_config.set(key.as_str(), value.as_str());
@@ -122,14 +143,16 @@ impl KafkaSink {
         let producer = config.to_rdkafka()?.create().context(KafkaCreateFailed)?;
         Ok(KafkaSink {
             producer,
-            topic: Template::from(config.topic),
+            topic: config.topic,
             key_field: config.key_field,
+            header_fields: config.header_fields,
+            partitioner: config.partitioner,
             encoding: config.encoding.into(),
             in_flight: FuturesUnordered::new(),
             acker,
             seq_head: 0,
             seq_tail: 0,
-            pending_acks: HashSet::new(),
+            pending_acks: HashMap::new(),
         })
     }
 }
@@ -139,35 +162,70 @@ impl Sink for KafkaSink {
     type SinkError = ();
 
     fn start_send(&mut self, item: Self::SinkItem) -> StartSend<Self::SinkItem, Self::SinkError> {
-        let topic = self.topic.render_string(&item).map_err(|missing_keys| {
-            error!(message = "Missing keys for topic", ?missing_keys);
-            ()
-        })?;
+        let seqno = self.seq_head;
 
-        let (key, body) = encode_event(item.clone(), &self.key_field, &self.encoding);
+        let topic = match self.topic.render_string(&item) {
+            Ok(topic) => topic,
+            Err(missing_keys) => {
+                warn!(
+                    message = "Missing keys for topic, dropping event.",
+                    ?missing_keys,
+                    rate_limit_secs = 30
+                );
+                self.seq_head += 1;
+                self.ack(seqno, EventStatus::Failed);
+                return Ok(AsyncSink::Ready);
+            }
+        };
+
+        let (key, body) = match encode_event(item.clone(), &self.key_field, &self.encoding) {
+            Ok(encoded) => encoded,
+            Err(error) => {
+                error!(
+                    message = "Unable to encode event, dropping it.",
+                    %error,
+                    rate_limit_secs = 30
+                );
+                self.seq_head += 1;
+                self.ack(seqno, EventStatus::Failed);
+                return Ok(AsyncSink::Ready);
+            }
+        };
+        let headers = encode_headers(&item, &self.header_fields);
 
-        let record = FutureRecord::to(&topic).key(&key).payload(&body[..]);
+        let mut record = FutureRecord::to(&topic).key(&key).payload(&body[..]);
+        if let Some(headers) = headers {
+            record = record.headers(headers);
+        }
+        if let KafkaPartitioner::Field { field } = &self.partitioner {
+            if let Some(partition) = partition(&item, field) {
+                record = record.partition(partition);
+            }
+        }
 
         debug!(message = "sending event.", count = 1);
         let future = match self.producer.send_result(record) {
             Ok(f) => f,
-            Err((e, record)) => {
-                // Docs suggest this will only happen when the producer queue is full, so let's
-                // treat it as we do full buffers in other sinks
-                debug!("rdkafka queue full: {}", e);
+            Err((KafkaError::MessageProduction(RDKafkaError::QueueFull), record)) => {
+                // A full producer queue is treated as we do full buffers in
+                // other sinks.
+                debug!("rdkafka queue full.");
                 self.poll_complete()?;
 
                 match self.producer.send_result(record) {
                     Ok(f) => f,
-                    Err((e, _record)) => {
-                        debug!("rdkafka queue still full: {}", e);
+                    Err((KafkaError::MessageProduction(RDKafkaError::QueueFull), _record)) => {
+                        debug!("rdkafka queue still full.");
                         return Ok(AsyncSink::NotReady(item));
                     }
+                    Err((error, _record)) => return Ok(self.reject(seqno, error)),
                 }
             }
+            // Sending it again wouldn't help, for example with a message too
+            // large or an unknown partition.
+            Err((error, _record)) => return Ok(self.reject(seqno, error)),
         };
 
-        let seqno = self.seq_head;
         self.seq_head += 1;
 
         self.in_flight
@@ -186,23 +244,22 @@ impl Sink for KafkaSink {
 
                 // request finished, check for success
                 Ok(Async::Ready(Some((result, seqno)))) => {
-                    match result {
-                        Ok((partition, offset)) => trace!(
-                            "produced message to partition {} at offset {}",
-                            partition,
-                            offset
-                        ),
-                        Err((e, _msg)) => error!("kafka error: {}", e),
+                    let status = match result {
+                        Ok((partition, offset)) => {
+                            trace!(
+                                "produced message to partition {} at offset {}",
+                                partition,
+                                offset
+                            );
+                            EventStatus::Delivered
+                        }
+                        Err((e, _msg)) => {
+                            error!("kafka error: {}", e);
+                            EventStatus::Errored
+                        }
                     };
 
-                    self.pending_acks.insert(seqno);
-
-                    let mut num_to_ack = 0;
-                    while self.pending_acks.remove(&self.seq_tail) {
-                        num_to_ack += 1;
-                        self.seq_tail += 1
-                    }
-                    self.acker.ack(num_to_ack);
+                    self.ack(seqno, status);
                 }
 
                 // request got canceled (according to docs)
@@ -212,9 +269,34 @@ impl Sink for KafkaSink {
     }
 }
 
+impl KafkaSink {
+    /// Acknowledges the events up to the oldest one still in flight.
+    fn ack(&mut self, seqno: usize, status: EventStatus) {
+        self.pending_acks.insert(seqno, status);
+
+        while let Some(status) = self.pending_acks.remove(&self.seq_tail) {
+            self.acker.ack_with(1, status);
+            self.seq_tail += 1
+        }
+    }
+
+    /// Drops an event the producer refused for another reason than its queue
+    /// being full.
+    fn reject(&mut self, seqno: usize, error: KafkaError) -> AsyncSink<Event> {
+        error!(
+            message = "Failed to send event to kafka, dropping it.",
+            %error,
+            rate_limit_secs = 30
+        );
+        self.seq_head += 1;
+        self.ack(seqno, EventStatus::Failed);
+        AsyncSink::Ready
+    }
+}
+
 fn healthcheck(config: KafkaSinkConfig) -> super::Healthcheck {
     let client = config.to_rdkafka().unwrap();
-    let topic = match Template::from(config.topic).render_string(&Event::from("")) {
+    let topic = match config.topic.render_string(&Event::from("")) {
         Ok(topic) => Some(topic),
         Err(missing_keys) => {
             warn!(
@@ -241,33 +323,58 @@ fn healthcheck(config: KafkaSinkConfig) -> super::Healthcheck {
 }
 
 fn encode_event(
-    mut event: Event,
+    event: Event,
     key_field: &Option<Atom>,
-    encoding: &EncodingConfig<Encoding>,
-) -> (Vec<u8>, Vec<u8>) {
-    encoding.apply_rules(&mut event);
+    encoding: &EncodingConfig<StandardEncodings>,
+) -> crate::Result<(Vec<u8>, Vec<u8>)> {
+    // The key is taken before the encoding rules could remove its field.
     let key = key_field
         .as_ref()
         .and_then(|f| event.as_log().get(f))
         .map(|v| v.as_bytes().to_vec())
         .unwrap_or_default();
 
-    let body = match encoding.codec() {
-        Encoding::Json => serde_json::to_vec(&event.as_log()).unwrap(),
-        Encoding::Text => event
-            .as_log()
-            .get(&event::log_schema().message_key())
-            .map(|v| v.as_bytes().to_vec())
-            .unwrap_or_default(),
-    };
+    let body = encoding.encode(event)?;
+
+    Ok((key, body))
+}
+
+/// Fields missing from the event are left out of the headers.
+fn encode_headers(event: &Event, header_fields: &[Atom]) -> Option<OwnedHeaders> {
+    if header_fields.is_empty() {
+        return None;
+    }
 
-    (key, body)
+    let log = event.as_log();
+    let headers = header_fields
+        .iter()
+        .filter_map(|field| log.get(field).map(|value| (field, value.as_bytes())))
+        .fold(
+            OwnedHeaders::new_with_capacity(header_fields.len()),
+            |headers, (field, value)| headers.add(&field[..], &value[..]),
+        );
+    Some(headers)
+}
+
+/// Values that aren't a valid partition number are left to the key hash.
+fn partition(event: &Event, field: &Atom) -> Option<i32> {
+    let partition = match event.as_log().get(field)? {
+        Value::Integer(partition) => i32::try_from(*partition).ok()?,
+        Value::Bytes(bytes) => String::from_utf8_lossy(bytes).parse().ok()?,
+        _ => return None,
+    };
+    Some(partition).filter(|partition| *partition >= 0)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::event::{self, Event};
+    use crate::event::{
+        self,
+        finalization::{BatchNotifier, BatchStatus},
+        Event,
+    };
+    use rdkafka::message::Headers;
     use std::collections::BTreeMap;
 
     #[test]
@@ -277,8 +384,9 @@ mod tests {
         let (key_bytes, bytes) = encode_event(
             message.clone().into(),
             &None,
-            &EncodingConfig::from(Encoding::Text),
-        );
+            &EncodingConfig::from(StandardEncodings::Text),
+        )
+        .unwrap();
 
         assert_eq!(&key_bytes[..], key.as_bytes());
         assert_eq!(&bytes[..], message.as_bytes());
@@ -294,8 +402,9 @@ mod tests {
         let (key, bytes) = encode_event(
             event,
             &Some("key".into()),
-            &EncodingConfig::from(Encoding::Json),
-        );
+            &EncodingConfig::from(StandardEncodings::Json),
+        )
+        .unwrap();
 
         let map: BTreeMap<String, String> = serde_json::from_slice(&bytes[..]).unwrap();
 
@@ -304,6 +413,92 @@ mod tests {
         assert_eq!(map["key"], "value".to_string());
         assert_eq!(map["foo"], "bar".to_string());
     }
+
+    #[test]
+    fn kafka_encode_headers() {
+        let mut event = Event::from("hello world");
+        event.as_mut_log().insert("service", "api");
+        event.as_mut_log().insert("attempt", 2);
+
+        let headers = encode_headers(
+            &event,
+            &["service".into(), "attempt".into(), "missing".into()],
+        )
+        .unwrap();
+
+        assert_eq!(headers.count(), 2);
+        assert_eq!(headers.get(0), Some(("service", &b"api"[..])));
+        assert_eq!(headers.get(1), Some(("attempt", &b"2"[..])));
+        assert!(encode_headers(&event, &[]).is_none());
+    }
+
+    #[test]
+    fn kafka_partition_from_field() {
+        let mut event = Event::from("hello world");
+        event.as_mut_log().insert("integer", 3);
+        event.as_mut_log().insert("string", "4");
+        event.as_mut_log().insert("invalid", "four");
+        event.as_mut_log().insert("negative", -1);
+        event.as_mut_log().insert("negative_string", "-1");
+        event
+            .as_mut_log()
+            .insert("too_large", i64::from(i32::MAX) + 1);
+        event.as_mut_log().insert("too_large_string", "2147483648");
+        event.as_mut_log().insert("largest", i64::from(i32::MAX));
+
+        assert_eq!(partition(&event, &"integer".into()), Some(3));
+        assert_eq!(partition(&event, &"string".into()), Some(4));
+        assert_eq!(partition(&event, &"invalid".into()), None);
+        assert_eq!(partition(&event, &"missing".into()), None);
+        assert_eq!(partition(&event, &"negative".into()), None);
+        assert_eq!(partition(&event, &"negative_string".into()), None);
+        assert_eq!(partition(&event, &"too_large".into()), None);
+        assert_eq!(partition(&event, &"too_large_string".into()), None);
+        assert_eq!(partition(&event, &"largest".into()), Some(i32::MAX));
+    }
+
+    #[test]
+    fn kafka_partitioner_config() {
+        let config: KafkaSinkConfig = toml::from_str(
+            r#"
+            bootstrap_servers = "localhost:9092"
+            topic = "logs-{{ service }}"
+            encoding = "text"
+            partitioner = { strategy = "field", field = "partition" }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.partitioner,
+            KafkaPartitioner::Field {
+                field: "partition".into()
+            }
+        );
+        assert!(config.topic.is_dynamic());
+    }
+
+    #[test]
+    fn kafka_fails_events_without_topic() {
+        let config: KafkaSinkConfig = toml::from_str(
+            r#"
+            bootstrap_servers = "localhost:9092"
+            topic = "logs-{{ service }}"
+            encoding = "text"
+            "#,
+        )
+        .unwrap();
+        let (acker, _) = Acker::new_for_testing();
+        let (acker, pending) = acker.finalizing(None, None);
+        let mut sink = KafkaSink::new(config, acker).unwrap();
+
+        let (batch, receiver) = BatchNotifier::new_with_receiver();
+        let mut event = Event::from("hello world");
+        event.as_mut_log().add_batch_notifier(batch);
+        pending.push(&mut event);
+
+        assert!(sink.start_send(event).unwrap().is_ready());
+        assert_eq!(receiver.wait(), Ok(BatchStatus::Failed));
+    }
 }
 
 #[cfg(feature = "kafka-integration-tests")]
@@ -334,9 +529,9 @@ mod integration_test {
 
         let config = KafkaSinkConfig {
             bootstrap_servers: "localhost:9092".into(),
-            topic: topic.clone(),
+            topic: topic.clone().into(),
             compression: None,
-            encoding: EncodingConfigWithDefault::from(Encoding::Text),
+            encoding: EncodingConfigWithDefault::from(StandardEncodings::Text),
             key_field: None,
             tls: None,
             socket_timeout_ms: 60000,
@@ -398,9 +593,9 @@ mod integration_test {
         let tls_enabled = tls.as_ref().map(|tls| tls.enabled()).unwrap_or(false);
         let config = KafkaSinkConfig {
             bootstrap_servers: server.to_string(),
-            topic: format!("{}-%Y%m%d", topic),
+            topic: format!("{}-%Y%m%d", topic).into(),
             compression,
-            encoding: EncodingConfigWithDefault::from(Encoding::Text),
+            encoding: EncodingConfigWithDefault::from(StandardEncodings::Text),
             key_field: None,
             tls,
             socket_timeout_ms: 60000,
@@ -1,10 +1,9 @@
 use crate::{
     event::log_schema,
     sinks::util::{
-        encoding::{EncodingConfig, EncodingConfiguration},
+        encoding::{EncodingConfig, EncodingConfiguration, StandardEncodings},
         tcp::{tcp_healthcheck, TcpSink},
         uri::UriSerde,
-        Encoding,
     },
     tls::{MaybeTlsSettings, TlsSettings},
     topology::config::{DataType, SinkConfig, SinkContext, SinkDescription},
@@ -18,7 +17,7 @@ use syslog::{Facility, Formatter3164, LogFormat, Severity};
 #[serde(deny_unknown_fields)]
 pub struct PapertrailConfig {
     endpoint: UriSerde,
-    encoding: EncodingConfig<Encoding>,
+    encoding: EncodingConfig<StandardEncodings>,
 }
 
 inventory::submit! {
@@ -67,7 +66,7 @@ impl SinkConfig for PapertrailConfig {
 fn encode_event(
     mut event: crate::Event,
     pid: u32,
-    encoding: &EncodingConfig<Encoding>,
+    encoding: &EncodingConfig<StandardEncodings>,
 ) -> Option<Bytes> {
     encoding.apply_rules(&mut event);
 
@@ -86,15 +85,11 @@ fn encode_event(
 
     let mut s: Vec<u8> = Vec::new();
 
-    let log = event.into_log();
-
-    let message = match encoding.codec() {
-        Encoding::Json => serde_json::to_string(&log).unwrap(),
-        Encoding::Text => log
-            .get(&log_schema().message_key())
-            .map(|v| v.to_string_lossy())
-            .unwrap_or_default(),
-    };
+    let message = encoding
+        .encode(event)
+        .map_err(|error| error!(message = "Unable to encode.", %error))
+        .ok()?;
+    let message = String::from_utf8_lossy(&message);
 
     formatter
         .format(&mut s, Severity::LOG_INFO, message)
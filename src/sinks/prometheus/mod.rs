@@ -21,6 +21,8 @@ use std::{
 use stream_cancel::{Trigger, Tripwire};
 use tracing::field;
 
+pub mod remote_write;
+
 const MIN_FLUSH_PERIOD_SECS: u64 = 1;
 
 #[derive(Debug, Snafu)]
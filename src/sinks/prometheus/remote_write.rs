@@ -0,0 +1,462 @@
+use super::encode_namespace;
+use crate::{
+    event::metric::{Metric, MetricValue},
+    prometheus::{
+        proto::{Label, Sample, TimeSeries, WriteRequest},
+        METRIC_NAME_LABEL,
+    },
+    sinks::{
+        util::{
+            http::{Auth, BatchedHttpSink, HttpSink},
+            BatchEventsConfig, TowerRequestConfig, UriSerde,
+        },
+        Healthcheck, RouterSink,
+    },
+    template::Template,
+    tls::{TlsOptions, TlsSettings},
+    topology::config::{DataType, SinkConfig, SinkContext, SinkDescription},
+    Event,
+};
+use chrono::Utc;
+use futures01::{future, Sink};
+use indexmap::IndexMap;
+use prost::Message;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct RemoteWriteConfig {
+    pub endpoint: UriSerde,
+    #[serde(default)]
+    pub namespace: String,
+    /// Labels added to every time series, they take precedence over tags.
+    #[serde(default)]
+    pub labels: IndexMap<String, Template>,
+    /// Sent as the `X-Scope-OrgID` header, which Cortex and Thanos use to
+    /// tell tenants apart.
+    pub tenant_id: Option<String>,
+    pub auth: Option<Auth>,
+    #[serde(default)]
+    pub batch: BatchEventsConfig,
+    #[serde(default)]
+    pub request: TowerRequestConfig,
+    pub tls: Option<TlsOptions>,
+}
+
+inventory::submit! {
+    SinkDescription::new_without_default::<RemoteWriteConfig>("prometheus_remote_write")
+}
+
+#[typetag::serde(name = "prometheus_remote_write")]
+impl SinkConfig for RemoteWriteConfig {
+    fn build(&self, cx: SinkContext) -> crate::Result<(RouterSink, Healthcheck)> {
+        let batch = self.batch.unwrap_or(1000, 1);
+        let request = self.request.unwrap_with(&TowerRequestConfig::default());
+        let tls = TlsSettings::from_options(&self.tls)?;
+
+        let sink = RemoteWriteSink {
+            config: self.clone(),
+            counters: Mutex::new(Counters::new()),
+        };
+        let sink = BatchedHttpSink::new(sink, Vec::new(), request, batch, Some(tls), &cx)
+            .sink_map_err(|e| error!("Fatal prometheus remote write sink error: {}", e));
+
+        // The protocol has no endpoint to check, errors show up with the
+        // first request.
+        let healthcheck = Box::new(future::ok(()));
+
+        Ok((Box::new(sink), healthcheck))
+    }
+
+    fn input_type(&self) -> DataType {
+        DataType::Metric
+    }
+
+    fn sink_type(&self) -> &'static str {
+        "prometheus_remote_write"
+    }
+
+    fn supports_dead_letter(&self) -> bool {
+        true
+    }
+}
+
+type SeriesKey = (String, Option<BTreeMap<String, String>>);
+
+/// How long a series received as increments is remembered without updates.
+const SERIES_IDLE_TIMEOUT: Duration = Duration::from_secs(60 * 60);
+
+/// The totals of counters and gauges received as increments, as remote
+/// storage expects absolute values.
+struct Counters {
+    totals: HashMap<SeriesKey, (f64, Instant)>,
+    last_expired: Instant,
+}
+
+impl Counters {
+    fn new() -> Self {
+        Self {
+            totals: HashMap::new(),
+            last_expired: Instant::now(),
+        }
+    }
+
+    /// Adds `value` to the total of the series and returns the new total.
+    fn add(&mut self, key: SeriesKey, value: f64, now: Instant) -> f64 {
+        self.expire(now);
+        let (total, last_seen) = self.totals.entry(key).or_insert((0.0, now));
+        *total += value;
+        *last_seen = now;
+        *total
+    }
+
+    /// Forgets the series that haven't been updated for a while, at most
+    /// once per timeout so it doesn't scan the map on every event.
+    fn expire(&mut self, now: Instant) {
+        if now.duration_since(self.last_expired) < SERIES_IDLE_TIMEOUT {
+            return;
+        }
+        self.totals
+            .retain(|_, (_, last_seen)| now.duration_since(*last_seen) < SERIES_IDLE_TIMEOUT);
+        self.last_expired = now;
+    }
+}
+
+struct RemoteWriteSink {
+    config: RemoteWriteConfig,
+    counters: Mutex<Counters>,
+}
+
+impl RemoteWriteSink {
+    /// Returns the absolute value of a counter or a gauge. Only series
+    /// received as increments are tracked, absolute values pass through.
+    fn absolute_value(&self, metric: &Metric, value: f64) -> f64 {
+        if metric.kind.is_absolute() {
+            return value;
+        }
+        let key = (metric.name.clone(), metric.tags.clone());
+        self.counters
+            .lock()
+            .unwrap()
+            .add(key, value, Instant::now())
+    }
+
+    fn labels(&self, event: &Event) -> Vec<(String, String)> {
+        let mut labels = event.as_metric().tags.clone().unwrap_or_default();
+        for (name, template) in &self.config.labels {
+            match template.render_string(event) {
+                Ok(value) => {
+                    labels.insert(name.clone(), value);
+                }
+                Err(missing_keys) => {
+                    warn!(
+                        message = "Failed to render label, leaving it out.",
+                        label = %name,
+                        ?missing_keys,
+                        rate_limit_secs = 30,
+                    );
+                }
+            }
+        }
+        labels.into_iter().collect()
+    }
+}
+
+impl HttpSink for RemoteWriteSink {
+    type Input = Vec<TimeSeries>;
+    type Output = Vec<Vec<TimeSeries>>;
+
+    fn encode_event(&self, event: Event) -> Option<Self::Input> {
+        let labels = self.labels(&event);
+        let metric = event.into_metric();
+        let name = encode_namespace(&self.config.namespace, &metric.name);
+        let timestamp = metric.timestamp.unwrap_or_else(Utc::now).timestamp_millis();
+
+        let series = |suffix: &str, extra: Option<(&str, String)>, value: f64| {
+            let mut labels = labels.clone();
+            labels.extend(extra.map(|(name, value)| (name.to_string(), value)));
+            labels.push((METRIC_NAME_LABEL.to_string(), format!("{}{}", name, suffix)));
+            labels.sort();
+            TimeSeries {
+                labels: labels
+                    .into_iter()
+                    .map(|(name, value)| Label { name, value })
+                    .collect(),
+                samples: vec![Sample { value, timestamp }],
+            }
+        };
+
+        match &metric.value {
+            MetricValue::Counter { value } | MetricValue::Gauge { value } => {
+                Some(vec![series("", None, self.absolute_value(&metric, *value))])
+            }
+            MetricValue::AggregatedHistogram {
+                buckets,
+                counts,
+                count,
+                sum,
+            } if metric.kind.is_absolute() => {
+                let mut result = buckets
+                    .iter()
+                    .zip(counts.iter())
+                    .map(|(bucket, count)| {
+                        series("_bucket", Some(("le", bucket.to_string())), *count as f64)
+                    })
+                    .collect::<Vec<_>>();
+                result.push(series(
+                    "_bucket",
+                    Some(("le", "+Inf".to_string())),
+                    *count as f64,
+                ));
+                result.push(series("_sum", None, *sum));
+                result.push(series("_count", None, *count as f64));
+                Some(result)
+            }
+            MetricValue::AggregatedSummary {
+                quantiles,
+                values,
+                count,
+                sum,
+            } if metric.kind.is_absolute() => {
+                let mut result = quantiles
+                    .iter()
+                    .zip(values.iter())
+                    .map(|(quantile, value)| {
+                        series("", Some(("quantile", quantile.to_string())), *value)
+                    })
+                    .collect::<Vec<_>>();
+                result.push(series("_sum", None, *sum));
+                result.push(series("_count", None, *count as f64));
+                Some(result)
+            }
+            _ => {
+                warn!(
+                    message = "Dropping metric, only counters, gauges, and absolute histograms and summaries can be written.",
+                    name = %metric.name,
+                    rate_limit_secs = 30,
+                );
+                None
+            }
+        }
+    }
+
+    fn build_request(&self, events: Self::Output) -> http::Request<Vec<u8>> {
+        let request = WriteRequest {
+            timeseries: events.into_iter().flatten().collect(),
+            metadata: Vec::new(),
+        };
+        let mut body = Vec::with_capacity(request.encoded_len());
+        request.encode(&mut body).expect("Vec has enough capacity");
+        let body = snap::raw::Encoder::new()
+            .compress_vec(&body)
+            .expect("snappy can compress any input");
+
+        let mut builder = http::Request::post(self.config.endpoint.to_string());
+        builder
+            .header("Content-Encoding", "snappy")
+            .header("Content-Type", "application/x-protobuf")
+            .header("X-Prometheus-Remote-Write-Version", "0.1.0");
+        if let Some(tenant_id) = &self.config.tenant_id {
+            builder.header("X-Scope-OrgID", tenant_id.as_str());
+        }
+
+        let mut request = builder.body(body).unwrap();
+        if let Some(auth) = &self.config.auth {
+            auth.apply(&mut request);
+        }
+        request
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        event::metric::MetricKind,
+        sinks::util::test::{build_test_server, load_sink},
+        test_util::next_addr,
+    };
+    use chrono::TimeZone;
+    use futures01::{stream, Stream};
+
+    fn sink(config: &str) -> RemoteWriteSink {
+        let (config, _cx, _rt) = load_sink::<RemoteWriteConfig>(config).unwrap();
+        RemoteWriteSink {
+            config,
+            counters: Mutex::new(Counters::new()),
+        }
+    }
+
+    fn counter(kind: MetricKind, value: f64) -> Event {
+        Event::Metric(Metric {
+            name: "requests".into(),
+            timestamp: Some(Utc.ymd(2020, 6, 1).and_hms(10, 0, 0)),
+            tags: Some(
+                vec![("code".to_owned(), "200".to_owned())]
+                    .into_iter()
+                    .collect(),
+            ),
+            kind,
+            value: MetricValue::Counter { value },
+        })
+    }
+
+    fn labels(series: &TimeSeries) -> Vec<(&str, &str)> {
+        series
+            .labels
+            .iter()
+            .map(|label| (label.name.as_str(), label.value.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn accumulates_incremental_counters() {
+        let sink = sink(r#"endpoint = "http://localhost:9090/api/v1/write""#);
+
+        let values = vec![
+            counter(MetricKind::Incremental, 1.0),
+            counter(MetricKind::Incremental, 2.0),
+            counter(MetricKind::Absolute, 10.0),
+            counter(MetricKind::Incremental, 1.5),
+        ]
+        .into_iter()
+        .map(|event| sink.encode_event(event).unwrap()[0].samples[0].value)
+        .collect::<Vec<_>>();
+
+        assert_eq!(values, vec![1.0, 3.0, 10.0, 4.5]);
+    }
+
+    #[test]
+    fn does_not_track_absolute_series() {
+        let sink = sink(r#"endpoint = "http://localhost:9090/api/v1/write""#);
+
+        sink.encode_event(counter(MetricKind::Absolute, 10.0))
+            .unwrap();
+
+        assert!(sink.counters.lock().unwrap().totals.is_empty());
+    }
+
+    #[test]
+    fn expires_idle_series() {
+        let start = Instant::now();
+        let key = |name: &str| (name.to_owned(), None);
+        let mut counters = Counters::new();
+        counters.last_expired = start;
+
+        assert_eq!(counters.add(key("idle"), 1.0, start), 1.0);
+        assert_eq!(counters.add(key("busy"), 1.0, start), 1.0);
+
+        let later = start + SERIES_IDLE_TIMEOUT / 2;
+        assert_eq!(counters.add(key("busy"), 1.0, later), 2.0);
+
+        let expired = start + SERIES_IDLE_TIMEOUT;
+        assert_eq!(counters.add(key("busy"), 1.0, expired), 3.0);
+        assert_eq!(counters.add(key("idle"), 1.0, expired), 1.0);
+    }
+
+    #[test]
+    fn renders_labels() {
+        let sink = sink(
+            r#"
+            endpoint = "http://localhost:9090/api/v1/write"
+            namespace = "vector"
+            labels = { code = "overridden", metric = "{{ name }}", status = "{{ tags.code }}", missing = "{{ foo }}" }
+            "#,
+        );
+
+        let series = sink
+            .encode_event(counter(MetricKind::Absolute, 1.0))
+            .unwrap();
+        assert_eq!(series.len(), 1);
+        assert_eq!(
+            labels(&series[0]),
+            vec![
+                ("__name__", "vector_requests"),
+                ("code", "overridden"),
+                ("metric", "requests"),
+                ("status", "200"),
+            ]
+        );
+        assert_eq!(series[0].samples[0].timestamp, 1_591_005_600_000);
+    }
+
+    #[test]
+    fn encodes_histograms() {
+        let sink = sink(r#"endpoint = "http://localhost:9090/api/v1/write""#);
+        let event = Event::Metric(Metric {
+            name: "latency".into(),
+            timestamp: None,
+            tags: None,
+            kind: MetricKind::Absolute,
+            value: MetricValue::AggregatedHistogram {
+                buckets: vec![0.5, 1.0],
+                counts: vec![2, 5],
+                count: 6,
+                sum: 4.5,
+            },
+        });
+
+        let series = sink.encode_event(event).unwrap();
+        let series = series
+            .iter()
+            .map(|series| (labels(series), series.samples[0].value))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            series,
+            vec![
+                (vec![("__name__", "latency_bucket"), ("le", "0.5")], 2.0),
+                (vec![("__name__", "latency_bucket"), ("le", "1")], 5.0),
+                (vec![("__name__", "latency_bucket"), ("le", "+Inf")], 6.0),
+                (vec![("__name__", "latency_sum")], 4.5),
+                (vec![("__name__", "latency_count")], 6.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn sends_write_requests() {
+        let in_addr = next_addr();
+        let (config, cx, mut rt) = load_sink::<RemoteWriteConfig>(&format!(
+            r#"
+            endpoint = "http://{}/api/v1/write"
+            tenant_id = "team-a"
+            auth = {{ strategy = "bearer", token = "secret" }}
+            "#,
+            in_addr
+        ))
+        .unwrap();
+
+        let (sink, _) = config.build(cx).unwrap();
+        let (rx, trigger, server) = build_test_server(&in_addr);
+        rt.spawn(server);
+
+        let events = vec![
+            counter(MetricKind::Incremental, 1.0),
+            counter(MetricKind::Incremental, 2.0),
+        ];
+        let _ = rt.block_on(sink.send_all(stream::iter_ok(events))).unwrap();
+        drop(trigger);
+
+        let requests = rx.wait().map(Result::unwrap).collect::<Vec<_>>();
+        assert_eq!(requests.len(), 1);
+        let (parts, body) = &requests[0];
+        assert_eq!(parts.uri.path(), "/api/v1/write");
+        assert_eq!(parts.headers["Content-Encoding"], "snappy");
+        assert_eq!(parts.headers["X-Scope-OrgID"], "team-a");
+        assert_eq!(parts.headers["Authorization"], "Bearer secret");
+
+        let body = snap::raw::Decoder::new().decompress_vec(&body).unwrap();
+        let request = WriteRequest::decode(&body[..]).unwrap();
+        let values = request
+            .timeseries
+            .iter()
+            .map(|series| series.samples[0].value)
+            .collect::<Vec<_>>();
+        assert_eq!(values, vec![1.0, 3.0]);
+    }
+}
@@ -1,7 +1,8 @@
 use crate::{
     buffers::Acker,
-    event::{self, Event},
+    event::Event,
     runtime::TaskExecutor,
+    sinks::util::encoding::{EncodingConfig, EncodingConfiguration, StandardEncodings},
     topology::config::{DataType, SinkConfig, SinkContext, SinkDescription},
 };
 use futures01::{
@@ -26,7 +27,7 @@ enum BuildError {
 pub struct PulsarSinkConfig {
     address: String,
     topic: String,
-    encoding: Encoding,
+    encoding: EncodingConfig<StandardEncodings>,
     auth: Option<AuthConfig>,
 }
 
@@ -36,16 +37,9 @@ pub struct AuthConfig {
     token: String, // <jwt token>
 }
 
-#[derive(Deserialize, Serialize, Debug, Eq, PartialEq, Clone, Copy)]
-#[serde(rename_all = "snake_case")]
-pub enum Encoding {
-    Text,
-    Json,
-}
-
 struct PulsarSink {
     topic: String,
-    encoding: Encoding,
+    encoding: EncodingConfig<StandardEncodings>,
     producer: Producer,
     pulsar: Pulsar,
     in_flight: FuturesUnordered<MetadataFuture<SendFuture, usize>>,
@@ -119,7 +113,9 @@ impl Sink for PulsarSink {
     type SinkError = ();
 
     fn start_send(&mut self, item: Self::SinkItem) -> StartSend<Self::SinkItem, Self::SinkError> {
-        let message = encode_event(item, self.encoding).map_err(|_| ())?;
+        let message = self.encoding.encode(item).map_err(|error| {
+            error!(message = "Unable to encode event", %error);
+        })?;
         let fut = self.producer.send(self.topic.clone(), &message[..]);
 
         let seqno = self.seq_head;
@@ -155,18 +151,6 @@ impl Sink for PulsarSink {
     }
 }
 
-fn encode_event(item: Event, enc: Encoding) -> crate::Result<Vec<u8>> {
-    let log = item.into_log();
-    let data = match enc {
-        Encoding::Json => serde_json::to_vec(&log)?,
-        Encoding::Text => log
-            .get(&event::log_schema().message_key())
-            .map(|v| v.as_bytes().to_vec())
-            .unwrap_or_default(),
-    };
-    Ok(data)
-}
-
 fn healthcheck(config: PulsarSinkConfig, pulsar: Pulsar) -> super::Healthcheck {
     Box::new(future::lazy(move || {
         pulsar
@@ -193,7 +177,9 @@ mod tests {
         let msg = "hello_world".to_owned();
         let mut evt = Event::from(msg.clone());
         evt.as_mut_log().insert("key", "value");
-        let result = encode_event(evt, Encoding::Json).unwrap();
+        let result = EncodingConfig::from(StandardEncodings::Json)
+            .encode(evt)
+            .unwrap();
         let map: HashMap<String, String> = serde_json::from_slice(&result[..]).unwrap();
         assert_eq!(msg, map[&event::log_schema().message_key().to_string()]);
     }
@@ -202,7 +188,9 @@ mod tests {
     fn pulsar_event_text() {
         let msg = "hello_world".to_owned();
         let evt = Event::from(msg.clone());
-        let event = encode_event(evt, Encoding::Text).unwrap();
+        let event = EncodingConfig::from(StandardEncodings::Text)
+            .encode(evt)
+            .unwrap();
 
         assert_eq!(&event[..], msg.as_bytes());
     }
@@ -225,7 +213,7 @@ mod integration_tests {
         let cnf = PulsarSinkConfig {
             address: "127.0.0.1:6650".to_owned(),
             topic: topic.clone(),
-            encoding: Encoding::Text,
+            encoding: StandardEncodings::Text.into(),
             auth: None,
         };
         let (acker, ack_counter) = Acker::new_for_testing();
@@ -1,7 +1,11 @@
 #[cfg(unix)]
 use crate::sinks::util::unix::UnixSinkConfig;
 use crate::{
-    sinks::util::{encoding::EncodingConfig, tcp::TcpSinkConfig, udp::UdpSinkConfig, Encoding},
+    sinks::util::{
+        encoding::{EncodingConfig, StandardEncodings},
+        tcp::TcpSinkConfig,
+        udp::UdpSinkConfig,
+    },
     tls::TlsConfig,
     topology::config::{DataType, SinkConfig, SinkContext, SinkDescription},
 };
@@ -31,7 +35,7 @@ inventory::submit! {
 impl SocketSinkConfig {
     pub fn make_tcp_config(
         address: String,
-        encoding: EncodingConfig<Encoding>,
+        encoding: EncodingConfig<StandardEncodings>,
         tls: Option<TlsConfig>,
     ) -> Self {
         TcpSinkConfig {
@@ -43,7 +47,7 @@ impl SocketSinkConfig {
     }
 
     pub fn make_basic_tcp_config(address: String) -> Self {
-        TcpSinkConfig::new(address, EncodingConfig::from(Encoding::Text)).into()
+        TcpSinkConfig::new(address, EncodingConfig::from(StandardEncodings::Text)).into()
     }
 }
 
@@ -108,7 +112,7 @@ mod test {
         let config = SocketSinkConfig {
             mode: Mode::Udp(UdpSinkConfig {
                 address: addr.to_string(),
-                encoding: Encoding::Json.into(),
+                encoding: StandardEncodings::Json.into(),
             }),
         };
         let mut rt = runtime();
@@ -138,7 +142,7 @@ mod test {
         let config = SocketSinkConfig {
             mode: Mode::Tcp(TcpSinkConfig {
                 address: addr.to_string(),
-                encoding: Encoding::Json.into(),
+                encoding: StandardEncodings::Json.into(),
                 tls: None,
             }),
         };
@@ -176,7 +180,7 @@ mod test {
         let config = SocketSinkConfig {
             mode: Mode::Tcp(TcpSinkConfig {
                 address: addr.to_string(),
-                encoding: Encoding::Text.into(),
+                encoding: StandardEncodings::Text.into(),
                 // TODO: enable TLS here since this is where
                 // ran into the issue before!
                 tls: None,
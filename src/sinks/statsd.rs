@@ -104,14 +104,17 @@ impl StatsdSvc {
     }
 }
 
+/// Encodes tags the DogStatsD way, `true` values being left out. Characters
+/// separating tags or fields are replaced, so that they can't break the line.
 fn encode_tags(tags: &BTreeMap<String, String>) -> String {
     let mut parts: Vec<_> = tags
         .iter()
         .map(|(name, value)| {
+            let name = sanitize_tag(name, &[':']);
             if value == "true" {
-                name.to_string()
+                name
             } else {
-                format!("{}:{}", name, value)
+                format!("{}:{}", name, sanitize_tag(value, &[]))
             }
         })
         .collect();
@@ -119,75 +122,76 @@ fn encode_tags(tags: &BTreeMap<String, String>) -> String {
     parts.join(",")
 }
 
-fn encode_event(event: Event, namespace: &str) -> Option<Vec<u8>> {
-    let mut buf = Vec::new();
+fn sanitize_tag(tag: &str, reserved: &[char]) -> String {
+    tag.replace(
+        |c| c == '|' || c == ',' || c == '#' || c == '\n' || reserved.contains(&c),
+        "_",
+    )
+}
 
+fn encode_event(event: Event, namespace: &str) -> Option<Vec<u8>> {
     let metric = event.as_metric();
-    match metric.kind {
-        MetricKind::Incremental => match &metric.value {
-            MetricValue::Counter { value } => {
-                buf.push(format!("{}:{}", metric.name, value));
-                buf.push("c".to_string());
-                if let Some(t) = &metric.tags {
-                    buf.push(format!("#{}", encode_tags(t)));
-                };
-            }
-            MetricValue::Gauge { value } => {
-                buf.push(format!("{}:{:+}", metric.name, value));
-                buf.push("g".to_string());
-                if let Some(t) = &metric.tags {
-                    buf.push(format!("#{}", encode_tags(t)));
-                };
-            }
+
+    // one line per value, made of pipe separated parts
+    let lines: Vec<Vec<String>> = match (&metric.kind, &metric.value) {
+        (MetricKind::Incremental, MetricValue::Counter { value }) => {
+            vec![vec![value.to_string(), "c".to_string()]]
+        }
+        (MetricKind::Incremental, MetricValue::Gauge { value }) => {
+            vec![vec![format!("{:+}", value), "g".to_string()]]
+        }
+        (
+            MetricKind::Incremental,
             MetricValue::Distribution {
                 values,
                 sample_rates,
-            } => {
-                for (val, sample_rate) in values.iter().zip(sample_rates.iter()) {
-                    buf.push(format!("{}:{}", metric.name, val));
-                    buf.push("h".to_string());
-                    if *sample_rate != 1 {
-                        buf.push(format!("@{}", 1.0 / f64::from(*sample_rate)));
-                    };
-                    if let Some(t) = &metric.tags {
-                        buf.push(format!("#{}", encode_tags(t)));
-                    };
-                }
-            }
-            MetricValue::Set { values } => {
-                for val in values {
-                    buf.push(format!("{}:{}", metric.name, val));
-                    buf.push("s".to_string());
-                    if let Some(t) = &metric.tags {
-                        buf.push(format!("#{}", encode_tags(t)));
-                    };
-                }
-            }
-            _ => {}
-        },
-        MetricKind::Absolute => {
-            match &metric.value {
-                MetricValue::Gauge { value } => {
-                    buf.push(format!("{}:{}", metric.name, value));
-                    buf.push("g".to_string());
-                    if let Some(t) = &metric.tags {
-                        buf.push(format!("#{}", encode_tags(t)));
-                    };
-                }
-                _ => {}
-            };
+            },
+        ) => values
+            .iter()
+            .zip(sample_rates.iter())
+            .map(|(val, sample_rate)| {
+                let mut parts = vec![val.to_string(), "h".to_string()];
+                if *sample_rate != 1 {
+                    parts.push(format!("@{}", 1.0 / f64::from(*sample_rate)));
+                };
+                parts
+            })
+            .collect(),
+        (MetricKind::Incremental, MetricValue::Set { values }) => values
+            .iter()
+            .map(|val| vec![val.clone(), "s".to_string()])
+            .collect(),
+        (MetricKind::Absolute, MetricValue::Gauge { value }) => {
+            vec![vec![value.to_string(), "g".to_string()]]
         }
-    }
-
-    let mut message: String = buf.join("|");
-    if !namespace.is_empty() {
-        message = format!("{}.{}", namespace, message);
+        _ => return None,
     };
 
-    let mut body: Vec<u8> = message.into_bytes();
-    body.push(b'\n');
+    let name = if namespace.is_empty() {
+        metric.name.clone()
+    } else {
+        format!("{}.{}", namespace, metric.name)
+    };
+    let tags = metric
+        .tags
+        .as_ref()
+        .map(|tags| format!("#{}", encode_tags(tags)));
+
+    let mut body = String::new();
+    for mut parts in lines {
+        parts[0] = format!("{}:{}", name, parts[0]);
+        if let Some(tags) = &tags {
+            parts.push(tags.clone());
+        }
+        body.push_str(&parts.join("|"));
+        body.push('\n');
+    }
 
-    Some(body)
+    if body.is_empty() {
+        None
+    } else {
+        Some(body.into_bytes())
+    }
 }
 
 impl Service<Vec<u8>> for StatsdSvc {
@@ -247,6 +251,17 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_encode_reserved_tags() {
+        let tags = vec![
+            ("url".to_owned(), "http://a.b/?c=d|e#f".to_owned()),
+            ("a:b,c".to_owned(), "true".to_owned()),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(&encode_tags(&tags), "a_b_c,url:http://a.b/?c=d_e_f");
+    }
+
     #[cfg(feature = "sources-statsd")]
     #[test]
     fn test_encode_counter() {
@@ -316,6 +331,26 @@ mod test {
         assert_eq!(metric1, metric2);
     }
 
+    #[test]
+    fn test_encode_distribution_values() {
+        let event = Event::Metric(Metric {
+            name: "distribution".to_owned(),
+            timestamp: None,
+            tags: Some(tags()),
+            kind: MetricKind::Incremental,
+            value: MetricValue::Distribution {
+                values: vec![1.5, 2.0],
+                sample_rates: vec![1, 10],
+            },
+        });
+        let frame = encode_event(event, "vector").unwrap();
+        assert_eq!(
+            String::from_utf8(frame).unwrap(),
+            "vector.distribution:1.5|h|#empty_tag:,normal_tag:value,true_tag\n\
+             vector.distribution:2|h|@0.1|#empty_tag:,normal_tag:value,true_tag\n"
+        );
+    }
+
     #[test]
     fn test_send_to_statsd() {
         crate::test_util::trace_init();
@@ -0,0 +1,555 @@
+#[cfg(unix)]
+use crate::sinks::util::unix::{unix_healthcheck, UnixSink};
+use crate::{
+    event::{self, Event, Value},
+    sinks::util::{
+        encoding::{EncodingConfigWithDefault, EncodingConfiguration, StandardEncodings},
+        tcp::{tcp_healthcheck, TcpSink},
+        udp::UdpSink,
+        SinkBuildError, StreamSink,
+    },
+    template::Template,
+    tls::{MaybeTlsSettings, TlsConfig},
+    topology::config::{DataType, SinkConfig, SinkContext, SinkDescription},
+};
+use bytes::Bytes;
+use chrono::{DateTime, Local, SecondsFormat, Utc};
+use futures01::{future, stream::iter_ok, Sink};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+#[cfg(unix)]
+use std::path::PathBuf;
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+// TODO: add back when serde-rs/serde#1358 is addressed
+// #[serde(deny_unknown_fields)]
+pub struct SyslogSinkConfig {
+    #[serde(flatten)]
+    pub mode: Mode,
+    #[serde(default)]
+    pub format: Format,
+    pub facility: Option<Template>,
+    pub severity: Option<Template>,
+    pub hostname: Option<Template>,
+    pub app_name: Option<Template>,
+    pub proc_id: Option<Template>,
+    pub msg_id: Option<Template>,
+    /// Parameters of the structured data elements, by element id.
+    #[serde(default)]
+    pub structured_data: IndexMap<String, IndexMap<String, Template>>,
+    #[serde(
+        default,
+        skip_serializing_if = "crate::serde::skip_serializing_if_default"
+    )]
+    pub encoding: EncodingConfigWithDefault<StandardEncodings>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum Mode {
+    Tcp {
+        address: String,
+        tls: Option<TlsConfig>,
+        #[serde(default)]
+        framing: Framing,
+    },
+    Udp {
+        address: String,
+    },
+    #[cfg(unix)]
+    Unix {
+        path: PathBuf,
+        #[serde(default)]
+        framing: Framing,
+    },
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Derivative)]
+#[serde(rename_all = "lowercase")]
+#[derivative(Default)]
+pub enum Format {
+    #[derivative(Default)]
+    Rfc5424,
+    Rfc3164,
+}
+
+/// How messages are told apart in a stream, see RFC 6587. Each datagram holds
+/// a single message.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Derivative)]
+#[serde(rename_all = "snake_case")]
+#[derivative(Default)]
+pub enum Framing {
+    #[derivative(Default)]
+    NewlineDelimited,
+    OctetCounting,
+}
+
+inventory::submit! {
+    SinkDescription::new_without_default::<SyslogSinkConfig>("syslog")
+}
+
+#[typetag::serde(name = "syslog")]
+impl SinkConfig for SyslogSinkConfig {
+    fn build(&self, cx: SinkContext) -> crate::Result<(super::RouterSink, super::Healthcheck)> {
+        let formatter = Formatter::new(self);
+
+        let (sink, healthcheck, framing): (super::RouterSink, super::Healthcheck, _) =
+            match &self.mode {
+                Mode::Tcp {
+                    address,
+                    tls,
+                    framing,
+                } => {
+                    let (host, port) = parse_address(address)?;
+                    let tls = MaybeTlsSettings::from_config(tls, false)?;
+                    let sink = TcpSink::new(host.clone(), port, cx.resolver(), tls);
+                    let healthcheck = tcp_healthcheck(host, port, cx.resolver());
+                    (
+                        Box::new(StreamSink::new(sink, cx.acker())),
+                        healthcheck,
+                        Some(*framing),
+                    )
+                }
+                Mode::Udp { address } => {
+                    let (host, port) = parse_address(address)?;
+                    let sink = UdpSink::new(host, port, cx.resolver())?;
+                    (
+                        Box::new(StreamSink::new(sink, cx.acker())),
+                        Box::new(future::ok(())),
+                        None,
+                    )
+                }
+                #[cfg(unix)]
+                Mode::Unix { path, framing } => {
+                    let sink = UnixSink::new(path.clone());
+                    (
+                        Box::new(StreamSink::new(sink, cx.acker())),
+                        unix_healthcheck(path.clone()),
+                        Some(*framing),
+                    )
+                }
+            };
+
+        let sink = sink.with_flat_map(move |event| iter_ok(formatter.frame(event, framing)));
+
+        Ok((Box::new(sink), healthcheck))
+    }
+
+    fn input_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn sink_type(&self) -> &'static str {
+        "syslog"
+    }
+}
+
+fn parse_address(address: &str) -> crate::Result<(String, u16)> {
+    let uri = address.parse::<http::Uri>()?;
+    let host = uri.host().ok_or(SinkBuildError::MissingHost)?.to_string();
+    let port = uri.port_u16().ok_or(SinkBuildError::MissingPort)?;
+    Ok((host, port))
+}
+
+struct Formatter {
+    format: Format,
+    facility: Option<Template>,
+    severity: Option<Template>,
+    hostname: Option<Template>,
+    app_name: Option<Template>,
+    proc_id: Option<Template>,
+    msg_id: Option<Template>,
+    structured_data: IndexMap<String, IndexMap<String, Template>>,
+    encoding: EncodingConfigWithDefault<StandardEncodings>,
+}
+
+impl Formatter {
+    fn new(config: &SyslogSinkConfig) -> Self {
+        Self {
+            format: config.format,
+            facility: config.facility.clone(),
+            severity: config.severity.clone(),
+            hostname: config.hostname.clone(),
+            app_name: config.app_name.clone(),
+            proc_id: config.proc_id.clone(),
+            msg_id: config.msg_id.clone(),
+            structured_data: config.structured_data.clone(),
+            encoding: config.encoding.clone(),
+        }
+    }
+
+    fn frame(&self, event: Event, framing: Option<Framing>) -> Option<Bytes> {
+        let mut message = self.format(event)?;
+        match framing {
+            Some(Framing::NewlineDelimited) => message.push(b'\n'),
+            Some(Framing::OctetCounting) => {
+                let mut frame = format!("{} ", message.len()).into_bytes();
+                frame.extend_from_slice(&message);
+                message = frame;
+            }
+            None => (),
+        }
+        Some(message.into())
+    }
+
+    fn format(&self, event: Event) -> Option<Vec<u8>> {
+        let facility = self
+            .render(&self.facility, &event)
+            .map(|facility| {
+                parse_code(&facility, &FACILITIES).unwrap_or_else(|| {
+                    warn!(
+                        message = "Unknown facility, using \"user\".",
+                        %facility,
+                        rate_limit_secs = 30,
+                    );
+                    USER
+                })
+            })
+            .unwrap_or(USER);
+        let severity = self
+            .render(&self.severity, &event)
+            .map(|severity| {
+                parse_code(&severity, &SEVERITIES).unwrap_or_else(|| {
+                    warn!(
+                        message = "Unknown severity, using \"info\".",
+                        %severity,
+                        rate_limit_secs = 30,
+                    );
+                    INFO
+                })
+            })
+            .unwrap_or(INFO);
+
+        let hostname = match &self.hostname {
+            Some(_) => self.render(&self.hostname, &event),
+            None => event
+                .as_log()
+                .get(&event::log_schema().host_key())
+                .map(Value::to_string_lossy),
+        };
+        let app_name = self
+            .render(&self.app_name, &event)
+            .unwrap_or_else(|| "vector".into());
+        let proc_id = self.render(&self.proc_id, &event);
+        let msg_id = self.render(&self.msg_id, &event);
+        let timestamp = match event.as_log().get(&event::log_schema().timestamp_key()) {
+            Some(Value::Timestamp(timestamp)) => *timestamp,
+            _ => Utc::now(),
+        };
+
+        let mut header = format!("<{}>", facility * 8 + severity);
+        match self.format {
+            Format::Rfc5424 => {
+                header.push_str(&format!(
+                    "1 {} {} {} {} {} {}",
+                    timestamp.to_rfc3339_opts(SecondsFormat::AutoSi, true),
+                    header_field(hostname, 255),
+                    header_field(Some(app_name), 48),
+                    header_field(proc_id, 128),
+                    header_field(msg_id, 32),
+                    self.structured_data(&event),
+                ));
+            }
+            Format::Rfc3164 => {
+                // Timestamps are in local time, without a year.
+                let timestamp = DateTime::<Local>::from(timestamp).format("%b %e %H:%M:%S");
+                let tag = match proc_id {
+                    Some(proc_id) => format!("{}[{}]", app_name, proc_id),
+                    None => app_name,
+                };
+                header.push_str(&format!(
+                    "{} {} {}:",
+                    timestamp,
+                    header_field(hostname, 255),
+                    tag
+                ));
+            }
+        }
+
+        let msg = self
+            .encoding
+            .encode(event)
+            .map_err(|error| {
+                warn!(
+                    message = "Unable to encode event; Dropping event.",
+                    %error,
+                    rate_limit_secs = 30,
+                )
+            })
+            .ok()?;
+
+        let mut message = header.into_bytes();
+        if !msg.is_empty() {
+            message.push(b' ');
+            message.extend_from_slice(&msg);
+        }
+        Some(message)
+    }
+
+    /// Renders an optional field, missing event fields leave it empty.
+    fn render(&self, template: &Option<Template>, event: &Event) -> Option<String> {
+        template
+            .as_ref()
+            .and_then(|template| template.render_string(event).ok())
+            .filter(|value| !value.is_empty())
+    }
+
+    fn structured_data(&self, event: &Event) -> String {
+        let mut data = String::new();
+        for (id, params) in &self.structured_data {
+            data.push('[');
+            data.push_str(&sd_name(id));
+            for (name, template) in params {
+                if let Ok(value) = template.render_string(event) {
+                    data.push_str(&format!(" {}=\"{}\"", sd_name(name), escape_param(&value)));
+                }
+            }
+            data.push(']');
+        }
+        if data.is_empty() {
+            data.push('-');
+        }
+        data
+    }
+}
+
+/// Header fields are made of up to `max` printable ASCII characters, `-`
+/// stands for an empty field.
+fn header_field(value: Option<String>, max: usize) -> String {
+    let value = value
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| c.is_ascii_graphic())
+        .take(max)
+        .collect::<String>();
+    if value.is_empty() {
+        "-".into()
+    } else {
+        value
+    }
+}
+
+/// SD names are header fields of up to 32 characters that can't contain `=`,
+/// `]` or `"`.
+fn sd_name(name: &str) -> String {
+    header_field(
+        Some(name.replace(|c| c == '=' || c == ']' || c == '"', "")),
+        32,
+    )
+}
+
+fn escape_param(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if c == '"' || c == '\\' || c == ']' {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+const USER: u8 = 1;
+const INFO: u8 = 6;
+
+const FACILITIES: [&[&str]; 24] = [
+    &["kern"],
+    &["user"],
+    &["mail"],
+    &["daemon"],
+    &["auth", "security"],
+    &["syslog"],
+    &["lpr"],
+    &["news"],
+    &["uucp"],
+    &["cron"],
+    &["authpriv"],
+    &["ftp"],
+    &["ntp"],
+    &["audit"],
+    &["alert"],
+    &["clockd"],
+    &["local0"],
+    &["local1"],
+    &["local2"],
+    &["local3"],
+    &["local4"],
+    &["local5"],
+    &["local6"],
+    &["local7"],
+];
+
+const SEVERITIES: [&[&str]; 8] = [
+    &["emerg", "emergency", "panic"],
+    &["alert"],
+    &["crit", "critical"],
+    &["err", "error"],
+    &["warning", "warn"],
+    &["notice"],
+    &["info", "informational"],
+    &["debug"],
+];
+
+/// Finds the code of a facility or severity, given either by name or by
+/// number.
+fn parse_code(value: &str, names: &[&[&str]]) -> Option<u8> {
+    let value = value.trim().to_lowercase();
+    match value.parse::<usize>() {
+        Ok(code) if code < names.len() => Some(code as u8),
+        Ok(_) => None,
+        Err(_) => names
+            .iter()
+            .position(|aliases| aliases.contains(&value.as_str()))
+            .map(|code| code as u8),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{next_addr, receive, runtime};
+    use chrono::TimeZone;
+
+    fn config(toml: &str) -> SyslogSinkConfig {
+        toml::from_str(toml).unwrap()
+    }
+
+    fn event() -> Event {
+        let mut event = Event::from("hello world");
+        let log = event.as_mut_log();
+        log.insert(
+            event::log_schema().timestamp_key().clone(),
+            Utc.ymd(2020, 6, 1).and_hms_milli(10, 0, 0, 250),
+        );
+        log.insert(event::log_schema().host_key().clone(), "web-1");
+        log.insert("level", "err");
+        log.insert("pid", 42);
+        log.insert("user", "a \"quoted\" ]name");
+        event
+    }
+
+    fn format(config: &SyslogSinkConfig, event: Event) -> String {
+        String::from_utf8(Formatter::new(config).format(event).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn formats_rfc5424() {
+        let config = config(
+            r#"
+            mode = "udp"
+            address = "127.0.0.1:514"
+            facility = "local3"
+            severity = "{{ level }}"
+            app_name = "app"
+            proc_id = "{{ pid }}"
+            msg_id = "ID{{ missing }}"
+            structured_data = { origin = { user = "{{ user }}", software = "vector" } }
+            "#,
+        );
+
+        assert_eq!(
+            format(&config, event()),
+            r#"<155>1 2020-06-01T10:00:00.250Z web-1 app 42 - [origin user="a \"quoted\" \]name" software="vector"] hello world"#
+        );
+    }
+
+    #[test]
+    fn formats_defaults() {
+        let config = config(
+            r#"
+            mode = "udp"
+            address = "127.0.0.1:514"
+            "#,
+        );
+
+        let mut event = Event::from("hi");
+        event.as_mut_log().insert(
+            event::log_schema().timestamp_key().clone(),
+            Utc.ymd(2020, 6, 1).and_hms(10, 0, 0),
+        );
+        assert_eq!(
+            format(&config, event),
+            "<14>1 2020-06-01T10:00:00Z - vector - - - hi"
+        );
+    }
+
+    #[test]
+    fn formats_rfc3164() {
+        let config = config(
+            r#"
+            mode = "udp"
+            address = "127.0.0.1:514"
+            format = "rfc3164"
+            severity = "{{ level }}"
+            proc_id = "{{ pid }}"
+            encoding = { codec = "json", only_fields = ["message"] }
+            "#,
+        );
+
+        let message = format(&config, event());
+        let timestamp = DateTime::<Local>::from(Utc.ymd(2020, 6, 1).and_hms(10, 0, 0))
+            .format("%b %e %H:%M:%S")
+            .to_string();
+        assert_eq!(
+            message,
+            format!(
+                r#"<11>{} web-1 vector[42]: {{"message":"hello world"}}"#,
+                timestamp
+            )
+        );
+    }
+
+    #[test]
+    fn parses_codes() {
+        assert_eq!(parse_code("Local7", &FACILITIES), Some(23));
+        assert_eq!(parse_code("4", &FACILITIES), Some(4));
+        assert_eq!(parse_code("24", &FACILITIES), None);
+        assert_eq!(parse_code("warn", &SEVERITIES), Some(4));
+        assert_eq!(parse_code("verbose", &SEVERITIES), None);
+    }
+
+    #[test]
+    fn frames_messages() {
+        let config = config(
+            r#"
+            mode = "udp"
+            address = "127.0.0.1:514"
+            app_name = "app"
+            "#,
+        );
+        let formatter = Formatter::new(&config);
+        let expected = "<14>1 2020-06-01T10:00:00.250Z web-1 app - - - hello world";
+
+        let frame = formatter.frame(event(), None).unwrap();
+        assert_eq!(frame, expected.as_bytes());
+        let frame = formatter
+            .frame(event(), Some(Framing::NewlineDelimited))
+            .unwrap();
+        assert_eq!(frame, format!("{}\n", expected).as_bytes());
+        let frame = formatter
+            .frame(event(), Some(Framing::OctetCounting))
+            .unwrap();
+        assert_eq!(frame, format!("{} {}", expected.len(), expected).as_bytes());
+    }
+
+    #[test]
+    fn tcp_syslog() {
+        let addr = next_addr();
+        let config = config(&format!(
+            r#"
+            mode = "tcp"
+            address = "{}"
+            "#,
+            addr
+        ));
+
+        let mut rt = runtime();
+        let receiver = receive(&addr);
+        let (sink, _healthcheck) = config.build(SinkContext::new_test(rt.executor())).unwrap();
+        rt.block_on(sink.send_all(iter_ok(vec![event(), event()])))
+            .unwrap();
+
+        let expected = "<14>1 2020-06-01T10:00:00.250Z web-1 vector - - - hello world";
+        assert_eq!(receiver.wait(), vec![expected, expected]);
+    }
+}
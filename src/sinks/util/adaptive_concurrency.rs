@@ -0,0 +1,398 @@
+//! Request concurrency limit that adapts to the downstream service.
+//!
+//! The limit starts at one and follows the AIMD scheme of TCP congestion
+//! control. Responses are observed over windows lasting about one round trip.
+//! When every slot was used during a window and neither back pressure (a
+//! retriable error or response, a timeout) nor a rise of the round trip time
+//! was seen, the limit grows by one. Otherwise, if any of those showed up, it
+//! shrinks by a ratio.
+
+use crate::{emit, internal_events::AdaptiveConcurrencyLimitChanged};
+use futures01::{
+    task::{self, Task},
+    Async, Future, Poll,
+};
+use std::{
+    mem,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tower::{layer::Layer, Service};
+
+/// Ratio applied to the limit when the service pushes back.
+const DECREASE_RATIO: f64 = 0.9;
+/// Weight of the latest window in the average round trip time.
+const EWMA_ALPHA: f64 = 0.4;
+/// How much slower than average a window may get before it counts as back
+/// pressure.
+const RTT_THRESHOLD_RATIO: f64 = 0.05;
+
+/// Tells whether the outcome of a request means the service is overloaded.
+pub type BackPressure<Response> =
+    Arc<dyn Fn(Result<&Response, &crate::Error>) -> bool + Send + Sync>;
+
+#[derive(Debug)]
+struct Controller {
+    max: usize,
+    adaptive: bool,
+    component_name: String,
+    inner: Mutex<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    limit: usize,
+    in_flight: usize,
+    waiting: Vec<Task>,
+    past_rtt: Option<Duration>,
+    window: Window,
+}
+
+#[derive(Debug)]
+struct Window {
+    start: Instant,
+    rtt_total: Duration,
+    responses: u32,
+    reached_limit: bool,
+    back_pressure: bool,
+}
+
+impl Window {
+    fn new(start: Instant) -> Self {
+        Self {
+            start,
+            rtt_total: Duration::from_secs(0),
+            responses: 0,
+            reached_limit: false,
+            back_pressure: false,
+        }
+    }
+}
+
+impl Controller {
+    fn new(max: usize, adaptive: bool, component_name: &str) -> Self {
+        let max = max.max(1);
+        Self {
+            max,
+            adaptive,
+            component_name: component_name.to_owned(),
+            inner: Mutex::new(Inner {
+                limit: if adaptive { 1 } else { max },
+                in_flight: 0,
+                waiting: Vec::new(),
+                past_rtt: None,
+                window: Window::new(Instant::now()),
+            }),
+        }
+    }
+
+    /// Takes a slot, or arranges for the current task to be notified once
+    /// one is released.
+    fn acquire(&self) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.in_flight < inner.limit {
+            inner.in_flight += 1;
+            if inner.in_flight == inner.limit {
+                inner.window.reached_limit = true;
+            }
+            true
+        } else {
+            inner.waiting.push(task::current());
+            false
+        }
+    }
+
+    /// Gives a slot back, along with the round trip time of the request and
+    /// whether it met back pressure when it got a response.
+    fn release(&self, response: Option<(Duration, bool)>) {
+        let waiting = {
+            let mut inner = self.inner.lock().unwrap();
+            inner.in_flight -= 1;
+            if let (true, Some((rtt, back_pressure))) = (self.adaptive, response) {
+                self.observe(&mut inner, Instant::now(), rtt, back_pressure);
+            }
+            mem::replace(&mut inner.waiting, Vec::new())
+        };
+
+        for task in waiting {
+            task.notify();
+        }
+    }
+
+    fn observe(&self, inner: &mut Inner, now: Instant, rtt: Duration, back_pressure: bool) {
+        let window = &mut inner.window;
+        window.rtt_total += rtt;
+        window.responses += 1;
+        window.back_pressure |= back_pressure;
+
+        // Without any history, the first response closes the window.
+        let length = inner.past_rtt.unwrap_or_else(|| Duration::from_secs(0));
+        if now.duration_since(window.start) < length {
+            return;
+        }
+
+        let current_rtt = window.rtt_total / window.responses;
+        let slower = inner.past_rtt.map_or(false, |past_rtt| {
+            current_rtt.as_secs_f64() > past_rtt.as_secs_f64() * (1.0 + RTT_THRESHOLD_RATIO)
+        });
+
+        let previous = inner.limit;
+        if window.back_pressure || slower {
+            inner.limit = ((inner.limit as f64 * DECREASE_RATIO) as usize).max(1);
+        } else if window.reached_limit && inner.limit < self.max {
+            inner.limit += 1;
+        }
+
+        inner.past_rtt = Some(match inner.past_rtt {
+            Some(past_rtt) => Duration::from_secs_f64(
+                past_rtt.as_secs_f64() * (1.0 - EWMA_ALPHA)
+                    + current_rtt.as_secs_f64() * EWMA_ALPHA,
+            ),
+            None => current_rtt,
+        });
+
+        if inner.limit != previous {
+            emit!(AdaptiveConcurrencyLimitChanged {
+                component_name: &self.component_name,
+                limit: inner.limit,
+                back_pressure: window.back_pressure,
+                current_rtt,
+            });
+        }
+
+        // The window may have been full before the limit went down.
+        let reached_limit = inner.in_flight >= inner.limit;
+        inner.window = Window::new(now);
+        inner.window.reached_limit = reached_limit;
+    }
+}
+
+/// Limits the number of requests in flight to a fixed number, or to one
+/// between 1 and `max` adapted to the service when `adaptive` is set.
+pub struct AdaptiveConcurrencyLimitLayer<Response> {
+    max: usize,
+    adaptive: bool,
+    back_pressure: BackPressure<Response>,
+    component_name: String,
+}
+
+impl<Response> AdaptiveConcurrencyLimitLayer<Response> {
+    /// The limit of the sink named `component_name` is reported as the
+    /// `adaptive_concurrency_limit` gauge.
+    pub fn new(
+        max: usize,
+        adaptive: bool,
+        back_pressure: BackPressure<Response>,
+        component_name: &str,
+    ) -> Self {
+        Self {
+            max,
+            adaptive,
+            back_pressure,
+            component_name: component_name.to_owned(),
+        }
+    }
+}
+
+impl<S, Response> Layer<S> for AdaptiveConcurrencyLimitLayer<Response> {
+    type Service = AdaptiveConcurrencyLimit<S, Response>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AdaptiveConcurrencyLimit {
+            inner,
+            controller: Arc::new(Controller::new(
+                self.max,
+                self.adaptive,
+                &self.component_name,
+            )),
+            back_pressure: Arc::clone(&self.back_pressure),
+            reserved: false,
+        }
+    }
+}
+
+/// Clones share the same limit.
+pub struct AdaptiveConcurrencyLimit<S, Response> {
+    inner: S,
+    controller: Arc<Controller>,
+    back_pressure: BackPressure<Response>,
+    reserved: bool,
+}
+
+impl<S: Clone, Response> Clone for AdaptiveConcurrencyLimit<S, Response> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            controller: Arc::clone(&self.controller),
+            back_pressure: Arc::clone(&self.back_pressure),
+            reserved: false,
+        }
+    }
+}
+
+impl<S, Response> Drop for AdaptiveConcurrencyLimit<S, Response> {
+    fn drop(&mut self) {
+        if self.reserved {
+            self.controller.release(None);
+        }
+    }
+}
+
+impl<S, Request, Response> Service<Request> for AdaptiveConcurrencyLimit<S, Response>
+where
+    S: Service<Request, Response = Response>,
+    S::Error: Into<crate::Error>,
+{
+    type Response = Response;
+    type Error = crate::Error;
+    type Future = ResponseFuture<S::Future, Response>;
+
+    fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+        if !self.reserved {
+            if !self.controller.acquire() {
+                return Ok(Async::NotReady);
+            }
+            self.reserved = true;
+        }
+        self.inner.poll_ready().map_err(Into::into)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        assert!(self.reserved, "poll_ready must be called before call");
+        self.reserved = false;
+
+        ResponseFuture {
+            inner: self.inner.call(request),
+            start: Instant::now(),
+            controller: Some(Arc::clone(&self.controller)),
+            back_pressure: Arc::clone(&self.back_pressure),
+        }
+    }
+}
+
+pub struct ResponseFuture<F, Response> {
+    inner: F,
+    start: Instant,
+    controller: Option<Arc<Controller>>,
+    back_pressure: BackPressure<Response>,
+}
+
+impl<F, Response> Future for ResponseFuture<F, Response>
+where
+    F: Future<Item = Response>,
+    F::Error: Into<crate::Error>,
+{
+    type Item = Response;
+    type Error = crate::Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        let result = match self.inner.poll() {
+            Ok(Async::NotReady) => return Ok(Async::NotReady),
+            Ok(Async::Ready(response)) => Ok(response),
+            Err(error) => Err(error.into()),
+        };
+
+        if let Some(controller) = self.controller.take() {
+            let back_pressure = (self.back_pressure)(result.as_ref());
+            controller.release(Some((self.start.elapsed(), back_pressure)));
+        }
+
+        result.map(Async::Ready)
+    }
+}
+
+impl<F, Response> Drop for ResponseFuture<F, Response> {
+    fn drop(&mut self) {
+        if let Some(controller) = self.controller.take() {
+            controller.release(None);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn observe(controller: &Controller, at: Instant, rtt_ms: u64, back_pressure: bool) -> usize {
+        let mut inner = controller.inner.lock().unwrap();
+        controller.observe(&mut inner, at, Duration::from_millis(rtt_ms), back_pressure);
+        inner.limit
+    }
+
+    fn fill(controller: &Controller) {
+        let mut inner = controller.inner.lock().unwrap();
+        inner.window.reached_limit = true;
+    }
+
+    #[test]
+    fn fixed_limit_never_moves() {
+        let controller = Controller::new(5, false, "test");
+        assert_eq!(controller.inner.lock().unwrap().limit, 5);
+
+        futures01::future::lazy(|| {
+            for _ in 0..5 {
+                assert!(controller.acquire());
+            }
+            assert!(!controller.acquire());
+            controller.release(Some((Duration::from_secs(100), true)));
+            assert!(controller.acquire());
+            Ok::<_, ()>(())
+        })
+        .wait()
+        .unwrap();
+
+        assert_eq!(controller.inner.lock().unwrap().limit, 5);
+    }
+
+    #[test]
+    fn grows_while_the_limit_is_reached() {
+        let controller = Controller::new(3, true, "test");
+        let start = Instant::now();
+
+        fill(&controller);
+        assert_eq!(observe(&controller, start, 100, false), 2);
+
+        // Windows last about one round trip.
+        fill(&controller);
+        assert_eq!(
+            observe(&controller, start + Duration::from_millis(50), 100, false),
+            2
+        );
+        assert_eq!(
+            observe(&controller, start + Duration::from_millis(150), 100, false),
+            3
+        );
+
+        // Not beyond the maximum.
+        fill(&controller);
+        assert_eq!(
+            observe(&controller, start + Duration::from_millis(300), 100, false),
+            3
+        );
+    }
+
+    #[test]
+    fn stays_put_below_the_limit() {
+        let controller = Controller::new(3, true, "test");
+        assert_eq!(observe(&controller, Instant::now(), 100, false), 1);
+    }
+
+    #[test]
+    fn shrinks_on_back_pressure_and_slow_responses() {
+        let controller = Controller::new(50, true, "test");
+        controller.inner.lock().unwrap().limit = 20;
+        let start = Instant::now();
+
+        assert_eq!(observe(&controller, start, 100, true), 18);
+        assert_eq!(
+            observe(&controller, start + Duration::from_millis(200), 200, false),
+            16
+        );
+
+        controller.inner.lock().unwrap().limit = 1;
+        assert_eq!(
+            observe(&controller, start + Duration::from_secs(1), 100, true),
+            1
+        );
+    }
+}
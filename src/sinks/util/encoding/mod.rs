@@ -4,17 +4,21 @@
 //!   * `EncodingConfig<E>`: For sinks without a default `Encoding`.
 //!   * `EncodingConfigWithDefault<E: Default>`: For sinks that have a default `Encoding`.
 //!
-//! Your sink should define some `Encoding` enum that is used as the `E` parameter.
+//! Your sink should use `StandardEncodings` as the `E` parameter, unless the service it sends
+//! events to imposes its own format, in which case it defines some `Encoding` enum instead.
 //!
 //! You can use either of these for a sink! They both implement `EncodingConfiguration`, which you
 //! will need to import as well.
 //!
 //! # Using a configuration
 //!
-//! To use an `EncodingConfig` involves three steps:
+//! When `E` implements `Encoder`, call `encode(event)` (or `encode_frame(event)` when events are
+//! written one after the other to a stream) on this config **on each event** just before it gets
+//! sent. The `only_fields`, `except_fields` and `timestamp_format` rules are applied as part of
+//! it.
 //!
-//!  1. Choose between `EncodingConfig` and `EncodingConfigWithDefault`.
-//!  2. Call `apply_rules(&mut event)` on this config **on each event** just before it gets sent.
+//! Otherwise, call `apply_rules(&mut event)` on this config **on each event** just before it
+//! gets sent, then encode it.
 //!
 //! # Implementation notes
 //!
@@ -24,13 +28,11 @@
 //! We don't require `E: Default` we can't always assume that, so we need to create statically
 //! distinct types! Having `EncodingConfigWithDefault` is a relatively straightforward way to
 //! accomplish this without a bunch of magic.
-//!
-// TODO: To avoid users forgetting to apply the rules, the `E` param should require a trait
-//       `Encoder` that defines some `encode` function which this config then calls internally as
-//       part of it's own (yet to be written) `encode() -> Vec<u8>` function.
 
 mod config;
 pub use config::EncodingConfig;
+mod standard;
+pub use standard::StandardEncodings;
 mod with_default;
 pub use with_default::EncodingConfigWithDefault;
 
@@ -42,6 +44,20 @@ use serde::{Deserialize, Serialize};
 use std::{collections::VecDeque, fmt::Debug};
 use string_cache::DefaultAtom as Atom;
 
+/// Turns an event into the bytes sent by a sink.
+pub trait Encoder {
+    /// Encodes a single event, to which the encoding rules were already applied.
+    fn encode_event(&self, event: Event) -> Result<Vec<u8>>;
+
+    /// Encodes an event to be written after others to the same stream, by
+    /// default as a line.
+    fn encode_frame(&self, event: Event) -> Result<Vec<u8>> {
+        let mut bytes = self.encode_event(event)?;
+        bytes.push(b'\n');
+        Ok(bytes)
+    }
+}
+
 /// The behavior of a encoding configuration.
 pub trait EncodingConfiguration<E> {
     // Required Accessors
@@ -146,6 +162,25 @@ pub trait EncodingConfiguration<E> {
         self.apply_only_fields(event);
         self.apply_timestamp_format(event);
     }
+
+    /// Apply the EncodingConfig rules to the provided event, then encode it.
+    fn encode(&self, mut event: Event) -> Result<Vec<u8>>
+    where
+        E: Encoder,
+    {
+        self.apply_rules(&mut event);
+        self.codec().encode_event(event)
+    }
+
+    /// Apply the EncodingConfig rules to the provided event, then encode it as a frame of a
+    /// stream.
+    fn encode_frame(&self, mut event: Event) -> Result<Vec<u8>>
+    where
+        E: Encoder,
+    {
+        self.apply_rules(&mut event);
+        self.codec().encode_frame(event)
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
@@ -273,6 +308,28 @@ mod tests {
         assert!(!event.as_mut_log().contains(&Atom::from("c[0].x")));
     }
 
+    #[test]
+    fn encode_applies_rules() {
+        let config: EncodingConfig<StandardEncodings> = toml::from_str(
+            r#"
+            codec = "logfmt"
+            except_fields = ["timestamp", "secret"]
+            "#,
+        )
+        .unwrap();
+        let mut event = Event::from("hello");
+        event.as_mut_log().insert("secret", "hunter2");
+
+        assert_eq!(
+            config.encode(event.clone()).unwrap(),
+            b"message=hello".to_vec()
+        );
+        assert_eq!(
+            config.encode_frame(event).unwrap(),
+            b"message=hello\n".to_vec()
+        );
+    }
+
     const TOML_TIMESTAMP_FORMAT: &str = r#"
         encoding.codec = "Snoot"
         encoding.timestamp_format = "unix"
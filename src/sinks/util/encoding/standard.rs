@@ -0,0 +1,234 @@
+use crate::{
+    event::{self, proto, Event, LogEvent, Value},
+    sinks::util::encoding::Encoder,
+    Result,
+};
+use bytes::BufMut;
+use prost::Message;
+use serde::{Deserialize, Serialize};
+use string_cache::DefaultAtom as Atom;
+
+/// The encodings every sink writing arbitrary bytes supports.
+///
+/// `json` and `ndjson` encode an event the same way, they differ in how the
+/// events of a batch are put together, which is up to the sink.
+#[derive(Deserialize, Serialize, Debug, Eq, PartialEq, Clone, Derivative)]
+#[serde(rename_all = "snake_case")]
+#[derivative(Default)]
+pub enum StandardEncodings {
+    /// The `message` field of log events.
+    #[derivative(Default)]
+    Text,
+    Json,
+    Ndjson,
+    /// All the fields of log events as `key=value` pairs.
+    Logfmt,
+    /// A row made of the given fields of log events.
+    Csv {
+        fields: Vec<Atom>,
+    },
+    /// The `EventWrapper` protobuf message of `proto/event.proto`.
+    Native,
+}
+
+impl StandardEncodings {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            StandardEncodings::Text | StandardEncodings::Logfmt => "text/plain",
+            StandardEncodings::Json => "application/json",
+            StandardEncodings::Ndjson => "application/x-ndjson",
+            StandardEncodings::Csv { .. } => "text/csv",
+            StandardEncodings::Native => "application/octet-stream",
+        }
+    }
+}
+
+impl Encoder for StandardEncodings {
+    fn encode_event(&self, event: Event) -> Result<Vec<u8>> {
+        let log = match (self, event) {
+            (StandardEncodings::Native, event) => {
+                let event = proto::EventWrapper::from(event);
+                let mut bytes = Vec::with_capacity(event.encoded_len());
+                event.encode(&mut bytes)?;
+                return Ok(bytes);
+            }
+            (StandardEncodings::Json, Event::Log(log))
+            | (StandardEncodings::Ndjson, Event::Log(log)) => return Ok(serde_json::to_vec(&log)?),
+            // Metrics have no message, they are shown as JSON instead.
+            (StandardEncodings::Json, Event::Metric(metric))
+            | (StandardEncodings::Ndjson, Event::Metric(metric))
+            | (StandardEncodings::Text, Event::Metric(metric)) => {
+                return Ok(serde_json::to_vec(&metric)?)
+            }
+            (_, Event::Metric(_)) => {
+                return Err("Metric events can only be encoded as text, JSON or native.".into())
+            }
+            (_, Event::Log(log)) => log,
+        };
+
+        Ok(match self {
+            StandardEncodings::Logfmt => encode_logfmt(&log),
+            StandardEncodings::Csv { fields } => encode_csv(&log, fields),
+            _ => log
+                .get(&event::log_schema().message_key())
+                .map(|v| v.as_bytes().to_vec())
+                .unwrap_or_default(),
+        })
+    }
+
+    /// Binary messages can't be told apart by a delimiter, so they are
+    /// prefixed by their length as a 4 bytes big endian integer instead.
+    fn encode_frame(&self, event: Event) -> Result<Vec<u8>> {
+        let mut bytes = self.encode_event(event)?;
+        match self {
+            StandardEncodings::Native => {
+                let mut frame = Vec::with_capacity(bytes.len() + 4);
+                frame.put_u32_be(bytes.len() as u32);
+                frame.extend_from_slice(&bytes);
+                Ok(frame)
+            }
+            _ => {
+                bytes.push(b'\n');
+                Ok(bytes)
+            }
+        }
+    }
+}
+
+fn encode_logfmt(log: &LogEvent) -> Vec<u8> {
+    let pairs = log
+        .all_fields()
+        .map(|(key, value)| format!("{}={}", key, logfmt_value(value)))
+        .collect::<Vec<_>>();
+    pairs.join(" ").into_bytes()
+}
+
+fn logfmt_value(value: &Value) -> String {
+    let value = value.to_string_lossy();
+    if !value.is_empty()
+        && !value
+            .chars()
+            .any(|c| c == ' ' || c == '=' || c == '"' || c.is_control())
+    {
+        return value;
+    }
+    format!("{:?}", value)
+}
+
+fn encode_csv(log: &LogEvent, fields: &[Atom]) -> Vec<u8> {
+    let row = fields
+        .iter()
+        .map(|field| {
+            let value = log
+                .get(field)
+                .map(Value::to_string_lossy)
+                .unwrap_or_default();
+            if value.contains(|c| c == ',' || c == '"' || c == '\n' || c == '\r') {
+                format!("\"{}\"", value.replace('"', "\"\""))
+            } else {
+                value
+            }
+        })
+        .collect::<Vec<_>>();
+    row.join(",").into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::{Metric, MetricKind, MetricValue};
+    use chrono::{TimeZone, Utc};
+
+    fn log_event() -> Event {
+        let mut event = Event::new_empty_log();
+        let log = event.as_mut_log();
+        log.insert("message", "hello, \"world\"");
+        log.insert("level", "info");
+        log.insert("took", 3);
+        log.insert("timestamp", Utc.ymd(2020, 6, 1).and_hms(10, 0, 0));
+        event
+    }
+
+    fn encode(encoding: StandardEncodings, event: Event) -> String {
+        String::from_utf8(encoding.encode_event(event).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn encodes_text() {
+        assert_eq!(
+            encode(StandardEncodings::Text, log_event()),
+            "hello, \"world\""
+        );
+        assert_eq!(encode(StandardEncodings::Text, Event::new_empty_log()), "");
+    }
+
+    #[test]
+    fn encodes_json() {
+        let json: serde_json::Value =
+            serde_json::from_str(&encode(StandardEncodings::Json, log_event())).unwrap();
+        assert_eq!(json["level"], "info");
+        assert_eq!(json["took"], 3);
+        assert_eq!(
+            encode(StandardEncodings::Json, log_event()),
+            encode(StandardEncodings::Ndjson, log_event())
+        );
+    }
+
+    #[test]
+    fn encodes_logfmt() {
+        assert_eq!(
+            encode(StandardEncodings::Logfmt, log_event()),
+            r#"level=info message="hello, \"world\"" timestamp=2020-06-01T10:00:00Z took=3"#
+        );
+    }
+
+    #[test]
+    fn encodes_csv() {
+        let encoding = StandardEncodings::Csv {
+            fields: vec!["took".into(), "missing".into(), "message".into()],
+        };
+        assert_eq!(encode(encoding, log_event()), r#"3,,"hello, ""world""""#);
+    }
+
+    #[test]
+    fn encodes_native() {
+        let bytes = StandardEncodings::Native.encode_event(log_event()).unwrap();
+        let decoded = proto::EventWrapper::decode(&bytes[..]).unwrap();
+        assert_eq!(Event::from(decoded), log_event());
+
+        let frame = StandardEncodings::Native.encode_frame(log_event()).unwrap();
+        assert_eq!(&frame[..4], &(bytes.len() as u32).to_be_bytes()[..]);
+        assert_eq!(&frame[4..], &bytes[..]);
+    }
+
+    #[test]
+    fn encodes_metrics() {
+        let metric = Event::Metric(Metric {
+            name: "hits".into(),
+            timestamp: None,
+            tags: None,
+            kind: MetricKind::Incremental,
+            value: MetricValue::Counter { value: 1.0 },
+        });
+
+        assert!(encode(StandardEncodings::Text, metric.clone()).starts_with('{'));
+        assert!(StandardEncodings::Logfmt.encode_event(metric).is_err());
+    }
+
+    #[test]
+    fn parses_csv_config() {
+        #[derive(Deserialize)]
+        struct Config {
+            encoding: StandardEncodings,
+        }
+
+        let config: Config =
+            toml::from_str(r#"encoding = { csv = { fields = ["host", "message"] } }"#).unwrap();
+        assert_eq!(
+            config.encoding,
+            StandardEncodings::Csv {
+                fields: vec!["host".into(), "message".into()]
+            }
+        );
+    }
+}
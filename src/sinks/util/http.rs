@@ -54,7 +54,7 @@ pub struct BatchedHttpSink<T, B, L = HttpRetryLogic>
 where
     B: Batch,
     B::Output: Clone + Send + 'static,
-    L: RetryLogic<Response = hyper::Response<Bytes>> + Send + 'static,
+    L: RetryLogic<Response = hyper::Response<Bytes>> + Send + Sync + 'static,
 {
     sink: Arc<T>,
     inner: TowerBatchedSink<HttpBatchService<B::Output>, B, L, B::Output>,
@@ -94,7 +94,7 @@ impl<T, B, L> BatchedHttpSink<T, B, L>
 where
     B: Batch,
     B::Output: Clone + Send + 'static,
-    L: RetryLogic<Response = hyper::Response<Bytes>, Error = hyper::Error> + Send + 'static,
+    L: RetryLogic<Response = hyper::Response<Bytes>, Error = hyper::Error> + Send + Sync + 'static,
     T: HttpSink<Input = B::Input, Output = B::Output>,
 {
     pub fn with_retry_logic(
@@ -111,7 +111,7 @@ where
         let svc =
             HttpBatchService::new(cx.resolver(), tls_settings, move |b| sink1.build_request(b));
 
-        let inner = request_settings.batch_sink(logic, svc, batch, batch_settings, cx.acker());
+        let inner = request_settings.batch_sink(logic, svc, batch, batch_settings, cx);
 
         Self {
             sink,
@@ -126,7 +126,7 @@ where
     B: Batch,
     B::Output: Clone + Send + 'static,
     T: HttpSink<Input = B::Input, Output = B::Output>,
-    L: RetryLogic<Response = hyper::Response<Bytes>> + Send + 'static,
+    L: RetryLogic<Response = hyper::Response<Bytes>> + Send + Sync + 'static,
 {
     type SinkItem = crate::Event;
     type SinkError = crate::Error;
@@ -346,6 +346,7 @@ impl RetryLogic for HttpRetryLogic {
 #[serde(deny_unknown_fields, rename_all = "snake_case", tag = "strategy")]
 pub enum Auth {
     Basic { user: String, password: String },
+    Bearer { token: String },
 }
 
 impl Auth {
@@ -356,6 +357,13 @@ impl Auth {
                 let auth = headers::Authorization::basic(&user, &password);
                 req.headers_mut().typed_insert(auth);
             }
+            Auth::Bearer { token } => {
+                use headers::HeaderMapExt;
+                match headers::Authorization::bearer(&token) {
+                    Ok(auth) => req.headers_mut().typed_insert(auth),
+                    Err(error) => error!(message = "Invalid bearer token.", ?error),
+                }
+            }
         }
     }
 }
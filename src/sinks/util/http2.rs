@@ -58,7 +58,7 @@ pub struct BatchedHttpSink<T, B, L = HttpRetryLogic>
 where
     B: Batch,
     B::Output: Clone + Send + 'static,
-    L: RetryLogic<Response = http02::Response<Bytes>> + Send + 'static,
+    L: RetryLogic<Response = http02::Response<Bytes>> + Send + Sync + 'static,
 {
     sink: Arc<T>,
     inner: TowerBatchedSink<HttpBatchService<B::Output>, B, L, B::Output>,
@@ -98,7 +98,10 @@ impl<T, B, L> BatchedHttpSink<T, B, L>
 where
     B: Batch,
     B::Output: Clone + Send + 'static,
-    L: RetryLogic<Response = http02::Response<Bytes>, Error = hyper13::Error> + Send + 'static,
+    L: RetryLogic<Response = http02::Response<Bytes>, Error = hyper13::Error>
+        + Send
+        + Sync
+        + 'static,
     T: HttpSink<Input = B::Input, Output = B::Output>,
 {
     pub fn with_retry_logic(
@@ -115,7 +118,7 @@ where
         let svc =
             HttpBatchService::new(cx.resolver(), tls_settings, move |b| sink1.build_request(b));
 
-        let inner = request_settings.batch_sink(logic, svc, batch, batch_settings, cx.acker());
+        let inner = request_settings.batch_sink(logic, svc, batch, batch_settings, cx);
 
         Self {
             sink,
@@ -130,7 +133,7 @@ where
     B: Batch,
     B::Output: Clone + Send + 'static,
     T: HttpSink<Input = B::Input, Output = B::Output>,
-    L: RetryLogic<Response = http02::Response<Bytes>> + Send + 'static,
+    L: RetryLogic<Response = http02::Response<Bytes>> + Send + Sync + 'static,
 {
     type SinkItem = crate::Event;
     type SinkError = crate::Error;
@@ -1,3 +1,4 @@
+pub mod adaptive_concurrency;
 pub mod batch;
 pub mod buffer;
 pub mod encoding;
@@ -20,10 +21,9 @@ pub mod udp;
 pub mod unix;
 pub mod uri;
 
-use crate::event::{self, Event};
+use crate::event::Event;
 use bytes::Bytes;
-use encoding::{EncodingConfig, EncodingConfiguration};
-use serde::{Deserialize, Serialize};
+use encoding::{EncodingConfig, EncodingConfiguration, StandardEncodings};
 use snafu::Snafu;
 
 pub use batch::{Batch, BatchBytesConfig, BatchEventsConfig, BatchSettings};
@@ -36,47 +36,22 @@ pub use sink::{BatchSink, PartitionBatchSink, StreamSink};
 pub use uri::UriSerde;
 
 #[derive(Debug, Snafu)]
-enum SinkBuildError {
+pub(crate) enum SinkBuildError {
     #[snafu(display("Missing host in address field"))]
     MissingHost,
     #[snafu(display("Missing port in address field"))]
     MissingPort,
 }
 
-/**
- * Enum representing different ways to encode events as they are sent into a Sink.
- */
-#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
-#[serde(rename_all = "snake_case")]
-pub enum Encoding {
-    Text,
-    Json,
-}
-
 /**
 * Encodes the given event into raw bytes that can be sent into a Sink, according to
-* the given encoding.  If there are any errors encoding the event, logs a warning
-* and returns None.
+* the given encoding, as one frame of a stream.  If there are any errors encoding the
+* event, logs a warning and returns None.
 **/
-pub fn encode_event(mut event: Event, encoding: &EncodingConfig<Encoding>) -> Option<Bytes> {
-    encoding.apply_rules(&mut event);
-    let log = event.into_log();
-
-    let b = match encoding.codec() {
-        Encoding::Json => serde_json::to_vec(&log),
-        Encoding::Text => {
-            let bytes = log
-                .get(&event::log_schema().message_key())
-                .map(|v| v.as_bytes().to_vec())
-                .unwrap_or_default();
-            Ok(bytes)
-        }
-    };
-
-    b.map(|mut b| {
-        b.push(b'\n');
-        Bytes::from(b)
-    })
-    .map_err(|error| error!(message = "Unable to encode.", %error))
-    .ok()
+pub fn encode_event(event: Event, encoding: &EncodingConfig<StandardEncodings>) -> Option<Bytes> {
+    encoding
+        .encode_frame(event)
+        .map(Bytes::from)
+        .map_err(|error| error!(message = "Unable to encode.", %error))
+        .ok()
 }
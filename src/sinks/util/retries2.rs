@@ -1,9 +1,11 @@
-use crate::Error;
+use super::{adaptive_concurrency::BackPressure, sink::Rejections};
+use crate::{event::finalization::EventStatus, Error};
 use futures::FutureExt;
 use std::{
     cmp,
     future::Future,
     pin::Pin,
+    sync::Arc,
     task::{Context, Poll},
     time::Duration,
 };
@@ -31,6 +33,46 @@ pub trait RetryLogic: Clone {
     }
 }
 
+/// Recognizes the outcomes `FixedRetryPolicy` gave up on, so that `BatchSink`
+/// reports their events as rejected right away, or as errored once retries
+/// are exhausted.
+pub fn rejections<L>(logic: L) -> Rejections<L::Response>
+where
+    L: RetryLogic + Send + Sync + 'static,
+{
+    Arc::new(move |result| match result {
+        Ok(response) => match logic.should_retry_response(response) {
+            RetryAction::DontRetry(reason) => Some((EventStatus::Failed, reason)),
+            RetryAction::Retry(reason) => Some((EventStatus::Errored, reason)),
+            RetryAction::Successful => None,
+        },
+        Err(error) => error
+            .downcast_ref::<L::Error>()
+            .filter(|error| !logic.is_retriable_error(error))
+            .map(|error| (EventStatus::Failed, error.to_string())),
+    })
+}
+
+/// Recognizes the outcomes that tell the service is overloaded, to adapt the
+/// request concurrency to it.
+pub fn back_pressure<L>(logic: L) -> BackPressure<L::Response>
+where
+    L: RetryLogic + Send + Sync + 'static,
+{
+    Arc::new(move |result| match result {
+        Ok(response) => match logic.should_retry_response(response) {
+            RetryAction::Retry(_) => true,
+            RetryAction::DontRetry(_) | RetryAction::Successful => false,
+        },
+        Err(error) => {
+            error.downcast_ref::<Elapsed>().is_some()
+                || error
+                    .downcast_ref::<L::Error>()
+                    .map_or(false, |error| logic.is_retriable_error(error))
+        }
+    })
+}
+
 #[derive(Debug, Clone)]
 pub struct FixedRetryPolicy<L> {
     remaining_attempts: usize,
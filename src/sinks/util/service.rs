@@ -1,8 +1,9 @@
 use super::{
-    retries::{FixedRetryPolicy, RetryLogic},
+    adaptive_concurrency::{AdaptiveConcurrencyLimit, AdaptiveConcurrencyLimitLayer},
+    retries::{back_pressure, rejections, FixedRetryPolicy, RetryLogic},
     Batch, BatchSettings, BatchSink,
 };
-use crate::buffers::Acker;
+use crate::topology::config::SinkContext;
 use futures01::{Async, Future, Poll};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
@@ -11,14 +12,22 @@ use std::{error, fmt};
 use tokio01::timer::Delay;
 use tower::{
     layer::{util::Stack, Layer},
-    limit::{concurrency::ConcurrencyLimit, rate::RateLimit},
+    limit::rate::RateLimit,
     retry::Retry,
     util::BoxService,
     Service, ServiceBuilder,
 };
 
-pub type TowerBatchedSink<S, B, L, Request> =
-    BatchSink<ConcurrencyLimit<RateLimit<Retry<FixedRetryPolicy<L>, Timeout<S>>>>, B, Request>;
+pub type TowerBatchedSink<S, B, L, Request> = BatchSink<
+    RateLimit<
+        Retry<
+            FixedRetryPolicy<L>,
+            AdaptiveConcurrencyLimit<Timeout<S>, <S as Service<Request>>::Response>,
+        >,
+    >,
+    B,
+    Request,
+>;
 
 pub trait ServiceBuilderExt<L> {
     fn map<R1, R2, F>(self, f: F) -> ServiceBuilder<Stack<MapLayer<R1, R2>, L>>
@@ -29,6 +38,7 @@ pub trait ServiceBuilderExt<L> {
         self,
         settings: TowerRequestSettings,
         retry_logic: RL,
+        component_name: &str,
     ) -> ServiceBuilder<Stack<TowerRequestLayer<RL, Request>, L>>;
 }
 
@@ -44,10 +54,12 @@ impl<L> ServiceBuilderExt<L> for ServiceBuilder<L> {
         self,
         settings: TowerRequestSettings,
         retry_logic: RL,
+        component_name: &str,
     ) -> ServiceBuilder<Stack<TowerRequestLayer<RL, Request>, L>> {
         self.layer(TowerRequestLayer {
             settings,
             retry_logic,
+            component_name: component_name.to_owned(),
             _pd: std::marker::PhantomData,
         })
     }
@@ -57,6 +69,7 @@ impl<L> ServiceBuilderExt<L> for ServiceBuilder<L> {
 #[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
 pub struct TowerRequestConfig {
     pub in_flight_limit: Option<usize>,        // 5
+    pub adaptive_concurrency: Option<bool>,    // false
     pub timeout_secs: Option<u64>,             // 60
     pub rate_limit_duration_secs: Option<u64>, // 1
     pub rate_limit_num: Option<u64>,           // 5
@@ -72,6 +85,10 @@ impl TowerRequestConfig {
                 .in_flight_limit
                 .or(defaults.in_flight_limit)
                 .unwrap_or(5),
+            adaptive_concurrency: self
+                .adaptive_concurrency
+                .or(defaults.adaptive_concurrency)
+                .unwrap_or(false),
             timeout: Duration::from_secs(self.timeout_secs.or(defaults.timeout_secs).unwrap_or(60)),
             rate_limit_duration: Duration::from_secs(
                 self.rate_limit_duration_secs
@@ -100,6 +117,9 @@ impl TowerRequestConfig {
 #[derive(Debug, Clone)]
 pub struct TowerRequestSettings {
     pub in_flight_limit: usize,
+    /// Treat `in_flight_limit` as the ceiling of a limit adapted to the
+    /// responses of the service.
+    pub adaptive_concurrency: bool,
     pub timeout: Duration,
     pub rate_limit_duration: Duration,
     pub rate_limit_num: u64,
@@ -124,14 +144,14 @@ impl TowerRequestSettings {
         service: S,
         batch: B,
         batch_settings: BatchSettings,
-        acker: Acker,
+        cx: &SinkContext,
     ) -> TowerBatchedSink<S, B, L, Request>
     // Would like to return `impl Sink + SinkExt<T>` here, but that
     // doesn't work with later calls to `batched_with_min` etc (via
     // `trait SinkExt` above), as it is missing a bound on the
     // associated types that cannot be expressed in stable Rust.
     where
-        L: RetryLogic<Response = S::Response> + Send + 'static,
+        L: RetryLogic<Response = S::Response> + Send + Sync + 'static,
         S: Service<Request> + Clone + Send + 'static,
         S::Error: Into<crate::Error> + Send + Sync + 'static,
         S::Response: Send + std::fmt::Debug,
@@ -139,17 +159,26 @@ impl TowerRequestSettings {
         B: Batch<Output = Request>,
         Request: Send + Clone + 'static,
     {
+        let rejections = rejections(retry_logic.clone());
+        let back_pressure = back_pressure(retry_logic.clone());
         let policy = self.retry_policy(retry_logic);
+        // The limit sits inside the retries so that every attempt takes a
+        // slot and its outcome reaches the controller.
         let service = ServiceBuilder::new()
-            .concurrency_limit(self.in_flight_limit)
             .rate_limit(self.rate_limit_num, self.rate_limit_duration)
             .retry(policy)
+            .layer(AdaptiveConcurrencyLimitLayer::new(
+                self.in_flight_limit,
+                self.adaptive_concurrency,
+                back_pressure,
+                cx.name(),
+            ))
             .layer(TimeoutLayer {
                 timeout: self.timeout,
             })
             .service(service);
 
-        BatchSink::new(service, batch, batch_settings, acker)
+        BatchSink::new(service, batch, batch_settings, cx.acker()).with_rejections(rejections)
     }
 }
 
@@ -157,6 +186,7 @@ impl TowerRequestSettings {
 pub struct TowerRequestLayer<L, Request> {
     settings: TowerRequestSettings,
     retry_logic: L,
+    component_name: String,
     _pd: std::marker::PhantomData<Request>,
 }
 
@@ -166,7 +196,7 @@ where
     S::Response: Send + 'static,
     S::Error: Into<crate::Error> + Send + Sync + 'static,
     S::Future: Send + 'static,
-    L: RetryLogic<Response = S::Response> + Send + 'static,
+    L: RetryLogic<Response = S::Response> + Send + Sync + 'static,
     Request: Clone + Send + 'static,
 {
     type Service = BoxService<Request, S::Response, crate::Error>;
@@ -175,12 +205,17 @@ where
         let policy = self.settings.retry_policy(self.retry_logic.clone());
 
         let l = ServiceBuilder::new()
-            .concurrency_limit(self.settings.in_flight_limit)
             .rate_limit(
                 self.settings.rate_limit_num,
                 self.settings.rate_limit_duration,
             )
             .retry(policy)
+            .layer(AdaptiveConcurrencyLimitLayer::new(
+                self.settings.in_flight_limit,
+                self.settings.adaptive_concurrency,
+                back_pressure(self.retry_logic.clone()),
+                &self.component_name,
+            ))
             .layer(TimeoutLayer {
                 timeout: self.settings.timeout,
             })
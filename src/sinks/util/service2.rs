@@ -1,21 +1,26 @@
-use super::retries2::{FixedRetryPolicy, RetryLogic};
+use super::adaptive_concurrency::{AdaptiveConcurrencyLimit, AdaptiveConcurrencyLimitLayer};
+use super::retries2::{back_pressure, rejections, FixedRetryPolicy, RetryLogic};
 use super::{Batch, BatchSettings, BatchSink};
-use crate::buffers::Acker;
+use crate::topology::config::SinkContext;
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
+use tower::layer::Layer as _;
 use tower03::{
     layer::{util::Stack, Layer},
-    limit::{ConcurrencyLimit, RateLimit},
+    limit::RateLimit,
     retry::Retry,
     timeout::Timeout,
     util::BoxService,
     Service, ServiceBuilder,
 };
 
-pub use compat::TowerCompat;
+pub use compat::{Tower01Compat, TowerCompat};
 
-pub type Svc<S, L> = ConcurrencyLimit<RateLimit<Retry<FixedRetryPolicy<L>, Timeout<S>>>>;
-pub type TowerBatchedSink<S, B, L, Request> = BatchSink<TowerCompat<Svc<S, L>>, B, Request>;
+pub type Limited<S, Response> =
+    Tower01Compat<AdaptiveConcurrencyLimit<TowerCompat<Timeout<S>>, Response>>;
+pub type Svc<S, L, Response> = RateLimit<Retry<FixedRetryPolicy<L>, Limited<S, Response>>>;
+pub type TowerBatchedSink<S, B, L, Request> =
+    BatchSink<TowerCompat<Svc<S, L, <S as Service<Request>>::Response>>, B, Request>;
 
 pub trait ServiceBuilderExt<L> {
     fn settings<RL, Request>(
@@ -43,6 +48,7 @@ impl<L> ServiceBuilderExt<L> for ServiceBuilder<L> {
 #[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
 pub struct TowerRequestConfig {
     pub in_flight_limit: Option<usize>,        // 5
+    pub adaptive_concurrency: Option<bool>,    // false
     pub timeout_secs: Option<u64>,             // 60
     pub rate_limit_duration_secs: Option<u64>, // 1
     pub rate_limit_num: Option<u64>,           // 5
@@ -58,6 +64,10 @@ impl TowerRequestConfig {
                 .in_flight_limit
                 .or(defaults.in_flight_limit)
                 .unwrap_or(5),
+            adaptive_concurrency: self
+                .adaptive_concurrency
+                .or(defaults.adaptive_concurrency)
+                .unwrap_or(false),
             timeout: Duration::from_secs(self.timeout_secs.or(defaults.timeout_secs).unwrap_or(60)),
             rate_limit_duration: Duration::from_secs(
                 self.rate_limit_duration_secs
@@ -86,6 +96,7 @@ impl TowerRequestConfig {
 #[derive(Debug, Clone)]
 pub struct TowerRequestSettings {
     pub in_flight_limit: usize,
+    pub adaptive_concurrency: bool,
     pub timeout: Duration,
     pub rate_limit_duration: Duration,
     pub rate_limit_num: u64,
@@ -110,14 +121,14 @@ impl TowerRequestSettings {
         service: S,
         batch: B,
         batch_settings: BatchSettings,
-        acker: Acker,
+        cx: &SinkContext,
     ) -> TowerBatchedSink<S, B, L, Request>
     // Would like to return `impl Sink + SinkExt<T>` here, but that
     // doesn't work with later calls to `batched_with_min` etc (via
     // `trait SinkExt` above), as it is missing a bound on the
     // associated types that cannot be expressed in stable Rust.
     where
-        L: RetryLogic<Response = S::Response> + Send + 'static,
+        L: RetryLogic<Response = S::Response> + Send + Sync + 'static,
         S: Service<Request> + Clone + Send + 'static,
         S::Error: Into<crate::Error> + Send + Sync + 'static,
         S::Response: Send + std::fmt::Debug,
@@ -125,16 +136,26 @@ impl TowerRequestSettings {
         B: Batch<Output = Request>,
         Request: Send + Clone + 'static,
     {
+        let rejections = rejections(retry_logic.clone());
+        let back_pressure = back_pressure(retry_logic.clone());
         let policy = self.retry_policy(retry_logic);
+        // The limit is applied on the futures 0.1 side, like in `service`,
+        // inside the retries so that every attempt takes a slot and its
+        // outcome reaches the controller.
+        let limited = AdaptiveConcurrencyLimitLayer::new(
+            self.in_flight_limit,
+            self.adaptive_concurrency,
+            back_pressure,
+            cx.name(),
+        )
+        .layer(TowerCompat::new(Timeout::new(service, self.timeout)));
         let service = ServiceBuilder::new()
-            .concurrency_limit(self.in_flight_limit)
             .rate_limit(self.rate_limit_num, self.rate_limit_duration)
             .retry(policy)
-            .timeout(self.timeout)
-            .service(service);
+            .service(Tower01Compat::new(limited));
 
-        let service = TowerCompat::new(service);
-        BatchSink::new(service, batch, batch_settings, acker)
+        BatchSink::new(TowerCompat::new(service), batch, batch_settings, cx.acker())
+            .with_rejections(rejections)
     }
 }
 
@@ -174,12 +195,15 @@ where
 }
 
 mod compat {
-    use futures::compat::Compat;
+    use futures::compat::{Compat, Compat01As03};
     use futures01::Poll;
     use std::pin::Pin;
+    use std::task::{self, Context};
     use tower::Service as Service01;
     use tower03::Service as Service03;
 
+    /// Turns a tower 0.3 service into a tower 0.1 one.
+    #[derive(Clone)]
     pub struct TowerCompat<S> {
         inner: S,
     }
@@ -207,4 +231,34 @@ mod compat {
             Compat::new(Box::pin(self.inner.call(req)))
         }
     }
+
+    /// Turns a tower 0.1 service into a tower 0.3 one.
+    #[derive(Clone)]
+    pub struct Tower01Compat<S> {
+        inner: S,
+    }
+
+    impl<S> Tower01Compat<S> {
+        pub fn new(inner: S) -> Self {
+            Self { inner }
+        }
+    }
+
+    impl<S, Request> Service03<Request> for Tower01Compat<S>
+    where
+        S: Service01<Request>,
+    {
+        type Response = S::Response;
+        type Error = S::Error;
+        type Future = Compat01As03<S::Future>;
+
+        fn poll_ready(&mut self, cx: &mut Context<'_>) -> task::Poll<Result<(), Self::Error>> {
+            let inner = &mut self.inner;
+            task_compat::poll_01_to_03(task_compat::with_notify(cx, || inner.poll_ready()))
+        }
+
+        fn call(&mut self, req: Request) -> Self::Future {
+            Compat01As03::new(self.inner.call(req))
+        }
+    }
 }
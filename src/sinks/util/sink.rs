@@ -34,7 +34,7 @@
 
 use super::batch::{Batch, BatchSettings};
 use super::buffer::partition::Partition;
-use crate::buffers::Acker;
+use crate::{buffers::Acker, event::finalization::EventStatus};
 use futures01::{
     future::Either,
     stream::FuturesUnordered,
@@ -46,6 +46,7 @@ use std::{
     fmt,
     hash::Hash,
     marker::PhantomData,
+    sync::Arc,
     time::Instant,
 };
 use tokio01::{
@@ -55,6 +56,12 @@ use tokio01::{
 use tower::Service;
 use tracing_futures::Instrument;
 
+/// Decides whether the outcome of a request means its events weren't
+/// delivered, and why: `Errored` when sending them again may succeed, `Failed`
+/// when the downstream service permanently rejected them.
+pub type Rejections<Response> =
+    Arc<dyn Fn(Result<&Response, &crate::Error>) -> Option<(EventStatus, String)> + Send + Sync>;
+
 // === StreamSink ===
 
 const STREAM_SINK_MAX: usize = 10_000;
@@ -134,7 +141,10 @@ impl<T: Sink> Sink for StreamSink<T> {
 /// batches have been acked. This means if sequential requests r1, r2,
 /// and r3 are dispatched and r2 and r3 complete, all events contained
 /// in all requests will not be acked until r1 has completed.
-pub struct BatchSink<S, B, Request, E = DefaultExecutor> {
+pub struct BatchSink<S, B, Request, E = DefaultExecutor>
+where
+    S: Service<Request>,
+{
     service: ServiceSink<S, Request>,
     batch: B,
     settings: BatchSettings,
@@ -186,6 +196,13 @@ where
         }
     }
 
+    /// Acks the events of the requests recognized by `rejections` with the
+    /// status it gives, instead of as delivered or failing the sink.
+    pub fn with_rejections(mut self, rejections: Rejections<S::Response>) -> Self {
+        self.service.rejections = Some(rejections);
+        self
+    }
+
     fn should_send(&mut self) -> bool {
         self.closing || self.batch.len() >= self.settings.size || self.linger_elapsed()
     }
@@ -286,7 +303,7 @@ where
 
 impl<S, B, Request> fmt::Debug for BatchSink<S, B, Request>
 where
-    S: fmt::Debug,
+    S: Service<Request> + fmt::Debug,
     B: fmt::Debug,
 {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -316,7 +333,10 @@ type LingerDelay<K> = Box<dyn Future<Item = LingerState<K>, Error = ()> + Send +
 /// batches have been acked. This means if sequential requests r1, r2,
 /// and r3 are dispatched and r2 and r3 complete, all events contained
 /// in all requests will not be acked until r1 has completed.
-pub struct PartitionBatchSink<B, S, K, Request, E = DefaultExecutor> {
+pub struct PartitionBatchSink<B, S, K, Request, E = DefaultExecutor>
+where
+    S: Service<Request>,
+{
     batch: B,
     service: ServiceSink<S, Request>,
     exec: E,
@@ -387,6 +407,13 @@ where
         }
     }
 
+    /// Acks the events of the requests recognized by `rejections` with the
+    /// status it gives, instead of as delivered or failing the sink.
+    pub fn with_rejections(mut self, rejections: Rejections<S::Response>) -> Self {
+        self.service.rejections = Some(rejections);
+        self
+    }
+
     fn set_linger(&mut self, partition: K) {
         let (tx, rx) = oneshot::channel();
         let partition_clone = partition.clone();
@@ -574,7 +601,7 @@ where
 impl<B, S, K, Request> fmt::Debug for PartitionBatchSink<B, S, K, Request>
 where
     B: fmt::Debug,
-    S: fmt::Debug,
+    S: Service<Request> + fmt::Debug,
 {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("PartitionedBatchSink")
@@ -587,13 +614,21 @@ where
 
 // === ServiceSink ===
 
-struct ServiceSink<S, Request> {
+/// The sequence number and size of a completed request, and the status of its
+/// events along with the reason for it, if they weren't delivered.
+type Completed = (usize, usize, Option<(EventStatus, String)>);
+
+struct ServiceSink<S, Request>
+where
+    S: Service<Request>,
+{
     service: S,
-    in_flight: FuturesUnordered<Receiver<Result<(usize, usize), crate::Error>>>,
+    in_flight: FuturesUnordered<Receiver<Result<Completed, crate::Error>>>,
     acker: Acker,
+    rejections: Option<Rejections<S::Response>>,
     seq_head: usize,
     seq_tail: usize,
-    pending_acks: HashMap<usize, usize>,
+    pending_acks: HashMap<usize, (usize, Option<(EventStatus, String)>)>,
     next_request_id: usize,
     _pd: PhantomData<Request>,
 }
@@ -610,6 +645,7 @@ where
             service,
             in_flight: FuturesUnordered::new(),
             acker,
+            rejections: None,
             seq_head: 0,
             seq_tail: 0,
             pending_acks: HashMap::new(),
@@ -640,15 +676,23 @@ where
             message = "submitting service request.",
             in_flight_requests = self.in_flight.len()
         );
+        let rejections = self.rejections.clone();
         let response = self
             .service
             .call(req)
-            .map(move |response| {
-                trace!(message = "Response successful.", ?response);
-                (seqno, batch_size)
-            })
             .map_err(Into::into)
-            .then(|res| {
+            .then(move |result| {
+                let rejected = rejections
+                    .as_ref()
+                    .and_then(|rejections| rejections(result.as_ref()));
+                let res = match (result, rejected) {
+                    (_, Some(reason)) => Ok((seqno, batch_size, Some(reason))),
+                    (Ok(response), None) => {
+                        trace!(message = "Response successful.", ?response);
+                        Ok((seqno, batch_size, None))
+                    }
+                    (Err(error), None) => Err(error),
+                };
                 // If the rx end is dropped we still completed
                 // the request so this is a weird case that we can
                 // ignore for now.
@@ -665,13 +709,28 @@ where
             match self.in_flight.poll() {
                 Ok(Async::NotReady) => return Ok(Async::NotReady),
                 Ok(Async::Ready(None)) => return Ok(Async::Ready(())),
-                Ok(Async::Ready(Some(Ok((seqno, batch_size))))) => {
-                    self.pending_acks.insert(seqno, batch_size);
+                Ok(Async::Ready(Some(Ok((seqno, batch_size, rejected))))) => {
+                    self.pending_acks.insert(seqno, (batch_size, rejected));
 
                     let mut num_to_ack = 0;
-                    while let Some(ack_size) = self.pending_acks.remove(&self.seq_tail) {
-                        num_to_ack += ack_size;
-                        self.seq_tail += 1
+                    while let Some((ack_size, rejected)) = self.pending_acks.remove(&self.seq_tail)
+                    {
+                        self.seq_tail += 1;
+                        match rejected {
+                            None => num_to_ack += ack_size,
+                            Some((status, reason)) => {
+                                // Keep acks in stream order.
+                                self.acker.ack(num_to_ack);
+                                num_to_ack = 0;
+                                if status == EventStatus::Failed {
+                                    trace!(message = "rejecting events.", rejecting_num = ack_size);
+                                    self.acker.reject(ack_size, &reason);
+                                } else {
+                                    warn!(message = "Events not delivered.", %reason, count = ack_size);
+                                    self.acker.ack_with(ack_size, status);
+                                }
+                            }
+                        }
                     }
                     trace!(message = "acking events.", acking_num = num_to_ack);
                     self.acker.ack(num_to_ack);
@@ -692,7 +751,7 @@ where
 
 impl<S, Request> fmt::Debug for ServiceSink<S, Request>
 where
-    S: fmt::Debug,
+    S: Service<Request> + fmt::Debug,
 {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("ServiceSink")
@@ -709,6 +768,10 @@ where
 mod tests {
     use super::*;
     use crate::buffers::Acker;
+    use crate::event::{
+        finalization::{BatchNotifier, BatchStatus},
+        Event,
+    };
     use crate::sinks::util::{buffer::partition::Partition, BatchSettings, Buffer};
     use crate::test_util::runtime;
     use bytes::Bytes;
@@ -746,6 +809,55 @@ mod tests {
         assert_eq!(ack_counter.load(Relaxed), 22);
     }
 
+    #[test]
+    fn batch_sink_acks_undelivered_events_with_their_status() {
+        let rt = runtime();
+
+        let mut clock = MockClock::new();
+
+        let (acker, ack_counter) = Acker::new_for_testing();
+        let (acker, pending) = acker.finalizing(None, None);
+        let receivers = (0..20)
+            .map(|_| {
+                let (batch, receiver) = BatchNotifier::new_with_receiver();
+                let mut event = Event::from("message");
+                event.as_mut_log().add_batch_notifier(batch);
+                pending.push(&mut event);
+                receiver
+            })
+            .collect::<Vec<_>>();
+
+        // The second batch gets a response retries gave up on.
+        let svc = tower::service_fn(|req: Vec<usize>| future::ok::<_, std::io::Error>(req[0]));
+        let rejections: Rejections<usize> = Arc::new(|result| match result {
+            Ok(&first) if first >= 10 => {
+                Some((EventStatus::Errored, "retries exhausted".to_string()))
+            }
+            _ => None,
+        });
+        let buffered = BatchSink::with_executor(svc, Vec::new(), SETTINGS, acker, rt.executor())
+            .with_rejections(rejections);
+
+        let _ = clock.enter(|_| {
+            buffered
+                .sink_map_err(drop)
+                .send_all(futures01::stream::iter_ok(0..20))
+                .wait()
+                .unwrap()
+        });
+        drop(pending);
+
+        assert_eq!(ack_counter.load(Relaxed), 20);
+        for (i, receiver) in receivers.into_iter().enumerate() {
+            let expected = if i < 10 {
+                BatchStatus::Delivered
+            } else {
+                BatchStatus::Errored
+            };
+            assert_eq!(receiver.wait(), Ok(expected));
+        }
+    }
+
     #[test]
     fn batch_sink_acking_unordered() {
         // We need a mock executor here because we need to ensure
@@ -5,7 +5,11 @@ use crate::{
         TcpConnectionDisconnected, TcpConnectionEstablished, TcpConnectionFailed,
         TcpConnectionShutdown, TcpEventSent, TcpFlushError,
     },
-    sinks::util::{encode_event, encoding::EncodingConfig, Encoding, SinkBuildError, StreamSink},
+    sinks::util::{
+        encode_event,
+        encoding::{EncodingConfig, StandardEncodings},
+        SinkBuildError, StreamSink,
+    },
     sinks::{Healthcheck, RouterSink},
     tls::{MaybeTls, MaybeTlsConnector, MaybeTlsSettings, MaybeTlsStream, TlsConfig},
     topology::config::SinkContext,
@@ -30,12 +34,12 @@ use tokio_retry::strategy::ExponentialBackoff;
 #[serde(deny_unknown_fields)]
 pub struct TcpSinkConfig {
     pub address: String,
-    pub encoding: EncodingConfig<Encoding>,
+    pub encoding: EncodingConfig<StandardEncodings>,
     pub tls: Option<TlsConfig>,
 }
 
 impl TcpSinkConfig {
-    pub fn new(address: String, encoding: EncodingConfig<Encoding>) -> Self {
+    pub fn new(address: String, encoding: EncodingConfig<StandardEncodings>) -> Self {
         Self {
             address,
             encoding,
@@ -249,7 +253,7 @@ pub fn raw_tcp(
     host: String,
     port: u16,
     cx: SinkContext,
-    encoding: EncodingConfig<Encoding>,
+    encoding: EncodingConfig<StandardEncodings>,
     tls: MaybeTlsSettings,
 ) -> RouterSink {
     let tcp = TcpSink::new(host, port, cx.resolver(), tls);
@@ -1,4 +1,8 @@
-use super::{encode_event, encoding::EncodingConfig, Encoding, SinkBuildError, StreamSink};
+use super::{
+    encode_event,
+    encoding::{EncodingConfig, StandardEncodings},
+    SinkBuildError, StreamSink,
+};
 use crate::{
     dns::{Resolver, ResolverFuture},
     sinks::{Healthcheck, RouterSink},
@@ -25,11 +29,11 @@ pub enum UdpBuildError {
 #[serde(deny_unknown_fields)]
 pub struct UdpSinkConfig {
     pub address: String,
-    pub encoding: EncodingConfig<Encoding>,
+    pub encoding: EncodingConfig<StandardEncodings>,
 }
 
 impl UdpSinkConfig {
-    pub fn new(address: String, encoding: EncodingConfig<Encoding>) -> Self {
+    pub fn new(address: String, encoding: EncodingConfig<StandardEncodings>) -> Self {
         Self { address, encoding }
     }
 
@@ -49,7 +53,7 @@ impl UdpSinkConfig {
 pub fn raw_udp(
     host: String,
     port: u16,
-    encoding: EncodingConfig<Encoding>,
+    encoding: EncodingConfig<StandardEncodings>,
     cx: SinkContext,
 ) -> Result<RouterSink, UdpBuildError> {
     let sink = UdpSink::new(host, port, cx.resolver())?;
@@ -3,7 +3,11 @@ use crate::{
         UnixSocketConnectionEstablished, UnixSocketConnectionFailure, UnixSocketError,
         UnixSocketEventSent,
     },
-    sinks::util::{encode_event, encoding::EncodingConfig, Encoding, StreamSink},
+    sinks::util::{
+        encode_event,
+        encoding::{EncodingConfig, StandardEncodings},
+        StreamSink,
+    },
     sinks::{Healthcheck, RouterSink},
     topology::config::SinkContext,
 };
@@ -26,11 +30,11 @@ use tracing::field;
 #[serde(deny_unknown_fields)]
 pub struct UnixSinkConfig {
     pub path: PathBuf,
-    pub encoding: EncodingConfig<Encoding>,
+    pub encoding: EncodingConfig<StandardEncodings>,
 }
 
 impl UnixSinkConfig {
-    pub fn new(path: PathBuf, encoding: EncodingConfig<Encoding>) -> Self {
+    pub fn new(path: PathBuf, encoding: EncodingConfig<StandardEncodings>) -> Self {
         Self { path, encoding }
     }
 
@@ -53,7 +57,7 @@ enum HealthcheckError {
     ConnectError { source: std::io::Error },
 }
 
-fn unix_healthcheck(path: PathBuf) -> Healthcheck {
+pub fn unix_healthcheck(path: PathBuf) -> Healthcheck {
     // Lazy to avoid immediately connecting
     let check = future::lazy(move || {
         UnixStream::connect(&path)
@@ -226,7 +230,7 @@ mod tests {
         let out_path = temp_uds_path("unix_test");
 
         // Set up Sink
-        let config = UnixSinkConfig::new(out_path.clone(), Encoding::Text.into());
+        let config = UnixSinkConfig::new(out_path.clone(), StandardEncodings::Text.into());
         let mut rt = Runtime::new().unwrap();
         let cx = SinkContext::new_test(rt.executor());
         let (sink, _healthcheck) = config.build(cx).unwrap();
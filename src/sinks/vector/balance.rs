@@ -0,0 +1,476 @@
+//! Spreads the batches of version 2 of the protocol over the addresses the
+//! endpoints of the sink resolve to.
+//!
+//! Every address is a peer with its own connections. A peer failing to
+//! answer in time, or answering with a server error, is ejected: batches go to
+//! the other peers until it answers `/ping` again. Names are resolved again, and
+//! ejected peers checked, on every refresh.
+
+use super::build_request;
+use crate::{
+    dns::Resolver,
+    internal_events::{VectorPeerEjected, VectorPeerResolveFailed, VectorPeerRestored},
+    sinks::util::{
+        http::{HttpClient, HttpRetryLogic},
+        retries::{RetryAction, RetryLogic},
+    },
+    tls::MaybeTlsSettings,
+};
+use bytes::Bytes;
+use futures::{compat::Future01CompatExt, future::join_all, stream::StreamExt};
+use futures01::{future, Future, Poll, Stream};
+use http::{StatusCode, Uri};
+use hyper::Body;
+use serde::{Deserialize, Serialize};
+use snafu::Snafu;
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, RwLock, Weak,
+    },
+    time::Duration,
+};
+use tokio::time::{interval, timeout};
+use tower::Service;
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Derivative)]
+#[derivative(Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Strategy {
+    #[derivative(Default)]
+    RoundRobin,
+    LeastLoaded,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Derivative)]
+#[derivative(Default)]
+#[serde(deny_unknown_fields)]
+pub struct LoadBalancingConfig {
+    #[serde(default)]
+    pub strategy: Strategy,
+    #[serde(default = "default_refresh_interval_secs")]
+    #[derivative(Default(value = "default_refresh_interval_secs()"))]
+    pub refresh_interval_secs: u64,
+}
+
+fn default_refresh_interval_secs() -> u64 {
+    30
+}
+
+/// How long an ejected peer has to answer `/ping` on a refresh.
+const PING_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Snafu)]
+pub enum BalanceError {
+    #[snafu(display("None of the endpoints resolved to an address"))]
+    NoPeers,
+    #[snafu(display("Request failed: {}", source))]
+    Request { source: hyper::Error },
+}
+
+/// One of the addresses of the sink, as configured.
+#[derive(Debug, Clone)]
+pub struct Endpoint {
+    pub host: String,
+    pub port: u16,
+    pub uri: Uri,
+    pub ping: Uri,
+}
+
+struct Peer {
+    endpoint: usize,
+    address: SocketAddr,
+    uri: Uri,
+    ping: Uri,
+    client: HttpClient,
+    in_flight: AtomicUsize,
+    ejected: AtomicBool,
+}
+
+impl Peer {
+    fn new(
+        endpoint: usize,
+        config: &Endpoint,
+        ip: IpAddr,
+        resolver: &Resolver,
+        tls: &MaybeTlsSettings,
+    ) -> crate::Result<Self> {
+        // The client keeps the name of the endpoint for TLS, its connections
+        // going to this address only.
+        let client = HttpClient::new(resolver.pinned(ip), tls.clone())?;
+        Ok(Self {
+            endpoint,
+            address: SocketAddr::new(ip, config.port),
+            uri: config.uri.clone(),
+            ping: config.ping.clone(),
+            client,
+            in_flight: AtomicUsize::new(0),
+            ejected: AtomicBool::new(false),
+        })
+    }
+
+    fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::Relaxed)
+    }
+
+    fn is_ejected(&self) -> bool {
+        self.ejected.load(Ordering::Relaxed)
+    }
+
+    fn eject(&self, reason: String) {
+        if !self.ejected.swap(true, Ordering::Relaxed) {
+            emit!(VectorPeerEjected {
+                address: self.address,
+                reason,
+            });
+        }
+    }
+
+    fn restore(&self) {
+        if self.ejected.swap(false, Ordering::Relaxed) {
+            emit!(VectorPeerRestored {
+                address: self.address
+            });
+        }
+    }
+
+    async fn ping(&self) -> bool {
+        let request = http::Request::get(self.ping.clone())
+            .body(Body::empty())
+            .unwrap();
+        match self.client.clone().call(request).compat().await {
+            Ok(response) => response.status() == StatusCode::OK,
+            Err(_) => false,
+        }
+    }
+}
+
+/// Counts a request against its peer for as long as it is in flight,
+/// including when it is dropped on a timeout. A request dropped before its
+/// response came back ejects the peer, as timeouts are applied by dropping
+/// the request.
+struct InFlight {
+    peer: Arc<Peer>,
+    finished: bool,
+}
+
+impl InFlight {
+    fn new(peer: Arc<Peer>) -> Self {
+        peer.in_flight.fetch_add(1, Ordering::Relaxed);
+        Self {
+            peer,
+            finished: false,
+        }
+    }
+
+    fn finish(&mut self) -> &Peer {
+        self.finished = true;
+        &self.peer
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.peer.in_flight.fetch_sub(1, Ordering::Relaxed);
+        if !self.finished {
+            self.peer.eject("request timed out".into());
+        }
+    }
+}
+
+struct Inner {
+    endpoints: Vec<Endpoint>,
+    strategy: Strategy,
+    gzip: bool,
+    resolver: Resolver,
+    tls: MaybeTlsSettings,
+    peers: RwLock<Vec<Arc<Peer>>>,
+    next: AtomicUsize,
+}
+
+#[derive(Clone)]
+pub struct Balancer {
+    inner: Arc<Inner>,
+}
+
+impl Balancer {
+    /// Creates a balancer without any peer, which are then found by a task
+    /// refreshing them until the balancer is dropped.
+    pub fn spawn(
+        endpoints: Vec<Endpoint>,
+        config: &LoadBalancingConfig,
+        gzip: bool,
+        resolver: Resolver,
+        tls: MaybeTlsSettings,
+        exec: &crate::runtime::TaskExecutor,
+    ) -> Self {
+        let balancer = Self {
+            inner: Arc::new(Inner {
+                endpoints,
+                strategy: config.strategy,
+                gzip,
+                resolver,
+                tls,
+                peers: RwLock::new(Vec::new()),
+                next: AtomicUsize::new(0),
+            }),
+        };
+
+        let period = Duration::from_secs(config.refresh_interval_secs.max(1));
+        exec.spawn_std(run_refresh(Arc::downgrade(&balancer.inner), period));
+
+        balancer
+    }
+
+    fn select(&self) -> Option<Arc<Peer>> {
+        let peers = self.inner.peers.read().unwrap();
+        if peers.is_empty() {
+            return None;
+        }
+
+        let start = self.inner.next.fetch_add(1, Ordering::Relaxed);
+        let mut healthy = (0..peers.len())
+            .map(|offset| &peers[(start + offset) % peers.len()])
+            .filter(|peer| !peer.is_ejected());
+        let peer = match self.inner.strategy {
+            Strategy::RoundRobin => healthy.next(),
+            Strategy::LeastLoaded => healthy.min_by_key(|peer| peer.in_flight()),
+        };
+
+        // With every peer ejected, keep going round them so that the retries
+        // notice the first one coming back.
+        peer.or_else(|| peers.get(start % peers.len())).cloned()
+    }
+
+    async fn refresh(&self) {
+        let current = self.inner.peers.read().unwrap().clone();
+        let mut peers = Vec::with_capacity(current.len());
+
+        for (index, endpoint) in self.inner.endpoints.iter().enumerate() {
+            match self.inner.resolver.lookup_ip(&endpoint.host).compat().await {
+                Ok(ips) => {
+                    for ip in ips {
+                        let existing = current
+                            .iter()
+                            .find(|peer| peer.endpoint == index && peer.address.ip() == ip);
+                        let peer = match existing {
+                            Some(peer) => Arc::clone(peer),
+                            None => match Peer::new(
+                                index,
+                                endpoint,
+                                ip,
+                                &self.inner.resolver,
+                                &self.inner.tls,
+                            ) {
+                                Ok(peer) => Arc::new(peer),
+                                Err(error) => {
+                                    error!(message = "Unable to create client.", %error);
+                                    continue;
+                                }
+                            },
+                        };
+                        peers.push(peer);
+                    }
+                }
+                Err(error) => {
+                    emit!(VectorPeerResolveFailed {
+                        host: &endpoint.host,
+                        error,
+                    });
+                    peers.extend(
+                        current
+                            .iter()
+                            .filter(|peer| peer.endpoint == index)
+                            .cloned(),
+                    );
+                }
+            }
+        }
+
+        let pings = peers
+            .iter()
+            .filter(|peer| peer.is_ejected())
+            .map(|peer| async move {
+                if let Ok(true) = timeout(PING_TIMEOUT, peer.ping()).await {
+                    peer.restore();
+                }
+            });
+        join_all(pings).await;
+
+        let addresses = |peers: &[Arc<Peer>]| {
+            peers
+                .iter()
+                .map(|peer| (peer.endpoint, peer.address))
+                .collect::<Vec<_>>()
+        };
+        if addresses(&peers) != addresses(&current) {
+            debug!(message = "Peers changed.", count = peers.len());
+        }
+
+        *self.inner.peers.write().unwrap() = peers;
+    }
+}
+
+async fn run_refresh(inner: Weak<Inner>, period: Duration) {
+    let mut ticks = interval(period).map(|_| ());
+
+    while let Some(()) = ticks.next().await {
+        match inner.upgrade() {
+            Some(inner) => Balancer { inner }.refresh().await,
+            None => break,
+        }
+    }
+}
+
+impl Service<Vec<u8>> for Balancer {
+    type Response = http::Response<Bytes>;
+    type Error = BalanceError;
+    type Future = Box<dyn Future<Item = Self::Response, Error = Self::Error> + Send + 'static>;
+
+    fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+        Ok(().into())
+    }
+
+    fn call(&mut self, body: Vec<u8>) -> Self::Future {
+        let peer = match self.select() {
+            Some(peer) => peer,
+            None => return Box::new(future::err(BalanceError::NoPeers)),
+        };
+
+        let request = build_request(peer.uri.clone(), self.inner.gzip, body).map(Body::from);
+        let in_flight = InFlight::new(Arc::clone(&peer));
+
+        let fut = peer
+            .client
+            .clone()
+            .call(request)
+            .and_then(|response| {
+                let (parts, body) = response.into_parts();
+                body.concat2()
+                    .map(|body| http::Response::from_parts(parts, body.into_bytes()))
+            })
+            .then(move |result| {
+                let mut in_flight = in_flight;
+                let peer = in_flight.finish();
+                match &result {
+                    Err(error) if error.is_connect() || error.is_closed() => {
+                        peer.eject(error.to_string())
+                    }
+                    Ok(response) if response.status().is_server_error() => {
+                        peer.eject(response.status().to_string())
+                    }
+                    _ => (),
+                }
+                result.map_err(|source| BalanceError::Request { source })
+            });
+
+        Box::new(fut)
+    }
+}
+
+#[derive(Clone)]
+pub struct BalanceRetryLogic;
+
+impl RetryLogic for BalanceRetryLogic {
+    type Error = BalanceError;
+    type Response = http::Response<Bytes>;
+
+    fn is_retriable_error(&self, error: &Self::Error) -> bool {
+        match error {
+            BalanceError::NoPeers => true,
+            BalanceError::Request { source } => HttpRetryLogic.is_retriable_error(source),
+        }
+    }
+
+    fn should_retry_response(&self, response: &Self::Response) -> RetryAction {
+        HttpRetryLogic.should_retry_response(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::runtime;
+
+    fn balancer(strategy: Strategy, addresses: &[&str]) -> (crate::runtime::Runtime, Balancer) {
+        let rt = runtime();
+        let resolver = Resolver::new(Vec::new(), rt.executor()).unwrap();
+        let endpoint = Endpoint {
+            host: "vector.test".into(),
+            port: 9000,
+            uri: "http://vector.test:9000/v2/events".parse().unwrap(),
+            ping: "http://vector.test:9000/ping".parse().unwrap(),
+        };
+        let tls = MaybeTlsSettings::Raw(());
+        let peers = addresses
+            .iter()
+            .map(|ip| {
+                Arc::new(Peer::new(0, &endpoint, ip.parse().unwrap(), &resolver, &tls).unwrap())
+            })
+            .collect();
+
+        let balancer = Balancer {
+            inner: Arc::new(Inner {
+                endpoints: vec![endpoint],
+                strategy,
+                gzip: false,
+                resolver,
+                tls,
+                peers: RwLock::new(peers),
+                next: AtomicUsize::new(0),
+            }),
+        };
+        (rt, balancer)
+    }
+
+    fn select(balancer: &Balancer) -> String {
+        balancer.select().unwrap().address.ip().to_string()
+    }
+
+    #[test]
+    fn round_robin_skips_ejected_peers() {
+        let (_rt, balancer) = balancer(Strategy::RoundRobin, &["10.0.0.1", "10.0.0.2", "10.0.0.3"]);
+
+        let selected = (0..3).map(|_| select(&balancer)).collect::<Vec<_>>();
+        assert_eq!(selected, vec!["10.0.0.1", "10.0.0.2", "10.0.0.3"]);
+
+        balancer.inner.peers.read().unwrap()[1].eject("test".into());
+        let selected = (0..3).map(|_| select(&balancer)).collect::<Vec<_>>();
+        assert_eq!(selected, vec!["10.0.0.1", "10.0.0.3", "10.0.0.3"]);
+
+        for peer in balancer.inner.peers.read().unwrap().iter() {
+            peer.eject("test".into());
+        }
+        assert!(balancer.select().is_some());
+    }
+
+    #[test]
+    fn least_loaded_prefers_idle_peers() {
+        let (_rt, balancer) = balancer(Strategy::LeastLoaded, &["10.0.0.1", "10.0.0.2"]);
+
+        let first = balancer.select().unwrap();
+        let mut in_flight = InFlight::new(Arc::clone(&first));
+        assert_eq!(first.address.ip().to_string(), "10.0.0.1");
+        assert_eq!(select(&balancer), "10.0.0.2");
+        assert_eq!(select(&balancer), "10.0.0.2");
+
+        in_flight.finish();
+        drop(in_flight);
+        assert_eq!(first.in_flight(), 0);
+        assert!(!first.is_ejected());
+    }
+
+    #[test]
+    fn unfinished_requests_eject_their_peer() {
+        let (_rt, balancer) = balancer(Strategy::RoundRobin, &["10.0.0.1", "10.0.0.2"]);
+
+        let first = balancer.select().unwrap();
+        drop(InFlight::new(Arc::clone(&first)));
+        assert_eq!(first.in_flight(), 0);
+        assert!(first.is_ejected());
+
+        assert_eq!(select(&balancer), "10.0.0.2");
+        assert_eq!(select(&balancer), "10.0.0.2");
+    }
+}
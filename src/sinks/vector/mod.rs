@@ -0,0 +1,249 @@
+mod balance;
+
+pub use self::balance::{LoadBalancingConfig, Strategy};
+
+use self::balance::{BalanceRetryLogic, Balancer, Endpoint};
+use crate::{
+    dns::Resolver,
+    event::proto,
+    internal_events::VectorEventSent,
+    sinks::util::{
+        http::HttpClient, tcp::TcpSink, BatchBytesConfig, Buffer, Compression, StreamSink,
+        TowerRequestConfig,
+    },
+    tls::{MaybeTlsSettings, TlsConfig},
+    topology::config::{DataType, SinkConfig, SinkContext, SinkDescription},
+    vector_protocol::{self, Version},
+    Event,
+};
+use bytes::{BufMut, Bytes, BytesMut};
+use futures01::{future, stream::iter_ok, Future, Sink};
+use http::{StatusCode, Uri};
+use hyper::Body;
+use lazy_static::lazy_static;
+use prost::Message;
+use serde::{Deserialize, Serialize};
+use snafu::Snafu;
+use tower::Service;
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct VectorSinkConfig {
+    pub address: Option<String>,
+    /// Further addresses to balance the events over, which version 1 doesn't
+    /// support.
+    #[serde(default)]
+    pub addresses: Vec<String>,
+    #[serde(default)]
+    pub version: Version,
+    /// Only used by version 2, which compresses batches with gzip by default.
+    pub compression: Option<Compression>,
+    #[serde(default)]
+    pub batch: BatchBytesConfig,
+    #[serde(default)]
+    pub request: TowerRequestConfig,
+    #[serde(default)]
+    pub load_balancing: LoadBalancingConfig,
+    pub tls: Option<TlsConfig>,
+}
+
+impl VectorSinkConfig {
+    pub fn new(address: String) -> Self {
+        Self {
+            address: Some(address),
+            addresses: Vec::new(),
+            version: Version::V1,
+            compression: None,
+            batch: BatchBytesConfig::default(),
+            request: TowerRequestConfig::default(),
+            load_balancing: LoadBalancingConfig::default(),
+            tls: None,
+        }
+    }
+
+    fn addresses(&self) -> impl Iterator<Item = &String> {
+        self.address.iter().chain(self.addresses.iter())
+    }
+}
+
+lazy_static! {
+    static ref REQUEST_DEFAULTS: TowerRequestConfig = TowerRequestConfig {
+        in_flight_limit: Some(5),
+        timeout_secs: Some(30),
+        rate_limit_num: Some(1000),
+        ..Default::default()
+    };
+}
+
+#[derive(Debug, Snafu)]
+enum BuildError {
+    #[snafu(display("No address to send the events to"))]
+    MissingAddress,
+    #[snafu(display("Missing host in address field"))]
+    MissingHost,
+    #[snafu(display("Missing port in address field"))]
+    MissingPort,
+    #[snafu(display("Sending to several addresses requires version 2 of the protocol"))]
+    MultipleAddresses,
+}
+
+inventory::submit! {
+    SinkDescription::new_without_default::<VectorSinkConfig>("vector")
+}
+
+#[typetag::serde(name = "vector")]
+impl SinkConfig for VectorSinkConfig {
+    fn build(&self, cx: SinkContext) -> crate::Result<(super::RouterSink, super::Healthcheck)> {
+        let addresses = self
+            .addresses()
+            .map(String::as_str)
+            .map(parse_address)
+            .collect::<crate::Result<Vec<_>>>()?;
+
+        let tls = MaybeTlsSettings::from_config(&self.tls, false)?;
+
+        match self.version {
+            Version::V1 => {
+                let (host, port) = match addresses.as_slice() {
+                    [] => return Err(BuildError::MissingAddress.into()),
+                    [(host, port)] => (host.clone(), *port),
+                    _ => return Err(BuildError::MultipleAddresses.into()),
+                };
+
+                let sink = TcpSink::new(host.clone(), port, cx.resolver(), tls);
+                let sink = StreamSink::new(sink, cx.acker())
+                    .with_flat_map(move |event| iter_ok(encode_event(event)));
+                let healthcheck = super::util::tcp::tcp_healthcheck(host, port, cx.resolver());
+
+                Ok((Box::new(sink), healthcheck))
+            }
+            Version::V2 => {
+                if addresses.is_empty() {
+                    return Err(BuildError::MissingAddress.into());
+                }
+
+                let scheme = if tls.is_tls() { "https" } else { "http" };
+                let endpoints = addresses
+                    .into_iter()
+                    .map(|(host, port)| -> crate::Result<Endpoint> {
+                        let base = format!("{}://{}:{}", scheme, host, port);
+                        Ok(Endpoint {
+                            uri: format!("{}/{}", base, vector_protocol::EVENTS_PATH).parse()?,
+                            ping: format!("{}/ping", base).parse()?,
+                            host,
+                            port,
+                        })
+                    })
+                    .collect::<crate::Result<Vec<_>>>()?;
+
+                let gzip = match self.compression.unwrap_or(Compression::Gzip) {
+                    Compression::None => false,
+                    Compression::Gzip => true,
+                };
+                let batch = self.batch.unwrap_or(bytesize::mib(1u64), 1);
+                let request = self.request.unwrap_with(&REQUEST_DEFAULTS);
+
+                let healthcheck = healthcheck(
+                    endpoints.iter().map(|endpoint| endpoint.ping.clone()),
+                    cx.resolver(),
+                    tls.clone(),
+                )?;
+
+                let balancer = Balancer::spawn(
+                    endpoints,
+                    &self.load_balancing,
+                    gzip,
+                    cx.resolver(),
+                    tls,
+                    &cx.exec(),
+                );
+                let sink = request
+                    .batch_sink(BalanceRetryLogic, balancer, Buffer::new(gzip), batch, &cx)
+                    .sink_map_err(|e| error!("Fatal vector sink error: {}", e))
+                    .with_flat_map(move |event| {
+                        iter_ok(encode_event(event).map(|frame| frame.to_vec()))
+                    });
+
+                Ok((Box::new(sink), healthcheck))
+            }
+        }
+    }
+
+    fn input_type(&self) -> DataType {
+        DataType::Any
+    }
+
+    fn sink_type(&self) -> &'static str {
+        "vector"
+    }
+
+    fn supports_dead_letter(&self) -> bool {
+        // Version 1 streams over TCP without knowing what was rejected.
+        self.version == Version::V2
+    }
+}
+
+fn parse_address(address: &str) -> crate::Result<(String, u16)> {
+    let uri = address.parse::<http::Uri>()?;
+
+    let host = uri.host().ok_or(BuildError::MissingHost)?.to_string();
+    let port = uri.port_u16().ok_or(BuildError::MissingPort)?;
+
+    Ok((host, port))
+}
+
+#[derive(Debug, Snafu)]
+enum HealthcheckError {
+    #[snafu(display("Connect error: {}", source))]
+    ConnectError { source: std::io::Error },
+}
+
+fn encode_event(event: Event) -> Option<Bytes> {
+    let event = proto::EventWrapper::from(event);
+    let event_len = event.encoded_len();
+    let full_len = event_len + 4;
+
+    emit!(VectorEventSent {
+        byte_size: full_len
+    });
+
+    let mut out = BytesMut::with_capacity(full_len);
+    out.put_u32_be(event_len as u32);
+    event.encode(&mut out).unwrap();
+    Some(out.freeze())
+}
+
+/// Posts the same frames as the ones streamed by version 1, the sink only
+/// acknowledges them once the source answers with a success.
+fn build_request(uri: Uri, gzip: bool, body: Vec<u8>) -> http::Request<Vec<u8>> {
+    let mut builder = http::Request::post(uri);
+    builder.header("Content-Type", "application/octet-stream");
+    if gzip {
+        builder.header("Content-Encoding", "gzip");
+    }
+    builder.body(body).unwrap()
+}
+
+/// The source answers `/ping` along with the events path, the sink being
+/// healthy as long as one of its endpoints answers.
+fn healthcheck(
+    uris: impl Iterator<Item = Uri>,
+    resolver: Resolver,
+    tls: MaybeTlsSettings,
+) -> crate::Result<super::Healthcheck> {
+    let client = HttpClient::new(resolver, tls)?;
+
+    let checks = uris.map(move |uri| {
+        let request = http::Request::get(uri).body(Body::empty()).unwrap();
+        client
+            .clone()
+            .call(request)
+            .map_err(crate::Error::from)
+            .and_then(|response| match response.status() {
+                StatusCode::OK => Ok(()),
+                status => Err(super::HealthcheckError::UnexpectedStatus { status }.into()),
+            })
+    });
+
+    Ok(Box::new(future::select_ok(checks).map(|_| ())))
+}
@@ -0,0 +1,464 @@
+//! Runs a command and turns each line it writes, on its standard output or
+//! error, into an event.
+//!
+//! A scheduled command is run again on every interval, its output being sent
+//! once it exits so that its events carry the exit status. A streaming
+//! command is kept running, its output being sent as it comes, and is
+//! started again with a growing delay whenever it exits.
+
+use crate::{
+    decoding::{Decoder, DecodingConfig, FramingConfig},
+    event::{self, Event, Value},
+    internal_events::{ExecCommandExited, ExecEventReceived, ExecFailed},
+    shutdown::ShutdownSignal,
+    topology::config::{DataType, GlobalOptions, SourceConfig, SourceDescription},
+};
+use bytes::BytesMut;
+use futures::{
+    compat::Future01CompatExt,
+    future::{select, FutureExt, TryFutureExt},
+    stream::{self, Stream, StreamExt},
+};
+use futures01::{sync::mpsc, Sink};
+use serde::{Deserialize, Serialize};
+use snafu::Snafu;
+use std::{
+    io,
+    path::PathBuf,
+    process::{ExitStatus, Stdio},
+    time::{Duration, Instant},
+};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt},
+    process::Command,
+    time::delay_until,
+};
+use tokio_retry::strategy::ExponentialBackoff;
+
+const COMMAND_KEY: &str = "command";
+const PID_KEY: &str = "pid";
+const STREAM_KEY: &str = "stream";
+const EXIT_STATUS_KEY: &str = "exit_status";
+
+const STDOUT: &str = "stdout";
+const STDERR: &str = "stderr";
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ExecConfig {
+    /// The program to run followed by its arguments, which aren't
+    /// interpreted by a shell.
+    pub command: Vec<String>,
+    #[serde(default)]
+    pub mode: Mode,
+    #[serde(default = "default_interval_secs")]
+    pub interval_secs: u64,
+    #[serde(default = "default_respawn_max_backoff_secs")]
+    pub respawn_max_backoff_secs: u64,
+    pub working_directory: Option<PathBuf>,
+    #[serde(default)]
+    pub decoding: DecodingConfig,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Derivative)]
+#[derivative(Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Mode {
+    #[derivative(Default)]
+    Scheduled,
+    Streaming,
+}
+
+fn default_interval_secs() -> u64 {
+    60
+}
+
+fn default_respawn_max_backoff_secs() -> u64 {
+    60
+}
+
+#[derive(Debug, Snafu)]
+enum BuildError {
+    #[snafu(display("The command can't be empty"))]
+    EmptyCommand,
+}
+
+inventory::submit! {
+    SourceDescription::new_without_default::<ExecConfig>("exec")
+}
+
+#[typetag::serde(name = "exec")]
+impl SourceConfig for ExecConfig {
+    fn build(
+        &self,
+        _name: &str,
+        _globals: &GlobalOptions,
+        shutdown: ShutdownSignal,
+        out: mpsc::Sender<Event>,
+    ) -> crate::Result<super::Source> {
+        if self.command.is_empty() {
+            return Err(BuildError::EmptyCommand.into());
+        }
+
+        let config = self.clone();
+        let fut = async move {
+            // Dropping the command kills its process, which is how they are
+            // stopped on shutdown.
+            let run = match config.mode {
+                Mode::Scheduled => run_scheduled(config, out).boxed(),
+                Mode::Streaming => run_streaming(config, out).boxed(),
+            };
+            let _ = select(run, shutdown.compat()).await;
+            Ok(())
+        };
+
+        Ok(Box::new(fut.boxed().compat()))
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Log
+    }
+
+    fn source_type(&self) -> &'static str {
+        "exec"
+    }
+}
+
+async fn run_scheduled(config: ExecConfig, mut out: mpsc::Sender<Event>) -> Result<(), ()> {
+    let interval = Duration::from_secs(config.interval_secs);
+
+    loop {
+        let started = Instant::now();
+
+        let (mut events, status) = run_command(&config, None).await?;
+        if let Some(code) = status.and_then(|status| status.code()) {
+            for event in events.iter_mut() {
+                event.as_mut_log().insert(EXIT_STATUS_KEY, code);
+            }
+        }
+
+        out = match out
+            .send_all(futures01::stream::iter_ok(events))
+            .compat()
+            .await
+        {
+            Ok((out, _)) => out,
+            Err(error) => {
+                error!(message = "Error sending events.", %error);
+                return Err(());
+            }
+        };
+
+        // Runs don't overlap, a command outlasting the interval is run again
+        // right after exiting.
+        delay_until((started + interval).into()).await;
+    }
+}
+
+async fn run_streaming(config: ExecConfig, mut out: mpsc::Sender<Event>) -> Result<(), ()> {
+    let max_backoff = Duration::from_secs(config.respawn_max_backoff_secs);
+    let mut backoff = fresh_backoff(max_backoff);
+
+    loop {
+        let started = Instant::now();
+
+        run_command(&config, Some(&mut out)).await?;
+
+        // A command that kept running longer than the largest delay is
+        // considered to have been working until then.
+        if started.elapsed() >= max_backoff {
+            backoff = fresh_backoff(max_backoff);
+        }
+        let delay = backoff.next().unwrap();
+        warn!(
+            message = "Command exited, starting it again.",
+            command = %config.command.join(" "),
+            delay_millis = %delay.as_millis()
+        );
+        delay_until((Instant::now() + delay).into()).await;
+    }
+}
+
+fn fresh_backoff(max_delay: Duration) -> ExponentialBackoff {
+    ExponentialBackoff::from_millis(2)
+        .factor(250)
+        .max_delay(max_delay)
+}
+
+/// Runs the command once and returns its exit status, along with its events
+/// unless they were sent to `out` as they were decoded. Fails once events
+/// can't be sent anymore.
+async fn run_command(
+    config: &ExecConfig,
+    mut out: Option<&mut mpsc::Sender<Event>>,
+) -> Result<(Vec<Event>, Option<ExitStatus>), ()> {
+    let command_line = config.command.join(" ");
+
+    let mut command = Command::new(&config.command[0]);
+    command
+        .args(&config.command[1..])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    if let Some(working_directory) = &config.working_directory {
+        command.current_dir(working_directory);
+    }
+
+    let started = Instant::now();
+    let mut child = match command.spawn() {
+        Ok(child) => child,
+        Err(error) => {
+            emit!(ExecFailed {
+                command: &command_line,
+                error,
+            });
+            return Ok((Vec::new(), None));
+        }
+    };
+    let pid = child.id();
+
+    let default_framing = FramingConfig::NewlineDelimited { max_length: None };
+    let stdout = frames(
+        child.stdout.take().unwrap(),
+        config.decoding.decoder(default_framing.clone()),
+        STDOUT,
+    )
+    .boxed();
+    let stderr = frames(
+        child.stderr.take().unwrap(),
+        config.decoding.decoder(default_framing),
+        STDERR,
+    )
+    .boxed();
+    let mut output = stream::select(stdout, stderr);
+
+    let command_value = Value::from(
+        config
+            .command
+            .iter()
+            .map(|arg| Value::from(arg.as_str()))
+            .collect::<Vec<_>>(),
+    );
+    let mut events = Vec::new();
+    while let Some((stream, mut event, byte_size)) = output.next().await {
+        emit!(ExecEventReceived {
+            command: &command_line,
+            byte_size,
+        });
+
+        let log = event.as_mut_log();
+        log.insert(event::log_schema().source_type_key(), "exec");
+        log.insert(COMMAND_KEY, command_value.clone());
+        log.insert(PID_KEY, pid as i64);
+        log.insert(STREAM_KEY, stream);
+
+        match out.as_mut() {
+            // Waiting on the pipeline holds back the output of the command.
+            Some(out) => {
+                **out = out.clone().send(event).compat().await.map_err(|error| {
+                    error!(message = "Error sending event.", %error);
+                })?;
+            }
+            None => events.push(event),
+        }
+    }
+
+    let status = match (&mut child).await {
+        Ok(status) => {
+            emit!(ExecCommandExited {
+                command: &command_line,
+                exit_status: status.code(),
+                duration: started.elapsed(),
+            });
+            Some(status)
+        }
+        Err(error) => {
+            emit!(ExecFailed {
+                command: &command_line,
+                error,
+            });
+            None
+        }
+    };
+    Ok((events, status))
+}
+
+/// Decodes the output of the command as it is read, until it's closed.
+fn frames<R>(
+    reader: R,
+    decoder: Decoder,
+    stream: &'static str,
+) -> impl Stream<Item = (&'static str, Event, usize)>
+where
+    R: AsyncRead + Unpin,
+{
+    let state = (reader, decoder, BytesMut::new(), Vec::new(), false);
+    stream::unfold(
+        state,
+        move |(mut reader, mut decoder, mut buffer, mut pending, mut done)| {
+            async move {
+                use tokio01::codec::Decoder as _;
+
+                loop {
+                    if let Some((event, byte_size)) = pending.pop() {
+                        return Some((
+                            (stream, event, byte_size),
+                            (reader, decoder, buffer, pending, done),
+                        ));
+                    }
+                    if done {
+                        return None;
+                    }
+
+                    let mut chunk = [0u8; 4096];
+                    let read = match reader.read(&mut chunk).await {
+                        Ok(read) => read,
+                        Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+                        Err(error) => {
+                            error!(message = "Unable to read output.", %stream, %error);
+                            0
+                        }
+                    };
+                    buffer.extend_from_slice(&chunk[..read]);
+
+                    // Frames are kept in reverse so that they're popped in order.
+                    let decoded = if read == 0 {
+                        done = true;
+                        decode_all(&mut decoder, &mut buffer, |decoder, buffer| {
+                            decoder.decode_eof(buffer)
+                        })
+                    } else {
+                        decode_all(&mut decoder, &mut buffer, |decoder, buffer| {
+                            decoder.decode(buffer)
+                        })
+                    };
+                    pending = decoded.into_iter().rev().collect();
+                }
+            }
+        },
+    )
+}
+
+fn decode_all(
+    decoder: &mut Decoder,
+    buffer: &mut BytesMut,
+    decode: impl Fn(&mut Decoder, &mut BytesMut) -> io::Result<Option<(Event, usize)>>,
+) -> Vec<(Event, usize)> {
+    let mut decoded = Vec::new();
+    loop {
+        match decode(decoder, buffer) {
+            Ok(Some(frame)) => decoded.push(frame),
+            Ok(None) => break,
+            Err(error) => {
+                // The frame is dropped along with whatever was buffered.
+                warn!(message = "Invalid frame.", %error, rate_limit_secs = 30);
+                buffer.clear();
+                break;
+            }
+        }
+    }
+    decoded
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::test_util::{collect_n, runtime};
+    use string_cache::DefaultAtom as Atom;
+
+    fn config(command: &[&str], mode: Mode) -> ExecConfig {
+        ExecConfig {
+            command: command.iter().map(|arg| arg.to_string()).collect(),
+            mode,
+            interval_secs: 60,
+            respawn_max_backoff_secs: 1,
+            working_directory: None,
+            decoding: DecodingConfig::default(),
+        }
+    }
+
+    fn run(config: ExecConfig, count: usize) -> Vec<Event> {
+        let mut rt = runtime();
+        let (tx, rx) = mpsc::channel(10);
+        let (trigger, shutdown, _) = ShutdownSignal::new_wired();
+
+        let source = config
+            .build("default", &GlobalOptions::default(), shutdown, tx)
+            .unwrap();
+        rt.spawn(source);
+
+        let events = rt.block_on(collect_n(rx, count)).unwrap();
+        drop(trigger);
+        events
+    }
+
+    fn field(event: &Event, key: impl AsRef<str>) -> Value {
+        event.as_log()[&Atom::from(key.as_ref())].clone()
+    }
+
+    #[test]
+    fn scheduled_command_output() {
+        let events = run(
+            config(
+                &[
+                    "sh",
+                    "-c",
+                    "echo first; echo second >&2; echo third; exit 3",
+                ],
+                Mode::Scheduled,
+            ),
+            3,
+        );
+
+        let mut lines = events
+            .iter()
+            .map(|event| {
+                (
+                    field(event, STREAM_KEY).to_string_lossy(),
+                    field(event, event::log_schema().message_key()).to_string_lossy(),
+                )
+            })
+            .collect::<Vec<_>>();
+        lines.sort();
+        assert_eq!(
+            lines,
+            vec![
+                ("stderr".into(), "second".into()),
+                ("stdout".into(), "first".into()),
+                ("stdout".into(), "third".into()),
+            ]
+        );
+
+        for event in &events {
+            assert_eq!(field(event, EXIT_STATUS_KEY), Value::from(3));
+            assert_eq!(field(event, "source_type"), Value::from("exec"));
+            assert_eq!(field(event, "command[0]"), Value::from("sh"));
+            assert_eq!(field(event, PID_KEY), field(&events[0], PID_KEY));
+        }
+    }
+
+    #[test]
+    fn streaming_command_is_restarted() {
+        let events = run(config(&["echo", "hello"], Mode::Streaming), 2);
+
+        for event in &events {
+            assert_eq!(
+                field(event, event::log_schema().message_key()),
+                Value::from("hello")
+            );
+            assert!(event.as_log().get(&Atom::from(EXIT_STATUS_KEY)).is_none());
+        }
+        assert_ne!(field(&events[0], PID_KEY), field(&events[1], PID_KEY));
+    }
+
+    #[test]
+    fn decodes_output() {
+        let mut config = config(&["sh", "-c", r#"echo '{"answer": 42}'"#], Mode::Scheduled);
+        config.decoding = toml::from_str(r#"codec = "json""#).unwrap();
+
+        let events = run(config, 1);
+        assert_eq!(field(&events[0], "answer"), Value::from(42));
+        assert_eq!(field(&events[0], STREAM_KEY), Value::from("stdout"));
+    }
+}
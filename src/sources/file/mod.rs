@@ -1,5 +1,10 @@
 use crate::{
-    event::{self, Event},
+    decoding::{Decoder, DecodingConfig, Framer},
+    event::{
+        self,
+        finalization::{BatchNotifier, BatchStatus, BatchStatusReceiver},
+        Event,
+    },
     internal_events::FileEventReceived,
     shutdown::ShutdownSignal,
     topology::config::{DataType, GlobalOptions, SourceConfig, SourceDescription},
@@ -8,7 +13,7 @@ use crate::{
 use bytes::Bytes;
 use file_source::{
     paths_provider::glob::{Glob, MatchOptions},
-    FileServer, Fingerprinter,
+    AcknowledgedCheckpoints, FileFingerprint, FilePosition, FileServer, Fingerprinter, Line,
 };
 use futures::{
     compat::{Compat01As03Sink, Future01CompatExt},
@@ -18,8 +23,10 @@ use futures01::{future, sync::mpsc, Future, Sink, Stream};
 use regex::bytes::Regex;
 use serde::{Deserialize, Serialize};
 use snafu::{ResultExt, Snafu};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::convert::{TryFrom, TryInto};
 use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, SystemTime};
 use tokio::task::spawn_blocking;
 
@@ -71,6 +78,8 @@ enum BuildError {
         condition_pattern: String,
         source: regex::Error,
     },
+    #[snafu(display("decoding.framing is not supported, files are read line by line"))]
+    FramingNotSupported,
 }
 
 #[derive(Deserialize, Serialize, Debug, PartialEq)]
@@ -92,6 +101,8 @@ pub struct FileConfig {
     pub multiline: Option<MultilineConfig>,
     pub max_read_bytes: usize,
     pub oldest_first: bool,
+    pub acknowledgements: bool,
+    pub decoding: DecodingConfig,
 }
 
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
@@ -181,6 +192,8 @@ impl Default for FileConfig {
             multiline: None,
             max_read_bytes: 2048,
             oldest_first: false,
+            acknowledgements: false,
+            decoding: DecodingConfig::default(),
         }
     }
 }
@@ -212,6 +225,10 @@ impl SourceConfig for FileConfig {
             Regex::new(indicator).with_context(|| InvalidMessageStartIndicator { indicator })?;
         }
 
+        if self.decoding.framing.is_some() {
+            return Err(BuildError::FramingNotSupported.into());
+        }
+
         Ok(file_source(self, data_dir, shutdown, out))
     }
 
@@ -222,6 +239,10 @@ impl SourceConfig for FileConfig {
     fn source_type(&self) -> &'static str {
         "file"
     }
+
+    fn acknowledgements(&self) -> bool {
+        self.acknowledgements
+    }
 }
 
 pub fn file_source(
@@ -238,6 +259,12 @@ pub fn file_source(
     let paths_provider = Glob::new(&config.include, &config.exclude, MatchOptions::default())
         .expect("invalid glob patterns");
 
+    let acknowledged_checkpoints = if config.acknowledgements {
+        Some(AcknowledgedCheckpoints::default())
+    } else {
+        None
+    };
+
     let file_server = FileServer {
         paths_provider,
         max_read_bytes: config.max_read_bytes,
@@ -248,6 +275,7 @@ pub fn file_source(
         glob_minimum_cooldown,
         fingerprinter: config.fingerprinting.clone().into(),
         oldest_first: config.oldest_first,
+        acknowledged_checkpoints: acknowledged_checkpoints.clone(),
     };
 
     let file_key = config.file_key.clone();
@@ -262,12 +290,35 @@ pub fn file_source(
     let multiline_config = config.multiline.clone();
     let message_start_indicator = config.message_start_indicator.clone();
     let multi_line_timeout = config.multi_line_timeout;
+    // Lines are framed by the file server.
+    let decoder = Decoder::new(Framer::Bytes, config.decoding.codec);
     Box::new(future::lazy(move || {
         info!(message = "Starting file server.", ?include, ?exclude);
 
         // sizing here is just a guess
         let (tx, rx) = futures01::sync::mpsc::channel(100);
 
+        let offsets = acknowledged_checkpoints
+            .as_ref()
+            .map(|_| LineOffsets::default());
+        let rx = {
+            let offsets = offsets.clone();
+            rx.map(move |line: Line| {
+                if let Some(offsets) = &offsets {
+                    offsets.push(&line);
+                }
+                (line.text, line.filename)
+            })
+        };
+
+        // Checkpoints are updated in the order the lines were read, once
+        // their events have been delivered.
+        let pending_acks = acknowledged_checkpoints.map(|checkpoints| {
+            let (ack_tx, ack_rx) = mpsc::channel(PENDING_CHECKPOINTS);
+            tokio01::spawn(update_acked_checkpoints(checkpoints, ack_rx));
+            ack_tx
+        });
+
         let messages: Box<dyn Stream<Item = (Bytes, String), Error = ()> + Send> =
             if let Some(ref multiline_config) = multiline_config {
                 Box::new(LineAgg::new(
@@ -292,14 +343,39 @@ pub fn file_source(
         let span2 = span.clone();
         tokio01::spawn(
             messages
-                .map(move |(msg, file): (Bytes, String)| {
+                .filter_map(move |(msg, file): (Bytes, String)| {
                     let _enter = span2.enter();
                     emit!(FileEventReceived {
                         file: &file,
                         byte_size: msg.len(),
                     });
-                    create_event(msg, file, &host_key, &hostname, &file_key)
+                    let position = offsets
+                        .as_ref()
+                        .and_then(|offsets| offsets.pop(&file, &msg));
+                    let (event, _) = decoder.decode_frame(msg)?;
+                    let event = create_event(event, file, &host_key, &hostname, &file_key);
+                    Some((event, position))
                 })
+                .and_then(
+                    move |(mut event, position)| match (&pending_acks, position) {
+                        (Some(pending_acks), Some(position)) => {
+                            let (batch, receiver) = BatchNotifier::new_with_receiver();
+                            event.as_mut_log().add_batch_notifier(batch);
+                            // Waits for room, so that a slow sink holds back reading.
+                            future::Either::A(pending_acks.clone().send((receiver, position)).then(
+                                move |result| {
+                                    if result.is_err() {
+                                        error!(
+                                            message = "Checkpoint acknowledgement task stopped."
+                                        );
+                                    }
+                                    Ok(event)
+                                },
+                            ))
+                        }
+                        _ => future::Either::B(future::ok(event)),
+                    },
+                )
                 .forward(out.sink_map_err(|e| error!(%e)))
                 .map(|_| ())
                 .instrument(span),
@@ -319,15 +395,75 @@ pub fn file_source(
     }))
 }
 
+/// File positions of the lines read but not emitted yet, per file, so that
+/// messages aggregated out of several lines can be matched with the position
+/// of their last line.
+#[derive(Clone, Default)]
+struct LineOffsets(Arc<Mutex<HashMap<String, VecDeque<(FileFingerprint, FilePosition)>>>>);
+
+impl LineOffsets {
+    fn push(&self, line: &Line) {
+        self.0
+            .lock()
+            .unwrap()
+            .entry(line.filename.clone())
+            .or_default()
+            .push_back((line.file_id, line.offset));
+    }
+
+    /// Aggregated lines are joined with newlines.
+    fn pop(&self, file: &str, message: &Bytes) -> Option<(FileFingerprint, FilePosition)> {
+        let lines = message.iter().filter(|&&b| b == b'\n').count() + 1;
+        let mut offsets = self.0.lock().unwrap();
+        let queue = offsets.get_mut(file)?;
+        let position = queue.drain(..lines.min(queue.len())).last();
+        if queue.is_empty() {
+            offsets.remove(file);
+        }
+        position
+    }
+}
+
+/// How many lines can wait for their events to be delivered.
+const PENDING_CHECKPOINTS: usize = 1024;
+
+/// Updates the checkpoints in the order the lines were read. A file's
+/// checkpoint stops advancing at its first line that wasn't delivered, so
+/// that the file is read again from there once vector restarts.
+fn update_acked_checkpoints(
+    checkpoints: AcknowledgedCheckpoints,
+    pending: mpsc::Receiver<(BatchStatusReceiver, (FileFingerprint, FilePosition))>,
+) -> impl Future<Item = (), Error = ()> {
+    let mut stalled = HashSet::new();
+    pending
+        .and_then(|(receiver, position)| receiver.map(move |status| (position, status)))
+        .for_each(move |((file_id, offset), status)| {
+            if stalled.contains(&file_id) {
+                return Ok(());
+            }
+
+            match status {
+                BatchStatus::Delivered => checkpoints.update(file_id, offset),
+                BatchStatus::Errored | BatchStatus::Failed => {
+                    warn!(
+                        message = "Event was not delivered; the file's checkpoint won't advance past it.",
+                        %file_id,
+                        ?status,
+                    );
+                    stalled.insert(file_id);
+                }
+            }
+            Ok(())
+        })
+}
+
 fn create_event(
-    line: Bytes,
+    mut event: Event,
     file: String,
     host_key: &str,
     hostname: &Option<String>,
     file_key: &Option<String>,
 ) -> Event {
-    let mut event = Event::from(line);
-
     // Add source type
     event
         .as_mut_log()
@@ -338,7 +474,8 @@ fn create_event(
     }
 
     if let Some(hostname) = &hostname {
-        event.as_mut_log().insert(host_key, hostname.clone());
+        let host_key = host_key.into();
+        event.as_mut_log().try_insert(&host_key, hostname.clone());
     }
 
     event
@@ -465,7 +602,7 @@ mod tests {
         let hostname = Some("Some.Machine".to_string());
         let file_key = Some("file".to_string());
 
-        let event = create_event(line, file, &host_key, &hostname, &file_key);
+        let event = create_event(Event::from(line), file, &host_key, &hostname, &file_key);
         let log = event.into_log();
 
         assert_eq!(log[&"file".into()], "some_file.rs".into());
@@ -856,6 +993,26 @@ mod tests {
         shutdown_on_idle(rt);
     }
 
+    #[test]
+    fn line_offsets_follow_aggregated_lines() {
+        let offsets = LineOffsets::default();
+        for (i, offset) in [10, 20, 30].iter().enumerate() {
+            offsets.push(&Line {
+                text: Bytes::from(format!("line {}", i)),
+                filename: "file".to_owned(),
+                file_id: 1,
+                offset: *offset,
+            });
+        }
+
+        assert_eq!(
+            offsets.pop("file", &Bytes::from("line 0\nline 1")),
+            Some((1, 20))
+        );
+        assert_eq!(offsets.pop("file", &Bytes::from("line 2")), Some((1, 30)));
+        assert_eq!(offsets.pop("file", &Bytes::from("line 3")), None);
+    }
+
     #[test]
     fn file_start_position_server_restart() {
         let dir = tempdir().unwrap();
@@ -1160,6 +1317,69 @@ mod tests {
         );
     }
 
+    #[test]
+    fn file_decoding_codec() {
+        let (tx, rx) = futures01::sync::mpsc::channel(10);
+        let (trigger_shutdown, shutdown, _) = ShutdownSignal::new_wired();
+
+        let dir = tempdir().unwrap();
+        let config = file::FileConfig {
+            include: vec![dir.path().join("*")],
+            decoding: toml::from_str(r#"codec = "json""#).unwrap(),
+            ..test_default_file_config(&dir)
+        };
+
+        let source = file::file_source(&config, config.data_dir.clone().unwrap(), shutdown, tx);
+
+        let mut rt = runtime::Runtime::new().unwrap();
+
+        rt.spawn(source);
+
+        let path = dir.path().join("file");
+        let mut file = File::create(&path).unwrap();
+
+        sleep(); // The files must be observed at their original lengths before writing to them
+
+        writeln!(&mut file, r#"{{"status": 200}}"#).unwrap();
+        writeln!(&mut file, "not json").unwrap();
+        writeln!(&mut file).unwrap();
+        writeln!(&mut file, r#"{{"status": 404}}"#).unwrap();
+
+        sleep();
+
+        drop(trigger_shutdown);
+        shutdown_on_idle(rt);
+
+        let received = wait_with_timeout(rx.collect());
+
+        assert_eq!(received.len(), 2);
+        for (event, status) in received.iter().zip(&[200, 404]) {
+            let log = event.as_log();
+            assert_eq!(log[&"status".into()], (*status).into());
+            assert_eq!(log[&"file".into()], path.to_str().unwrap().into());
+            assert_eq!(log[event::log_schema().source_type_key()], "file".into());
+        }
+    }
+
+    #[test]
+    fn file_decoding_rejects_framing() {
+        let dir = tempdir().unwrap();
+        let config = file::FileConfig {
+            include: vec![dir.path().join("*")],
+            decoding: toml::from_str(r#"framing = { method = "octet_counting" }"#).unwrap(),
+            ..test_default_file_config(&dir)
+        };
+
+        assert!(config
+            .build(
+                "default",
+                &GlobalOptions::default(),
+                ShutdownSignal::noop(),
+                futures01::sync::mpsc::channel(1).0,
+            )
+            .is_err());
+    }
+
     #[test]
     fn test_multi_line_aggregation_legacy() {
         let (tx, rx) = futures01::sync::mpsc::channel(10);
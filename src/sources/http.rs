@@ -1,4 +1,5 @@
 use crate::{
+    decoding::{DecodingConfig, FramingConfig},
     event::{self, Event},
     shutdown::ShutdownSignal,
     sources::util::{ErrorMessage, HttpSource},
@@ -21,6 +22,8 @@ pub struct SimpleHttpConfig {
     address: SocketAddr,
     #[serde(default)]
     encoding: Encoding,
+    /// Takes precedence over `encoding`.
+    decoding: Option<DecodingConfig>,
     #[serde(default)]
     headers: Vec<String>,
     tls: Option<TlsConfig>,
@@ -33,6 +36,7 @@ inventory::submit! {
 #[derive(Clone)]
 struct SimpleHttpSource {
     encoding: Encoding,
+    decoding: Option<DecodingConfig>,
     headers: Vec<String>,
 }
 
@@ -52,7 +56,11 @@ impl HttpSource for SimpleHttpSource {
         body: FullBody,
         header_map: HeaderMap,
     ) -> Result<Vec<Event>, ErrorMessage> {
-        decode_body(body, self.encoding)
+        let events = match &self.decoding {
+            Some(decoding) => decode_body_with(body, decoding),
+            None => decode_body(body, self.encoding),
+        };
+        events
             .map(|events| add_headers(events, &self.headers, header_map))
             .map(|mut events| {
                 // Add source type
@@ -76,6 +84,7 @@ impl SourceConfig for SimpleHttpConfig {
     ) -> crate::Result<super::Source> {
         let source = SimpleHttpSource {
             encoding: self.encoding,
+            decoding: self.decoding.clone(),
             headers: self.headers.clone(),
         };
         source.run(self.address, "", &self.tls, out, shutdown)
@@ -149,6 +158,15 @@ fn decode_body(buf: FullBody, enc: Encoding) -> Result<Vec<Event>, ErrorMessage>
     }
 }
 
+fn decode_body_with(buf: FullBody, decoding: &DecodingConfig) -> Result<Vec<Event>, ErrorMessage> {
+    decoding
+        .decoder(FramingConfig::NewlineDelimited { max_length: None })
+        .decode_all(buf.collect())
+        .map_err(|error| {
+            ErrorMessage::new(StatusCode::BAD_REQUEST, format!("Bad request: {}", error))
+        })
+}
+
 fn json_parse_object(value: JsonValue) -> Result<Event, ErrorMessage> {
     let mut event = Event::new_empty_log();
     let log = event.as_mut_log();
@@ -202,6 +220,7 @@ fn json_value_to_type_string(value: &JsonValue) -> &'static str {
 #[cfg(test)]
 mod tests {
     use super::{Encoding, SimpleHttpConfig};
+    use crate::decoding::DecodingConfig;
     use warp::http::HeaderMap;
 
     use crate::shutdown::ShutdownSignal;
@@ -221,6 +240,15 @@ mod tests {
         rt: &mut Runtime,
         encoding: Encoding,
         headers: Vec<String>,
+    ) -> (mpsc::Receiver<Event>, SocketAddr) {
+        source_with(rt, encoding, None, headers)
+    }
+
+    fn source_with(
+        rt: &mut Runtime,
+        encoding: Encoding,
+        decoding: Option<DecodingConfig>,
+        headers: Vec<String>,
     ) -> (mpsc::Receiver<Event>, SocketAddr) {
         test_util::trace_init();
         let (sender, recv) = mpsc::channel(100);
@@ -229,6 +257,7 @@ mod tests {
             SimpleHttpConfig {
                 address,
                 encoding,
+                decoding,
                 headers,
                 tls: None,
             }
@@ -438,4 +467,33 @@ mod tests {
             assert_eq!(log[event::log_schema().source_type_key()], "http".into());
         }
     }
+
+    #[test]
+    fn http_decoding() {
+        let decoding = toml::from_str(r#"codec = "logfmt""#).unwrap();
+
+        let mut rt = test_util::runtime();
+        let (rx, addr) = source_with(&mut rt, Encoding::Json, Some(decoding), vec![]);
+
+        assert_eq!(
+            200,
+            send(addr, "level=info msg=one\nlevel=warn msg=\"two words\"\n")
+        );
+        assert_eq!(400, send(addr, "level=info\nno pairs here\n"));
+
+        let mut events = rt.block_on(collect_n(rx, 2)).unwrap();
+        {
+            let event = events.remove(0);
+            let log = event.as_log();
+            assert_eq!(log[&Atom::from("level")], "info".into());
+            assert_eq!(log[&Atom::from("msg")], "one".into());
+            assert!(log.get(&event::log_schema().timestamp_key()).is_some());
+            assert_eq!(log[event::log_schema().source_type_key()], "http".into());
+        }
+        {
+            let event = events.remove(0);
+            let log = event.as_log();
+            assert_eq!(log[&Atom::from("msg")], "two words".into());
+        }
+    }
 }
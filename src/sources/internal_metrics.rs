@@ -134,7 +134,7 @@ mod tests {
 
     #[test]
     fn captures_internal_metrics() {
-        crate::metrics::init().unwrap();
+        crate::metrics::init_test();
 
         let controller = get_controller().expect("no controller");
 
@@ -1,11 +1,17 @@
 use crate::{
-    event::{self, Event},
+    decoding::{DecodingConfig, FramingConfig},
+    event::{
+        self,
+        finalization::{BatchNotifier, BatchStatus, BatchStatusReceiver},
+        Event, Value,
+    },
     kafka::{KafkaCompression, KafkaTlsConfig},
     shutdown::ShutdownSignal,
     stream::StreamExt,
     topology::config::{DataType, GlobalOptions, SourceConfig, SourceDescription},
 };
-use bytes::Bytes;
+use bytes::BytesMut;
+use chrono::{TimeZone, Utc};
 use futures::compat::Compat;
 use futures01::{future, sync::mpsc, Future, Poll, Sink, Stream};
 use owning_ref::OwningHandle;
@@ -13,11 +19,15 @@ use rdkafka::{
     config::ClientConfig,
     consumer::{Consumer, DefaultConsumerContext, MessageStream, StreamConsumer},
     error::KafkaError,
-    message::{BorrowedMessage, Message},
+    message::{BorrowedMessage, Headers, Message},
+    Offset, TopicPartitionList,
 };
 use serde::{Deserialize, Serialize};
 use snafu::{ResultExt, Snafu};
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::Arc,
+};
 use tokio::task::block_in_place;
 
 #[derive(Debug, Snafu)]
@@ -47,8 +57,17 @@ pub struct KafkaSourceConfig {
     commit_interval_ms: u64,
     host_key: Option<String>,
     key_field: Option<String>,
+    topic_key: Option<String>,
+    partition_key: Option<String>,
+    offset_key: Option<String>,
+    timestamp_key: Option<String>,
+    headers_key: Option<String>,
     librdkafka_options: Option<HashMap<String, String>>,
     tls: Option<KafkaTlsConfig>,
+    #[serde(default)]
+    acknowledgements: bool,
+    #[serde(default)]
+    decoding: DecodingConfig,
 }
 
 fn default_session_timeout_ms() -> u64 {
@@ -94,6 +113,10 @@ impl SourceConfig for KafkaSourceConfig {
     fn source_type(&self) -> &'static str {
         "kafka"
     }
+
+    fn acknowledgements(&self) -> bool {
+        self.acknowledgements
+    }
 }
 
 fn kafka_source(
@@ -105,6 +128,16 @@ fn kafka_source(
     let source = future::lazy(move || {
         let consumer_ref = Arc::clone(&consumer);
 
+        // Offsets are stored in the order the messages were read, once their
+        // events have been delivered.
+        let pending_acks = if config.acknowledgements {
+            let (tx, rx) = mpsc::channel(PENDING_OFFSETS);
+            tokio01::spawn(store_acked_offsets(Arc::clone(&consumer), rx));
+            Some(tx)
+        } else {
+            None
+        };
+
         // See https://github.com/fede1024/rust-rdkafka/issues/85#issuecomment-439141656
         let stream = OwnedConsumerStream {
             upstream: OwningHandle::new_with_fn(consumer.clone(), |c| {
@@ -124,34 +157,105 @@ fn kafka_source(
                             Some(Err(e)) => {
                                 return Err(error!(message = "Cannot extract payload", error = ?e))
                             }
-                            Some(Ok(payload)) => Bytes::from(payload),
+                            Some(Ok(payload)) => BytesMut::from(payload),
                         };
-                        let mut event = Event::from(payload);
-
-                        // Add source type
-                        event
-                            .as_mut_log()
-                            .insert(event::log_schema().source_type_key(), "kafka");
-
-                        if let Some(key_field) = &config.key_field {
-                            match msg.key_view::<[u8]>() {
-                                None => (),
-                                Some(Err(e)) => {
-                                    return Err(error!(message = "Cannot extract key", error = ?e))
-                                }
-                                Some(Ok(key)) => {
-                                    event.as_mut_log().insert(key_field.clone(), key);
-                                }
+                        // Each message is a single frame unless configured otherwise.
+                        let mut events = match config
+                            .decoding
+                            .decoder(FramingConfig::Bytes)
+                            .decode_all(payload)
+                        {
+                            Ok(events) => events,
+                            Err(error) => {
+                                warn!(message = "Cannot decode payload", %error, rate_limit_secs = 30);
+                                Vec::new()
+                            }
+                        };
+
+                        let key = match (&config.key_field, msg.key_view::<[u8]>()) {
+                            (_, None) | (None, _) => None,
+                            (Some(_), Some(Err(e))) => {
+                                return Err(error!(message = "Cannot extract key", error = ?e))
+                            }
+                            (Some(key_field), Some(Ok(key))) => Some((key_field, key)),
+                        };
+
+                        let timestamp = msg
+                            .timestamp()
+                            .to_millis()
+                            .map(|millis| Utc.timestamp_millis(millis));
+                        let headers = config.headers_key.as_ref().map(|_| headers_map(&msg));
+
+                        for event in events.iter_mut() {
+                            let log = event.as_mut_log();
+
+                            // Add source type
+                            log.insert(event::log_schema().source_type_key(), "kafka");
+
+                            if let Some((key_field, key)) = key {
+                                log.insert(key_field.clone(), key);
+                            }
+
+                            if let Some(topic_key) = &config.topic_key {
+                                log.insert(topic_key.clone(), msg.topic());
+                            }
+
+                            if let Some(partition_key) = &config.partition_key {
+                                log.insert(partition_key.clone(), msg.partition());
+                            }
+
+                            if let Some(offset_key) = &config.offset_key {
+                                log.insert(offset_key.clone(), msg.offset());
+                            }
+
+                            if let (Some(timestamp_key), Some(timestamp)) =
+                                (&config.timestamp_key, timestamp)
+                            {
+                                log.insert(timestamp_key.clone(), timestamp);
+                            }
+
+                            if let (Some(headers_key), Some(headers)) =
+                                (&config.headers_key, &headers)
+                            {
+                                log.insert(headers_key.clone(), headers.clone());
                             }
                         }
 
-                        consumer_ref.store_offset(&msg).map_err(
-                            |e| error!(message = "Cannot store offset for the message", error = ?e),
-                        )?;
-                        Ok(event)
+                        let pending = match &pending_acks {
+                            Some(pending_acks) => {
+                                // The offset is stored once all the events of the message
+                                // are delivered, or right away if it had none.
+                                let (batch, receiver) = BatchNotifier::new_with_receiver();
+                                for event in events.iter_mut() {
+                                    event.as_mut_log().add_batch_notifier(Arc::clone(&batch));
+                                }
+
+                                let pending = PendingOffset {
+                                    receiver,
+                                    topic: msg.topic().to_owned(),
+                                    partition: msg.partition(),
+                                    offset: msg.offset(),
+                                };
+                                // Waits for room, so that a slow sink holds back reading.
+                                future::Either::A(
+                                    pending_acks.clone().send(pending).map(|_| ()).map_err(
+                                        |_| error!(message = "Offset acknowledgement task stopped"),
+                                    ),
+                                )
+                            }
+                            None => {
+                                consumer_ref.store_offset(&msg).map_err(
+                                    |e| error!(message = "Cannot store offset for the message", error = ?e),
+                                )?;
+                                future::Either::B(future::ok(()))
+                            }
+                        };
+                        Ok(pending.map(move |_| futures01::stream::iter_ok::<_, ()>(events)))
                     }
                 }
             })
+            .and_then(|pending| pending)
+            .flatten()
             .forward(out.sink_map_err(|e| error!(message = "Error sending to sink", error = ?e)))
             .map(|_| ())
     });
@@ -159,6 +263,77 @@ fn kafka_source(
     Ok(Box::new(source))
 }
 
+/// Header values are kept as bytes, the last one winning when a header is
+/// repeated.
+fn headers_map(msg: &BorrowedMessage) -> BTreeMap<String, Value> {
+    let mut map = BTreeMap::new();
+    if let Some(headers) = msg.headers() {
+        for index in 0..headers.count() {
+            if let Some((name, value)) = headers.get(index) {
+                map.insert(name.to_string(), Value::from(value));
+            }
+        }
+    }
+    map
+}
+
+/// How many messages can wait for their events to be delivered.
+const PENDING_OFFSETS: usize = 1024;
+
+/// A message whose offset is stored once its events are delivered.
+struct PendingOffset {
+    receiver: BatchStatusReceiver,
+    topic: String,
+    partition: i32,
+    offset: i64,
+}
+
+/// Stores the offsets in the order the messages were read. A partition stops
+/// advancing at its first message that wasn't delivered, so that it is read
+/// again by the next consumer of the partition.
+fn store_acked_offsets(
+    consumer: Arc<StreamConsumer>,
+    pending: mpsc::Receiver<PendingOffset>,
+) -> impl Future<Item = (), Error = ()> {
+    let mut stalled = HashSet::new();
+    pending
+        .and_then(|pending| {
+            let PendingOffset {
+                receiver,
+                topic,
+                partition,
+                offset,
+            } = pending;
+            receiver.map(move |status| (topic, partition, offset, status))
+        })
+        .for_each(move |(topic, partition, offset, status)| {
+            if stalled.contains(&(topic.clone(), partition)) {
+                return Ok(());
+            }
+
+            match status {
+                BatchStatus::Delivered => {
+                    let mut offsets = TopicPartitionList::new();
+                    offsets.add_partition_offset(&topic, partition, Offset::Offset(offset + 1));
+                    if let Err(error) = consumer.store_offsets(&offsets) {
+                        error!(message = "Cannot store offset for the message", ?error);
+                    }
+                }
+                BatchStatus::Errored | BatchStatus::Failed => {
+                    warn!(
+                        message = "Event was not delivered; the partition's offset won't advance past it.",
+                        %topic,
+                        %partition,
+                        %offset,
+                        ?status,
+                    );
+                    stalled.insert((topic, partition));
+                }
+            }
+            Ok(())
+        })
+}
+
 fn create_consumer(config: KafkaSourceConfig) -> crate::Result<StreamConsumer> {
     let mut client_config = ClientConfig::new();
     client_config
@@ -260,6 +435,7 @@ mod integration_test {
     use futures01::{sync::mpsc, Future};
     use rdkafka::{
         config::ClientConfig,
+        message::OwnedHeaders,
         producer::{FutureProducer, FutureRecord},
     };
     use string_cache::DefaultAtom as Atom;
@@ -274,7 +450,10 @@ mod integration_test {
             .create()
             .expect("Producer creation error");
 
-        let record = FutureRecord::to(topic).payload(text).key(key);
+        let record = FutureRecord::to(topic)
+            .payload(text)
+            .key(key)
+            .headers(OwnedHeaders::new().add("header-key", "header-value"));
 
         Compat::new(producer.send(record, 0))
             .map(|_| ())
@@ -296,6 +475,11 @@ mod integration_test {
             session_timeout_ms: 6000,
             commit_interval_ms: 5000,
             key_field: Some("message_key".to_string()),
+            topic_key: Some("topic".to_string()),
+            partition_key: Some("partition".to_string()),
+            offset_key: Some("offset".to_string()),
+            timestamp_key: Some("kafka_timestamp".to_string()),
+            headers_key: Some("headers".to_string()),
             socket_timeout_ms: 60000,
             fetch_wait_max_ms: 100,
             ..Default::default()
@@ -321,5 +505,16 @@ mod integration_test {
             events[0].as_log()[event::log_schema().source_type_key()],
             "kafka".into()
         );
+        assert_eq!(events[0].as_log()[&Atom::from("topic")], topic.into());
+        assert_eq!(events[0].as_log()[&Atom::from("partition")], 0.into());
+        assert_eq!(events[0].as_log()[&Atom::from("offset")], 0.into());
+        assert!(events[0]
+            .as_log()
+            .get(&Atom::from("kafka_timestamp"))
+            .is_some());
+        assert_eq!(
+            events[0].as_log()[&Atom::from("headers.header-key")],
+            "header-value".into()
+        );
     }
 }
@@ -3,6 +3,8 @@ use snafu::Snafu;
 
 #[cfg(feature = "sources-docker")]
 pub mod docker;
+#[cfg(feature = "sources-exec")]
+pub mod exec;
 #[cfg(feature = "sources-file")]
 pub mod file;
 #[cfg(feature = "sources-generator")]
@@ -17,6 +19,8 @@ pub mod journald;
 pub mod kafka;
 #[cfg(feature = "sources-logplex")]
 pub mod logplex;
+#[cfg(feature = "sources-opentelemetry")]
+pub mod opentelemetry;
 #[cfg(feature = "sources-prometheus")]
 pub mod prometheus;
 #[cfg(feature = "sources-socket")]
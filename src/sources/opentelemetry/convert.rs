@@ -0,0 +1,421 @@
+//! Conversion of the OTLP export requests into events.
+
+use super::proto::{
+    any_value, metric::Data, number_data_point, AnyValue, ExportLogsServiceRequest,
+    ExportMetricsServiceRequest, HistogramDataPoint, InstrumentationScope, KeyValue, LogRecord,
+    NumberDataPoint,
+};
+use crate::event::{
+    self,
+    metric::{Metric, MetricKind, MetricValue},
+    Event, Value,
+};
+use chrono::{DateTime, TimeZone, Utc};
+use std::collections::BTreeMap;
+
+/// The `AggregationTemporality` of sums and histograms holding deltas, any
+/// other one is taken as cumulative.
+const AGGREGATION_TEMPORALITY_DELTA: i32 = 1;
+
+pub fn decode_logs(request: ExportLogsServiceRequest) -> Vec<Event> {
+    let mut events = Vec::new();
+    for resource_logs in request.resource_logs {
+        let resources = resource_logs
+            .resource
+            .map(|resource| attributes(resource.attributes))
+            .unwrap_or_default();
+        for scope_logs in resource_logs.scope_logs {
+            let scope = scope_logs.scope.map(scope).unwrap_or_default();
+            for record in scope_logs.log_records {
+                events.push(decode_log(record, &resources, &scope));
+            }
+        }
+    }
+    events
+}
+
+fn decode_log(
+    record: LogRecord,
+    resources: &BTreeMap<String, Value>,
+    scope: &BTreeMap<String, Value>,
+) -> Event {
+    let mut event = Event::new_empty_log();
+    let log = event.as_mut_log();
+
+    if let Some(body) = record.body {
+        log.insert(event::log_schema().message_key().clone(), any_value(body));
+    }
+    let timestamp = timestamp(record.time_unix_nano)
+        .or_else(|| timestamp(record.observed_time_unix_nano))
+        .unwrap_or_else(Utc::now);
+    log.insert(event::log_schema().timestamp_key().clone(), timestamp);
+    log.insert(event::log_schema().source_type_key(), "opentelemetry");
+
+    if !record.attributes.is_empty() {
+        log.insert("attributes", attributes(record.attributes));
+    }
+    if !resources.is_empty() {
+        log.insert("resources", resources.clone());
+    }
+    if !scope.is_empty() {
+        log.insert("scope", scope.clone());
+    }
+    if !record.severity_text.is_empty() {
+        log.insert("severity_text", record.severity_text);
+    }
+    if record.severity_number != 0 {
+        log.insert("severity_number", i64::from(record.severity_number));
+    }
+    if !record.trace_id.is_empty() {
+        log.insert("trace_id", hex(&record.trace_id));
+    }
+    if !record.span_id.is_empty() {
+        log.insert("span_id", hex(&record.span_id));
+    }
+    if record.flags != 0 {
+        log.insert("flags", i64::from(record.flags));
+    }
+
+    event
+}
+
+pub fn decode_metrics(request: ExportMetricsServiceRequest) -> Vec<Event> {
+    let mut events = Vec::new();
+    for resource_metrics in request.resource_metrics {
+        let resources = resource_metrics
+            .resource
+            .map(|resource| tags(resource.attributes, &BTreeMap::new()))
+            .unwrap_or_default();
+        for metric in resource_metrics
+            .scope_metrics
+            .into_iter()
+            .flat_map(|scope_metrics| scope_metrics.metrics)
+        {
+            let name = metric.name;
+            match metric.data {
+                Some(Data::Gauge(gauge)) => {
+                    events.extend(gauge.data_points.into_iter().filter_map(|point| {
+                        number(&name, point, &resources, MetricKind::Absolute, false)
+                    }));
+                }
+                Some(Data::Sum(sum)) => {
+                    let kind = kind(sum.aggregation_temporality);
+                    let monotonic = sum.is_monotonic;
+                    events.extend(sum.data_points.into_iter().filter_map(|point| {
+                        number(&name, point, &resources, kind.clone(), monotonic)
+                    }));
+                }
+                Some(Data::Histogram(histogram)) => {
+                    let kind = kind(histogram.aggregation_temporality);
+                    events.extend(
+                        histogram
+                            .data_points
+                            .into_iter()
+                            .map(|point| self::histogram(&name, point, &resources, kind.clone())),
+                    );
+                }
+                None => {
+                    warn!(
+                        message = "Dropping metric of unsupported type.",
+                        %name,
+                        rate_limit_secs = 30,
+                    );
+                }
+            }
+        }
+    }
+    events
+}
+
+fn kind(aggregation_temporality: i32) -> MetricKind {
+    if aggregation_temporality == AGGREGATION_TEMPORALITY_DELTA {
+        MetricKind::Incremental
+    } else {
+        MetricKind::Absolute
+    }
+}
+
+/// Monotonic sums are counters, other sums and gauges are gauges.
+fn number(
+    name: &str,
+    point: NumberDataPoint,
+    resources: &BTreeMap<String, String>,
+    kind: MetricKind,
+    monotonic: bool,
+) -> Option<Event> {
+    let value = match point.value? {
+        number_data_point::Value::AsDouble(value) => value,
+        number_data_point::Value::AsInt(value) => value as f64,
+    };
+    let value = if monotonic {
+        MetricValue::Counter { value }
+    } else {
+        MetricValue::Gauge { value }
+    };
+    Some(Event::Metric(Metric {
+        name: name.into(),
+        timestamp: timestamp(point.time_unix_nano),
+        tags: Some(tags(point.attributes, resources)).filter(|tags| !tags.is_empty()),
+        kind,
+        value,
+    }))
+}
+
+/// The counts of OTLP buckets are their own, and the last bucket has no upper
+/// bound, while the counts of aggregated histograms include all the lower
+/// buckets and the total count stands for the unbounded one.
+fn histogram(
+    name: &str,
+    point: HistogramDataPoint,
+    resources: &BTreeMap<String, String>,
+    kind: MetricKind,
+) -> Event {
+    let mut total = 0;
+    let counts = point
+        .bucket_counts
+        .iter()
+        .take(point.explicit_bounds.len())
+        .map(|count| {
+            total += count;
+            total as u32
+        })
+        .collect();
+    Event::Metric(Metric {
+        name: name.into(),
+        timestamp: timestamp(point.time_unix_nano),
+        tags: Some(tags(point.attributes, resources)).filter(|tags| !tags.is_empty()),
+        kind,
+        value: MetricValue::AggregatedHistogram {
+            buckets: point.explicit_bounds,
+            counts,
+            count: point.count as u32,
+            sum: point.sum,
+        },
+    })
+}
+
+fn timestamp(nanos: u64) -> Option<DateTime<Utc>> {
+    if nanos == 0 {
+        None
+    } else {
+        Some(Utc.timestamp_nanos(nanos as i64))
+    }
+}
+
+fn any_value(value: AnyValue) -> Value {
+    match value.value {
+        Some(any_value::Value::StringValue(value)) => value.into(),
+        Some(any_value::Value::BoolValue(value)) => value.into(),
+        Some(any_value::Value::IntValue(value)) => value.into(),
+        Some(any_value::Value::DoubleValue(value)) => value.into(),
+        Some(any_value::Value::ArrayValue(array)) => {
+            Value::Array(array.values.into_iter().map(any_value).collect())
+        }
+        Some(any_value::Value::KvlistValue(list)) => Value::Map(attributes(list.values)),
+        Some(any_value::Value::BytesValue(value)) => value.into(),
+        None => Value::Null,
+    }
+}
+
+fn attributes(attributes: Vec<KeyValue>) -> BTreeMap<String, Value> {
+    attributes
+        .into_iter()
+        .map(|attribute| {
+            let value = attribute.value.map(any_value).unwrap_or(Value::Null);
+            (attribute.key, value)
+        })
+        .collect()
+}
+
+/// Tags of metrics hold the attributes of the resource, overridden by the
+/// ones of the data point.
+fn tags(
+    attributes: Vec<KeyValue>,
+    resources: &BTreeMap<String, String>,
+) -> BTreeMap<String, String> {
+    let mut tags = resources.clone();
+    tags.extend(
+        self::attributes(attributes)
+            .into_iter()
+            .map(|(key, value)| (key, value.to_string_lossy())),
+    );
+    tags
+}
+
+fn scope(scope: InstrumentationScope) -> BTreeMap<String, Value> {
+    let mut fields = BTreeMap::new();
+    if !scope.name.is_empty() {
+        fields.insert("name".into(), scope.name.into());
+    }
+    if !scope.version.is_empty() {
+        fields.insert("version".into(), scope.version.into());
+    }
+    if !scope.attributes.is_empty() {
+        fields.insert(
+            "attributes".into(),
+            Value::Map(attributes(scope.attributes)),
+        );
+    }
+    fields
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sources::opentelemetry::proto::{
+        metric, Histogram, Resource, ResourceLogs, ResourceMetrics, ScopeLogs, ScopeMetrics, Sum,
+    };
+
+    fn string(value: &str) -> Option<AnyValue> {
+        Some(AnyValue {
+            value: Some(any_value::Value::StringValue(value.into())),
+        })
+    }
+
+    fn key_value(key: &str, value: &str) -> KeyValue {
+        KeyValue {
+            key: key.into(),
+            value: string(value),
+        }
+    }
+
+    fn resource() -> Option<Resource> {
+        Some(Resource {
+            attributes: vec![key_value("service.name", "checkout")],
+        })
+    }
+
+    #[test]
+    fn decodes_logs() {
+        let request = ExportLogsServiceRequest {
+            resource_logs: vec![ResourceLogs {
+                resource: resource(),
+                scope_logs: vec![ScopeLogs {
+                    scope: Some(InstrumentationScope {
+                        name: "checkout.http".into(),
+                        version: "1.2.0".into(),
+                        attributes: Vec::new(),
+                    }),
+                    log_records: vec![LogRecord {
+                        time_unix_nano: 1_591_005_600_000_000_001,
+                        observed_time_unix_nano: 0,
+                        severity_number: 17,
+                        severity_text: "ERROR".into(),
+                        body: string("payment declined"),
+                        attributes: vec![KeyValue {
+                            key: "http.status_code".into(),
+                            value: Some(AnyValue {
+                                value: Some(any_value::Value::IntValue(402)),
+                            }),
+                        }],
+                        flags: 0,
+                        trace_id: vec![0x5b, 0x8e, 0xff, 0xf7],
+                        span_id: Vec::new(),
+                    }],
+                }],
+            }],
+        };
+
+        let events = decode_logs(request);
+        assert_eq!(events.len(), 1);
+        let log = events[0].as_log();
+        assert_eq!(
+            log[&event::log_schema().message_key()],
+            "payment declined".into()
+        );
+        assert_eq!(
+            log[&event::log_schema().timestamp_key()],
+            Utc.ymd(2020, 6, 1).and_hms_nano(10, 0, 0, 1).into()
+        );
+        assert_eq!(log[&"attributes.http\\.status_code".into()], 402.into());
+        assert_eq!(log[&"resources.service\\.name".into()], "checkout".into());
+        assert_eq!(log[&"scope.name".into()], "checkout.http".into());
+        assert_eq!(log[&"scope.version".into()], "1.2.0".into());
+        assert_eq!(log[&"severity_text".into()], "ERROR".into());
+        assert_eq!(log[&"severity_number".into()], 17.into());
+        assert_eq!(log[&"trace_id".into()], "5b8efff7".into());
+        assert!(log.get(&"span_id".into()).is_none());
+    }
+
+    #[test]
+    fn decodes_metrics() {
+        let request = ExportMetricsServiceRequest {
+            resource_metrics: vec![ResourceMetrics {
+                resource: resource(),
+                scope_metrics: vec![ScopeMetrics {
+                    scope: None,
+                    metrics: vec![
+                        super::super::proto::Metric {
+                            name: "requests".into(),
+                            description: String::new(),
+                            unit: String::new(),
+                            data: Some(metric::Data::Sum(Sum {
+                                data_points: vec![NumberDataPoint {
+                                    attributes: vec![key_value("code", "200")],
+                                    start_time_unix_nano: 0,
+                                    time_unix_nano: 1_591_005_600_000_000_000,
+                                    value: Some(number_data_point::Value::AsInt(3)),
+                                }],
+                                aggregation_temporality: AGGREGATION_TEMPORALITY_DELTA,
+                                is_monotonic: true,
+                            })),
+                        },
+                        super::super::proto::Metric {
+                            name: "latency".into(),
+                            description: String::new(),
+                            unit: "s".into(),
+                            data: Some(metric::Data::Histogram(Histogram {
+                                data_points: vec![HistogramDataPoint {
+                                    attributes: Vec::new(),
+                                    start_time_unix_nano: 0,
+                                    time_unix_nano: 0,
+                                    count: 6,
+                                    sum: 4.5,
+                                    bucket_counts: vec![2, 3, 1],
+                                    explicit_bounds: vec![0.5, 1.0],
+                                }],
+                                aggregation_temporality: 2,
+                            })),
+                        },
+                    ],
+                }],
+            }],
+        };
+
+        let tags = |tags: &[(&str, &str)]| {
+            Some(
+                tags.iter()
+                    .map(|(name, value)| (name.to_string(), value.to_string()))
+                    .collect(),
+            )
+        };
+        assert_eq!(
+            decode_metrics(request),
+            vec![
+                Event::Metric(Metric {
+                    name: "requests".into(),
+                    timestamp: Some(Utc.ymd(2020, 6, 1).and_hms(10, 0, 0)),
+                    tags: tags(&[("code", "200"), ("service.name", "checkout")]),
+                    kind: MetricKind::Incremental,
+                    value: MetricValue::Counter { value: 3.0 },
+                }),
+                Event::Metric(Metric {
+                    name: "latency".into(),
+                    timestamp: None,
+                    tags: tags(&[("service.name", "checkout")]),
+                    kind: MetricKind::Absolute,
+                    value: MetricValue::AggregatedHistogram {
+                        buckets: vec![0.5, 1.0],
+                        counts: vec![2, 5],
+                        count: 6,
+                        sum: 4.5,
+                    },
+                }),
+            ]
+        );
+    }
+}
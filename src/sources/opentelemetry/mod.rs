@@ -0,0 +1,618 @@
+use crate::{
+    shutdown::ShutdownSignal,
+    tls::{MaybeTlsSettings, TlsConfig},
+    topology::config::{DataType, GlobalOptions, SourceConfig, SourceDescription},
+    Event,
+};
+use bytes::Bytes;
+use flate2::read::GzDecoder;
+use futures01::{
+    future::{self, Either},
+    sync::mpsc,
+    Async, Future, Poll, Sink, Stream,
+};
+use http::{
+    header::{HeaderValue, CONTENT_ENCODING, CONTENT_TYPE},
+    request::Parts,
+    HeaderMap, Method, Request, Response, StatusCode,
+};
+use hyper::{
+    body::Payload,
+    service::{make_service_fn, service_fn},
+    Body, Server,
+};
+use prost::Message;
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
+use std::{
+    io::Read,
+    net::{Ipv4Addr, SocketAddr},
+};
+
+mod convert;
+
+mod proto {
+    include!(concat!(env!("OUT_DIR"), "/opentelemetry.rs"));
+}
+
+const LOGS_SERVICE_PATH: &str = "/opentelemetry.proto.collector.logs.v1.LogsService/Export";
+const METRICS_SERVICE_PATH: &str =
+    "/opentelemetry.proto.collector.metrics.v1.MetricsService/Export";
+
+/// HTTP/2 first for gRPC, then HTTP/1.1, in the ALPN wire format.
+const ALPN_PROTOCOLS: &[u8] = b"\x02h2\x08http/1.1";
+
+/// Receives logs and metrics sent with the OpenTelemetry protocol, either over
+/// gRPC or as protobuf over HTTP, on the same address.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct OpenTelemetryConfig {
+    #[serde(default = "default_address")]
+    address: SocketAddr,
+    tls: Option<TlsConfig>,
+}
+
+fn default_address() -> SocketAddr {
+    SocketAddr::new(Ipv4Addr::new(0, 0, 0, 0).into(), 4317)
+}
+
+inventory::submit! {
+    SourceDescription::new_without_default::<OpenTelemetryConfig>("opentelemetry")
+}
+
+#[typetag::serde(name = "opentelemetry")]
+impl SourceConfig for OpenTelemetryConfig {
+    fn build(
+        &self,
+        _: &str,
+        _: &GlobalOptions,
+        shutdown: ShutdownSignal,
+        out: mpsc::Sender<Event>,
+    ) -> crate::Result<super::Source> {
+        // gRPC clients only speak HTTP/2 over TLS once it is agreed on with
+        // ALPN.
+        let tls = MaybeTlsSettings::from_config(&self.tls, true)?.alpn_protocols(ALPN_PROTOCOLS);
+        let incoming = tls.bind(&self.address)?.incoming();
+
+        let make_service = make_service_fn(move |_| {
+            let out = out.clone();
+            service_fn(move |request| handle(request, out.clone()))
+        });
+
+        info!(message = "building opentelemetry server", addr = %self.address);
+
+        // HTTP/2 connections, as used by gRPC, are told apart from HTTP/1 ones
+        // by the server.
+        let server = Server::builder(incoming)
+            .serve(make_service)
+            .with_graceful_shutdown(shutdown.clone().map(|_| ()))
+            .map_err(|error| error!(message = "opentelemetry server error", %error))
+            .map(|_| drop(shutdown));
+
+        Ok(Box::new(server))
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Any
+    }
+
+    fn source_type(&self) -> &'static str {
+        "opentelemetry"
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Protocol {
+    Grpc,
+    Http,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Signal {
+    Logs,
+    Metrics,
+}
+
+#[derive(Debug, Snafu)]
+enum RequestError {
+    #[snafu(display("Unknown method or path"))]
+    NotFound,
+    #[snafu(display("Unsupported content type {:?}, only protobuf is", content_type))]
+    UnsupportedContentType { content_type: String },
+    #[snafu(display("Unsupported encoding {:?}", encoding))]
+    UnsupportedEncoding { encoding: String },
+    #[snafu(display("Truncated gRPC message"))]
+    Truncated,
+    #[snafu(display("Could not decompress body: {}", source))]
+    Decompress { source: std::io::Error },
+    #[snafu(display("Could not decode request: {}", source))]
+    Decode { source: prost::DecodeError },
+    #[snafu(display("Shutting down"))]
+    ShuttingDown,
+}
+
+impl RequestError {
+    fn status(&self) -> StatusCode {
+        match self {
+            RequestError::NotFound => StatusCode::NOT_FOUND,
+            RequestError::UnsupportedContentType { .. } => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            RequestError::ShuttingDown => StatusCode::SERVICE_UNAVAILABLE,
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+
+    /// The gRPC status codes are UNIMPLEMENTED, UNAVAILABLE and
+    /// INVALID_ARGUMENT.
+    fn grpc_status(&self) -> &'static str {
+        match self {
+            RequestError::NotFound => "12",
+            RequestError::ShuttingDown => "14",
+            _ => "3",
+        }
+    }
+}
+
+fn handle(
+    request: Request<Body>,
+    out: mpsc::Sender<Event>,
+) -> impl Future<Item = Response<ResponseBody>, Error = hyper::Error> {
+    let (parts, body) = request.into_parts();
+    let protocol = if is_grpc(&parts.headers) {
+        Protocol::Grpc
+    } else {
+        Protocol::Http
+    };
+
+    body.concat2()
+        .and_then(move |body| match decode(protocol, &parts, &body) {
+            Ok((signal, events)) => Either::A(
+                out.send_all(futures01::stream::iter_ok(events))
+                    .then(move |result| match result {
+                        Ok(_) => Ok(success(protocol, signal)),
+                        Err(_) => {
+                            error!("Failed to forward events, downstream is closed");
+                            Ok(failure(protocol, RequestError::ShuttingDown))
+                        }
+                    }),
+            ),
+            Err(error) => {
+                debug!(message = "invalid opentelemetry request", %error);
+                Either::B(future::ok(failure(protocol, error)))
+            }
+        })
+}
+
+fn is_grpc(headers: &HeaderMap) -> bool {
+    headers
+        .get(CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .map_or(false, |content_type| {
+            content_type.starts_with("application/grpc")
+        })
+}
+
+fn decode(
+    protocol: Protocol,
+    parts: &Parts,
+    body: &[u8],
+) -> Result<(Signal, Vec<Event>), RequestError> {
+    let signal = match (parts.method == Method::POST, protocol, parts.uri.path()) {
+        (true, Protocol::Grpc, LOGS_SERVICE_PATH) | (true, Protocol::Http, "/v1/logs") => {
+            Signal::Logs
+        }
+        (true, Protocol::Grpc, METRICS_SERVICE_PATH) | (true, Protocol::Http, "/v1/metrics") => {
+            Signal::Metrics
+        }
+        _ => return Err(RequestError::NotFound),
+    };
+
+    let message = match protocol {
+        Protocol::Grpc => grpc_message(&parts.headers, body)?,
+        Protocol::Http => http_message(&parts.headers, body)?,
+    };
+
+    let events = match signal {
+        Signal::Logs => convert::decode_logs(
+            proto::ExportLogsServiceRequest::decode(&message[..]).context(Decode)?,
+        ),
+        Signal::Metrics => convert::decode_metrics(
+            proto::ExportMetricsServiceRequest::decode(&message[..]).context(Decode)?,
+        ),
+    };
+    Ok((signal, events))
+}
+
+/// Unary gRPC requests hold a single message, prefixed with a byte telling if
+/// it is compressed and its length on four bytes.
+fn grpc_message(headers: &HeaderMap, body: &[u8]) -> Result<Vec<u8>, RequestError> {
+    if body.len() < 5 {
+        return Err(RequestError::Truncated);
+    }
+    let length = u32::from_be_bytes([body[1], body[2], body[3], body[4]]) as usize;
+    let message = body.get(5..5 + length).ok_or(RequestError::Truncated)?;
+    if body[0] == 0 {
+        Ok(message.to_vec())
+    } else {
+        decompress(header(headers, "grpc-encoding"), message)
+    }
+}
+
+fn http_message(headers: &HeaderMap, body: &[u8]) -> Result<Vec<u8>, RequestError> {
+    match header(headers, CONTENT_TYPE.as_str()) {
+        Some("application/x-protobuf") => (),
+        content_type => {
+            return Err(RequestError::UnsupportedContentType {
+                content_type: content_type.unwrap_or_default().into(),
+            })
+        }
+    }
+    match header(headers, CONTENT_ENCODING.as_str()) {
+        None | Some("identity") => Ok(body.to_vec()),
+        encoding => decompress(encoding, body),
+    }
+}
+
+fn header<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name).and_then(|value| value.to_str().ok())
+}
+
+fn decompress(encoding: Option<&str>, body: &[u8]) -> Result<Vec<u8>, RequestError> {
+    match encoding {
+        Some("gzip") => {
+            let mut decoded = Vec::new();
+            GzDecoder::new(body)
+                .read_to_end(&mut decoded)
+                .context(Decompress)?;
+            Ok(decoded)
+        }
+        encoding => Err(RequestError::UnsupportedEncoding {
+            encoding: encoding.unwrap_or_default().into(),
+        }),
+    }
+}
+
+/// Responses are empty messages, as partial successes aren't reported.
+fn success(protocol: Protocol, signal: Signal) -> Response<ResponseBody> {
+    let mut message = Vec::new();
+    let encoded = match signal {
+        Signal::Logs => proto::ExportLogsServiceResponse::default().encode(&mut message),
+        Signal::Metrics => proto::ExportMetricsServiceResponse::default().encode(&mut message),
+    };
+    encoded.expect("Vec grows as needed");
+
+    match protocol {
+        Protocol::Grpc => {
+            let mut body = Vec::with_capacity(5 + message.len());
+            body.push(0);
+            body.extend_from_slice(&(message.len() as u32).to_be_bytes());
+            body.extend_from_slice(&message);
+
+            let mut trailers = HeaderMap::new();
+            trailers.insert("grpc-status", HeaderValue::from_static("0"));
+            Response::builder()
+                .header(CONTENT_TYPE, "application/grpc")
+                .body(ResponseBody {
+                    data: Some(body.into()),
+                    trailers: Some(trailers),
+                })
+                .expect("valid response")
+        }
+        Protocol::Http => Response::builder()
+            .header(CONTENT_TYPE, "application/x-protobuf")
+            .body(ResponseBody {
+                data: Some(message.into()),
+                trailers: None,
+            })
+            .expect("valid response"),
+    }
+}
+
+/// Failed gRPC calls are answered with their status alone in the headers.
+fn failure(protocol: Protocol, error: RequestError) -> Response<ResponseBody> {
+    let message = error.to_string();
+    match protocol {
+        Protocol::Grpc => Response::builder()
+            .header(CONTENT_TYPE, "application/grpc")
+            .header("grpc-status", error.grpc_status())
+            .header(
+                "grpc-message",
+                HeaderValue::from_str(&message)
+                    .unwrap_or_else(|_| HeaderValue::from_static("invalid request")),
+            )
+            .body(ResponseBody {
+                data: None,
+                trailers: None,
+            })
+            .expect("valid response"),
+        Protocol::Http => Response::builder()
+            .status(error.status())
+            .header(CONTENT_TYPE, "text/plain")
+            .body(ResponseBody {
+                data: Some(message.into()),
+                trailers: None,
+            })
+            .expect("valid response"),
+    }
+}
+
+/// A response body with the trailers gRPC sends its status in, which are only
+/// sent over HTTP/2.
+struct ResponseBody {
+    data: Option<Bytes>,
+    trailers: Option<HeaderMap>,
+}
+
+impl Payload for ResponseBody {
+    type Data = Bytes;
+    type Error = hyper::Error;
+
+    fn poll_data(&mut self) -> Poll<Option<Self::Data>, Self::Error> {
+        Ok(Async::Ready(self.data.take()))
+    }
+
+    fn poll_trailers(&mut self) -> Poll<Option<HeaderMap>, Self::Error> {
+        Ok(Async::Ready(self.trailers.take()))
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.data.is_none() && self.trailers.is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        event::{self, metric::MetricValue},
+        runtime::Runtime,
+        test_util::{self, collect_n},
+        tls::TlsOptions,
+    };
+    use futures01::try_ready;
+    use hyper::client::HttpConnector;
+    use hyper_openssl::HttpsConnector;
+    use openssl::ssl::{SslConnector, SslConnectorBuilder, SslMethod, SslVerifyMode};
+    use proto::{
+        any_value, metric, AnyValue, ExportLogsServiceRequest, ExportMetricsServiceRequest, Gauge,
+        LogRecord, NumberDataPoint, ResourceLogs, ResourceMetrics, ScopeLogs, ScopeMetrics,
+    };
+    use std::net::TcpStream;
+
+    fn logs_request() -> ExportLogsServiceRequest {
+        ExportLogsServiceRequest {
+            resource_logs: vec![ResourceLogs {
+                resource: None,
+                scope_logs: vec![ScopeLogs {
+                    scope: None,
+                    log_records: vec![LogRecord {
+                        time_unix_nano: 0,
+                        observed_time_unix_nano: 0,
+                        severity_number: 9,
+                        severity_text: "INFO".into(),
+                        body: Some(AnyValue {
+                            value: Some(any_value::Value::StringValue("hello".into())),
+                        }),
+                        attributes: Vec::new(),
+                        flags: 0,
+                        trace_id: Vec::new(),
+                        span_id: Vec::new(),
+                    }],
+                }],
+            }],
+        }
+    }
+
+    fn metrics_request() -> ExportMetricsServiceRequest {
+        ExportMetricsServiceRequest {
+            resource_metrics: vec![ResourceMetrics {
+                resource: None,
+                scope_metrics: vec![ScopeMetrics {
+                    scope: None,
+                    metrics: vec![proto::Metric {
+                        name: "temperature".into(),
+                        description: String::new(),
+                        unit: String::new(),
+                        data: Some(metric::Data::Gauge(Gauge {
+                            data_points: vec![NumberDataPoint {
+                                attributes: Vec::new(),
+                                start_time_unix_nano: 0,
+                                time_unix_nano: 0,
+                                value: Some(proto::number_data_point::Value::AsDouble(21.5)),
+                            }],
+                        })),
+                    }],
+                }],
+            }],
+        }
+    }
+
+    fn encode(message: impl Message) -> Vec<u8> {
+        let mut body = Vec::new();
+        message.encode(&mut body).unwrap();
+        body
+    }
+
+    fn parts(path: &str, headers: &[(&str, &str)]) -> Parts {
+        let mut builder = Request::post(path);
+        for (name, value) in headers {
+            builder.header(*name, *value);
+        }
+        builder.body(()).unwrap().into_parts().0
+    }
+
+    #[test]
+    fn decodes_grpc_requests() {
+        let message = encode(logs_request());
+        let mut body = vec![0];
+        body.extend_from_slice(&(message.len() as u32).to_be_bytes());
+        body.extend_from_slice(&message);
+
+        let parts = parts(
+            LOGS_SERVICE_PATH,
+            &[("content-type", "application/grpc+proto")],
+        );
+        let (signal, events) = decode(Protocol::Grpc, &parts, &body).unwrap();
+        assert_eq!(signal, Signal::Logs);
+        assert_eq!(
+            events[0].as_log()[&event::log_schema().message_key()],
+            "hello".into()
+        );
+
+        assert!(matches!(
+            decode(Protocol::Grpc, &parts, &body[..body.len() - 1]),
+            Err(RequestError::Truncated)
+        ));
+    }
+
+    #[test]
+    fn decodes_http_requests() {
+        let body = encode(metrics_request());
+        let (signal, events) = decode(
+            Protocol::Http,
+            &parts("/v1/metrics", &[("content-type", "application/x-protobuf")]),
+            &body,
+        )
+        .unwrap();
+        assert_eq!(signal, Signal::Metrics);
+        assert_eq!(
+            events[0].as_metric().value,
+            MetricValue::Gauge { value: 21.5 }
+        );
+
+        assert!(matches!(
+            decode(
+                Protocol::Http,
+                &parts("/v1/metrics", &[("content-type", "application/json")]),
+                &body,
+            ),
+            Err(RequestError::UnsupportedContentType { .. })
+        ));
+        assert!(matches!(
+            decode(
+                Protocol::Http,
+                &parts("/v1/traces", &[("content-type", "application/x-protobuf")]),
+                &body,
+            ),
+            Err(RequestError::NotFound)
+        ));
+    }
+
+    #[test]
+    fn receives_http_requests() {
+        test_util::trace_init();
+        let mut rt = Runtime::new().unwrap();
+        let (sender, recv) = mpsc::channel(100);
+        let address = test_util::next_addr();
+        rt.spawn(
+            OpenTelemetryConfig { address, tls: None }
+                .build(
+                    "default",
+                    &GlobalOptions::default(),
+                    ShutdownSignal::noop(),
+                    sender,
+                )
+                .unwrap(),
+        );
+        test_util::wait_for_tcp(address);
+
+        let send = |path: &str, body: Vec<u8>| {
+            reqwest::Client::new()
+                .post(&format!("http://{}{}", address, path))
+                .header("Content-Type", "application/x-protobuf")
+                .body(body)
+                .send()
+                .unwrap()
+                .status()
+                .as_u16()
+        };
+        assert_eq!(200, send("/v1/logs", encode(logs_request())));
+        assert_eq!(200, send("/v1/metrics", encode(metrics_request())));
+        assert_eq!(400, send("/v1/logs", b"not protobuf".to_vec()));
+
+        let events = rt.block_on(collect_n(recv, 2)).ok().unwrap();
+        assert_eq!(
+            events[0].as_log()[&event::log_schema().message_key()],
+            "hello".into()
+        );
+        assert_eq!(events[1].as_metric().name, "temperature");
+    }
+
+    fn grpc_body(message: Vec<u8>) -> Vec<u8> {
+        let mut body = vec![0];
+        body.extend_from_slice(&(message.len() as u32).to_be_bytes());
+        body.extend_from_slice(&message);
+        body
+    }
+
+    fn ssl_connector(alpn_protocols: &[u8]) -> SslConnectorBuilder {
+        let mut builder = SslConnector::builder(SslMethod::tls()).unwrap();
+        builder.set_verify(SslVerifyMode::NONE);
+        builder.set_alpn_protos(alpn_protocols).unwrap();
+        builder
+    }
+
+    #[test]
+    fn receives_grpc_requests_over_tls() {
+        test_util::trace_init();
+        let mut rt = Runtime::new().unwrap();
+        let (sender, recv) = mpsc::channel(100);
+        let address = test_util::next_addr();
+        let tls = Some(TlsConfig {
+            enabled: Some(true),
+            options: TlsOptions {
+                crt_path: Some("tests/data/localhost.crt".into()),
+                key_path: Some("tests/data/localhost.key".into()),
+                ..Default::default()
+            },
+        });
+        rt.spawn(
+            OpenTelemetryConfig { address, tls }
+                .build(
+                    "default",
+                    &GlobalOptions::default(),
+                    ShutdownSignal::noop(),
+                    sender,
+                )
+                .unwrap(),
+        );
+        test_util::wait_for_tcp(address);
+
+        // Clients offering both protocols are steered to HTTP/2.
+        let stream = TcpStream::connect(address).unwrap();
+        let stream = ssl_connector(b"\x08http/1.1\x02h2")
+            .build()
+            .connect("localhost", stream)
+            .unwrap();
+        assert_eq!(stream.ssl().selected_alpn_protocol(), Some(&b"h2"[..]));
+
+        let mut http = HttpConnector::new(1);
+        http.enforce_http(false);
+        let https = HttpsConnector::with_connector(http, ssl_connector(b"\x02h2")).unwrap();
+        let client = hyper::Client::builder()
+            .http2_only(true)
+            .build::<_, Body>(https);
+
+        let request = Request::post(format!("https://{}{}", address, LOGS_SERVICE_PATH))
+            .header(CONTENT_TYPE, "application/grpc")
+            .header("te", "trailers")
+            .body(Body::from(grpc_body(encode(logs_request()))))
+            .unwrap();
+        let trailers = rt
+            .block_on(client.request(request).and_then(|response| {
+                let mut body = response.into_body();
+                future::poll_fn(move || {
+                    while try_ready!(body.poll_data()).is_some() {}
+                    body.poll_trailers()
+                })
+            }))
+            .unwrap()
+            .unwrap();
+        assert_eq!(trailers["grpc-status"], "0");
+
+        let events = rt.block_on(collect_n(recv, 1)).ok().unwrap();
+        assert_eq!(
+            events[0].as_log()[&event::log_schema().message_key()],
+            "hello".into()
+        );
+    }
+}
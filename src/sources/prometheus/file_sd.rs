@@ -0,0 +1,215 @@
+//! Discovery of scrape targets from files laid out like the Prometheus
+//! `file_sd_configs`: lists of target groups, each holding addresses and the
+//! labels shared by them.
+
+use super::Target;
+use crate::internal_events::PrometheusFileSdError;
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct FileSdConfig {
+    /// Paths of the target files, the last component of which may be a glob
+    /// pattern.
+    pub files: Vec<String>,
+    #[serde(default = "default_refresh_interval_secs")]
+    pub refresh_interval_secs: u64,
+}
+
+fn default_refresh_interval_secs() -> u64 {
+    60
+}
+
+#[derive(Debug, Snafu)]
+pub enum FileSdError {
+    #[snafu(display("Invalid pattern {:?}: {}", pattern, source))]
+    InvalidPattern {
+        pattern: String,
+        source: glob::PatternError,
+    },
+    #[snafu(display("Could not list files: {}", source))]
+    ListFiles { source: glob::GlobError },
+    #[snafu(display("Could not read {:?}: {}", path, source))]
+    ReadFile {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[snafu(display("Could not parse {:?}: {}", path, source))]
+    ParseJson {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+    #[snafu(display("Could not parse {:?}: {}", path, source))]
+    ParseYaml {
+        path: PathBuf,
+        source: serde_yaml::Error,
+    },
+    #[snafu(display("Invalid target {:?} in {:?}", target, path))]
+    InvalidTarget { path: PathBuf, target: String },
+}
+
+#[derive(Deserialize, Debug)]
+struct TargetGroup {
+    targets: Vec<String>,
+    #[serde(default)]
+    labels: BTreeMap<String, String>,
+}
+
+/// Keeps the targets read from the files, reading them again once the refresh
+/// interval is over. Targets are kept as they were when the files can't be
+/// read, so that a file being rewritten doesn't stop the scrapes.
+pub struct FileSd {
+    config: FileSdConfig,
+    targets: Vec<Target>,
+    refreshed: Option<Instant>,
+}
+
+impl FileSd {
+    pub fn new(config: FileSdConfig) -> Self {
+        Self {
+            config,
+            targets: Vec::new(),
+            refreshed: None,
+        }
+    }
+
+    pub fn targets(&mut self) -> &[Target] {
+        let interval = Duration::from_secs(self.config.refresh_interval_secs);
+        if self.refreshed.map_or(true, |at| at.elapsed() >= interval) {
+            self.refreshed = Some(Instant::now());
+            match read_targets(&self.config.files) {
+                Ok(targets) => {
+                    if targets != self.targets {
+                        debug!(message = "Discovered targets.", count = targets.len());
+                    }
+                    self.targets = targets;
+                }
+                Err(error) => emit!(PrometheusFileSdError { error }),
+            }
+        }
+        &self.targets
+    }
+}
+
+fn read_targets(patterns: &[String]) -> Result<Vec<Target>, FileSdError> {
+    let mut targets = Vec::new();
+    for pattern in patterns {
+        let paths = glob::glob(pattern).context(InvalidPattern { pattern })?;
+        for path in paths {
+            let path = path.context(ListFiles)?;
+            for group in read_groups(&path)? {
+                for address in group.targets {
+                    let target = Target::discovered(&address, &group.labels).ok_or_else(|| {
+                        FileSdError::InvalidTarget {
+                            path: path.clone(),
+                            target: address.clone(),
+                        }
+                    })?;
+                    targets.push(target);
+                }
+            }
+        }
+    }
+    Ok(targets)
+}
+
+/// Files ending with `.json` are read as JSON, any other one as YAML.
+fn read_groups(path: &Path) -> Result<Vec<TargetGroup>, FileSdError> {
+    let content = fs::read(path).context(ReadFile { path })?;
+    if path
+        .extension()
+        .map_or(false, |extension| extension == "json")
+    {
+        serde_json::from_slice(&content).context(ParseJson { path })
+    } else {
+        serde_yaml::from_slice(&content).context(ParseYaml { path })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::temp_dir;
+
+    fn labels(target: &Target) -> Vec<(&str, &str)> {
+        target
+            .labels
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn reads_json_and_yaml_files() {
+        let dir = temp_dir();
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("web.json"),
+            r#"[{"targets": ["web-1:9100", "web-2:9100"], "labels": {"env": "prod"}}]"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.join("db.yml"),
+            r#"
+- targets: ["db-1:9187"]
+  labels:
+    __scheme__: https
+    __metrics_path__: /probe
+    instance: primary
+"#,
+        )
+        .unwrap();
+
+        let mut targets = read_targets(&[
+            dir.join("*.json").to_string_lossy().into_owned(),
+            dir.join("*.yml").to_string_lossy().into_owned(),
+        ])
+        .unwrap();
+        targets.sort_by(|a, b| a.url.cmp(&b.url));
+
+        let urls = targets
+            .iter()
+            .map(|target| target.url.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            urls,
+            vec![
+                "http://web-1:9100/metrics",
+                "http://web-2:9100/metrics",
+                "https://db-1:9187/probe",
+            ]
+        );
+        assert_eq!(
+            labels(&targets[0]),
+            vec![("env", "prod"), ("instance", "web-1:9100")]
+        );
+        assert_eq!(labels(&targets[2]), vec![("instance", "primary")]);
+    }
+
+    #[test]
+    fn keeps_targets_on_errors() {
+        let dir = temp_dir();
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("targets.json");
+        fs::write(&path, r#"[{"targets": ["web-1:9100"]}]"#).unwrap();
+
+        let mut file_sd = FileSd::new(FileSdConfig {
+            files: vec![path.to_string_lossy().into_owned()],
+            refresh_interval_secs: 0,
+        });
+        assert_eq!(file_sd.targets().len(), 1);
+
+        fs::write(&path, r#"[{"targets": ["web-1:9100", "web-2:9100"]}]"#).unwrap();
+        assert_eq!(file_sd.targets().len(), 2);
+
+        fs::write(&path, "[{").unwrap();
+        assert_eq!(file_sd.targets().len(), 2);
+    }
+}
@@ -1,23 +1,37 @@
 use crate::{
-    internal_events::{PrometheusHttpError, PrometheusParseError, PrometheusRequestCompleted},
+    event::metric::{Metric, MetricKind, MetricValue},
+    internal_events::{
+        PrometheusHttpError, PrometheusHttpResponseError, PrometheusParseError,
+        PrometheusRequestCompleted,
+    },
     shutdown::ShutdownSignal,
     stream::StreamExt,
     topology::config::GlobalOptions,
     Event,
 };
-use futures01::{sync::mpsc, Future, Sink, Stream};
+use chrono::Utc;
+use file_sd::{FileSd, FileSdConfig};
+use futures01::{stream, sync::mpsc, Future, Sink, Stream};
 use http::Uri;
+use hyper::client::HttpConnector;
 use hyper_openssl::HttpsConnector;
 use serde::{Deserialize, Serialize};
-use snafu::ResultExt;
-use std::time::{Duration, Instant};
+use snafu::{ResultExt, Snafu};
+use std::{
+    collections::BTreeMap,
+    time::{Duration, Instant},
+};
 use tokio01::timer::Interval;
 
+pub mod file_sd;
 pub mod parser;
+pub mod remote_write;
 
 #[derive(Deserialize, Serialize, Clone, Debug)]
 struct PrometheusConfig {
+    #[serde(default)]
     hosts: Vec<String>,
+    file_sd: Option<FileSdConfig>,
     #[serde(default = "default_scrape_interval_secs")]
     scrape_interval_secs: u64,
 }
@@ -26,6 +40,12 @@ pub fn default_scrape_interval_secs() -> u64 {
     15
 }
 
+#[derive(Debug, Snafu)]
+enum BuildError {
+    #[snafu(display("At least one of `hosts` or `file_sd` must be set"))]
+    NoTargets,
+}
+
 #[typetag::serde(name = "prometheus")]
 impl crate::topology::config::SourceConfig for PrometheusConfig {
     fn build(
@@ -35,12 +55,25 @@ impl crate::topology::config::SourceConfig for PrometheusConfig {
         shutdown: ShutdownSignal,
         out: mpsc::Sender<Event>,
     ) -> crate::Result<super::Source> {
-        let mut urls = Vec::new();
+        if self.hosts.is_empty() && self.file_sd.is_none() {
+            return Err(BuildError::NoTargets.into());
+        }
+
+        let mut hosts = Vec::new();
         for host in self.hosts.iter() {
             let base_uri = host.parse::<Uri>().context(super::UriParseError)?;
-            urls.push(format!("{}metrics", base_uri));
+            hosts.push(Target::host(&base_uri));
         }
-        Ok(prometheus(urls, self.scrape_interval_secs, shutdown, out))
+        let targets = Targets {
+            hosts,
+            file_sd: self.file_sd.clone().map(FileSd::new),
+        };
+        Ok(prometheus(
+            targets,
+            self.scrape_interval_secs,
+            shutdown,
+            out,
+        ))
     }
 
     fn output_type(&self) -> crate::topology::config::DataType {
@@ -52,54 +85,191 @@ impl crate::topology::config::SourceConfig for PrometheusConfig {
     }
 }
 
+/// An endpoint to scrape, along with the labels added to its metrics.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Target {
+    url: String,
+    instance: String,
+    labels: BTreeMap<String, String>,
+}
+
+impl Target {
+    /// Hosts configured statically are scraped as they always were, without
+    /// labels.
+    fn host(base_uri: &Uri) -> Self {
+        Self {
+            url: format!("{}metrics", base_uri),
+            instance: base_uri
+                .authority_part()
+                .map(|authority| authority.to_string())
+                .unwrap_or_default(),
+            labels: BTreeMap::new(),
+        }
+    }
+
+    /// Builds a target from its address and the labels of its group. The
+    /// labels starting with `__` are only used to build the URL, following
+    /// Prometheus, and `instance` defaults to the address.
+    fn discovered(address: &str, labels: &BTreeMap<String, String>) -> Option<Self> {
+        let scheme = labels.get("__scheme__").map_or("http", String::as_str);
+        let path = labels
+            .get("__metrics_path__")
+            .map_or("/metrics", String::as_str);
+        let url = format!("{}://{}{}", scheme, address, path);
+        url.parse::<Uri>().ok()?;
+
+        let mut labels = labels
+            .iter()
+            .filter(|(name, _)| !name.starts_with("__"))
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect::<BTreeMap<_, _>>();
+        labels
+            .entry("instance".into())
+            .or_insert_with(|| address.into());
+
+        Some(Self {
+            url,
+            instance: address.into(),
+            labels,
+        })
+    }
+
+    /// Adds the labels of the target to a scraped metric. Tags the metric
+    /// already has are kept as `exported_<name>`, as Prometheus does.
+    fn add_labels(&self, metric: &mut Metric) {
+        if self.labels.is_empty() {
+            return;
+        }
+        let tags = metric.tags.get_or_insert_with(BTreeMap::new);
+        for (name, value) in &self.labels {
+            if let Some(exported) = tags.insert(name.clone(), value.clone()) {
+                if &exported != value {
+                    tags.insert(format!("exported_{}", name), exported);
+                }
+            }
+        }
+    }
+
+    /// The `up` and `scrape_duration_seconds` metrics of a scrape.
+    fn health(&self, up: bool, duration: Duration) -> Vec<Event> {
+        let mut tags = self.labels.clone();
+        tags.entry("instance".into())
+            .or_insert_with(|| self.instance.clone());
+        let timestamp = Some(Utc::now());
+
+        let gauge = |name: &str, value| {
+            Event::Metric(Metric {
+                name: name.into(),
+                timestamp,
+                tags: Some(tags.clone()),
+                kind: MetricKind::Absolute,
+                value: MetricValue::Gauge { value },
+            })
+        };
+        vec![
+            gauge("up", if up { 1.0 } else { 0.0 }),
+            gauge("scrape_duration_seconds", duration.as_secs_f64()),
+        ]
+    }
+}
+
+struct Targets {
+    hosts: Vec<Target>,
+    file_sd: Option<FileSd>,
+}
+
+impl Targets {
+    fn current(&mut self) -> Vec<Target> {
+        let mut targets = self.hosts.clone();
+        if let Some(file_sd) = &mut self.file_sd {
+            targets.extend_from_slice(file_sd.targets());
+        }
+        targets
+    }
+}
+
 fn prometheus(
-    urls: Vec<String>,
+    mut targets: Targets,
     interval: u64,
     shutdown: ShutdownSignal,
     out: mpsc::Sender<Event>,
 ) -> super::Source {
     let out = out.sink_map_err(|e| error!("error sending metric: {:?}", e));
 
+    let https = HttpsConnector::new(4).expect("TLS initialization failed");
+    let client = hyper::Client::builder().build(https);
+
     let task = Interval::new(Instant::now(), Duration::from_secs(interval))
         .map_err(|e| error!("timer error: {:?}", e))
         .take_until(shutdown)
-        .map(move |_| futures01::stream::iter_ok(urls.clone()))
+        .map(move |_| stream::iter_ok::<_, ()>(targets.current()))
         .flatten()
-        .map(move |url| {
-            let https = HttpsConnector::new(4).expect("TLS initialization failed");
-            let client = hyper::Client::builder().build(https);
-
-            let request = hyper::Request::get(&url)
-                .body(hyper::Body::empty())
-                .expect("error creating request");
-
-            client
-                .request(request)
-                .and_then(|response| response.into_body().concat2())
-                .map(|body| {
+        .and_then(move |target| scrape(&client, target))
+        .map(stream::iter_ok::<_, ()>)
+        .flatten()
+        .forward(out)
+        .map(|_| info!("finished sending"));
+
+    Box::new(task)
+}
+
+/// Scrapes a target, the returned future never fails as errors show up as the
+/// `up` metric being 0.
+fn scrape(
+    client: &hyper::Client<HttpsConnector<HttpConnector>>,
+    target: Target,
+) -> impl Future<Item = Vec<Event>, Error = ()> {
+    let start = Instant::now();
+    let request = hyper::Request::get(&target.url)
+        .body(hyper::Body::empty())
+        .expect("error creating request");
+
+    client
+        .request(request)
+        .and_then(|response| {
+            let status = response.status();
+            response
+                .into_body()
+                .concat2()
+                .map(move |body| (status, body))
+        })
+        .then(move |result| {
+            let metrics = match result {
+                Ok((status, body)) if status.is_success() => {
                     emit!(PrometheusRequestCompleted);
 
                     let packet = String::from_utf8_lossy(&body);
-                    let metrics = parser::parse(&packet)
+                    parser::parse(&packet)
                         .map_err(|error| {
                             emit!(PrometheusParseError { error });
                         })
-                        .unwrap_or_default()
-                        .into_iter()
-                        .map(Event::Metric);
-
-                    futures01::stream::iter_ok(metrics)
-                })
-                .flatten_stream()
-                .map_err(|error| {
+                        .ok()
+                }
+                Ok((code, _)) => {
+                    emit!(PrometheusHttpResponseError {
+                        code,
+                        url: target.url.clone(),
+                    });
+                    None
+                }
+                Err(error) => {
                     emit!(PrometheusHttpError { error });
+                    None
+                }
+            };
+
+            let up = metrics.is_some();
+            let mut events = metrics
+                .unwrap_or_default()
+                .into_iter()
+                .map(|mut metric| {
+                    target.add_labels(&mut metric);
+                    Event::Metric(metric)
                 })
+                .collect::<Vec<_>>();
+            events.extend(target.health(up, start.elapsed()));
+            Ok(events)
         })
-        .flatten()
-        .forward(out)
-        .map(|_| info!("finished sending"));
-
-    Box::new(task)
 }
 
 #[cfg(feature = "sinks-prometheus")]
@@ -108,13 +278,13 @@ mod test {
     use super::*;
     use crate::{
         sinks::prometheus::PrometheusSinkConfig,
-        test_util::{block_on, next_addr, runtime},
+        test_util::{block_on, collect_n, next_addr, runtime, temp_file},
         topology::{self, config},
     };
     use hyper::service::{make_service_fn, service_fn_ok};
     use hyper::{Body, Response, Server};
     use pretty_assertions::assert_eq;
-    use std::{thread, time::Duration};
+    use std::{net::SocketAddr, thread, time::Duration};
 
     #[test]
     fn test_prometheus_routing() {
@@ -167,6 +337,7 @@ mod test {
             "in",
             PrometheusConfig {
                 hosts: vec![format!("http://{}", in_addr)],
+                file_sd: None,
                 scrape_interval_secs: 1,
             },
         );
@@ -195,7 +366,9 @@ mod test {
             .lines()
             .collect::<Vec<_>>();
 
-        assert_eq!(lines, vec![
+        // The health of the scrape comes last.
+        let (scraped, health) = lines.split_at(lines.len() - 6);
+        assert_eq!(scraped.to_vec(), vec![
             "# HELP vector_promhttp_metric_handler_requests_total promhttp_metric_handler_requests_total",
             "# TYPE vector_promhttp_metric_handler_requests_total counter",
             "vector_promhttp_metric_handler_requests_total{code=\"200\"} 100",
@@ -224,7 +397,99 @@ mod test {
             "vector_rpc_duration_seconds_count{code=\"200\"} 2693",
             ],
         );
+        assert_eq!(
+            health[..3].to_vec(),
+            vec![
+                "# HELP vector_up up",
+                "# TYPE vector_up gauge",
+                format!("vector_up{{instance=\"{}\"}} 1", in_addr).as_str(),
+            ]
+        );
+        assert!(health[5].starts_with(&format!(
+            "vector_scrape_duration_seconds{{instance=\"{}\"}} ",
+            in_addr
+        )));
 
         block_on(topology.stop()).unwrap();
     }
+
+    #[test]
+    fn scrapes_discovered_targets() {
+        let mut rt = runtime();
+        let in_addr = next_addr();
+        let dead_addr = next_addr();
+
+        let make_svc = make_service_fn(|_| {
+            service_fn_ok(move |_| Response::new(Body::from("requests_total{env=\"dev\"} 3\n")))
+        });
+        let server = Server::bind(&in_addr).serve(make_svc);
+        rt.spawn(server.map_err(|e| {
+            error!("server error: {:?}", e);
+        }));
+
+        let path = temp_file();
+        std::fs::write(
+            &path,
+            format!(
+                "- targets: [\"{}\", \"{}\"]\n  labels:\n    env: prod\n",
+                in_addr, dead_addr
+            ),
+        )
+        .unwrap();
+
+        let (tx, rx) = mpsc::channel(100);
+        let targets = Targets {
+            hosts: Vec::new(),
+            file_sd: Some(FileSd::new(FileSdConfig {
+                files: vec![path.to_string_lossy().into_owned()],
+                refresh_interval_secs: 60,
+            })),
+        };
+        rt.spawn(prometheus(targets, 1, ShutdownSignal::noop(), tx));
+
+        let events = block_on(collect_n(rx, 5)).unwrap();
+        let metrics = events
+            .into_iter()
+            .map(|event| {
+                let metric = event.into_metric();
+                (metric.name, metric.tags.unwrap(), metric.value)
+            })
+            .collect::<Vec<_>>();
+
+        let tags = |addr: SocketAddr, exported: bool| {
+            let mut tags = vec![
+                ("env".to_owned(), "prod".to_owned()),
+                ("instance".to_owned(), addr.to_string()),
+            ];
+            if exported {
+                tags.push(("exported_env".to_owned(), "dev".to_owned()));
+            }
+            tags.into_iter().collect::<BTreeMap<_, _>>()
+        };
+        assert_eq!(
+            metrics[0],
+            (
+                "requests_total".to_owned(),
+                tags(in_addr, true),
+                MetricValue::Gauge { value: 3.0 }
+            )
+        );
+        assert_eq!(
+            metrics[1],
+            (
+                "up".to_owned(),
+                tags(in_addr, false),
+                MetricValue::Gauge { value: 1.0 }
+            )
+        );
+        assert_eq!(metrics[2].0, "scrape_duration_seconds");
+        assert_eq!(
+            metrics[3],
+            (
+                "up".to_owned(),
+                tags(dead_addr, false),
+                MetricValue::Gauge { value: 0.0 }
+            )
+        );
+    }
 }
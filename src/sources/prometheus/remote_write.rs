@@ -0,0 +1,349 @@
+use crate::{
+    event::metric::{Metric, MetricKind, MetricValue},
+    prometheus::{
+        proto::{metric_metadata::MetricType, TimeSeries, WriteRequest},
+        METRIC_NAME_LABEL,
+    },
+    shutdown::ShutdownSignal,
+    sources::util::{ErrorMessage, HttpSource},
+    tls::TlsConfig,
+    topology::config::{DataType, GlobalOptions, SourceConfig, SourceDescription},
+    Event,
+};
+use bytes::{Buf, BytesMut};
+use chrono::{TimeZone, Utc};
+use futures01::sync::mpsc;
+use prost::Message;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashSet},
+    net::{Ipv4Addr, SocketAddr},
+    sync::{Arc, Mutex},
+};
+use warp::filters::body::FullBody;
+use warp::http::{HeaderMap, StatusCode};
+
+/// Receives the metrics pushed by Prometheus servers and agents through the
+/// remote write protocol.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct PrometheusRemoteWriteConfig {
+    #[serde(default = "default_address")]
+    address: SocketAddr,
+    tls: Option<TlsConfig>,
+}
+
+fn default_address() -> SocketAddr {
+    SocketAddr::new(Ipv4Addr::new(0, 0, 0, 0).into(), 9090)
+}
+
+inventory::submit! {
+    SourceDescription::new_without_default::<PrometheusRemoteWriteConfig>("prometheus_remote_write")
+}
+
+#[typetag::serde(name = "prometheus_remote_write")]
+impl SourceConfig for PrometheusRemoteWriteConfig {
+    fn build(
+        &self,
+        _: &str,
+        _: &GlobalOptions,
+        shutdown: ShutdownSignal,
+        out: mpsc::Sender<Event>,
+    ) -> crate::Result<crate::sources::Source> {
+        RemoteWriteSource::default().run(self.address, "", &self.tls, out, shutdown)
+    }
+
+    fn output_type(&self) -> DataType {
+        DataType::Metric
+    }
+
+    fn source_type(&self) -> &'static str {
+        "prometheus_remote_write"
+    }
+}
+
+#[derive(Clone, Default)]
+struct RemoteWriteSource {
+    /// Names of the counters, as given by the metadata of all the requests so
+    /// far. Prometheus sends metadata on its own schedule, apart from the
+    /// samples.
+    counters: Arc<Mutex<HashSet<String>>>,
+}
+
+impl HttpSource for RemoteWriteSource {
+    fn build_event(&self, body: FullBody, _: HeaderMap) -> Result<Vec<Event>, ErrorMessage> {
+        // Requests are always compressed with the block format of snappy.
+        let body = body.collect::<BytesMut>();
+        let body = snap::raw::Decoder::new()
+            .decompress_vec(&body)
+            .map_err(|error| {
+                ErrorMessage::new(
+                    StatusCode::BAD_REQUEST,
+                    format!("Could not decompress body: {}", error),
+                )
+            })?;
+        let request = WriteRequest::decode(&body[..]).map_err(|error| {
+            ErrorMessage::new(
+                StatusCode::BAD_REQUEST,
+                format!("Could not decode write request: {}", error),
+            )
+        })?;
+        Ok(self.decode_request(request))
+    }
+}
+
+impl RemoteWriteSource {
+    /// Converts the samples of time series into metrics. Prometheus only
+    /// sends the type of metrics along with their metadata, so all of them
+    /// are gauges but for the counters found in it, in this request or an
+    /// earlier one.
+    fn decode_request(&self, request: WriteRequest) -> Vec<Event> {
+        let mut counters = self.counters.lock().unwrap();
+        for metadata in request.metadata {
+            if metadata.r#type == MetricType::Counter as i32 {
+                counters.insert(metadata.metric_family_name);
+            } else {
+                counters.remove(&metadata.metric_family_name);
+            }
+        }
+        decode_series(request.timeseries, &counters)
+    }
+}
+
+fn decode_series(timeseries: Vec<TimeSeries>, counters: &HashSet<String>) -> Vec<Event> {
+    let mut events = Vec::new();
+    for series in timeseries {
+        let mut name = None;
+        let mut tags = BTreeMap::new();
+        for label in series.labels {
+            if label.name == METRIC_NAME_LABEL {
+                name = Some(label.value);
+            } else {
+                tags.insert(label.name, label.value);
+            }
+        }
+        let name = match name {
+            Some(name) => name,
+            None => {
+                warn!(
+                    message = "Dropping time series without a name.",
+                    rate_limit_secs = 30
+                );
+                continue;
+            }
+        };
+        let counter = counters.contains(&name);
+        let tags = if tags.is_empty() { None } else { Some(tags) };
+
+        for sample in series.samples {
+            let timestamp = match Utc.timestamp_millis_opt(sample.timestamp).single() {
+                Some(timestamp) => timestamp,
+                None => {
+                    warn!(
+                        message = "Dropping sample with an out of range timestamp.",
+                        timestamp = sample.timestamp,
+                        rate_limit_secs = 30
+                    );
+                    continue;
+                }
+            };
+            let value = if counter {
+                MetricValue::Counter {
+                    value: sample.value,
+                }
+            } else {
+                MetricValue::Gauge {
+                    value: sample.value,
+                }
+            };
+            events.push(Event::Metric(Metric {
+                name: name.clone(),
+                timestamp: Some(timestamp),
+                tags: tags.clone(),
+                kind: MetricKind::Absolute,
+                value,
+            }));
+        }
+    }
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        prometheus::proto::{Label, MetricMetadata, Sample},
+        runtime::Runtime,
+        test_util::{self, collect_n},
+    };
+    use http::Method;
+
+    fn label(name: &str, value: &str) -> Label {
+        Label {
+            name: name.into(),
+            value: value.into(),
+        }
+    }
+
+    fn request() -> WriteRequest {
+        WriteRequest {
+            timeseries: vec![
+                TimeSeries {
+                    labels: vec![
+                        label("__name__", "http_requests_total"),
+                        label("code", "200"),
+                    ],
+                    samples: vec![
+                        Sample {
+                            value: 10.0,
+                            timestamp: 1_591_005_600_000,
+                        },
+                        Sample {
+                            value: 12.0,
+                            timestamp: 1_591_005_615_000,
+                        },
+                    ],
+                },
+                TimeSeries {
+                    labels: vec![label("__name__", "temperature")],
+                    samples: vec![Sample {
+                        value: 21.5,
+                        timestamp: 1_591_005_600_000,
+                    }],
+                },
+                TimeSeries {
+                    labels: vec![label("job", "nameless")],
+                    samples: vec![Sample {
+                        value: 1.0,
+                        timestamp: 1_591_005_600_000,
+                    }],
+                },
+            ],
+            metadata: vec![MetricMetadata {
+                r#type: MetricType::Counter as i32,
+                metric_family_name: "http_requests_total".into(),
+                help: "The number of HTTP requests.".into(),
+                unit: "".into(),
+            }],
+        }
+    }
+
+    fn expected() -> Vec<Event> {
+        vec![
+            Event::Metric(Metric {
+                name: "http_requests_total".into(),
+                timestamp: Some(Utc.ymd(2020, 6, 1).and_hms(10, 0, 0)),
+                tags: Some(
+                    vec![("code".to_owned(), "200".to_owned())]
+                        .into_iter()
+                        .collect(),
+                ),
+                kind: MetricKind::Absolute,
+                value: MetricValue::Counter { value: 10.0 },
+            }),
+            Event::Metric(Metric {
+                name: "http_requests_total".into(),
+                timestamp: Some(Utc.ymd(2020, 6, 1).and_hms(10, 0, 15)),
+                tags: Some(
+                    vec![("code".to_owned(), "200".to_owned())]
+                        .into_iter()
+                        .collect(),
+                ),
+                kind: MetricKind::Absolute,
+                value: MetricValue::Counter { value: 12.0 },
+            }),
+            Event::Metric(Metric {
+                name: "temperature".into(),
+                timestamp: Some(Utc.ymd(2020, 6, 1).and_hms(10, 0, 0)),
+                tags: None,
+                kind: MetricKind::Absolute,
+                value: MetricValue::Gauge { value: 21.5 },
+            }),
+        ]
+    }
+
+    #[test]
+    fn decodes_request() {
+        assert_eq!(
+            RemoteWriteSource::default().decode_request(request()),
+            expected()
+        );
+    }
+
+    #[test]
+    fn drops_out_of_range_timestamps() {
+        let mut request = request();
+        request.timeseries[1].samples[0].timestamp = i64::MAX;
+        request.timeseries[0].samples[1].timestamp = i64::MIN;
+
+        let mut expected = expected();
+        expected.remove(2);
+        expected.remove(1);
+        assert_eq!(
+            RemoteWriteSource::default().decode_request(request),
+            expected
+        );
+    }
+
+    #[test]
+    fn remembers_counters_across_requests() {
+        let source = RemoteWriteSource::default();
+        let mut metadata = request();
+        metadata.timeseries.clear();
+        assert_eq!(source.decode_request(metadata), vec![]);
+
+        let mut samples = request();
+        samples.metadata.clear();
+        assert_eq!(source.decode_request(samples), expected());
+
+        let mut gauge = request();
+        gauge.metadata[0].r#type = MetricType::Gauge as i32;
+        let events = source.decode_request(gauge);
+        assert_eq!(
+            events[0].as_metric().value,
+            MetricValue::Gauge { value: 10.0 }
+        );
+    }
+
+    #[test]
+    fn receives_remote_write() {
+        test_util::trace_init();
+        let mut rt = Runtime::new().unwrap();
+        let (sender, recv) = mpsc::channel(100);
+        let address = test_util::next_addr();
+        rt.spawn(
+            PrometheusRemoteWriteConfig { address, tls: None }
+                .build(
+                    "default",
+                    &GlobalOptions::default(),
+                    ShutdownSignal::noop(),
+                    sender,
+                )
+                .unwrap(),
+        );
+
+        test_util::wait_for_tcp(address);
+
+        let mut body = Vec::new();
+        request().encode(&mut body).unwrap();
+        let body = snap::raw::Encoder::new().compress_vec(&body).unwrap();
+
+        let send = |body: Vec<u8>| {
+            reqwest::Client::new()
+                .request(Method::POST, &format!("http://{}/", address))
+                .header("Content-Encoding", "snappy")
+                .header("Content-Type", "application/x-protobuf")
+                .body(body)
+                .send()
+                .unwrap()
+                .status()
+                .as_u16()
+        };
+
+        assert_eq!(200, send(body));
+        assert_eq!(400, send(b"not snappy".to_vec()));
+
+        let events = rt.block_on(collect_n(recv, 3)).ok().unwrap();
+        assert_eq!(events, expected());
+    }
+}
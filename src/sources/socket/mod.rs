@@ -94,7 +94,13 @@ impl SourceConfig for SocketConfig {
                     .host_key
                     .clone()
                     .unwrap_or(event::log_schema().host_key().clone());
-                Ok(udp::udp(config.address, host_key, shutdown, out))
+                Ok(udp::udp(
+                    config.address,
+                    config.decoding,
+                    host_key,
+                    shutdown,
+                    out,
+                ))
             }
             #[cfg(unix)]
             Mode::Unix(config) => {
@@ -105,6 +111,7 @@ impl SourceConfig for SocketConfig {
                 Ok(unix::unix(
                     config.path,
                     config.max_length,
+                    config.decoding,
                     host_key,
                     shutdown,
                     out,
@@ -1,11 +1,11 @@
 use crate::{
+    decoding::{Decoder, DecodingConfig, FramingConfig},
     event::{self, Event},
     internal_events::TcpEventReceived,
     sources::util::{SocketListenAddr, TcpSource},
     tls::TlsConfig,
 };
 use bytes::Bytes;
-use codec::{self, BytesDelimitedCodec};
 use serde::{Deserialize, Serialize};
 use string_cache::DefaultAtom as Atom;
 use tracing::field;
@@ -20,6 +20,8 @@ pub struct TcpConfig {
     pub shutdown_timeout_secs: u64,
     pub host_key: Option<Atom>,
     pub tls: Option<TlsConfig>,
+    #[serde(default)]
+    pub decoding: DecodingConfig,
 }
 
 fn default_max_length() -> usize {
@@ -38,6 +40,7 @@ impl TcpConfig {
             host_key: None,
             shutdown_timeout_secs: default_shutdown_timeout_secs(),
             tls: Default::default(),
+            decoding: DecodingConfig::default(),
         }
     }
 }
@@ -48,16 +51,17 @@ pub struct RawTcpSource {
 }
 
 impl TcpSource for RawTcpSource {
-    type Decoder = BytesDelimitedCodec;
+    type Decoder = Decoder;
 
     fn decoder(&self) -> Self::Decoder {
-        BytesDelimitedCodec::new_with_max_length(b'\n', self.config.max_length)
+        self.config
+            .decoding
+            .decoder(FramingConfig::NewlineDelimited {
+                max_length: Some(self.config.max_length),
+            })
     }
 
-    fn build_event(&self, frame: Bytes, host: Bytes) -> Option<Event> {
-        let byte_size = frame.len();
-        let mut event = Event::from(frame);
-
+    fn build_event(&self, (mut event, byte_size): (Event, usize), host: Bytes) -> Option<Event> {
         event
             .as_mut_log()
             .insert(event::log_schema().source_type_key(), "socket");
@@ -68,7 +72,7 @@ impl TcpSource for RawTcpSource {
             &event::log_schema().host_key()
         };
 
-        event.as_mut_log().insert(host_key.clone(), host);
+        event.as_mut_log().try_insert(host_key, host);
 
         trace!(
             message = "Received one event.",
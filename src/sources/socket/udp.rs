@@ -1,12 +1,11 @@
 use crate::{
+    decoding::{DecodingConfig, FramingConfig},
     event::{self, Event},
     internal_events::{UdpEventReceived, UdpSocketError},
     shutdown::ShutdownSignal,
     sources::Source,
     stream::StreamExt,
 };
-use bytes::Bytes;
-use codec::BytesDelimitedCodec;
 use futures01::{future, sync::mpsc, Future, Sink, Stream};
 use serde::{Deserialize, Serialize};
 use std::{io, net::SocketAddr};
@@ -19,6 +18,8 @@ use tokio01::net::udp::{UdpFramed, UdpSocket};
 pub struct UdpConfig {
     pub address: SocketAddr,
     pub host_key: Option<Atom>,
+    #[serde(default)]
+    pub decoding: DecodingConfig,
 }
 
 impl UdpConfig {
@@ -26,12 +27,14 @@ impl UdpConfig {
         Self {
             address,
             host_key: None,
+            decoding: DecodingConfig::default(),
         }
     }
 }
 
 pub fn udp(
     address: SocketAddr,
+    decoding: DecodingConfig,
     host_key: Atom,
     shutdown: ShutdownSignal,
     out: mpsc::Sender<Event>,
@@ -50,19 +53,15 @@ pub fn udp(
             let host_key = host_key.clone();
             // UDP processes messages per packet, where messages are separated by newline.
             // And stretch to end of packet.
-            UdpFramed::with_decode(socket, BytesDelimitedCodec::new(b'\n'), true)
+            let decoder = decoding.decoder(FramingConfig::NewlineDelimited { max_length: None });
+            UdpFramed::with_decode(socket, decoder, true)
                 .take_until(shutdown)
-                .map(move |(line, addr): (Bytes, _)| {
-                    let byte_size = line.len();
-                    let mut event = Event::from(line);
-
+                .map(move |((mut event, byte_size), addr): ((Event, usize), _)| {
                     event
                         .as_mut_log()
                         .insert(event::log_schema().source_type_key(), "socket");
 
-                    event
-                        .as_mut_log()
-                        .insert(host_key.clone(), addr.to_string());
+                    event.as_mut_log().try_insert(&host_key, addr.to_string());
 
                     emit!(UdpEventReceived { byte_size });
                     event
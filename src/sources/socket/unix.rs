@@ -1,4 +1,5 @@
 use crate::{
+    decoding::{DecodingConfig, FramingConfig},
     event::{self, Event},
     internal_events::UnixSocketEventReceived,
     shutdown::ShutdownSignal,
@@ -16,6 +17,8 @@ pub struct UnixConfig {
     #[serde(default = "default_max_length")]
     pub max_length: usize,
     pub host_key: Option<String>,
+    #[serde(default)]
+    pub decoding: DecodingConfig,
 }
 
 fn default_max_length() -> usize {
@@ -28,22 +31,26 @@ impl UnixConfig {
             path,
             max_length: default_max_length(),
             host_key: None,
+            decoding: DecodingConfig::default(),
         }
     }
 }
 
 /**
 * Function to pass to build_unix_source, specific to the basic unix source.
-* Takes a single line of a received message and builds an Event object.
+* Takes a single decoded frame of a received message and completes the Event object.
 **/
-fn build_event(host_key: &str, received_from: Option<Bytes>, line: &str) -> Option<Event> {
-    let byte_size = line.len();
-    let mut event = Event::from(line);
+fn build_event(
+    host_key: &str,
+    received_from: Option<Bytes>,
+    (mut event, byte_size): (Event, usize),
+) -> Option<Event> {
     event
         .as_mut_log()
         .insert(event::log_schema().source_type_key(), "socket");
     if let Some(host) = received_from {
-        event.as_mut_log().insert(host_key, host);
+        let host_key = host_key.into();
+        event.as_mut_log().try_insert(&host_key, host);
     }
     emit!(UnixSocketEventReceived { byte_size });
     Some(event)
@@ -52,9 +59,15 @@ fn build_event(host_key: &str, received_from: Option<Bytes>, line: &str) -> Opti
 pub fn unix(
     path: PathBuf,
     max_length: usize,
+    decoding: DecodingConfig,
     host_key: String,
     shutdown: ShutdownSignal,
     out: mpsc::Sender<Event>,
 ) -> Source {
-    build_unix_source(path, max_length, host_key, shutdown, out, build_event)
+    let build_decoder = move || {
+        decoding.decoder(FramingConfig::NewlineDelimited {
+            max_length: Some(max_length),
+        })
+    };
+    build_unix_source(path, build_decoder, host_key, shutdown, out, build_event)
 }
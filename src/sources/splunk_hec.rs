@@ -1,10 +1,15 @@
 use crate::{
-    event::{self, Event, LogEvent, Value},
+    decoding::{DecodingConfig, FramingConfig},
+    event::{
+        self,
+        finalization::{BatchNotifier, BatchStatus},
+        Event, LogEvent, Value,
+    },
     shutdown::ShutdownSignal,
     tls::{MaybeTlsSettings, TlsConfig},
     topology::config::{DataType, GlobalOptions, SourceConfig},
 };
-use bytes::{Buf, Bytes};
+use bytes::{Buf, Bytes, BytesMut};
 use chrono::{DateTime, TimeZone, Utc};
 use flate2::read::GzDecoder;
 use futures01::{sync::mpsc, Async, Future, Sink, Stream};
@@ -16,6 +21,7 @@ use snafu::Snafu;
 use std::{
     io::Read,
     net::{Ipv4Addr, SocketAddr},
+    sync::Arc,
 };
 use string_cache::DefaultAtom as Atom;
 use warp::{body::FullBody, filters::BoxedFilter, path, Filter, Rejection, Reply};
@@ -38,6 +44,10 @@ pub struct SplunkConfig {
     /// Splunk HEC token
     token: Option<String>,
     tls: Option<TlsConfig>,
+    /// Only respond once the events of a request have been delivered
+    acknowledgements: bool,
+    /// How the bodies sent to the raw endpoint are split and parsed
+    decoding: DecodingConfig,
 }
 
 impl SplunkConfig {
@@ -56,6 +66,8 @@ impl Default for SplunkConfig {
             address: default_socket_address(),
             token: None,
             tls: None,
+            acknowledgements: false,
+            decoding: DecodingConfig::default(),
         }
     }
 }
@@ -109,11 +121,17 @@ impl SourceConfig for SplunkConfig {
     fn source_type(&self) -> &'static str {
         "splunk_hec"
     }
+
+    fn acknowledgements(&self) -> bool {
+        self.acknowledgements
+    }
 }
 
 /// Shared data for responding to requests.
 struct SplunkSource {
     credentials: Option<Bytes>,
+    acknowledgements: bool,
+    decoding: DecodingConfig,
 }
 
 impl SplunkSource {
@@ -123,10 +141,13 @@ impl SplunkSource {
                 .token
                 .as_ref()
                 .map(|token| format!("Splunk {}", token).into()),
+            acknowledgements: config.acknowledgements,
+            decoding: config.decoding.clone(),
         }
     }
 
     fn event_service(&self, out: mpsc::Sender<Event>) -> BoxedFilter<(Response<Body>,)> {
+        let acknowledgements = self.acknowledgements;
         warp::post2()
             .and(
                 warp::path::end()
@@ -147,19 +168,17 @@ impl SplunkSource {
                       body: FullBody| {
                     // Construct event parser
                     if gzip {
-                        Box::new(
-                            EventStream::new(GzDecoder::new(body.reader()), channel, host)
-                                .forward(out.clone().sink_map_err(|_| ApiError::ServerShutdown))
-                                .map(|_| ()),
+                        forward_events(
+                            EventStream::new(GzDecoder::new(body.reader()), channel, host),
+                            out.clone(),
+                            acknowledgements,
                         )
-                            as Box<dyn Future<Item = (), Error = Rejection> + Send>
                     } else {
-                        Box::new(
-                            EventStream::new(body.reader(), channel, host)
-                                .forward(out.clone().sink_map_err(|_| ApiError::ServerShutdown))
-                                .map(|_| ()),
+                        forward_events(
+                            EventStream::new(body.reader(), channel, host),
+                            out.clone(),
+                            acknowledgements,
                         )
-                            as Box<dyn Future<Item = (), Error = Rejection> + Send>
                     }
                 },
             )
@@ -168,6 +187,8 @@ impl SplunkSource {
     }
 
     fn raw_service(&self, out: mpsc::Sender<Event>) -> BoxedFilter<(Response<Body>,)> {
+        let acknowledgements = self.acknowledgements;
+        let decoding = self.decoding.clone();
         warp::post2()
             .and(
                 (path!("raw" / "1.0").and(warp::path::end()))
@@ -190,10 +211,14 @@ impl SplunkSource {
             .and(warp::body::concat())
             .and_then(
                 move |_, _, channel: String, host: Option<String>, gzip: bool, body: FullBody| {
-                    // Construct event parser
-                    futures01::stream::once(raw_event(body, gzip, channel, host))
-                        .forward(out.clone().sink_map_err(|_| ApiError::ServerShutdown))
-                        .map(|_| ())
+                    let events = raw_events(body, gzip, &decoding, channel, host);
+                    forward_events(
+                        futures01::future::result(events)
+                            .map(futures01::stream::iter_ok)
+                            .flatten_stream(),
+                        out.clone(),
+                        acknowledgements,
+                    )
                 },
             )
             .map(finish_ok)
@@ -286,6 +311,37 @@ impl SplunkSource {
     }
 }
 
+/// Sends the events of a request to `out`. With acknowledgements, resolves
+/// once all of them have been delivered by the sinks.
+fn forward_events<S>(
+    events: S,
+    out: mpsc::Sender<Event>,
+    acknowledgements: bool,
+) -> Box<dyn Future<Item = (), Error = Rejection> + Send>
+where
+    S: Stream<Item = Event, Error = Rejection> + Send + 'static,
+{
+    let out = out.sink_map_err(|_| ApiError::ServerShutdown);
+    if !acknowledgements {
+        return Box::new(events.forward(out).map(|_| ()));
+    }
+
+    let (batch, receiver) = BatchNotifier::new_with_receiver();
+    // The batch is released along with the stream once everything has been sent.
+    let events = events.map(move |mut event| {
+        event.as_mut_log().add_batch_notifier(Arc::clone(&batch));
+        event
+    });
+    Box::new(events.forward(out).and_then(|_| {
+        receiver
+            .map_err(|_| Rejection::from(ApiError::ServerShutdown))
+            .and_then(|status| match status {
+                BatchStatus::Delivered => Ok(()),
+                BatchStatus::Errored | BatchStatus::Failed => Err(ApiError::DeliveryFailed.into()),
+            })
+    }))
+}
+
 /// Constructs one ore more events from json-s coming from reader.
 /// If errors, it's done with input.
 struct EventStream<R: Read> {
@@ -540,52 +596,57 @@ enum Time {
     Provided(DateTime<Utc>),
 }
 
-/// Creates event from raw request
-fn raw_event(
+/// Creates events from raw request
+fn raw_events(
     bytes: FullBody,
     gzip: bool,
+    decoding: &DecodingConfig,
     channel: String,
     host: Option<String>,
-) -> Result<Event, Rejection> {
+) -> Result<Vec<Event>, Rejection> {
     // Process gzip
-    let message: Value = if gzip {
+    let body = if gzip {
         let mut data = Vec::new();
         match GzDecoder::new(bytes.reader()).read_to_end(&mut data) {
             Ok(0) => return Err(ApiError::NoData.into()),
-            Ok(_) => data.into(),
+            Ok(_) => BytesMut::from(data),
             Err(error) => {
                 error!(message = "Malformed request body",%error);
                 return Err(ApiError::InvalidDataFormat { event: 0 }.into());
             }
         }
     } else {
-        bytes.bytes().into()
+        bytes.collect()
     };
+    let empty = body.is_empty();
+
+    // The whole body is one message unless configured otherwise.
+    let mut decoder = decoding.decoder(FramingConfig::Bytes);
+    let mut events = decoder.decode_all(body).map_err(|error| {
+        error!(message = "Malformed request body",%error);
+        Rejection::from(ApiError::InvalidDataFormat { event: 0 })
+    })?;
+    // Framing finds nothing in an empty body, which is still an empty message.
+    if empty {
+        events.extend(decoder.decode_frame(Bytes::new()).map(|(event, _)| event));
+    }
 
-    // Construct event
-    let mut event = Event::new_empty_log();
-    let log = event.as_mut_log();
+    for event in events.iter_mut() {
+        let log = event.as_mut_log();
 
-    // Add message
-    log.insert(event::log_schema().message_key().clone(), message);
+        // Add channel
+        log.insert(CHANNEL.clone(), channel.as_bytes());
 
-    // Add channel
-    log.insert(CHANNEL.clone(), channel.as_bytes());
+        // Add host
+        if let Some(host) = &host {
+            log.try_insert(event::log_schema().host_key(), host.as_bytes());
+        }
 
-    // Add host
-    if let Some(host) = host {
-        log.insert(event::log_schema().host_key().clone(), host.as_bytes());
+        // Add source type
+        log.try_insert(event::log_schema().source_type_key(), "splunk_hec");
     }
 
-    // Add timestamp
-    log.insert(event::log_schema().timestamp_key().clone(), Utc::now());
-
-    // Add source type
-    event
-        .as_mut_log()
-        .try_insert(event::log_schema().source_type_key(), "splunk_hec");
-
-    Ok(event)
+    Ok(events)
 }
 
 #[derive(Debug, Snafu)]
@@ -597,6 +658,7 @@ enum ApiError {
     NoData,
     InvalidDataFormat { event: usize },
     ServerShutdown,
+    DeliveryFailed,
     EmptyEventField { event: usize },
     MissingEventField { event: usize },
     BadRequest,
@@ -666,6 +728,10 @@ fn finish_err(rejection: Rejection) -> Result<(Response<Body>,), Rejection> {
                 StatusCode::SERVICE_UNAVAILABLE,
                 splunk_response::SERVER_SHUTDOWN.as_ref(),
             ),
+            ApiError::DeliveryFailed => response_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                splunk_response::SERVER_ERROR.as_ref(),
+            ),
             ApiError::InvalidDataFormat { event } => event_error("Invalid data format", 6, *event),
             ApiError::EmptyEventField { event } => {
                 event_error("Event field cannot be blank", 13, *event)
@@ -718,7 +784,7 @@ mod tests {
     use crate::runtime::{Runtime, TaskExecutor};
     use crate::test_util::{self, collect_n};
     use crate::{
-        event::{self, Event},
+        event::{self, finalization::EventStatus, Event},
         shutdown::ShutdownSignal,
         sinks::{
             splunk_hec::{Encoding, HecSinkConfig},
@@ -728,6 +794,7 @@ mod tests {
         topology::config::{GlobalOptions, SinkConfig, SinkContext, SourceConfig},
     };
     use chrono::{TimeZone, Utc};
+    use flate2::write::GzEncoder;
     use futures01::{stream, sync::mpsc, Sink};
     use http::Method;
     use std::net::SocketAddr;
@@ -738,26 +805,40 @@ mod tests {
     const CHANNEL_CAPACITY: usize = 1000;
 
     fn source(rt: &mut Runtime) -> (mpsc::Receiver<Event>, SocketAddr) {
-        source_with(rt, Some(TOKEN.to_owned()))
+        source_with(rt, Some(TOKEN.to_owned()), false)
+    }
+
+    fn source_with(
+        rt: &mut Runtime,
+        token: Option<String>,
+        acknowledgements: bool,
+    ) -> (mpsc::Receiver<Event>, SocketAddr) {
+        source_with_config(
+            rt,
+            SplunkConfig {
+                token,
+                acknowledgements,
+                ..SplunkConfig::default()
+            },
+        )
     }
 
-    fn source_with(rt: &mut Runtime, token: Option<String>) -> (mpsc::Receiver<Event>, SocketAddr) {
+    fn source_with_config(
+        rt: &mut Runtime,
+        config: SplunkConfig,
+    ) -> (mpsc::Receiver<Event>, SocketAddr) {
         test_util::trace_init();
         let (sender, recv) = mpsc::channel(CHANNEL_CAPACITY);
         let address = test_util::next_addr();
         rt.spawn(
-            SplunkConfig {
-                address,
-                token,
-                tls: None,
-            }
-            .build(
-                "default",
-                &GlobalOptions::default(),
-                ShutdownSignal::noop(),
-                sender,
-            )
-            .unwrap(),
+            SplunkConfig { address, ..config }
+                .build(
+                    "default",
+                    &GlobalOptions::default(),
+                    ShutdownSignal::noop(),
+                    sender,
+                )
+                .unwrap(),
         );
         (recv, address)
     }
@@ -1014,6 +1095,82 @@ mod tests {
         );
     }
 
+    #[test]
+    fn raw_decoding() {
+        let mut rt = test_util::runtime();
+        let (source, address) = source_with_config(
+            &mut rt,
+            SplunkConfig {
+                token: Some(TOKEN.to_owned()),
+                decoding: toml::from_str(
+                    r#"
+                    codec = "json"
+                    framing = { method = "newline_delimited" }
+                    "#,
+                )
+                .unwrap(),
+                ..SplunkConfig::default()
+            },
+        );
+
+        assert_eq!(
+            200,
+            post(
+                address,
+                "services/collector/raw",
+                "{\"greeting\": \"hello\"}\n{\"greeting\": \"bye\"}\n"
+            )
+        );
+        assert_eq!(400, post(address, "services/collector/raw", "not json"));
+
+        let events = rt.block_on(collect_n(source, 2)).unwrap();
+        assert_eq!(events[0].as_log()[&"greeting".into()], "hello".into());
+        assert_eq!(events[1].as_log()[&"greeting".into()], "bye".into());
+        for event in events {
+            assert_eq!(event.as_log()[&super::CHANNEL], "guid".into());
+            assert_eq!(
+                event.as_log()[event::log_schema().source_type_key()],
+                "splunk_hec".into()
+            );
+        }
+    }
+
+    #[test]
+    fn raw_empty() {
+        let mut rt = test_util::runtime();
+        let (source, address) = source(&mut rt);
+
+        assert_eq!(200, post(address, "services/collector/raw", ""));
+
+        let event = rt.block_on(collect_n(source, 1)).unwrap().remove(0);
+        assert_eq!(
+            event.as_log()[&event::log_schema().message_key()],
+            "".into()
+        );
+        assert_eq!(event.as_log()[&super::CHANNEL], "guid".into());
+    }
+
+    #[test]
+    fn raw_empty_gzip() {
+        let mut rt = test_util::runtime();
+        let (_source, address) = source(&mut rt);
+
+        let body = GzEncoder::new(Vec::new(), flate2::Compression::default())
+            .finish()
+            .unwrap();
+        let status = reqwest::Client::new()
+            .post(&format!("http://{}/services/collector/raw", address))
+            .header("Authorization", format!("Splunk {}", TOKEN))
+            .header("x-splunk-request-channel", "guid")
+            .header("Content-Encoding", "gzip")
+            .body(body)
+            .send()
+            .unwrap()
+            .status()
+            .as_u16();
+        assert_eq!(400, status);
+    }
+
     #[test]
     fn no_data() {
         let mut rt = test_util::runtime();
@@ -1043,7 +1200,7 @@ mod tests {
     fn no_autorization() {
         let message = "no_autorization";
         let mut rt = test_util::runtime();
-        let (source, address) = source_with(&mut rt, None);
+        let (source, address) = source_with(&mut rt, None, false);
         let (sink, health) = sink(address, Encoding::Text, Compression::Gzip, rt.executor());
         assert!(rt.block_on(health).is_ok());
 
@@ -1078,6 +1235,28 @@ mod tests {
         );
     }
 
+    #[test]
+    fn acknowledgements() {
+        let mut rt = test_util::runtime();
+        let (source, address) = source_with(&mut rt, Some(TOKEN.to_owned()), true);
+
+        let request = std::thread::spawn(move || {
+            post(
+                address,
+                "services/collector/event",
+                r#"{"event":"first"}{"event":"second"}"#,
+            )
+        });
+
+        let mut events = rt.block_on(collect_n(source, 2)).unwrap();
+        events[1]
+            .take_finalizers()
+            .update_status(EventStatus::Errored);
+        drop(events);
+
+        assert_eq!(500, request.join().unwrap());
+    }
+
     #[test]
     fn default() {
         let message = r#"{"event":"first","source":"main"}{"event":"second"}{"event":"third","source":"secondary"}"#;
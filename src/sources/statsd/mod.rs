@@ -1,22 +1,92 @@
-use crate::{shutdown::ShutdownSignal, stream::StreamExt, topology::config::GlobalOptions, Event};
+use crate::{
+    shutdown::ShutdownSignal,
+    sources::util::{SocketListenAddr, TcpSource},
+    stream::StreamExt,
+    tls::{MaybeTlsSettings, TlsConfig},
+    topology::config::GlobalOptions,
+    Event,
+};
+use bytes::Bytes;
+use codec::BytesDelimitedCodec;
 use futures01::{future, sync::mpsc, Future, Sink, Stream};
-use parser::parse;
+#[cfg(unix)]
+use futures01::{try_ready, Async};
+use parser::parse_event;
 use serde::{Deserialize, Serialize};
-use std::net::SocketAddr;
+#[cfg(unix)]
+use snafu::{ResultExt, Snafu};
+use std::{io, net::SocketAddr};
+#[cfg(unix)]
+use std::{os::unix::fs::FileTypeExt, path::PathBuf};
+#[cfg(unix)]
+use tokio01::reactor::Handle;
 use tokio01::{
     self,
     codec::BytesCodec,
     net::{UdpFramed, UdpSocket},
 };
+#[cfg(unix)]
+use tokio_uds::UnixDatagram;
 use tracing::field;
 
+/// Datagrams longer than this are truncated.
+#[cfg(unix)]
+const MAX_DATAGRAM_SIZE: usize = 65_536;
+
 pub mod parser;
 
-#[derive(Deserialize, Serialize, Debug)]
-struct StatsdConfig {
+#[cfg(unix)]
+#[derive(Debug, Snafu)]
+enum BuildError {
+    #[snafu(display("Unable to remove stale socket {:?}: {}", path, source))]
+    RemoveStaleSocket { path: PathBuf, source: io::Error },
+    #[snafu(display("Unable to bind to unix datagram socket {:?}: {}", path, source))]
+    UnixBind { path: PathBuf, source: io::Error },
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(untagged)]
+enum StatsdConfig {
+    Mode(StatsdMode),
+    /// Configs written before `mode` was added listen on UDP.
+    Legacy(UdpConfig),
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+enum StatsdMode {
+    Tcp(TcpConfig),
+    Udp(UdpConfig),
+    #[cfg(unix)]
+    Unix(UnixConfig),
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+struct UdpConfig {
     address: SocketAddr,
 }
 
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+struct TcpConfig {
+    address: SocketListenAddr,
+    tls: Option<TlsConfig>,
+    #[serde(default = "default_shutdown_timeout_secs")]
+    shutdown_timeout_secs: u64,
+}
+
+fn default_shutdown_timeout_secs() -> u64 {
+    30
+}
+
+#[cfg(unix)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+struct UnixConfig {
+    path: PathBuf,
+}
+
 #[typetag::serde(name = "statsd")]
 impl crate::topology::config::SourceConfig for StatsdConfig {
     fn build(
@@ -26,11 +96,29 @@ impl crate::topology::config::SourceConfig for StatsdConfig {
         shutdown: ShutdownSignal,
         out: mpsc::Sender<Event>,
     ) -> crate::Result<super::Source> {
-        Ok(statsd(self.address, shutdown, out))
+        match self {
+            StatsdConfig::Mode(StatsdMode::Udp(config)) | StatsdConfig::Legacy(config) => {
+                Ok(statsd_udp(config.address, shutdown, out))
+            }
+            StatsdConfig::Mode(StatsdMode::Tcp(config)) => {
+                let tls = MaybeTlsSettings::from_config(&config.tls, true)?;
+                StatsdTcpSource.run(
+                    config.address,
+                    config.shutdown_timeout_secs,
+                    tls,
+                    shutdown,
+                    out,
+                )
+            }
+            #[cfg(unix)]
+            StatsdConfig::Mode(StatsdMode::Unix(config)) => {
+                statsd_unix(config.path.clone(), shutdown, out)
+            }
+        }
     }
 
     fn output_type(&self) -> crate::topology::config::DataType {
-        crate::topology::config::DataType::Metric
+        crate::topology::config::DataType::Any
     }
 
     fn source_type(&self) -> &'static str {
@@ -38,7 +126,27 @@ impl crate::topology::config::SourceConfig for StatsdConfig {
     }
 }
 
-fn statsd(addr: SocketAddr, shutdown: ShutdownSignal, out: mpsc::Sender<Event>) -> super::Source {
+fn parse_line(line: &str) -> Option<Event> {
+    parse_event(line).map_err(|error| error!("{}", error)).ok()
+}
+
+/// Datagrams carry one metric per line.
+fn parse_packet(packet: &[u8]) -> futures01::stream::IterOk<std::vec::IntoIter<Event>, io::Error> {
+    let packet = String::from_utf8_lossy(packet);
+    let metrics = packet.lines().filter_map(parse_line).collect::<Vec<_>>();
+    futures01::stream::iter_ok(metrics)
+}
+
+/// Streams carry one metric per line, like datagrams do.
+fn line_decoder() -> BytesDelimitedCodec {
+    BytesDelimitedCodec::new_with_max_length(b'\n', bytesize::kib(100u64) as usize)
+}
+
+fn statsd_udp(
+    addr: SocketAddr,
+    shutdown: ShutdownSignal,
+    out: mpsc::Sender<Event>,
+) -> super::Source {
     let out = out.sink_map_err(|e| error!("error sending metric: {:?}", e));
 
     Box::new(
@@ -56,16 +164,7 @@ fn statsd(addr: SocketAddr, shutdown: ShutdownSignal, out: mpsc::Sender<Event>)
         .and_then(move |socket| {
             let metrics_in = UdpFramed::new(socket, BytesCodec::new())
                 .take_until(shutdown)
-                .map(|(bytes, _sock)| {
-                    let packet = String::from_utf8_lossy(bytes.as_ref());
-                    let metrics = packet
-                        .lines()
-                        .map(parse)
-                        .filter_map(|res| res.map_err(|e| error!("{}", e)).ok())
-                        .map(Event::Metric)
-                        .collect::<Vec<_>>();
-                    futures01::stream::iter_ok::<_, std::io::Error>(metrics)
-                })
+                .map(|(bytes, _sock)| parse_packet(&bytes))
                 .flatten()
                 .map_err(|e| error!("error reading datagram: {:?}", e));
 
@@ -74,17 +173,92 @@ fn statsd(addr: SocketAddr, shutdown: ShutdownSignal, out: mpsc::Sender<Event>)
     )
 }
 
+#[derive(Clone)]
+struct StatsdTcpSource;
+
+impl TcpSource for StatsdTcpSource {
+    type Decoder = BytesDelimitedCodec;
+
+    fn decoder(&self) -> Self::Decoder {
+        line_decoder()
+    }
+
+    fn build_event(&self, line: Bytes, _host: Bytes) -> Option<Event> {
+        parse_line(&String::from_utf8_lossy(&line))
+    }
+}
+
+#[cfg(unix)]
+fn statsd_unix(
+    path: PathBuf,
+    shutdown: ShutdownSignal,
+    out: mpsc::Sender<Event>,
+) -> crate::Result<super::Source> {
+    // A socket left behind by a previous run would make binding fail.
+    if let Ok(metadata) = std::fs::symlink_metadata(&path) {
+        if metadata.file_type().is_socket() {
+            std::fs::remove_file(&path).context(RemoveStaleSocket { path: &path })?;
+        }
+    }
+    let socket = std::os::unix::net::UnixDatagram::bind(&path).context(UnixBind { path: &path })?;
+
+    let out = out.sink_map_err(|e| error!("error sending metric: {:?}", e));
+
+    Ok(Box::new(
+        future::lazy(move || {
+            info!(message = "listening.", ?path, r#type = "unix");
+
+            UnixDatagram::from_std(socket, &Handle::default())
+                .map_err(|e| error!("failed to register socket: {:?}", e))
+        })
+        .and_then(move |socket| {
+            let mut buf = vec![0; MAX_DATAGRAM_SIZE];
+            let datagrams = futures01::stream::poll_fn(move || {
+                let size = try_ready!(socket.poll_recv(&mut buf));
+                Ok(Async::Ready(Some(Bytes::from(&buf[..size]))))
+            });
+
+            let metrics_in = datagrams
+                .take_until(shutdown)
+                .map(|packet| parse_packet(&packet))
+                .flatten()
+                .map_err(|e| error!("error reading datagram: {:?}", e));
+
+            metrics_in.forward(out).map(|_| info!("finished sending"))
+        }),
+    ))
+}
+
 #[cfg(feature = "sinks-prometheus")]
 #[cfg(test)]
 mod test {
-    use super::StatsdConfig;
+    #[cfg(unix)]
+    use super::UnixConfig;
+    use super::{StatsdConfig, StatsdMode, TcpConfig, UdpConfig};
+    #[cfg(unix)]
+    use crate::shutdown::ShutdownSignal;
     use crate::{
         sinks::prometheus::PrometheusSinkConfig,
-        test_util::{block_on, next_addr, runtime, shutdown_on_idle},
+        test_util::{block_on, next_addr, runtime, shutdown_on_idle, wait_for_tcp},
         topology::{self, config},
     };
     use futures01::Stream;
-    use std::{thread, time::Duration};
+    use std::{io::Write, net::TcpStream, thread, time::Duration};
+
+    #[test]
+    fn parses_config_without_mode() {
+        let config: StatsdConfig = toml::from_str(r#"address = "127.0.0.1:8126""#).unwrap();
+        assert!(matches!(config, StatsdConfig::Legacy(UdpConfig { .. })));
+
+        let config: StatsdConfig = toml::from_str(
+            r#"
+            mode = "tcp"
+            address = "127.0.0.1:8126"
+            "#,
+        )
+        .unwrap();
+        assert!(matches!(config, StatsdConfig::Mode(StatsdMode::Tcp(_))));
+    }
 
     fn parse_count(lines: &Vec<&str>, prefix: &str) -> usize {
         lines
@@ -97,12 +271,92 @@ mod test {
     }
 
     #[test]
-    fn test_statsd() {
+    fn test_statsd_udp() {
+        let in_addr = next_addr();
+        let socket = std::net::UdpSocket::bind(&next_addr()).unwrap();
+
+        test_statsd(
+            StatsdConfig::Mode(StatsdMode::Udp(UdpConfig { address: in_addr })),
+            move |bytes| {
+                socket.send_to(bytes, &in_addr).unwrap();
+            },
+        );
+    }
+
+    #[test]
+    fn test_statsd_tcp() {
         let in_addr = next_addr();
+        let mut stream = None;
+
+        test_statsd(
+            StatsdConfig::Mode(StatsdMode::Tcp(TcpConfig {
+                address: in_addr.into(),
+                tls: None,
+                shutdown_timeout_secs: 30,
+            })),
+            move |bytes| {
+                stream
+                    .get_or_insert_with(|| {
+                        wait_for_tcp(in_addr);
+                        TcpStream::connect(in_addr).unwrap()
+                    })
+                    .write_all(bytes)
+                    .unwrap();
+            },
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_statsd_unix() {
+        use crate::test_util::wait_for;
+        use std::os::unix::net::UnixDatagram;
+
+        let in_path = tempfile::tempdir().unwrap().into_path().join("unix_test");
+        let socket = UnixDatagram::unbound().unwrap();
+        let mut bound = false;
+
+        test_statsd(
+            StatsdConfig::Mode(StatsdMode::Unix(UnixConfig {
+                path: in_path.clone(),
+            })),
+            move |bytes| {
+                if !bound {
+                    wait_for(|| in_path.exists());
+                    bound = true;
+                }
+                socket.send_to(bytes, &in_path).unwrap();
+            },
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn statsd_unix_removes_stale_socket() {
+        let path = tempfile::tempdir().unwrap().into_path().join("stale");
+        drop(std::os::unix::net::UnixDatagram::bind(&path).unwrap());
+        assert!(path.exists());
+
+        let (tx, _rx) = futures01::sync::mpsc::channel(10);
+        assert!(super::statsd_unix(path, ShutdownSignal::noop(), tx).is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn statsd_unix_fails_to_build_over_a_file() {
+        let path = tempfile::tempdir().unwrap().into_path().join("file");
+        std::fs::write(&path, b"").unwrap();
+
+        let (tx, _rx) = futures01::sync::mpsc::channel(10);
+        assert!(super::statsd_unix(path.clone(), ShutdownSignal::noop(), tx).is_err());
+        assert!(path.exists());
+    }
+
+    fn test_statsd(statsd_config: StatsdConfig, mut send: impl FnMut(&[u8])) {
         let out_addr = next_addr();
 
         let mut config = config::Config::empty();
-        config.add_source("in", StatsdConfig { address: in_addr });
+        config.add_source("in", statsd_config);
         config.add_sink(
             "out",
             &["in"],
@@ -118,16 +372,10 @@ mod test {
 
         let (topology, _crash) = topology::start(config, &mut rt, false).unwrap();
 
-        let bind_addr = next_addr();
-        let socket = std::net::UdpSocket::bind(&bind_addr).unwrap();
-
         for _ in 0..100 {
-            socket
-                .send_to(
-                    b"foo:1|c|#a,b:b\nbar:42|g\nfoo:1|c|#a,b:c\nglork:3|h|@0.1\nmilliglork:3000|ms|@0.1\nset:0|s\nset:1|s\n",
-                    &in_addr,
-                )
-                .unwrap();
+            send(
+                b"foo:1|c|#a,b:b\nbar:42|g\nfoo:1|c|#a,b:c\nglork:3|h|@0.1\nmilliglork:3000|ms|@0.1\nset:0|s\nset:1|s\n",
+            );
             // Space things out slightly to try to avoid dropped packets
             thread::sleep(Duration::from_millis(10));
         }
@@ -197,7 +445,7 @@ mod test {
 
             // Recheck that set is also reseted------------
 
-            socket.send_to(b"set:0|s\nset:1|s\n", &in_addr).unwrap();
+            send(b"set:0|s\nset:1|s\n");
             // Space things out slightly to try to avoid dropped packets
             thread::sleep(Duration::from_millis(10));
             // Give packets some time to flow through
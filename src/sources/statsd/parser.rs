@@ -1,4 +1,9 @@
-use crate::event::metric::{Metric, MetricKind, MetricValue};
+use crate::event::{
+    self,
+    metric::{Metric, MetricKind, MetricValue},
+    Event, Value,
+};
+use chrono::{DateTime, TimeZone, Utc};
 use lazy_static::lazy_static;
 use regex::Regex;
 use std::{
@@ -32,26 +37,17 @@ pub fn parse(packet: &str) -> Result<Metric, ParseError> {
     let name = sanitize_key(key);
     let metric_type = parts[1];
 
-    // sampling part is optional and comes after metric type part
-    let sampling = parts.get(2).filter(|s| s.starts_with('@'));
-    let sample_rate = if let Some(s) = sampling {
-        1.0 / sanitize_sampling(parse_sampling(s)?)
-    } else {
-        1.0
-    };
-
-    // tags are optional and could be found either after sampling of after metric type part
-    let tags = if sampling.is_none() {
-        parts.get(2)
-    } else {
-        parts.get(3)
-    };
-    let tags = tags.filter(|s| s.starts_with('#'));
-    let tags = if let Some(t) = tags {
-        Some(parse_tags(t)?)
-    } else {
-        None
-    };
+    // sampling and tags are optional and come after the metric type part, in
+    // any order; other DogStatsD fields such as container ids are ignored
+    let mut sample_rate = 1.0;
+    let mut tags = None;
+    for part in &parts[2..] {
+        if part.starts_with('@') {
+            sample_rate = 1.0 / sanitize_sampling(parse_sampling(part)?);
+        } else if part.starts_with('#') {
+            tags = Some(parse_tags(part)?);
+        }
+    }
 
     let metric = match metric_type {
         "c" => {
@@ -66,16 +62,20 @@ pub fn parse(packet: &str) -> Result<Metric, ParseError> {
                 },
             }
         }
-        unit @ "h" | unit @ "ms" => {
-            let val: f64 = parts[0].parse()?;
+        unit @ "h" | unit @ "ms" | unit @ "d" => {
+            // DogStatsD packs several values of a metric as `name:1:2:3|d`
+            let values = parts[0]
+                .split(':')
+                .map(|val| Ok(convert_to_base_units(unit, val.parse()?)))
+                .collect::<Result<Vec<f64>, ParseError>>()?;
             Metric {
                 name,
                 timestamp: None,
                 tags,
                 kind: MetricKind::Incremental,
                 value: MetricValue::Distribution {
-                    values: vec![convert_to_base_units(unit, val)],
-                    sample_rates: vec![sample_rate as u32],
+                    sample_rates: vec![sample_rate as u32; values.len()],
+                    values,
                 },
             }
         }
@@ -124,6 +124,154 @@ pub fn parse(packet: &str) -> Result<Metric, ParseError> {
     Ok(metric)
 }
 
+/// Parses a DogStatsD line, which can hold an event or a service check as
+/// well as a metric. Events and service checks become log events.
+pub fn parse_event(line: &str) -> Result<Event, ParseError> {
+    if line.starts_with("_e{") {
+        parse_dogstatsd_event(line)
+    } else if line.starts_with("_sc|") {
+        parse_service_check(line)
+    } else {
+        parse(line).map(Event::Metric)
+    }
+}
+
+fn parse_dogstatsd_event(line: &str) -> Result<Event, ParseError> {
+    // https://docs.datadoghq.com/developers/dogstatsd/datagram_shell/#events
+    let lengths_and_body = line[3..].splitn(2, "}:").collect::<Vec<_>>();
+    if lengths_and_body.len() != 2 {
+        return Err(ParseError::Malformed(
+            "event should start with '_e{<title length>,<text length>}:'",
+        ));
+    }
+    let (lengths, body) = (lengths_and_body[0], lengths_and_body[1]);
+
+    let lengths = lengths.splitn(2, ',').collect::<Vec<_>>();
+    if lengths.len() != 2 {
+        return Err(ParseError::Malformed(
+            "event lengths should be comma separated",
+        ));
+    }
+    let title_length: usize = lengths[0].parse()?;
+    let text_length: usize = lengths[1].parse()?;
+
+    // lengths are in bytes, the text follows the title after a pipe
+    let too_long = || ParseError::Malformed("event title and text are too long");
+    let text_start = title_length.checked_add(1).ok_or_else(too_long)?;
+    let text_end = text_start.checked_add(text_length).ok_or_else(too_long)?;
+    let title = body.get(..title_length);
+    let text = body.get(text_start..text_end);
+    let (title, text) = match (title, text) {
+        (Some(title), Some(text)) if body.as_bytes()[title_length] == b'|' => (title, text),
+        _ => {
+            return Err(ParseError::Malformed(
+                "event title and text don't match their lengths",
+            ))
+        }
+    };
+
+    let mut event = Event::from(text.replace("\\n", "\n"));
+    let log = event.as_mut_log();
+    log.insert("title", title.replace("\\n", "\n"));
+    log.insert(event::log_schema().source_type_key(), "statsd");
+
+    for field in body[text_end..]
+        .split('|')
+        .filter(|field| !field.is_empty())
+    {
+        match field.splitn(2, ':').collect::<Vec<_>>().as_slice() {
+            ["d", timestamp] => {
+                log.insert(
+                    event::log_schema().timestamp_key(),
+                    parse_timestamp(timestamp)?,
+                );
+            }
+            ["h", host] => {
+                log.insert(event::log_schema().host_key(), *host);
+            }
+            ["k", key] => {
+                log.insert("aggregation_key", *key);
+            }
+            ["p", priority] => {
+                log.insert("priority", *priority);
+            }
+            ["s", source_type_name] => {
+                log.insert("source_type_name", *source_type_name);
+            }
+            ["t", alert_type] => {
+                log.insert("alert_type", *alert_type);
+            }
+            _ if field.starts_with('#') => {
+                log.insert("tags", tags_value(parse_tags(field)?));
+            }
+            _ => {}
+        }
+    }
+
+    Ok(event)
+}
+
+fn parse_timestamp(timestamp: &str) -> Result<DateTime<Utc>, ParseError> {
+    Utc.timestamp_opt(timestamp.parse()?, 0)
+        .single()
+        .ok_or(ParseError::Malformed("timestamp should be in range"))
+}
+
+fn parse_service_check(line: &str) -> Result<Event, ParseError> {
+    // https://docs.datadoghq.com/developers/dogstatsd/datagram_shell/#service-checks
+    // the message must be the last field, it can't be told apart otherwise
+    let (line, message) = match line.find("|m:") {
+        Some(index) => (&line[..index], &line[index + 3..]),
+        None => (line, ""),
+    };
+
+    let parts = line.split('|').collect::<Vec<_>>();
+    if parts.len() < 3 || parts[1].is_empty() {
+        return Err(ParseError::Malformed(
+            "service check should have a name and a status",
+        ));
+    }
+    let status: i64 = parts[2].parse()?;
+    if status < 0 || status > 3 {
+        return Err(ParseError::Malformed(
+            "service check status should be between 0 and 3",
+        ));
+    }
+
+    let mut event = Event::from(message.replace("\\n", "\n"));
+    let log = event.as_mut_log();
+    log.insert("check", parts[1]);
+    log.insert("status", status);
+    log.insert(event::log_schema().source_type_key(), "statsd");
+
+    for field in &parts[3..] {
+        match field.splitn(2, ':').collect::<Vec<_>>().as_slice() {
+            ["d", timestamp] => {
+                log.insert(
+                    event::log_schema().timestamp_key(),
+                    parse_timestamp(timestamp)?,
+                );
+            }
+            ["h", host] => {
+                log.insert(event::log_schema().host_key(), *host);
+            }
+            _ if field.starts_with('#') => {
+                log.insert("tags", tags_value(parse_tags(field)?));
+            }
+            _ => {}
+        }
+    }
+
+    Ok(event)
+}
+
+fn tags_value(tags: BTreeMap<String, String>) -> Value {
+    tags.into_iter()
+        .map(|(key, value)| (key, value.into()))
+        .collect::<BTreeMap<String, Value>>()
+        .into()
+}
+
 fn parse_sampling(input: &str) -> Result<f64, ParseError> {
     if !input.starts_with('@') || input.len() < 2 {
         return Err(ParseError::Malformed(
@@ -150,7 +298,7 @@ fn parse_tags(input: &str) -> Result<BTreeMap<String, String>, ParseError> {
 
     let chunks = input[1..].split(',').collect::<Vec<_>>();
     for chunk in chunks {
-        let pair: Vec<_> = chunk.split(':').collect();
+        let pair: Vec<_> = chunk.splitn(2, ':').collect();
         let key = &pair[0];
         // same as in telegraf plugin:
         // if tag value is not provided, use "true"
@@ -229,8 +377,12 @@ impl From<ParseFloatError> for ParseError {
 
 #[cfg(test)]
 mod test {
-    use super::{parse, sanitize_key, sanitize_sampling};
-    use crate::event::metric::{Metric, MetricKind, MetricValue};
+    use super::{parse, parse_event, sanitize_key, sanitize_sampling, ParseError};
+    use crate::event::{
+        self,
+        metric::{Metric, MetricKind, MetricValue},
+    };
+    use chrono::{DateTime, TimeZone, Utc};
 
     #[test]
     fn basic_counter() {
@@ -391,6 +543,112 @@ mod test {
         );
     }
 
+    #[test]
+    fn distribution() {
+        assert_eq!(
+            parse("latency:0.5:1.5|d|#route:/api/v1:users|@0.5"),
+            Ok(Metric {
+                name: "latency".into(),
+                timestamp: None,
+                tags: Some(
+                    vec![("route".to_owned(), "/api/v1:users".to_owned())]
+                        .into_iter()
+                        .collect(),
+                ),
+                kind: MetricKind::Incremental,
+                value: MetricValue::Distribution {
+                    values: vec![0.5, 1.5],
+                    sample_rates: vec![2, 2],
+                },
+            }),
+        );
+    }
+
+    #[test]
+    fn ignores_extension_fields() {
+        assert_eq!(
+            parse("foo:1|c|#env:prod|c:83c0a99c0a54"),
+            parse("foo:1|c|#env:prod"),
+        );
+    }
+
+    #[test]
+    fn dogstatsd_event() {
+        let event = parse_event(
+            "_e{5,13}:Oops!|Disk\\nis full|d:1591005600|h:web-1|p:low|t:error|#env:prod,critical",
+        )
+        .unwrap();
+        let log = event.as_log();
+
+        assert_eq!(
+            log[&event::log_schema().message_key()],
+            "Disk\nis full".into()
+        );
+        assert_eq!(log[&"title".into()], "Oops!".into());
+        assert_eq!(
+            log[&event::log_schema().timestamp_key()],
+            Utc.ymd(2020, 6, 1).and_hms(10, 0, 0).into()
+        );
+        assert_eq!(log[&event::log_schema().host_key()], "web-1".into());
+        assert_eq!(log[&"priority".into()], "low".into());
+        assert_eq!(log[&"alert_type".into()], "error".into());
+        assert_eq!(log[&"tags.env".into()], "prod".into());
+        assert_eq!(log[&"tags.critical".into()], "true".into());
+    }
+
+    #[test]
+    fn malformed_dogstatsd_event() {
+        assert_eq!(
+            parse_event("_e{10,2}:Oops!|hi").unwrap_err(),
+            ParseError::Malformed("event title and text don't match their lengths")
+        );
+    }
+
+    #[test]
+    fn out_of_range_timestamps() {
+        let error = ParseError::Malformed("timestamp should be in range");
+        assert_eq!(
+            parse_event("_e{5,2}:Oops!|hi|d:9223372036854775807").unwrap_err(),
+            error
+        );
+        assert_eq!(
+            parse_event("_sc|db.up|2|d:-9223372036854775808").unwrap_err(),
+            error
+        );
+    }
+
+    #[test]
+    fn overflowing_event_lengths() {
+        let error = ParseError::Malformed("event title and text are too long");
+        let max = usize::MAX;
+        assert_eq!(
+            parse_event(&format!("_e{{{},2}}:Oops!|hi", max)).unwrap_err(),
+            error
+        );
+        assert_eq!(
+            parse_event(&format!("_e{{5,{}}}:Oops!|hi", max)).unwrap_err(),
+            error
+        );
+    }
+
+    #[test]
+    fn service_check() {
+        let event =
+            parse_event("_sc|db.up|2|h:db-1|#env:prod|m:Connection refused | retrying").unwrap();
+        let log = event.as_log();
+
+        assert_eq!(
+            log[&event::log_schema().message_key()],
+            "Connection refused | retrying".into()
+        );
+        assert_eq!(log[&"check".into()], "db.up".into());
+        assert_eq!(log[&"status".into()], 2.into());
+        assert_eq!(log[&event::log_schema().host_key()], "db-1".into());
+        assert_eq!(log[&"tags.env".into()], "prod".into());
+
+        assert!(parse_event("_sc|db.up|4").is_err());
+    }
+
     #[test]
     fn sanitizing_keys() {
         assert_eq!("foo-bar-baz", sanitize_key("foo/bar/baz"));
@@ -1,4 +1,5 @@
 use crate::{
+    decoding::{DecodedStream, DecodingConfig, FramingConfig},
     event::{self, Event},
     shutdown::ShutdownSignal,
     stream::StreamExt,
@@ -29,6 +30,7 @@ pub struct StdinConfig {
     #[serde(default = "default_max_length")]
     pub max_length: usize,
     pub host_key: Option<String>,
+    pub decoding: DecodingConfig,
 }
 
 impl Default for StdinConfig {
@@ -36,6 +38,7 @@ impl Default for StdinConfig {
         StdinConfig {
             max_length: default_max_length(),
             host_key: None,
+            decoding: DecodingConfig::default(),
         }
     }
 }
@@ -70,7 +73,7 @@ impl SourceConfig for StdinConfig {
 }
 
 pub fn stdin_source<R>(
-    stdin: R,
+    mut stdin: R,
     config: StdinConfig,
     shutdown: ShutdownSignal,
     out: mpsc::Sender<Event>,
@@ -123,30 +126,33 @@ where
             thread::spawn(move || {
                 info!("Capturing STDIN.");
 
-                for line in stdin.lines() {
-                    match line {
+                // Chunks are passed as they are read, each source frames them.
+                loop {
+                    let chunk = match stdin.fill_buf() {
+                        Ok(buf) if buf.is_empty() => break,
+                        Ok(buf) => Bytes::from(buf),
+                        Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
                         Err(e) => {
                             error!(message = "Unable to read from source.", error = %e);
                             break;
                         }
-                        Ok(line) => {
-                            if sender.send(Bytes::from(line)).is_err() {
-                                // There are no active receivers.
-                                // Try to stop.
-                                let mut guard =
-                                    CRITICAL_SECTION.lock().expect("CRITICAL_SECTION poisoned");
-
-                                if sender.receiver_count() == 0 {
-                                    guard.take();
-                                    return;
-                                }
-
-                                // A new receiver has shown up.
-
-                                // It's fine not to resend the line since it came from
-                                // before this new receiver has shown up.
-                            }
+                    };
+                    stdin.consume(chunk.len());
+
+                    if sender.send(chunk).is_err() {
+                        // There are no active receivers.
+                        // Try to stop.
+                        let mut guard = CRITICAL_SECTION.lock().expect("CRITICAL_SECTION poisoned");
+
+                        if sender.receiver_count() == 0 {
+                            guard.take();
+                            return;
                         }
+
+                        // A new receiver has shown up.
+
+                        // It's fine not to resend the chunk since it came from
+                        // before this new receiver has shown up.
                     }
                 }
 
@@ -161,10 +167,15 @@ where
     };
     std::mem::drop(guard);
 
+    let decoder = config.decoding.decoder(FramingConfig::NewlineDelimited {
+        max_length: Some(config.max_length),
+    });
+    let chunks = Compat::new(receiver).map_err(|e| io::Error::new(io::ErrorKind::Other, e));
+
     Ok(Box::new(
-        Compat::new(receiver)
+        DecodedStream::new(chunks, decoder)
             .take_until(shutdown)
-            .map(move |line| create_event(line, &host_key, &hostname))
+            .map(move |(event, _)| create_event(event, &host_key, &hostname))
             .map_err(|e| error!("error reading line: {:?}", e))
             .forward(
                 out.sink_map_err(|e| error!(message = "Unable to send event to out.", error = %e)),
@@ -173,16 +184,15 @@ where
     ))
 }
 
-fn create_event(line: Bytes, host_key: &str, hostname: &Option<String>) -> Event {
-    let mut event = Event::from(line);
-
+fn create_event(mut event: Event, host_key: &str, hostname: &Option<String>) -> Event {
     // Add source type
     event
         .as_mut_log()
         .insert(event::log_schema().source_type_key(), "stdin");
 
     if let Some(hostname) = &hostname {
-        event.as_mut_log().insert(host_key, hostname.clone());
+        let host_key = host_key.into();
+        event.as_mut_log().try_insert(&host_key, hostname.clone());
     }
 
     event
@@ -203,7 +213,7 @@ mod tests {
         let host_key = "host".to_string();
         let hostname = Some("Some.Machine".to_string());
 
-        let event = create_event(line, &host_key, &hostname);
+        let event = create_event(Event::from(line), &host_key, &hostname);
         let log = event.into_log();
 
         assert_eq!(log[&"host".into()], "Some.Machine".into());
@@ -219,7 +229,8 @@ mod tests {
         crate::test_util::trace_init();
         let (tx, mut rx) = mpsc::channel(10);
         let config = StdinConfig::default();
-        let buf = Cursor::new(String::from("hello world\nhello world again"));
+        // The second line ends with CRLF, as on Windows.
+        let buf = Cursor::new(String::from("hello world\nhello world again\r\n"));
 
         let mut rt = Runtime::new().unwrap();
         let source = stdin_source(buf, config, ShutdownSignal::noop(), tx).unwrap();
@@ -2,7 +2,8 @@ use super::util::{SocketListenAddr, TcpSource};
 #[cfg(unix)]
 use crate::sources::util::build_unix_source;
 use crate::{
-    event::{self, Event, Value},
+    decoding::{insert_fields_from_syslog, resolve_year},
+    event::{self, Event},
     internal_events::{SyslogEventReceived, SyslogUdpReadError},
     shutdown::ShutdownSignal,
     stream::StreamExt,
@@ -10,17 +11,17 @@ use crate::{
     topology::config::{DataType, GlobalOptions, SourceConfig, SourceDescription},
 };
 use bytes::Bytes;
-use chrono::{Datelike, Utc};
+use chrono::Utc;
+use codec::Rfc6587Codec;
 use derive_is_enum_variant::is_enum_variant;
 use futures01::{future, sync::mpsc, Future, Sink, Stream};
 use serde::{Deserialize, Serialize};
 use std::net::SocketAddr;
 #[cfg(unix)]
 use std::path::PathBuf;
-use syslog_loose::{self, IncompleteDate, Message, ProcId, Protocol};
 use tokio01::{
     self,
-    codec::{BytesCodec, LinesCodec},
+    codec::BytesCodec,
     net::{UdpFramed, UdpSocket},
 };
 use tracing::field;
@@ -96,14 +97,20 @@ impl SourceConfig for SyslogConfig {
             }
             Mode::Udp { address } => Ok(udp(address, self.max_length, host_key, shutdown, out)),
             #[cfg(unix)]
-            Mode::Unix { path } => Ok(build_unix_source(
-                path,
-                self.max_length,
-                host_key,
-                shutdown,
-                out,
-                event_from_str,
-            )),
+            Mode::Unix { path } => {
+                let max_length = self.max_length;
+                Ok(build_unix_source(
+                    path,
+                    move || Rfc6587Codec::new_with_max_length(max_length),
+                    host_key,
+                    shutdown,
+                    out,
+                    |host_key, default_host, frame: Bytes| {
+                        let line = String::from_utf8_lossy(&frame);
+                        event_from_str(host_key, default_host, &line)
+                    },
+                ))
+            }
         }
     }
 
@@ -123,14 +130,15 @@ struct SyslogTcpSource {
 }
 
 impl TcpSource for SyslogTcpSource {
-    type Decoder = LinesCodec;
+    type Decoder = Rfc6587Codec;
 
     fn decoder(&self) -> Self::Decoder {
-        LinesCodec::new_with_max_length(self.max_length)
+        Rfc6587Codec::new_with_max_length(self.max_length)
     }
 
-    fn build_event(&self, frame: String, host: Bytes) -> Option<Event> {
-        event_from_str(&self.host_key, Some(host), &frame).map(|event| {
+    fn build_event(&self, frame: Bytes, host: Bytes) -> Option<Event> {
+        let line = String::from_utf8_lossy(&frame);
+        event_from_str(&self.host_key, Some(host), &line).map(|event| {
             trace!(
                 message = "Received one event.",
                 event = field::debug(&event)
@@ -182,25 +190,11 @@ pub fn udp(
     )
 }
 
-/// Function used to resolve the year for syslog messages that don't include the year.
-/// If the current month is January, and the syslog message is for December, it will take the previous year.
-/// Otherwise, take the current year.
-fn resolve_year((month, _date, _hour, _min, _sec): IncompleteDate) -> i32 {
-    let now = Utc::now();
-    if now.month() == 1 && month == 12 {
-        now.year() - 1
-    } else {
-        now.year()
-    }
-}
-
 /**
 * Function to pass to build_unix_source, specific to the Unix mode of the syslog source.
 * Handles the logic of parsing and decoding the syslog message format.
 **/
-// TODO: many more cases to handle:
-// octet framing (i.e. num bytes as ascii string prefix) with and without delimiters
-// null byte delimiter in place of newline
+// TODO: null byte delimiter in place of newline
 fn event_from_str(host_key: &str, default_host: Option<Bytes>, line: &str) -> Option<Event> {
     emit!(SyslogEventReceived {
         byte_size: line.len()
@@ -239,40 +233,6 @@ fn event_from_str(host_key: &str, default_host: Option<Bytes>, line: &str) -> Op
     Some(event)
 }
 
-fn insert_fields_from_syslog(event: &mut Event, parsed: Message<&str>) {
-    let log = event.as_mut_log();
-
-    if let Some(severity) = parsed.severity {
-        log.insert("severity", severity.as_str());
-    }
-    if let Some(facility) = parsed.facility {
-        log.insert("facility", facility.as_str());
-    }
-    if let Protocol::RFC5424(version) = parsed.protocol {
-        log.insert("version", version as i64);
-    }
-    if let Some(app_name) = parsed.appname {
-        log.insert("appname", app_name);
-    }
-    if let Some(msg_id) = parsed.msgid {
-        log.insert("msgid", msg_id);
-    }
-    if let Some(procid) = parsed.procid {
-        let value: Value = match procid {
-            ProcId::PID(pid) => pid.into(),
-            ProcId::Name(name) => name.into(),
-        };
-        log.insert("procid", value);
-    }
-
-    for element in parsed.structured_data.iter() {
-        for (name, value) in element.params.iter() {
-            let key = format!("{}.{}", element.id, name);
-            log.insert(key, value.clone());
-        }
-    }
-}
-
 #[cfg(test)]
 mod test {
     use super::{event_from_str, SyslogConfig};
@@ -1,13 +1,21 @@
-use crate::event::Event;
+use crate::event::{
+    finalization::{BatchNotifier, BatchStatus},
+    Event,
+};
 use crate::{
     shutdown::ShutdownSignal,
     tls::{MaybeTlsSettings, TlsConfig},
 };
-use futures01::{sync::mpsc, Future, IntoFuture, Sink};
+use futures01::{
+    future::{self, Either},
+    sync::mpsc,
+    Future, IntoFuture, Sink,
+};
 use serde::Serialize;
 use std::error::Error;
 use std::fmt::{self, Display};
 use std::net::SocketAddr;
+use std::sync::Arc;
 use warp::filters::{body::FullBody, BoxedFilter};
 use warp::http::{HeaderMap, StatusCode};
 use warp::{Filter, Rejection};
@@ -39,6 +47,13 @@ pub trait HttpSource: Clone + Send + Sync + 'static {
         header_map: HeaderMap,
     ) -> Result<Vec<Event>, ErrorMessage>;
 
+    /// Whether requests are only answered once their events are delivered,
+    /// failing with a server error when they weren't. Metric events aren't
+    /// tracked, so a request carrying only metrics is answered right away.
+    fn acknowledgements(&self) -> bool {
+        false
+    }
+
     fn run(
         self,
         address: SocketAddr,
@@ -59,22 +74,57 @@ pub trait HttpSource: Clone + Send + Sync + 'static {
             .and(warp::body::concat())
             .and_then(move |headers: HeaderMap, body| {
                 let out = out.clone();
+                let acknowledgements = self.acknowledgements();
                 info!("Handling http request: {:?}", headers);
 
                 self.build_event(body, headers)
                     .map_err(warp::reject::custom)
                     .into_future()
-                    .and_then(|events| {
-                        out.send_all(futures01::stream::iter_ok(events)).map_err(
-                            move |e: mpsc::SendError<Event>| {
+                    .and_then(move |mut events| {
+                        let receiver = if acknowledgements {
+                            let (batch, receiver) = BatchNotifier::new_with_receiver();
+                            // Metrics carry no finalizers and count as delivered.
+                            for event in &mut events {
+                                if let Event::Log(log) = event {
+                                    log.add_batch_notifier(Arc::clone(&batch));
+                                }
+                            }
+                            Some(receiver)
+                        } else {
+                            None
+                        };
+
+                        out.send_all(futures01::stream::iter_ok(events))
+                            .map_err(move |e: mpsc::SendError<Event>| {
                                 // can only fail if receiving end disconnected, so we are shuting down,
                                 // probably not gracefully.
                                 error!("Failed to forward events, downstream is closed");
                                 error!("Tried to send the following event: {:?}", e);
 
                                 warp::reject::custom("shutting down")
-                            },
-                        )
+                            })
+                            .and_then(move |_| match receiver {
+                                None => Either::A(future::ok(())),
+                                Some(receiver) => Either::B(
+                                    receiver
+                                        .map_err(|_| warp::reject::custom("shutting down"))
+                                        .and_then(|status| match status {
+                                            BatchStatus::Delivered => Ok(()),
+                                            BatchStatus::Errored => {
+                                                Err(warp::reject::custom(ErrorMessage::new(
+                                                    StatusCode::SERVICE_UNAVAILABLE,
+                                                    "Events could not be delivered".into(),
+                                                )))
+                                            }
+                                            BatchStatus::Failed => {
+                                                Err(warp::reject::custom(ErrorMessage::new(
+                                                    StatusCode::INTERNAL_SERVER_ERROR,
+                                                    "Events were rejected downstream".into(),
+                                                )))
+                                            }
+                                        }),
+                                ),
+                            })
                     })
                     .map(|_| warp::reply())
             });
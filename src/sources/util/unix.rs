@@ -7,7 +7,7 @@ use futures01::{future, sync::mpsc, Future, Sink, Stream};
 use std::path::PathBuf;
 use tokio01::{
     self,
-    codec::{FramedRead, LinesCodec},
+    codec::{Decoder, FramedRead},
 };
 use tokio_uds::UnixListener;
 use tracing::field;
@@ -15,21 +15,25 @@ use tracing_futures::Instrument;
 
 /**
 * Returns a Source object corresponding to a Unix domain socket.  Passing in different functions
-* for build_event can allow for different source-specific logic (such as decoding syslog messages
-* in the syslog source).
+* for build_decoder and build_event can allow for different source-specific logic (such as
+* decoding syslog messages in the syslog source).
 **/
-pub fn build_unix_source(
+pub fn build_unix_source<D>(
     path: PathBuf,
-    max_length: usize,
+    build_decoder: impl Fn() -> D + std::marker::Send + 'static,
     host_key: String,
     shutdown: ShutdownSignal,
     out: mpsc::Sender<Event>,
-    build_event: impl Fn(&str, Option<Bytes>, &str) -> Option<Event>
+    build_event: impl Fn(&str, Option<Bytes>, D::Item) -> Option<Event>
         + std::marker::Send
         + std::marker::Sync
         + std::clone::Clone
         + 'static,
-) -> Source {
+) -> Source
+where
+    D: Decoder<Error = std::io::Error> + std::marker::Send + 'static,
+    D::Item: std::marker::Send,
+{
     let out = out.sink_map_err(|e| error!("error sending line: {:?}", e));
 
     Box::new(future::lazy(move || {
@@ -62,9 +66,9 @@ pub fn build_unix_source(
                 let build_event = build_event.clone();
                 let received_from: Option<Bytes> =
                     path.map(|p| p.to_string_lossy().into_owned().into());
-                let lines_in = FramedRead::new(socket, LinesCodec::new_with_max_length(max_length))
+                let lines_in = FramedRead::new(socket, build_decoder())
                     .take_until(shutdown.clone())
-                    .filter_map(move |line| build_event(&host_key, received_from.clone(), &line))
+                    .filter_map(move |frame| build_event(&host_key, received_from.clone(), frame))
                     .map_err(move |error| {
                         emit!(UnixSocketError {
                             error,
@@ -1,22 +1,30 @@
-use super::util::{SocketListenAddr, TcpSource};
+use super::util::{ErrorMessage, HttpSource, SocketListenAddr, TcpSource};
 use crate::{
     event::proto,
     internal_events::{VectorEventReceived, VectorProtoDecodeError},
     shutdown::ShutdownSignal,
     tls::{MaybeTlsSettings, TlsConfig},
     topology::config::{DataType, GlobalOptions, SourceConfig, SourceDescription},
+    vector_protocol::{self, Version},
     Event,
 };
-use bytes::{Bytes, BytesMut};
+use bytes::{Buf, Bytes, BytesMut};
+use flate2::read::GzDecoder;
 use futures01::sync::mpsc;
 use prost::Message;
 use serde::{Deserialize, Serialize};
-use tokio01::codec::LengthDelimitedCodec;
+use snafu::Snafu;
+use std::io::Read;
+use tokio01::codec::{Decoder, LengthDelimitedCodec};
+use warp::filters::body::FullBody;
+use warp::http::{HeaderMap, StatusCode};
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(deny_unknown_fields)]
 pub struct VectorConfig {
     pub address: SocketListenAddr,
+    #[serde(default)]
+    pub version: Version,
     #[serde(default = "default_shutdown_timeout_secs")]
     pub shutdown_timeout_secs: u64,
     tls: Option<TlsConfig>,
@@ -31,12 +39,19 @@ impl VectorConfig {
     pub fn new(address: SocketListenAddr, tls: Option<TlsConfig>) -> Self {
         Self {
             address,
+            version: Version::V1,
             shutdown_timeout_secs: default_shutdown_timeout_secs(),
             tls,
         }
     }
 }
 
+#[derive(Debug, Snafu)]
+enum BuildError {
+    #[snafu(display("Version 2 of the protocol can't use systemd sockets"))]
+    SystemdSocket,
+}
+
 inventory::submit! {
     SourceDescription::new_without_default::<VectorConfig>("vector")
 }
@@ -50,9 +65,20 @@ impl SourceConfig for VectorConfig {
         shutdown: ShutdownSignal,
         out: mpsc::Sender<Event>,
     ) -> crate::Result<super::Source> {
-        let vector = VectorSource;
-        let tls = MaybeTlsSettings::from_config(&self.tls, true)?;
-        vector.run(self.address, self.shutdown_timeout_secs, tls, shutdown, out)
+        match (self.version, self.address) {
+            (Version::V1, address) => {
+                let tls = MaybeTlsSettings::from_config(&self.tls, true)?;
+                VectorSource.run(address, self.shutdown_timeout_secs, tls, shutdown, out)
+            }
+            (Version::V2, SocketListenAddr::SocketAddr(address)) => VectorHttpSource.run(
+                address,
+                vector_protocol::EVENTS_PATH,
+                &self.tls,
+                out,
+                shutdown,
+            ),
+            (Version::V2, SocketListenAddr::SystemdFd(_)) => Err(BuildError::SystemdSocket.into()),
+        }
     }
 
     fn output_type(&self) -> DataType {
@@ -62,6 +88,10 @@ impl SourceConfig for VectorConfig {
     fn source_type(&self) -> &'static str {
         "vector"
     }
+
+    fn acknowledgements(&self) -> bool {
+        self.version == Version::V2
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -75,39 +105,118 @@ impl TcpSource for VectorSource {
     }
 
     fn build_event(&self, frame: BytesMut, _host: Bytes) -> Option<Event> {
-        let byte_size = frame.len();
-        match proto::EventWrapper::decode(frame).map(Event::from) {
-            Ok(event) => {
-                emit!(VectorEventReceived { byte_size });
-                Some(event)
-            }
-            Err(error) => {
-                emit!(VectorProtoDecodeError { error });
-                None
-            }
+        decode_event(frame)
+    }
+}
+
+fn decode_event(frame: BytesMut) -> Option<Event> {
+    let byte_size = frame.len();
+    match proto::EventWrapper::decode(frame).map(Event::from) {
+        Ok(event) => {
+            emit!(VectorEventReceived { byte_size });
+            Some(event)
+        }
+        Err(error) => {
+            emit!(VectorProtoDecodeError { error });
+            None
         }
     }
 }
 
+#[derive(Debug, Clone)]
+struct VectorHttpSource;
+
+impl HttpSource for VectorHttpSource {
+    fn build_event(&self, body: FullBody, headers: HeaderMap) -> Result<Vec<Event>, ErrorMessage> {
+        decode_batch(body.collect(), &headers)
+    }
+
+    fn acknowledgements(&self) -> bool {
+        true
+    }
+}
+
+/// A batch is rejected as a whole when any of its events can't be decoded, as
+/// the sink would send it again as is.
+fn decode_batch(body: BytesMut, headers: &HeaderMap) -> Result<Vec<Event>, ErrorMessage> {
+    let mut body = match headers
+        .get("Content-Encoding")
+        .map(|value| value.as_bytes())
+    {
+        None | Some(b"identity") => body,
+        Some(b"gzip") => {
+            let mut decoded = Vec::new();
+            GzDecoder::new(&body[..])
+                .read_to_end(&mut decoded)
+                .map_err(|error| {
+                    ErrorMessage::new(
+                        StatusCode::BAD_REQUEST,
+                        format!("Could not decompress body: {}", error),
+                    )
+                })?;
+            decoded.into()
+        }
+        Some(encoding) => {
+            return Err(ErrorMessage::new(
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                format!(
+                    "Unsupported encoding {:?}",
+                    String::from_utf8_lossy(encoding)
+                ),
+            ))
+        }
+    };
+
+    let invalid = || ErrorMessage::new(StatusCode::BAD_REQUEST, "Invalid event in batch".into());
+    let mut codec = LengthDelimitedCodec::new();
+    let mut events = Vec::new();
+    while let Some(frame) = codec.decode(&mut body).map_err(|_| invalid())? {
+        events.push(decode_event(frame).ok_or_else(invalid)?);
+    }
+    if body.is_empty() {
+        Ok(events)
+    } else {
+        Err(invalid())
+    }
+}
+
 #[cfg(feature = "sinks-vector")]
 #[cfg(test)]
 mod test {
-    use super::VectorConfig;
-    use crate::shutdown::ShutdownSignal;
+    use super::*;
     use crate::{
         event::{
+            finalization::EventStatus,
             metric::{MetricKind, MetricValue},
             Metric,
         },
-        sinks::vector::VectorSinkConfig,
-        test_util::{next_addr, wait_for_tcp, CollectCurrent},
-        tls::{TlsConfig, TlsOptions},
-        topology::config::{GlobalOptions, SinkConfig, SinkContext, SourceConfig},
-        Event,
+        runtime::Runtime,
+        sinks::{util::Compression, vector::VectorSinkConfig},
+        test_util::{collect_n, next_addr, wait_for_tcp, CollectCurrent},
+        tls::TlsOptions,
+        topology::config::{SinkConfig, SinkContext},
     };
-    use futures01::{stream, sync::mpsc, Future, Sink};
+    use bytes::BufMut;
+    use futures01::{stream, sync::oneshot, Future, Sink, Stream};
     use std::net::SocketAddr;
 
+    /// Marks the events delivered as they come out of the source, as the
+    /// topology does once a sink sent them, and passes them on.
+    fn deliver(rt: &mut Runtime, rx: mpsc::Receiver<Event>) -> mpsc::Receiver<Event> {
+        let (tx, delivered) = mpsc::channel(100);
+        rt.spawn(
+            rx.map(|mut event| {
+                event
+                    .take_finalizers()
+                    .update_status(EventStatus::Delivered);
+                event
+            })
+            .forward(tx.sink_map_err(drop))
+            .map(drop),
+        );
+        delivered
+    }
+
     fn stream_test(addr: SocketAddr, source: VectorConfig, sink: VectorSinkConfig) {
         let (tx, rx) = mpsc::channel(100);
 
@@ -120,6 +229,7 @@ mod test {
             )
             .unwrap();
         let mut rt = crate::runtime::Runtime::new().unwrap();
+        let rx = deliver(&mut rt, rx);
         rt.spawn(server);
         wait_for_tcp(addr);
 
@@ -154,16 +264,42 @@ mod test {
         assert_eq!(events, output);
     }
 
+    fn tls_config() -> Option<TlsConfig> {
+        Some(TlsConfig {
+            enabled: Some(true),
+            options: TlsOptions {
+                crt_path: Some("tests/data/localhost.crt".into()),
+                key_path: Some("tests/data/localhost.key".into()),
+                ..Default::default()
+            },
+        })
+    }
+
+    fn sink_config(addr: SocketAddr, version: Version, tls: bool) -> VectorSinkConfig {
+        VectorSinkConfig {
+            version,
+            tls: if tls {
+                Some(TlsConfig {
+                    enabled: Some(true),
+                    options: TlsOptions {
+                        verify_certificate: Some(false),
+                        ..Default::default()
+                    },
+                })
+            } else {
+                None
+            },
+            ..VectorSinkConfig::new(format!("localhost:{}", addr.port()))
+        }
+    }
+
     #[test]
     fn it_works_with_vector_sink() {
         let addr = next_addr();
         stream_test(
             addr,
             VectorConfig::new(addr.into(), None),
-            VectorSinkConfig {
-                address: format!("localhost:{}", addr.port()),
-                tls: None,
-            },
+            sink_config(addr, Version::V1, false),
         );
     }
 
@@ -172,27 +308,179 @@ mod test {
         let addr = next_addr();
         stream_test(
             addr,
-            VectorConfig::new(
-                addr.into(),
-                Some(TlsConfig {
-                    enabled: Some(true),
-                    options: TlsOptions {
-                        crt_path: Some("tests/data/localhost.crt".into()),
-                        key_path: Some("tests/data/localhost.key".into()),
-                        ..Default::default()
-                    },
-                }),
-            ),
+            VectorConfig::new(addr.into(), tls_config()),
+            sink_config(addr, Version::V1, true),
+        );
+    }
+
+    #[test]
+    fn it_works_with_vector_sink_v2() {
+        let addr = next_addr();
+        stream_test(
+            addr,
+            VectorConfig {
+                version: Version::V2,
+                ..VectorConfig::new(addr.into(), None)
+            },
+            sink_config(addr, Version::V2, false),
+        );
+    }
+
+    #[test]
+    fn it_works_with_vector_sink_v2_tls_uncompressed() {
+        let addr = next_addr();
+        stream_test(
+            addr,
+            VectorConfig {
+                version: Version::V2,
+                ..VectorConfig::new(addr.into(), tls_config())
+            },
             VectorSinkConfig {
-                address: format!("localhost:{}", addr.port()),
-                tls: Some(TlsConfig {
-                    enabled: Some(true),
-                    options: TlsOptions {
-                        verify_certificate: Some(false),
-                        ..Default::default()
-                    },
-                }),
+                compression: Some(Compression::None),
+                ..sink_config(addr, Version::V2, true)
             },
         );
     }
+
+    #[test]
+    fn v2_retries_until_the_source_accepts() {
+        let addr = next_addr();
+        let mut rt = crate::runtime::Runtime::new().unwrap();
+
+        let cx = SinkContext::new_test(rt.executor());
+        let (sink, _) = sink_config(addr, Version::V2, false).build(cx).unwrap();
+        let events = vec![Event::from("sent"), Event::from("before the source")];
+        let (done_tx, done_rx) = oneshot::channel();
+        rt.spawn(
+            sink.send_all(stream::iter_ok(events.clone()))
+                .map(|_| done_tx.send(()).unwrap()),
+        );
+
+        std::thread::sleep(std::time::Duration::from_millis(500));
+
+        let (tx, rx) = mpsc::channel(100);
+        let rx = deliver(&mut rt, rx);
+        let server = VectorConfig {
+            version: Version::V2,
+            ..VectorConfig::new(addr.into(), None)
+        }
+        .build(
+            "default",
+            &GlobalOptions::default(),
+            ShutdownSignal::noop(),
+            tx,
+        )
+        .unwrap();
+        rt.spawn(server);
+
+        rt.block_on(done_rx).unwrap();
+        let output = rt.block_on(collect_n(rx, events.len())).unwrap();
+        assert_eq!(events, output);
+    }
+
+    #[test]
+    fn v2_fails_over_to_the_live_address() {
+        let addr = next_addr();
+        let dead = next_addr();
+        let (tx, rx) = mpsc::channel(100);
+
+        let server = VectorConfig {
+            version: Version::V2,
+            ..VectorConfig::new(addr.into(), None)
+        }
+        .build(
+            "default",
+            &GlobalOptions::default(),
+            ShutdownSignal::noop(),
+            tx,
+        )
+        .unwrap();
+        let mut rt = crate::runtime::Runtime::new().unwrap();
+        let rx = deliver(&mut rt, rx);
+        rt.spawn(server);
+        wait_for_tcp(addr);
+
+        let cx = SinkContext::new_test(rt.executor());
+        let (mut sink, _) = VectorSinkConfig {
+            address: Some(dead.to_string()),
+            addresses: vec![addr.to_string()],
+            ..sink_config(addr, Version::V2, false)
+        }
+        .build(cx)
+        .unwrap();
+
+        let events = (0..10)
+            .map(|i| Event::from(format!("event {}", i)))
+            .collect::<Vec<_>>();
+        // Each event goes in a batch of its own, so that both addresses get
+        // picked in turn.
+        for event in events.clone() {
+            sink = rt.block_on(sink.send(event)).unwrap();
+        }
+
+        let output = rt.block_on(collect_n(rx, events.len())).unwrap();
+        assert_eq!(events, output);
+    }
+
+    #[test]
+    fn v2_rejects_invalid_batches() {
+        let event = proto::EventWrapper::from(Event::from("valid"));
+        let mut body = BytesMut::new();
+        body.reserve(4 + event.encoded_len());
+        body.put_u32_be(event.encoded_len() as u32);
+        event.encode(&mut body).unwrap();
+        assert_eq!(
+            decode_batch(body.clone(), &HeaderMap::new()).unwrap().len(),
+            1
+        );
+
+        // A truncated frame after a valid one.
+        body.extend_from_slice(&[0, 0, 0, 2, 0xff]);
+        let error = decode_batch(body, &HeaderMap::new()).unwrap_err();
+        assert_eq!(error.to_string(), "400: Invalid event in batch");
+    }
+
+    #[test]
+    fn v2_fails_requests_with_undelivered_events() {
+        let addr = next_addr();
+        let (tx, rx) = mpsc::channel(100);
+        let server = VectorConfig {
+            version: Version::V2,
+            ..VectorConfig::new(addr.into(), None)
+        }
+        .build(
+            "default",
+            &GlobalOptions::default(),
+            ShutdownSignal::noop(),
+            tx,
+        )
+        .unwrap();
+        let mut rt = Runtime::new().unwrap();
+        rt.spawn(server);
+        wait_for_tcp(addr);
+
+        let event = proto::EventWrapper::from(Event::from("undelivered"));
+        let mut body = BytesMut::new();
+        body.reserve(4 + event.encoded_len());
+        body.put_u32_be(event.encoded_len() as u32);
+        event.encode(&mut body).unwrap();
+
+        let request = std::thread::spawn(move || {
+            reqwest::Client::new()
+                .post(&format!("http://{}/{}", addr, vector_protocol::EVENTS_PATH))
+                .body(body.to_vec())
+                .send()
+                .unwrap()
+                .status()
+                .as_u16()
+        });
+
+        let mut events = rt.block_on(collect_n(rx, 1)).unwrap();
+        events[0]
+            .take_finalizers()
+            .update_status(EventStatus::Errored);
+        drop(events);
+
+        assert_eq!(request.join().unwrap(), 503);
+    }
 }
@@ -0,0 +1,92 @@
+use crate::runtime::Runtime;
+use futures01::Stream;
+use std::io::Write;
+use structopt::StructOpt;
+
+#[derive(StructOpt, Debug)]
+#[structopt(rename_all = "kebab-case")]
+pub struct Opts {
+    /// Address of the API of the running Vector instance.
+    #[structopt(long, default_value = "http://127.0.0.1:8686")]
+    url: String,
+
+    /// Print events as JSON or only their message.
+    #[structopt(long, default_value = "json", possible_values = &["json", "text"])]
+    format: String,
+
+    /// Number of events held for the terminal before newer ones are dropped.
+    #[structopt(long, default_value = "100")]
+    limit: usize,
+
+    /// Outputs to tap, globs such as `in*` are allowed. Every output is tapped if none is given.
+    patterns: Vec<String>,
+}
+
+pub fn cmd(opts: &Opts) -> exitcode::ExitCode {
+    let query = url::form_urlencoded::Serializer::new(String::new())
+        .append_pair("outputs", &opts.patterns.join(","))
+        .append_pair("format", &opts.format)
+        .append_pair("limit", &opts.limit.to_string())
+        .finish();
+
+    let uri = match format!("{}/tap?{}", opts.url.trim_end_matches('/'), query).parse() {
+        Ok(uri) => uri,
+        Err(error) => {
+            eprintln!("Invalid url {:?}: {}", opts.url, error);
+            return exitcode::USAGE;
+        }
+    };
+
+    let mut rt = match Runtime::single_threaded() {
+        Ok(rt) => rt,
+        Err(error) => {
+            eprintln!("Unable to create runtime: {}", error);
+            return exitcode::OSERR;
+        }
+    };
+
+    let response = match rt.block_on(hyper::Client::new().get(uri)) {
+        Ok(response) => response,
+        Err(error) => {
+            eprintln!("Unable to reach Vector at {}: {}", opts.url, error);
+            return exitcode::UNAVAILABLE;
+        }
+    };
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = rt.block_on(response.into_body().concat2());
+        eprintln!(
+            "Unable to tap ({}): {}",
+            status,
+            body.map(|body| String::from_utf8_lossy(&body).into_owned())
+                .unwrap_or_default()
+        );
+        return exitcode::UNAVAILABLE;
+    }
+
+    // Stop quietly once stdout goes away, e.g. when piped into `head`.
+    let stdout = std::io::stdout();
+    let printed = response.into_body().for_each(|chunk| {
+        let mut stdout = stdout.lock();
+        if stdout
+            .write_all(&chunk)
+            .and_then(|_| stdout.flush())
+            .is_err()
+        {
+            std::process::exit(exitcode::OK);
+        }
+        Ok(())
+    });
+
+    match rt.block_on(printed) {
+        Ok(()) => {
+            eprintln!("Vector closed the tap.");
+            exitcode::OK
+        }
+        Err(error) => {
+            eprintln!("Tap interrupted: {}", error);
+            exitcode::UNAVAILABLE
+        }
+    }
+}
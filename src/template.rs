@@ -112,11 +112,12 @@ fn render_fields(src: &str, event: &Event) -> Result<String, Vec<Atom>> {
                 .get(1)
                 .map(|s| Atom::from(s.as_str().trim()))
                 .expect("src should match regex");
-            if let Some(val) = event.as_log().get(&key) {
-                val.to_string_lossy()
-            } else {
-                missing_fields.push(key.clone());
-                String::new()
+            match render_field(&key, event) {
+                Some(val) => val,
+                None => {
+                    missing_fields.push(key.clone());
+                    String::new()
+                }
             }
         })
         .into_owned();
@@ -127,12 +128,29 @@ fn render_fields(src: &str, event: &Event) -> Result<String, Vec<Atom>> {
     }
 }
 
+/// Metrics only have their `name` and their tags, as `tags.<name>`, to render.
+fn render_field(key: &Atom, event: &Event) -> Option<String> {
+    match event {
+        Event::Log(log) => log.get(key).map(Value::to_string_lossy),
+        Event::Metric(metric) => match key.as_ref() {
+            "name" => Some(metric.name.clone()),
+            key if key.starts_with("tags.") => metric
+                .tags
+                .as_ref()
+                .and_then(|tags| tags.get(&key["tags.".len()..]))
+                .cloned(),
+            _ => None,
+        },
+    }
+}
+
 fn render_timestamp(src: &str, event: &Event) -> String {
     let timestamp = match event {
         Event::Log(log) => log
             .get(&event::log_schema().timestamp_key())
-            .and_then(Value::as_timestamp),
-        _ => None,
+            .and_then(Value::as_timestamp)
+            .cloned(),
+        Event::Metric(metric) => metric.timestamp,
     };
     if let Some(ts) = timestamp {
         ts.format(src).to_string()
@@ -360,4 +378,30 @@ mod tests {
             template.render(&event)
         )
     }
+
+    #[test]
+    fn render_metric() {
+        use crate::event::metric::{Metric, MetricKind, MetricValue};
+
+        let event = Event::Metric(Metric {
+            name: "requests".into(),
+            timestamp: Some(Utc.ymd(2001, 2, 3).and_hms(4, 5, 6)),
+            tags: Some(
+                vec![("code".to_owned(), "200".to_owned())]
+                    .into_iter()
+                    .collect(),
+            ),
+            kind: MetricKind::Incremental,
+            value: MetricValue::Counter { value: 1.0 },
+        });
+
+        assert_eq!(
+            Ok(Bytes::from("requests-200-2001-02-03")),
+            Template::from("{{ name }}-{{ tags.code }}-%F").render(&event)
+        );
+        assert_eq!(
+            Err(vec![Atom::from("tags.host"), Atom::from("message")]),
+            Template::from("{{ tags.host }} {{ message }}").render(&event)
+        );
+    }
 }
@@ -3,7 +3,7 @@ use super::{
     TcpBind, TlsError, TlsSettings,
 };
 use futures01::{try_ready, Async, Future, Stream};
-use openssl::ssl::{HandshakeError, SslAcceptor, SslMethod};
+use openssl::ssl::{self, AlpnError, HandshakeError, SslAcceptor, SslMethod};
 use snafu::ResultExt;
 use std::{
     fmt::{self, Debug, Formatter},
@@ -62,6 +62,11 @@ impl TlsSettings {
                 let mut acceptor =
                     SslAcceptor::mozilla_intermediate(SslMethod::tls()).context(CreateAcceptor)?;
                 self.apply_context(&mut acceptor)?;
+                if let Some(protocols) = self.alpn_protocols.clone() {
+                    acceptor.set_alpn_select_callback(move |_, client| {
+                        ssl::select_next_proto(&protocols, client).ok_or(AlpnError::NOACK)
+                    });
+                }
                 Ok(acceptor.build())
             }
         }
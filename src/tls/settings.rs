@@ -41,6 +41,7 @@ pub struct TlsSettings {
     pub(super) verify_hostname: bool,
     authority: Option<X509>,
     pub(super) identity: Option<IdentityStore>, // openssl::pkcs12::ParsedPkcs12 doesn't impl Clone yet
+    pub(super) alpn_protocols: Option<Vec<u8>>,
 }
 
 #[derive(Clone)]
@@ -124,6 +125,7 @@ impl TlsSettings {
             verify_hostname: options.verify_hostname.unwrap_or(!for_server),
             authority,
             identity,
+            alpn_protocols: None,
         })
     }
 
@@ -288,6 +290,17 @@ impl MaybeTlsSettings {
         Ok(Self::Tls(tls))
     }
 
+    /// Make servers pick one of `protocols` when clients negotiate the
+    /// application protocol with ALPN, as HTTP/2 clients do. The
+    /// protocols are given in the wire format, each name prefixed with
+    /// its length.
+    pub(crate) fn alpn_protocols(mut self, protocols: &[u8]) -> Self {
+        if let Self::Tls(tls) = &mut self {
+            tls.alpn_protocols = Some(protocols.into());
+        }
+        self
+    }
+
     /// Generate an optional settings struct from the given optional
     /// configuration reference. If `config` is `None`, TLS is
     /// disabled. The `for_server` parameter indicates the options
@@ -0,0 +1,297 @@
+use crate::runtime::Runtime;
+use colored::*;
+use futures01::{Future, Stream};
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    fmt::Write,
+    time::{Duration, Instant},
+};
+use structopt::StructOpt;
+
+#[derive(StructOpt, Debug)]
+#[structopt(rename_all = "kebab-case")]
+pub struct Opts {
+    /// Address of the API of the running Vector instance.
+    #[structopt(long, default_value = "http://127.0.0.1:8686")]
+    url: String,
+
+    /// Milliseconds between refreshes.
+    #[structopt(short, long, default_value = "1000")]
+    interval: u64,
+
+    /// Print the table once per refresh instead of redrawing the screen.
+    #[structopt(long)]
+    no_clear: bool,
+}
+
+/// What the API reports about a component, see `api::ComponentView`.
+#[derive(Deserialize, Debug, Clone)]
+struct Component {
+    name: String,
+    kind: String,
+    #[serde(rename = "type")]
+    component_type: String,
+    received_events: u64,
+    received_bytes: u64,
+    sent_events: u64,
+    sent_bytes: u64,
+    errors: u64,
+    buffered_events: u64,
+    buffer_capacity: Option<u64>,
+}
+
+pub fn cmd(opts: &Opts) -> exitcode::ExitCode {
+    let uri: hyper::Uri = match format!("{}/components", opts.url.trim_end_matches('/')).parse() {
+        Ok(uri) => uri,
+        Err(error) => {
+            eprintln!("Invalid url {:?}: {}", opts.url, error);
+            return exitcode::USAGE;
+        }
+    };
+
+    let mut rt = match Runtime::single_threaded() {
+        Ok(rt) => rt,
+        Err(error) => {
+            eprintln!("Unable to create runtime: {}", error);
+            return exitcode::OSERR;
+        }
+    };
+
+    let client = hyper::Client::new();
+    let interval = Duration::from_millis(opts.interval.max(100));
+    let mut previous: Option<(Instant, HashMap<String, Component>)> = None;
+
+    loop {
+        let fetched = client
+            .get(uri.clone())
+            .and_then(|response| {
+                let status = response.status();
+                response
+                    .into_body()
+                    .concat2()
+                    .map(move |body| (status, body))
+            })
+            .map_err(|error| error.to_string())
+            .and_then(|(status, body)| {
+                if status.is_success() {
+                    serde_json::from_slice::<Vec<Component>>(&body)
+                        .map_err(|error| format!("Invalid response: {}", error))
+                } else {
+                    Err(format!("{}: {}", status, String::from_utf8_lossy(&body)))
+                }
+            });
+
+        let components = match rt.block_on(fetched) {
+            Ok(components) => components,
+            Err(error) => {
+                eprintln!("Unable to fetch components from {}: {}", opts.url, error);
+                return exitcode::UNAVAILABLE;
+            }
+        };
+        let now = Instant::now();
+
+        let table = render(
+            &components,
+            previous
+                .as_ref()
+                .map(|(then, before)| (now.duration_since(*then), before)),
+        );
+        if opts.no_clear {
+            println!("{}", table);
+        } else {
+            // Move the cursor home and clear the screen before redrawing.
+            print!("\x1B[2J\x1B[H{}", table);
+        }
+
+        previous = Some((
+            now,
+            components
+                .into_iter()
+                .map(|component| (component.name.clone(), component))
+                .collect(),
+        ));
+        std::thread::sleep(interval);
+    }
+}
+
+const HEADER: [&str; 9] = [
+    "NAME",
+    "KIND",
+    "TYPE",
+    "EVENTS IN/S",
+    "EVENTS OUT/S",
+    "BYTES IN/S",
+    "BYTES OUT/S",
+    "ERRORS",
+    "BUFFER",
+];
+
+/// Lays out one row per component. Rates need an earlier sample of the same
+/// component and are left blank until there is one.
+fn render(
+    components: &[Component],
+    previous: Option<(Duration, &HashMap<String, Component>)>,
+) -> String {
+    let mut rows = vec![HEADER
+        .iter()
+        .map(|title| title.to_string())
+        .collect::<Vec<_>>()];
+
+    let mut components = components.iter().collect::<Vec<_>>();
+    components.sort_by_key(|component| (kind_order(&component.kind), &component.name));
+
+    for component in components {
+        let before = previous.and_then(|(elapsed, before)| {
+            before
+                .get(&component.name)
+                .map(|before| (elapsed.as_secs_f64(), before))
+        });
+        let rate = |now: u64, then: fn(&Component) -> u64| match before {
+            Some((secs, before)) if secs > 0.0 => {
+                Some(now.saturating_sub(then(before)) as f64 / secs)
+            }
+            _ => None,
+        };
+
+        let buffer = match (component.kind.as_str(), component.buffer_capacity) {
+            ("sink", Some(capacity)) if capacity > 0 => format!(
+                "{}/{} ({:.0}%)",
+                component.buffered_events,
+                capacity,
+                component.buffered_events as f64 * 100.0 / capacity as f64
+            ),
+            ("sink", _) => component.buffered_events.to_string(),
+            _ => "-".to_string(),
+        };
+
+        rows.push(vec![
+            component.name.clone(),
+            component.kind.clone(),
+            component.component_type.clone(),
+            format_rate(
+                rate(component.received_events, |c| c.received_events),
+                format_count,
+            ),
+            format_rate(rate(component.sent_events, |c| c.sent_events), format_count),
+            format_rate(
+                rate(component.received_bytes, |c| c.received_bytes),
+                format_bytes,
+            ),
+            format_rate(rate(component.sent_bytes, |c| c.sent_bytes), format_bytes),
+            component.errors.to_string(),
+            buffer,
+        ]);
+    }
+
+    let widths = (0..HEADER.len())
+        .map(|column| rows.iter().map(|row| row[column].len()).max().unwrap_or(0))
+        .collect::<Vec<_>>();
+
+    let mut table = String::new();
+    for (i, row) in rows.iter().enumerate() {
+        let mut line = String::new();
+        for (column, cell) in row.iter().enumerate() {
+            // Text columns are left aligned, numbers right aligned.
+            if column < 3 {
+                let _ = write!(line, "{:<width$}  ", cell, width = widths[column]);
+            } else {
+                let _ = write!(line, "{:>width$}  ", cell, width = widths[column]);
+            }
+        }
+        let line = line.trim_end();
+        if i == 0 {
+            let _ = writeln!(table, "{}", line.bold());
+        } else {
+            let _ = writeln!(table, "{}", line);
+        }
+    }
+    table
+}
+
+fn kind_order(kind: &str) -> usize {
+    match kind {
+        "source" => 0,
+        "transform" => 1,
+        _ => 2,
+    }
+}
+
+fn format_rate(rate: Option<f64>, format: fn(f64) -> String) -> String {
+    rate.map(format).unwrap_or_else(|| "-".to_string())
+}
+
+fn format_count(count: f64) -> String {
+    scaled(count, 1000.0, &["", "k", "M", "G"])
+}
+
+fn format_bytes(bytes: f64) -> String {
+    scaled(bytes, 1024.0, &["B", "KiB", "MiB", "GiB"])
+}
+
+fn scaled(mut value: f64, step: f64, units: &[&str]) -> String {
+    let mut unit = 0;
+    while value >= step && unit + 1 < units.len() {
+        value /= step;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{:.0}{}", value, units[unit])
+    } else {
+        format!("{:.1}{}", value, units[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn component(name: &str, kind: &str, events: u64) -> Component {
+        Component {
+            name: name.into(),
+            kind: kind.into(),
+            component_type: "test".into(),
+            received_events: events,
+            received_bytes: events * 100,
+            sent_events: events / 2,
+            sent_bytes: events * 50,
+            errors: 0,
+            buffered_events: events - events / 2,
+            buffer_capacity: Some(500),
+        }
+    }
+
+    #[test]
+    fn scales_units() {
+        assert_eq!(format_count(999.0), "999");
+        assert_eq!(format_count(1500.0), "1.5k");
+        assert_eq!(format_bytes(512.0), "512B");
+        assert_eq!(format_bytes(3.0 * 1024.0 * 1024.0), "3.0MiB");
+    }
+
+    #[test]
+    fn renders_rates_from_previous_sample() {
+        colored::control::set_override(false);
+
+        let before = vec![component("out", "sink", 100), component("in", "source", 10)]
+            .into_iter()
+            .map(|component| (component.name.clone(), component))
+            .collect::<HashMap<_, _>>();
+        let now = vec![component("out", "sink", 300), component("in", "source", 20)];
+
+        let table = render(&now, None);
+        let lines = table.lines().collect::<Vec<_>>();
+        assert!(lines[0].starts_with("NAME"));
+        // Sources come first, rates are unknown without an earlier sample.
+        assert!(lines[1].starts_with("in "));
+        assert!(lines[2].contains(" - "));
+
+        let table = render(&now, Some((Duration::from_secs(2), &before)));
+        let out = table.lines().nth(2).unwrap();
+        let cells = out.split_whitespace().collect::<Vec<_>>();
+        assert_eq!(
+            cells,
+            vec!["out", "sink", "test", "100", "50", "9.8KiB", "4.9KiB", "0", "150/500", "(30%)"]
+        );
+    }
+}
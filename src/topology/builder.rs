@@ -1,24 +1,35 @@
 use super::{
-    config::{DataType, SinkContext, TransformContext},
-    fanout::{self, Fanout},
+    component::{ComponentKind, ComponentStats, HealthStatus},
+    config::{dead_letter_output, transform_output, DataType, SinkContext, TransformContext},
+    fanout::{self, Fanout, OutputRouter},
     task::Task,
     ConfigDiff,
 };
-use crate::{buffers, dns::Resolver, event::Event, runtime, shutdown::SourceShutdownCoordinator};
+use crate::{
+    buffers, dns::Resolver, event::Event, runtime, shutdown::SourceShutdownCoordinator,
+    transforms::ERRORS_OUTPUT,
+};
 use futures01::{
     future::{lazy, Either},
     sync::mpsc,
     Future, Stream,
 };
-use std::{collections::HashMap, time::Duration};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
 use tokio01::util::FutureExt;
 
 pub struct Pieces {
     pub inputs: HashMap<String, (buffers::BufferInputCloner, Vec<String>)>,
     pub outputs: HashMap<String, fanout::ControlChannel>,
+    /// Outputs of a component besides the one named after it.
+    pub extra_outputs: HashMap<String, Vec<String>>,
     pub tasks: HashMap<String, Task>,
     pub source_tasks: HashMap<String, Task>,
     pub healthchecks: HashMap<String, Task>,
+    pub stats: HashMap<String, Arc<ComponentStats>>,
     pub shutdown_coordinator: SourceShutdownCoordinator,
 }
 
@@ -26,7 +37,46 @@ pub fn check(config: &super::Config) -> Result<Vec<String>, Vec<String>> {
     let mut errors = vec![];
     let mut warnings = vec![];
 
+    // Every name that can be used as an input
+    let named_outputs = config
+        .transforms
+        .iter()
+        .flat_map(|(name, transform)| {
+            transform
+                .inner
+                .named_outputs()
+                .into_iter()
+                .map(move |output| transform_output(name, &output))
+        })
+        .collect::<HashSet<_>>();
+    let dead_letters = config
+        .sinks
+        .iter()
+        .filter(|(_, sink)| sink.dead_letter)
+        .map(|(name, _)| dead_letter_output(name))
+        .collect::<HashSet<_>>();
+
     // Warnings and errors
+    for (name, source) in config.sources.iter() {
+        if source.acknowledgements() && source.output_type() == DataType::Metric {
+            errors.push(format!(
+                "Source {:?} of type {:?} can't acknowledge events, as metric events aren't tracked to delivery.",
+                name,
+                source.source_type()
+            ));
+        }
+    }
+
+    for (name, sink) in config.sinks.iter() {
+        if sink.dead_letter && !sink.inner.supports_dead_letter() {
+            errors.push(format!(
+                "Sink {:?} of type {:?} doesn't support dead_letter.",
+                name,
+                sink.inner.sink_type()
+            ));
+        }
+    }
+
     let sink_inputs = config
         .sinks
         .iter()
@@ -45,7 +95,11 @@ pub fn check(config: &super::Config) -> Result<Vec<String>, Vec<String>> {
         }
 
         for input in inputs {
-            if !config.sources.contains_key(&input) && !config.transforms.contains_key(&input) {
+            if !config.sources.contains_key(&input)
+                && !config.transforms.contains_key(&input)
+                && !named_outputs.contains(&input)
+                && !dead_letters.contains(&input)
+            {
                 errors.push(format!(
                     "Input {:?} for {} {:?} doesn't exist.",
                     input, output_type, name
@@ -60,14 +114,23 @@ pub fn check(config: &super::Config) -> Result<Vec<String>, Vec<String>> {
         .keys()
         .map(|name| ("transform", name.clone()));
     for (input_type, name) in transform_names.chain(source_names) {
+        // Consuming any of the named outputs of a transform counts as well
+        let outputs = match config.transforms.get(&name) {
+            Some(transform) => transform
+                .inner
+                .named_outputs()
+                .iter()
+                .map(|output| transform_output(&name, output))
+                .chain(std::iter::once(name.clone()))
+                .collect(),
+            None => vec![name.clone()],
+        };
+        let consumes = |inputs: &Vec<String>| inputs.iter().any(|input| outputs.contains(input));
         if !config
             .transforms
             .iter()
-            .any(|(_, transform)| transform.inputs.contains(&name))
-            && !config
-                .sinks
-                .iter()
-                .any(|(_, sink)| sink.inputs.contains(&name))
+            .any(|(_, transform)| consumes(&transform.inputs))
+            && !config.sinks.iter().any(|(_, sink)| consumes(&sink.inputs))
         {
             warnings.push(format!(
                 "{} {:?} has no consumers",
@@ -96,9 +159,11 @@ pub fn build_pieces(
 ) -> Result<(Pieces, Vec<String>), Vec<String>> {
     let mut inputs = HashMap::new();
     let mut outputs = HashMap::new();
+    let mut extra_outputs = HashMap::new();
     let mut tasks = HashMap::new();
     let mut source_tasks = HashMap::new();
     let mut healthchecks = HashMap::new();
+    let mut stats = HashMap::new();
     let mut shutdown_coordinator = SourceShutdownCoordinator::new();
 
     let mut errors = vec![];
@@ -134,8 +199,15 @@ pub fn build_pieces(
             Ok(server) => server,
         };
 
+        let source_stats = Arc::new(ComponentStats::new(name, ComponentKind::Source, typetag));
         let (output, control) = Fanout::new();
-        let pump = rx.forward(output).map(|_| ());
+        let pump = rx
+            .map(record_with(&source_stats, |stats, event| {
+                stats.record_received(event);
+                stats.record_sent(event);
+            }))
+            .forward(output)
+            .map(|_| ());
         let pump = Task::new(&name, &typetag, pump);
 
         // The force_shutdown_tripwire is a Future that when it resolves means that this source
@@ -152,6 +224,7 @@ pub fn build_pieces(
         outputs.insert(name.clone(), control);
         tasks.insert(name.clone(), pump);
         source_tasks.insert(name.clone(), server);
+        stats.insert(name.clone(), source_stats);
     }
 
     // Build transforms
@@ -170,6 +243,7 @@ pub fn build_pieces(
         };
 
         let input_type = transform.inner.input_type();
+        let named_outputs = transform.inner.named_outputs();
         let transform = match transform.inner.build(cx) {
             Err(error) => {
                 errors.push(format!("Transform \"{}\": {}", name, error));
@@ -183,15 +257,56 @@ pub fn build_pieces(
 
         let (output, control) = Fanout::new();
 
-        let transform = transform
-            .transform_stream(filter_event_type(input_rx, input_type))
-            .forward(output)
-            .map(|_| ());
+        let transform_stats = Arc::new(ComponentStats::new(
+            name,
+            ComponentKind::Transform,
+            *typetag,
+        ));
+        let input_rx = Box::new(filter_event_type(input_rx, input_type).map(record_with(
+            &transform_stats,
+            ComponentStats::record_received,
+        )));
+        let transform = if named_outputs.is_empty() {
+            Either::A(
+                transform
+                    .transform_stream(input_rx)
+                    .map(record_with(&transform_stats, ComponentStats::record_sent))
+                    .forward(output)
+                    .map(|_| ()),
+            )
+        } else {
+            let mut router = OutputRouter::new(output);
+            let mut names = Vec::new();
+            for output in named_outputs {
+                let (fanout, control) = Fanout::new();
+                let full_name = transform_output(&name, &output);
+                outputs.insert(full_name.clone(), control);
+                names.push(full_name);
+                router.add(output, fanout);
+            }
+            extra_outputs.insert(name.clone(), names);
+            let routed = Arc::clone(&transform_stats);
+            Either::B(
+                transform
+                    .transform_stream_named(input_rx)
+                    .map(move |(output, event)| {
+                        if output.as_deref() == Some(ERRORS_OUTPUT) {
+                            routed.record_errors(1);
+                        } else {
+                            routed.record_sent(&event);
+                        }
+                        (output, event)
+                    })
+                    .forward(router)
+                    .map(|_| ()),
+            )
+        };
         let task = Task::new(&name, &typetag, transform);
 
         inputs.insert(name.clone(), (input_tx, trans_inputs.clone()));
         outputs.insert(name.clone(), control);
         tasks.insert(name.clone(), task);
+        stats.insert(name.clone(), transform_stats);
     }
 
     // Build sinks
@@ -215,7 +330,28 @@ pub fn build_pieces(
             Ok(buffer) => buffer,
         };
 
+        let sink_stats = Arc::new(
+            ComponentStats::new(name, ComponentKind::Sink, typetag)
+                .with_buffer_capacity(sink.buffer.max_events().map(|max| max as u64)),
+        );
+        let tx = buffers::BufferInputCloner::Observed(Box::new(tx), sink_stats.clone());
+
+        let (dead_letter, dead_letter_pump) = if sink.dead_letter {
+            let (dead_letter, dead_letter_rx) = buffers::DeadLetter::new(&name);
+            let (output, control) = Fanout::new();
+            outputs.insert(dead_letter_output(&name), control);
+            extra_outputs.insert(name.clone(), vec![dead_letter_output(&name)]);
+            (
+                Some(dead_letter),
+                Either::A(dead_letter_rx.forward(output).map(|_| ())),
+            )
+        } else {
+            (None, Either::B(futures01::future::ok(())))
+        };
+
+        let (acker, pending_finalizers) = acker.finalizing(dead_letter, Some(sink_stats.clone()));
         let cx = SinkContext {
+            name: name.clone(),
             resolver: resolver.clone(),
             acker,
             exec: exec.clone(),
@@ -229,17 +365,36 @@ pub fn build_pieces(
             Ok((sink, healthcheck)) => (sink, healthcheck),
         };
 
-        let sink = filter_event_type(rx, input_type).forward(sink).map(|_| ());
+        let record = record_with(&sink_stats, ComponentStats::record_sent);
+        let sink = filter_event_type(rx, input_type)
+            .map(move |mut event| {
+                pending_finalizers.push(&mut event);
+                record(event)
+            })
+            .forward(sink)
+            // Releases the sink's acker, and so the dead letter sender, for
+            // the pump to finish.
+            .map(drop)
+            .join(dead_letter_pump)
+            .map(|_| ());
         let task = Task::new(&name, &typetag, sink);
 
         let healthcheck_task = if enable_healthcheck {
+            let (passed, failed) = (Arc::clone(&sink_stats), Arc::clone(&sink_stats));
             let healthcheck_task = healthcheck
                 // TODO: Add healthcheck timeouts per sink
                 .timeout(Duration::from_secs(10))
-                .map(move |_| info!("Healthcheck: Passed."))
-                .map_err(move |err| error!("Healthcheck: Failed Reason: {}", err));
+                .map(move |_| {
+                    passed.set_health(HealthStatus::Passed);
+                    info!("Healthcheck: Passed.")
+                })
+                .map_err(move |err| {
+                    failed.set_health(HealthStatus::Failed);
+                    error!("Healthcheck: Failed Reason: {}", err)
+                });
             Either::A(healthcheck_task)
         } else {
+            sink_stats.set_health(HealthStatus::Disabled);
             Either::B(lazy(|| {
                 info!("Healthcheck: Disabled.");
                 Ok(())
@@ -250,6 +405,7 @@ pub fn build_pieces(
         inputs.insert(name.clone(), (tx, sink_inputs.clone()));
         healthchecks.insert(name.clone(), healthcheck_task);
         tasks.insert(name.clone(), task);
+        stats.insert(name.clone(), sink_stats);
     }
 
     // Warnings and errors
@@ -266,9 +422,11 @@ pub fn build_pieces(
         let pieces = Pieces {
             inputs,
             outputs,
+            extra_outputs,
             tasks,
             source_tasks,
             healthchecks,
+            stats,
             shutdown_coordinator,
         };
 
@@ -286,6 +444,17 @@ fn capitalize(s: &str) -> String {
     s
 }
 
+fn record_with(
+    stats: &Arc<ComponentStats>,
+    record: fn(&ComponentStats, &Event),
+) -> impl Fn(Event) -> Event + Send {
+    let stats = Arc::clone(stats);
+    move |event| {
+        record(&stats, &event);
+        event
+    }
+}
+
 fn filter_event_type<S>(
     stream: S,
     data_type: DataType,
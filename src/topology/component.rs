@@ -0,0 +1,247 @@
+use crate::{
+    buffers::BufferObserver,
+    emit,
+    internal_events::{
+        self, ComponentEventReceived, ComponentEventSent, ComponentEventsDiscarded,
+        ComponentProcessingErrors,
+    },
+    Event,
+};
+use metrics::Label;
+use serde::Serialize;
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, Mutex,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ComponentKind {
+    Source,
+    Transform,
+    Sink,
+}
+
+impl ComponentKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ComponentKind::Source => "source",
+            ComponentKind::Transform => "transform",
+            ComponentKind::Sink => "sink",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthStatus {
+    /// Not a sink, or its healthcheck hasn't finished yet.
+    Unknown,
+    Disabled,
+    Passed,
+    Failed,
+}
+
+/// Counters of a running component, shared by its tasks. Every update is
+/// also emitted as an internal metric tagged with the component.
+///
+/// Sources count what they emit as both received and sent. Transforms count
+/// their input and output, and the events they route to their `errors`
+/// output as errors. Sinks count the events entering their buffer as
+/// received, the ones read back out of it as sent, rejected ones as errors
+/// and the ones shed by a full buffer as discarded, so the difference
+/// between received and sent is the buffer fill.
+#[derive(Debug)]
+pub struct ComponentStats {
+    name: String,
+    labels: Vec<Label>,
+    received_events: AtomicU64,
+    received_bytes: AtomicU64,
+    sent_events: AtomicU64,
+    sent_bytes: AtomicU64,
+    errors: AtomicU64,
+    discarded_events: AtomicU64,
+    buffer_capacity: Option<u64>,
+    health: Mutex<HealthStatus>,
+}
+
+impl ComponentStats {
+    pub fn new(name: &str, kind: ComponentKind, component_type: &'static str) -> Self {
+        Self {
+            name: name.to_owned(),
+            labels: internal_events::component_labels(name, kind.as_str(), component_type),
+            received_events: AtomicU64::new(0),
+            received_bytes: AtomicU64::new(0),
+            sent_events: AtomicU64::new(0),
+            sent_bytes: AtomicU64::new(0),
+            errors: AtomicU64::new(0),
+            discarded_events: AtomicU64::new(0),
+            buffer_capacity: None,
+            health: Mutex::new(HealthStatus::Unknown),
+        }
+    }
+
+    pub fn with_buffer_capacity(mut self, buffer_capacity: Option<u64>) -> Self {
+        self.buffer_capacity = buffer_capacity;
+        self
+    }
+
+    fn id(&self) -> internal_events::ComponentId<'_> {
+        internal_events::ComponentId {
+            name: &self.name,
+            labels: &self.labels,
+        }
+    }
+
+    pub fn record_received(&self, event: &Event) {
+        self.record_received_size(event.size_of());
+    }
+
+    pub fn record_received_size(&self, byte_size: usize) {
+        self.received_events.fetch_add(1, Ordering::Relaxed);
+        self.received_bytes
+            .fetch_add(byte_size as u64, Ordering::Relaxed);
+        emit!(ComponentEventReceived {
+            component: self.id(),
+            byte_size,
+        });
+    }
+
+    pub fn record_sent(&self, event: &Event) {
+        let byte_size = event.size_of();
+        self.sent_events.fetch_add(1, Ordering::Relaxed);
+        self.sent_bytes
+            .fetch_add(byte_size as u64, Ordering::Relaxed);
+        emit!(ComponentEventSent {
+            component: self.id(),
+            byte_size,
+        });
+    }
+
+    pub fn record_errors(&self, count: usize) {
+        self.errors.fetch_add(count as u64, Ordering::Relaxed);
+        emit!(ComponentProcessingErrors {
+            component: self.id(),
+            count,
+        });
+    }
+
+    pub fn record_discarded(&self, count: usize) {
+        self.discarded_events
+            .fetch_add(count as u64, Ordering::Relaxed);
+        emit!(ComponentEventsDiscarded {
+            component: self.id(),
+            count,
+        });
+    }
+
+    pub fn received_events(&self) -> u64 {
+        self.received_events.load(Ordering::Relaxed)
+    }
+
+    pub fn received_bytes(&self) -> u64 {
+        self.received_bytes.load(Ordering::Relaxed)
+    }
+
+    pub fn sent_events(&self) -> u64 {
+        self.sent_events.load(Ordering::Relaxed)
+    }
+
+    pub fn sent_bytes(&self) -> u64 {
+        self.sent_bytes.load(Ordering::Relaxed)
+    }
+
+    pub fn errors(&self) -> u64 {
+        self.errors.load(Ordering::Relaxed)
+    }
+
+    pub fn discarded_events(&self) -> u64 {
+        self.discarded_events.load(Ordering::Relaxed)
+    }
+
+    /// Events waiting in the buffer of a sink.
+    pub fn buffered_events(&self) -> u64 {
+        self.received_events().saturating_sub(self.sent_events())
+    }
+
+    /// Maximum number of events the buffer holds, if it's bounded by events.
+    pub fn buffer_capacity(&self) -> Option<u64> {
+        self.buffer_capacity
+    }
+
+    pub fn health(&self) -> HealthStatus {
+        *self.health.lock().unwrap()
+    }
+
+    pub fn set_health(&self, health: HealthStatus) {
+        *self.health.lock().unwrap() = health;
+    }
+}
+
+impl BufferObserver for ComponentStats {
+    fn accepted(&self, byte_size: usize) {
+        self.record_received_size(byte_size);
+    }
+
+    fn shed(&self) {
+        self.record_discarded(1);
+    }
+
+    fn rejected(&self, count: usize) {
+        self.record_errors(count);
+    }
+}
+
+/// A component of the running topology, as reported to the outside world.
+#[derive(Clone, Debug)]
+pub struct Component {
+    pub name: String,
+    pub kind: ComponentKind,
+    pub component_type: &'static str,
+    pub inputs: Vec<String>,
+    pub stats: Arc<ComponentStats>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ComponentKind, ComponentStats};
+    use crate::Event;
+    use std::collections::BTreeMap;
+
+    fn counter_labels(name: &str, component: &str) -> Option<BTreeMap<String, String>> {
+        let controller = crate::metrics::CONTROLLER.get().unwrap();
+        controller
+            .snapshot()
+            .into_measurements()
+            .into_iter()
+            .map(|(key, _)| key)
+            .find(|key| {
+                key.name() == name
+                    && key
+                        .labels()
+                        .any(|label| label.key() == "component_name" && label.value() == component)
+            })
+            .map(|key| {
+                key.labels()
+                    .map(|label| (label.key().to_owned(), label.value().to_owned()))
+                    .collect()
+            })
+    }
+
+    #[test]
+    fn counters_are_tagged_with_component() {
+        crate::metrics::init_test();
+
+        let stats = ComponentStats::new("tagged", ComponentKind::Transform, "sampler");
+        stats.record_received(&Event::from("message"));
+        stats.record_errors(1);
+
+        for name in &["events_received", "bytes_received", "processing_errors"] {
+            let labels = counter_labels(name, "tagged").expect("counter not recorded");
+            assert_eq!(labels["component_kind"], "transform");
+            assert_eq!(labels["component_type"], "sampler");
+        }
+        assert_eq!(stats.received_events(), 1);
+        assert_eq!(stats.errors(), 1);
+    }
+}
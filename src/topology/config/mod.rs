@@ -46,6 +46,11 @@ pub struct GlobalOptions {
         default
     )]
     pub log_schema: event::LogSchema,
+    #[serde(
+        skip_serializing_if = "crate::serde::skip_serializing_if_default",
+        default
+    )]
+    pub api: crate::api::Options,
 }
 
 pub fn default_data_dir() -> Option<PathBuf> {
@@ -138,6 +143,13 @@ pub trait SourceConfig: core::fmt::Debug {
     fn output_type(&self) -> DataType;
 
     fn source_type(&self) -> &'static str;
+
+    /// Whether the source waits for its events to be delivered before
+    /// acknowledging them upstream. Only log events carry finalizers, so this
+    /// is refused for sources that only emit metrics.
+    fn acknowledgements(&self) -> bool {
+        false
+    }
 }
 
 pub type SourceDescription = ComponentDescription<Box<dyn SourceConfig>>;
@@ -150,11 +162,25 @@ pub struct SinkOuter {
     pub buffer: crate::buffers::BufferConfig,
     #[serde(default = "healthcheck_default")]
     pub healthcheck: bool,
+    /// Send the events rejected by the downstream service to the
+    /// `<name>.dead_letter` output instead of dropping them.
+    #[serde(default)]
+    pub dead_letter: bool,
     pub inputs: Vec<String>,
     #[serde(flatten)]
     pub inner: Box<dyn SinkConfig>,
 }
 
+/// Full name of one of the named outputs of a transform.
+pub fn transform_output(transform: &str, output: &str) -> String {
+    format!("{}.{}", transform, output)
+}
+
+/// Name of the output the events rejected by a sink are sent to.
+pub fn dead_letter_output(sink: &str) -> String {
+    format!("{}.dead_letter", sink)
+}
+
 #[typetag::serde(tag = "type")]
 pub trait SinkConfig: core::fmt::Debug {
     fn build(&self, cx: SinkContext) -> crate::Result<(sinks::RouterSink, sinks::Healthcheck)>;
@@ -162,10 +188,17 @@ pub trait SinkConfig: core::fmt::Debug {
     fn input_type(&self) -> DataType;
 
     fn sink_type(&self) -> &'static str;
+
+    /// Whether the sink tells apart the events its downstream service
+    /// rejected, which `dead_letter` relies on.
+    fn supports_dead_letter(&self) -> bool {
+        false
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct SinkContext {
+    pub(super) name: String,
     pub(super) acker: Acker,
     pub(super) resolver: Resolver,
     pub(super) exec: TaskExecutor,
@@ -175,12 +208,18 @@ impl SinkContext {
     #[cfg(test)]
     pub fn new_test(exec: TaskExecutor) -> Self {
         Self {
+            name: "test".into(),
             acker: Acker::Null,
             resolver: Resolver::new(Vec::new(), exec.clone()).unwrap(),
             exec,
         }
     }
 
+    /// Name of the sink in the config.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
     pub fn acker(&self) -> Acker {
         self.acker.clone()
     }
@@ -219,6 +258,13 @@ pub trait TransformConfig: core::fmt::Debug {
 
     fn transform_type(&self) -> &'static str;
 
+    /// Names of the outputs this transform can send events to besides its
+    /// default one. Each is addressed as `<transform>.<output>` by the
+    /// `inputs` of downstream components.
+    fn named_outputs(&self) -> Vec<String> {
+        Vec::new()
+    }
+
     /// Allows a transform configuration to expand itself into multiple "child"
     /// transformations to replace it. This allows a transform to act as a macro
     /// for various patterns.
@@ -314,6 +360,7 @@ impl Config {
                 data_dir: None,
                 dns_servers: Vec::new(),
                 log_schema: event::LogSchema::default(),
+                api: Default::default(),
             },
             sources: IndexMap::new(),
             sinks: IndexMap::new(),
@@ -331,6 +378,7 @@ impl Config {
         let sink = SinkOuter {
             buffer: Default::default(),
             healthcheck: true,
+            dead_letter: false,
             inner: Box::new(sink),
             inputs,
         };
@@ -424,6 +472,14 @@ impl Config {
             // we consider this an error.
             errors.push("conflicting values for 'data_dir' found".to_owned());
         }
+        if with.global.api != Default::default() {
+            if self.global.api != Default::default() && self.global.api != with.global.api {
+                errors.push("conflicting values for 'api' found".to_owned());
+            } else {
+                self.global.api = with.global.api.clone();
+            }
+        }
+
         self.global.dns_servers.append(&mut with.global.dns_servers);
         self.global.dns_servers.sort();
         self.global.dns_servers.dedup();
@@ -1,4 +1,7 @@
-use crate::topology::{config::DataType, Config};
+use crate::topology::{
+    config::{dead_letter_output, transform_output, DataType},
+    Config,
+};
 use std::collections::HashMap;
 
 pub fn typecheck(config: &Config) -> Result<(), Vec<String>> {
@@ -138,10 +141,22 @@ impl From<&Config> for Graph {
                 config.inner.output_type(),
                 config.inputs.clone(),
             );
+
+            // Named outputs hang off their transform like a pass-through child.
+            let ty = config.inner.output_type();
+            for output in config.inner.named_outputs() {
+                graph.add_transform(&transform_output(name, &output), ty, ty, vec![name]);
+            }
         }
 
         for (name, config) in config.sinks.iter() {
-            graph.add_sink(name, config.inner.input_type(), config.inputs.clone());
+            let ty = config.inner.input_type();
+            graph.add_sink(name, ty, config.inputs.clone());
+
+            // Rejected events come out of the sink as they went in.
+            if config.dead_letter {
+                graph.add_transform(&dead_letter_output(name), ty, ty, config.inputs.clone());
+            }
         }
 
         graph
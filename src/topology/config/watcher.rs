@@ -81,7 +81,7 @@ pub fn config_watcher(_config_paths: Vec<PathBuf>, _delay: Duration) -> Result<(
 }
 
 #[cfg(unix)]
-fn raise_sighup() {
+pub fn raise_sighup() {
     use nix::sys::signal;
     let _ = signal::raise(signal::Signal::SIGHUP).map_err(|error| {
         error!(message = "Unable to reload configuration file. Restart Vector to reload it.", cause = ?error)
@@ -2,6 +2,7 @@ use crate::sinks::RouterSink;
 use crate::Event;
 use futures01::sync::mpsc;
 use futures01::{future, Async, AsyncSink, Poll, Sink, StartSend, Stream};
+use std::collections::HashMap;
 
 pub struct Fanout {
     sinks: Vec<(String, RouterSink)>,
@@ -140,9 +141,71 @@ impl Sink for Fanout {
     }
 }
 
+/// Sends the events of a transform with named outputs to the fanout of the
+/// output each one was emitted to.
+pub struct OutputRouter {
+    default: Fanout,
+    named: HashMap<String, Fanout>,
+}
+
+impl OutputRouter {
+    pub fn new(default: Fanout) -> Self {
+        Self {
+            default,
+            named: HashMap::new(),
+        }
+    }
+
+    pub fn add(&mut self, name: String, fanout: Fanout) {
+        self.named.insert(name, fanout);
+    }
+}
+
+impl Sink for OutputRouter {
+    type SinkItem = (Option<String>, Event);
+    type SinkError = ();
+
+    fn start_send(&mut self, item: Self::SinkItem) -> StartSend<Self::SinkItem, Self::SinkError> {
+        let (output, event) = item;
+        let fanout = match &output {
+            None => &mut self.default,
+            Some(name) => match self.named.get_mut(name) {
+                Some(fanout) => fanout,
+                None => {
+                    error!(
+                        message = "Transform sent an event to an undeclared output.",
+                        output = %name,
+                        rate_limit_secs = 30
+                    );
+                    return Ok(AsyncSink::Ready);
+                }
+            },
+        };
+
+        match fanout.start_send(event)? {
+            AsyncSink::Ready => Ok(AsyncSink::Ready),
+            AsyncSink::NotReady(event) => Ok(AsyncSink::NotReady((output, event))),
+        }
+    }
+
+    fn poll_complete(&mut self) -> Poll<(), Self::SinkError> {
+        let mut all_complete = self.default.poll_complete()?.is_ready();
+
+        for fanout in self.named.values_mut() {
+            all_complete &= fanout.poll_complete()?.is_ready();
+        }
+
+        if all_complete {
+            Ok(Async::Ready(()))
+        } else {
+            Ok(Async::NotReady)
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{ControlMessage, Fanout};
+    use super::{ControlMessage, Fanout, OutputRouter};
     use crate::runtime;
     use crate::test_util::{self, CollectCurrent};
     use crate::Event;
@@ -458,4 +521,39 @@ mod tests {
             vec![rec3.clone()]
         );
     }
+
+    #[test]
+    fn output_router_routes_by_name() {
+        let (tx_default, rx_default) = mpsc::unbounded();
+        let tx_default = Box::new(tx_default.sink_map_err(|_| unreachable!()));
+        let (tx_errors, rx_errors) = mpsc::unbounded();
+        let tx_errors = Box::new(tx_errors.sink_map_err(|_| unreachable!()));
+
+        let mut default = Fanout::new().0;
+        default.add("default".to_string(), tx_default);
+        let mut errors = Fanout::new().0;
+        errors.add("errors".to_string(), tx_errors);
+
+        let mut router = OutputRouter::new(default);
+        router.add("errors".to_string(), errors);
+
+        let rec1 = Event::from("line 1".to_string());
+        let rec2 = Event::from("line 2".to_string());
+        let rec3 = Event::from("line 3".to_string());
+
+        let _router = router
+            .send_all(stream::iter_ok::<_, ()>(vec![
+                (None, rec1.clone()),
+                (Some("errors".to_string()), rec2.clone()),
+                (Some("unknown".to_string()), rec3.clone()),
+            ]))
+            .wait()
+            .unwrap();
+
+        assert_eq!(
+            CollectCurrent::new(rx_default).wait().unwrap().1,
+            vec![rec1]
+        );
+        assert_eq!(CollectCurrent::new(rx_errors).wait().unwrap().1, vec![rec2]);
+    }
 }
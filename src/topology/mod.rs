@@ -7,15 +7,20 @@
 //! each type of component.
 
 pub mod builder;
+pub mod component;
 pub mod config;
-mod fanout;
+pub(crate) mod fanout;
 mod task;
 pub mod unit_test;
 
 pub use self::config::Config;
 pub use self::config::SinkContext;
 
-use crate::topology::builder::Pieces;
+use crate::topology::{
+    builder::Pieces,
+    component::{Component, ComponentKind, ComponentStats},
+    config::{dead_letter_output, transform_output},
+};
 
 use crate::buffers;
 use crate::runtime;
@@ -29,6 +34,7 @@ use futures01::{
 use indexmap::IndexMap;
 use std::collections::{HashMap, HashSet};
 use std::panic::AssertUnwindSafe;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio01::timer;
 use tracing_futures::Instrument;
@@ -39,6 +45,7 @@ pub struct RunningTopology {
     outputs: HashMap<String, fanout::ControlChannel>,
     source_tasks: HashMap<String, oneshot::SpawnHandle<(), ()>>,
     tasks: HashMap<String, oneshot::SpawnHandle<(), ()>>,
+    stats: HashMap<String, Arc<ComponentStats>>,
     shutdown_coordinator: SourceShutdownCoordinator,
     config: Config,
     abort_tx: mpsc::UnboundedSender<()>,
@@ -70,6 +77,7 @@ pub fn start_validated(
         shutdown_coordinator: SourceShutdownCoordinator::new(),
         source_tasks: HashMap::new(),
         tasks: HashMap::new(),
+        stats: HashMap::new(),
         abort_tx,
     };
 
@@ -105,6 +113,56 @@ impl RunningTopology {
         self.shutdown_coordinator.shutdown_tripwire()
     }
 
+    /// Control channels of the fanouts of every output, to attach extra
+    /// consumers to them.
+    pub(crate) fn outputs(&self) -> &HashMap<String, fanout::ControlChannel> {
+        &self.outputs
+    }
+
+    /// Lists the running components along with their live counters.
+    pub fn components(&self) -> Vec<Component> {
+        let sources = self.config.sources.iter().map(|(name, source)| {
+            (
+                name,
+                ComponentKind::Source,
+                source.source_type(),
+                Vec::new(),
+            )
+        });
+        let transforms = self.config.transforms.iter().map(|(name, transform)| {
+            (
+                name,
+                ComponentKind::Transform,
+                transform.inner.transform_type(),
+                transform.inputs.clone(),
+            )
+        });
+        let sinks = self.config.sinks.iter().map(|(name, sink)| {
+            (
+                name,
+                ComponentKind::Sink,
+                sink.inner.sink_type(),
+                sink.inputs.clone(),
+            )
+        });
+
+        sources
+            .chain(transforms)
+            .chain(sinks)
+            .map(|(name, kind, component_type, inputs)| Component {
+                name: name.clone(),
+                kind,
+                component_type,
+                inputs,
+                stats: self
+                    .stats
+                    .get(name)
+                    .cloned()
+                    .unwrap_or_else(|| Arc::new(ComponentStats::new(name, kind, component_type))),
+            })
+            .collect()
+    }
+
     /// Sends the shutdown signal to all sources and returns a future that resolves
     /// once all components (sources, transforms, and sinks) have finished shutting down.
     /// Transforms and sinks should shut down automatically once their input tasks finish.
@@ -226,6 +284,10 @@ impl RunningTopology {
             return Ok(false);
         }
 
+        if self.config.global.api != new_config.global.api {
+            warn!("api options cannot be changed while reloading config file; restart Vector to apply them.");
+        }
+
         if let Err(errors) = builder::check(&new_config) {
             for error in errors {
                 error!("Configuration error: {}", error);
@@ -320,6 +382,7 @@ impl RunningTopology {
             info!("Removing source {:?}", name);
 
             self.tasks.remove(name).unwrap().forget();
+            self.stats.remove(name);
 
             self.remove_outputs(name);
             source_shutdown_complete_futures
@@ -357,9 +420,15 @@ impl RunningTopology {
             info!("Removing transform {:?}", name);
 
             self.tasks.remove(name).unwrap().forget();
+            self.stats.remove(name);
 
             self.remove_inputs(&name);
             self.remove_outputs(&name);
+            self.remove_named_outputs(&name);
+        }
+
+        for name in &diff.transforms.to_change {
+            self.remove_named_outputs(&name);
         }
 
         // Sinks
@@ -367,8 +436,15 @@ impl RunningTopology {
             info!("Removing sink {:?}", name);
 
             self.tasks.remove(name).unwrap().forget();
+            self.stats.remove(name);
 
             self.remove_inputs(&name);
+            self.remove_outputs(&dead_letter_output(name));
+        }
+
+        // A changed sink brings its own dead letter output, if any.
+        for name in &diff.sinks.to_change {
+            self.remove_outputs(&dead_letter_output(name));
         }
     }
 
@@ -389,15 +465,23 @@ impl RunningTopology {
             self.spawn_source(&name, &mut new_pieces, rt);
         }
 
+        // Dead letter outputs can feed transforms and other sinks, so they need to be in place
+        // before any input is wired
+        for name in diff.sinks.to_change.iter().chain(&diff.sinks.to_add) {
+            self.setup_extra_outputs(&name, &mut new_pieces);
+        }
+
         // Transforms
 
         // Make sure all transform outputs are set up before another transform might try use
         // it as an input
         for name in &diff.transforms.to_change {
             self.setup_outputs(&name, &mut new_pieces);
+            self.setup_extra_outputs(&name, &mut new_pieces);
         }
         for name in &diff.transforms.to_add {
             self.setup_outputs(&name, &mut new_pieces);
+            self.setup_extra_outputs(&name, &mut new_pieces);
         }
 
         for name in &diff.transforms.to_change {
@@ -415,6 +499,7 @@ impl RunningTopology {
         }
 
         // Sinks
+
         for name in &diff.sinks.to_change {
             info!("Rebuilding sink {:?}", name);
 
@@ -436,6 +521,7 @@ impl RunningTopology {
         new_pieces: &mut builder::Pieces,
         rt: &mut runtime::Runtime,
     ) {
+        self.take_stats(name, new_pieces);
         let task = new_pieces.tasks.remove(name).unwrap();
         let span = info_span!("sink", name = %task.name(), r#type = %task.typetag());
         let task = handle_errors(task.instrument(span), self.abort_tx.clone());
@@ -451,6 +537,7 @@ impl RunningTopology {
         new_pieces: &mut builder::Pieces,
         rt: &mut runtime::Runtime,
     ) {
+        self.take_stats(name, new_pieces);
         let task = new_pieces.tasks.remove(name).unwrap();
         let span = info_span!("transform", name = %task.name(), r#type = %task.typetag());
         let task = handle_errors(task.instrument(span), self.abort_tx.clone());
@@ -466,6 +553,7 @@ impl RunningTopology {
         new_pieces: &mut builder::Pieces,
         rt: &mut runtime::Runtime,
     ) {
+        self.take_stats(name, new_pieces);
         let task = new_pieces.tasks.remove(name).unwrap();
         let span = info_span!("source", name = %task.name(), r#type = %task.typetag());
 
@@ -486,10 +574,23 @@ impl RunningTopology {
         );
     }
 
+    fn take_stats(&mut self, name: &str, new_pieces: &mut builder::Pieces) {
+        if let Some(stats) = new_pieces.stats.remove(name) {
+            self.stats.insert(name.to_string(), stats);
+        }
+    }
+
     fn remove_outputs(&mut self, name: &str) {
         self.outputs.remove(name);
     }
 
+    fn remove_named_outputs(&mut self, name: &str) {
+        let named_outputs = self.config.transforms[name].inner.named_outputs();
+        for output in named_outputs {
+            self.remove_outputs(&transform_output(name, &output));
+        }
+    }
+
     fn remove_inputs(&mut self, name: &str) {
         self.inputs.remove(name);
 
@@ -537,6 +638,14 @@ impl RunningTopology {
         self.outputs.insert(name.to_string(), output);
     }
 
+    fn setup_extra_outputs(&mut self, name: &str, new_pieces: &mut builder::Pieces) {
+        if let Some(outputs) = new_pieces.extra_outputs.remove(name) {
+            for output in outputs {
+                self.setup_outputs(&output, new_pieces);
+            }
+        }
+    }
+
     fn setup_inputs(&mut self, name: &str, new_pieces: &mut builder::Pieces) {
         let (tx, inputs) = new_pieces.inputs.remove(name).unwrap();
 
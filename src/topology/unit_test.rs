@@ -3,7 +3,8 @@ use crate::{
     event::{Event, Value},
     runtime::Runtime,
     topology::config::{
-        TestCondition, TestDefinition, TestInput, TestInputValue, TransformContext,
+        transform_output, TestCondition, TestDefinition, TestInput, TestInputValue,
+        TransformContext,
     },
     transforms::Transform,
 };
@@ -20,6 +21,7 @@ pub struct UnitTestCheck {
 pub struct UnitTestTransform {
     transform: Box<dyn Transform>,
     next: Vec<String>,
+    named_next: IndexMap<String, Vec<String>>,
 }
 
 pub struct UnitTest {
@@ -65,19 +67,51 @@ fn walk(
     aggregated_results: &mut HashMap<String, (Vec<Event>, Vec<Event>)>,
 ) {
     let mut results = Vec::new();
+    let mut named_results = Vec::new();
     let mut targets = Vec::new();
+    let mut named_targets = IndexMap::new();
 
     if let Some(target) = transforms.get_mut(node) {
         for input in inputs.clone() {
-            target.transform.transform_into(&mut results, input);
+            target
+                .transform
+                .transform_into_named(&mut results, &mut named_results, input);
         }
         targets = target.next.clone();
+        named_targets = target.named_next.clone();
     }
 
     for child in targets {
         walk(&child, results.clone(), transforms, aggregated_results);
     }
 
+    // Named outputs are recorded as nodes of their own so that they can be
+    // extracted from.
+    for (output, children) in named_targets {
+        let output_results = named_results
+            .iter()
+            .filter(|(name, _)| name == &output)
+            .map(|(_, event)| event.clone())
+            .collect::<Vec<_>>();
+        for child in children {
+            walk(
+                &child,
+                output_results.clone(),
+                transforms,
+                aggregated_results,
+            );
+        }
+
+        let output_node = transform_output(node, &output);
+        let mut output_inputs = inputs.clone();
+        let mut output_results = output_results;
+        if let Some((mut e_inputs, mut e_results)) = aggregated_results.remove(&output_node) {
+            output_inputs.append(&mut e_inputs);
+            output_results.append(&mut e_results);
+        }
+        aggregated_results.insert(output_node, (output_inputs, output_results));
+    }
+
     if let Some((mut e_inputs, mut e_results)) = aggregated_results.remove(node) {
         inputs.append(&mut e_inputs);
         results.append(&mut e_results);
@@ -327,12 +361,17 @@ fn build_unit_test(
     };
 
     // Maps transform names with their output targets (transforms that use it as
-    // an input).
-    let mut transform_outputs: IndexMap<String, IndexMap<String, ()>> = config
-        .transforms
-        .iter()
-        .map(|(k, _)| (k.clone(), IndexMap::new()))
-        .collect();
+    // an input). Named outputs are children of their transform.
+    let mut transform_outputs: IndexMap<String, IndexMap<String, ()>> = IndexMap::new();
+    for (k, t) in &config.transforms {
+        let mut children = IndexMap::new();
+        for output in t.inner.named_outputs() {
+            let output = transform_output(k, &output);
+            transform_outputs.insert(output.clone(), IndexMap::new());
+            children.insert(output, ());
+        }
+        transform_outputs.insert(k.clone(), children);
+    }
 
     config.transforms.iter().for_each(|(k, t)| {
         t.inputs.iter().for_each(|i| {
@@ -379,8 +418,18 @@ fn build_unit_test(
 
     // Build reduced transforms.
     let mut transforms: IndexMap<String, UnitTestTransform> = IndexMap::new();
+    let mut named_outputs: Vec<String> = Vec::new();
     for (name, transform_config) in &config.transforms {
-        if let Some(outputs) = transform_outputs.remove(name) {
+        if let Some(mut outputs) = transform_outputs.remove(name) {
+            let mut named_next = IndexMap::new();
+            for output in transform_config.inner.named_outputs() {
+                let full_name = transform_output(name, &output);
+                if outputs.remove(&full_name).is_some() {
+                    let children = transform_outputs.remove(&full_name).unwrap_or_default();
+                    named_next.insert(output, children.into_iter().map(|(k, _)| k).collect());
+                    named_outputs.push(full_name);
+                }
+            }
             match transform_config
                 .inner
                 .build(TransformContext::new_test(rt.executor()))
@@ -391,6 +440,7 @@ fn build_unit_test(
                         UnitTestTransform {
                             transform,
                             next: outputs.into_iter().map(|(k, _)| k).collect(),
+                            named_next,
                         },
                     );
                 }
@@ -406,7 +456,7 @@ fn build_unit_test(
     }
 
     definition.outputs.iter().for_each(|o| {
-        if !transforms.contains_key(&o.extract_from) {
+        if !transforms.contains_key(&o.extract_from) && !named_outputs.contains(&o.extract_from) {
             let targets = inputs.iter().map(|(i, _)| i).flatten().collect::<Vec<_>>();
             if targets.len() == 1 {
                 errors.push(format!(
@@ -17,6 +17,7 @@ pub struct JsonParserConfig {
     pub drop_field: bool,
     pub target_field: Option<String>,
     pub overwrite_target: Option<bool>,
+    pub reroute_errors: bool,
 }
 
 inventory::submit! {
@@ -40,6 +41,14 @@ impl TransformConfig for JsonParserConfig {
     fn transform_type(&self) -> &'static str {
         "json_parser"
     }
+
+    fn named_outputs(&self) -> Vec<String> {
+        if self.reroute_errors {
+            vec![super::ERRORS_OUTPUT.into()]
+        } else {
+            Vec::new()
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -49,6 +58,7 @@ pub struct JsonParser {
     drop_field: bool,
     target_field: Option<Atom>,
     overwrite_target: bool,
+    reroute_errors: bool,
 }
 
 impl From<JsonParserConfig> for JsonParser {
@@ -65,12 +75,15 @@ impl From<JsonParserConfig> for JsonParser {
             drop_field: config.drop_field,
             target_field: config.target_field.map(Atom::from),
             overwrite_target: config.overwrite_target.unwrap_or(false),
+            reroute_errors: config.reroute_errors,
         }
     }
 }
 
-impl Transform for JsonParser {
-    fn transform(&mut self, mut event: Event) -> Option<Event> {
+impl JsonParser {
+    /// Parses the event in place, handing it back untouched as the error if
+    /// the field isn't a JSON object.
+    fn parse(&self, mut event: Event) -> Result<Event, Event> {
         let log = event.as_mut_log();
         let to_parse = log.get(&self.field).map(|s| s.as_bytes());
 
@@ -120,11 +133,36 @@ impl Transform for JsonParser {
                     }
                 }
             }
-        } else if self.drop_invalid {
-            return None;
+            Ok(event)
+        } else {
+            Err(event)
         }
+    }
+}
 
-        Some(event)
+impl Transform for JsonParser {
+    fn transform(&mut self, event: Event) -> Option<Event> {
+        match self.parse(event) {
+            Ok(event) => Some(event),
+            Err(_) if self.drop_invalid => None,
+            Err(event) => Some(event),
+        }
+    }
+
+    fn transform_into_named(
+        &mut self,
+        output: &mut Vec<Event>,
+        named: &mut Vec<(String, Event)>,
+        event: Event,
+    ) {
+        if self.reroute_errors {
+            match self.parse(event) {
+                Ok(event) => output.push(event),
+                Err(event) => named.push((super::ERRORS_OUTPUT.into(), event)),
+            }
+        } else {
+            self.transform_into(output, event)
+        }
     }
 }
 
@@ -301,6 +339,28 @@ mod test {
         assert!(event.as_log().get(&Atom::from("greeting")).is_none());
     }
 
+    #[test]
+    fn json_parser_reroute_errors() {
+        let mut parser = JsonParser::from(JsonParserConfig {
+            reroute_errors: true,
+            ..Default::default()
+        });
+
+        let mut output = Vec::new();
+        let mut named = Vec::new();
+        parser.transform_into_named(&mut output, &mut named, Event::from(r#"{"greeting": 1}"#));
+        parser.transform_into_named(&mut output, &mut named, Event::from(r#"{"greeting": "#));
+
+        assert_eq!(output.len(), 1);
+        assert_eq!(output[0].as_log()[&Atom::from("greeting")], 1.into());
+        assert_eq!(named.len(), 1);
+        assert_eq!(named[0].0, "errors");
+        assert_eq!(
+            named[0].1.as_log()[&event::log_schema().message_key()],
+            r#"{"greeting": "#.into()
+        );
+    }
+
     #[test]
     fn json_parser_drop_invalid() {
         let valid = r#"{"greeting": "hello", "name": "bob"}"#;
@@ -56,6 +56,10 @@ pub mod tokenizer;
 
 use futures01::Stream;
 
+/// Named output the parsing transforms send events they failed to parse to,
+/// when asked to.
+pub const ERRORS_OUTPUT: &str = "errors";
+
 pub trait Transform: Send {
     fn transform(&mut self, event: Event) -> Option<Event>;
 
@@ -83,6 +87,43 @@ pub trait Transform: Send {
                 .flatten(),
         )
     }
+
+    /// Like `transform_into`, but for transforms declaring named outputs in
+    /// their config. Events for the default output go to `output`, the rest
+    /// go to `named` along with the name of the output they are meant for.
+    fn transform_into_named(
+        &mut self,
+        output: &mut Vec<Event>,
+        named: &mut Vec<(String, Event)>,
+        event: Event,
+    ) {
+        let _ = named;
+        self.transform_into(output, event);
+    }
+
+    /// Stream counterpart of `transform_into_named`, yielding each event with
+    /// the named output it belongs to, or `None` for the default output.
+    fn transform_stream_named(
+        self: Box<Self>,
+        input_rx: Box<dyn Stream<Item = Event, Error = ()> + Send>,
+    ) -> Box<dyn Stream<Item = (Option<String>, Event), Error = ()> + Send>
+    where
+        Self: 'static,
+    {
+        let mut me = self;
+        Box::new(
+            input_rx
+                .map(move |event| {
+                    let mut output = Vec::with_capacity(1);
+                    let mut named = Vec::new();
+                    me.transform_into_named(&mut output, &mut named, event);
+                    let output = output.into_iter().map(|event| (None, event));
+                    let named = named.into_iter().map(|(name, event)| (Some(name), event));
+                    futures01::stream::iter_ok(output.chain(named))
+                })
+                .flatten(),
+        )
+    }
 }
 
 #[derive(Debug, Snafu)]
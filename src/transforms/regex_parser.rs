@@ -29,6 +29,7 @@ pub struct RegexParserConfig {
     #[derivative(Default(value = "true"))]
     pub overwrite_target: bool,
     pub types: HashMap<Atom, String>,
+    pub reroute_errors: bool,
 }
 
 inventory::submit! {
@@ -52,6 +53,14 @@ impl TransformConfig for RegexParserConfig {
     fn transform_type(&self) -> &'static str {
         "regex"
     }
+
+    fn named_outputs(&self) -> Vec<String> {
+        if self.reroute_errors {
+            vec![super::ERRORS_OUTPUT.into()]
+        } else {
+            Vec::new()
+        }
+    }
 }
 
 pub struct RegexParser {
@@ -60,6 +69,7 @@ pub struct RegexParser {
     field: Atom,
     drop_field: bool,
     drop_failed: bool,
+    reroute_errors: bool,
     target_field: Option<Atom>,
     overwrite_target: bool,
     capture_names: Vec<(usize, Atom, Conversion)>,
@@ -121,7 +131,7 @@ impl RegexParser {
 
         let types = parse_check_conversion_map(&config.types, names)?;
 
-        Ok(Box::new(RegexParser::new(
+        let mut parser = RegexParser::new(
             regexset,
             patterns,
             field.clone(),
@@ -130,7 +140,10 @@ impl RegexParser {
             config.target_field.clone(),
             config.overwrite_target,
             types,
-        )))
+        );
+        parser.reroute_errors = config.reroute_errors;
+
+        Ok(Box::new(parser))
     }
 
     pub fn new(
@@ -180,6 +193,7 @@ impl RegexParser {
             field,
             drop_field,
             drop_failed,
+            reroute_errors: false,
             target_field,
             overwrite_target,
             capture_names,
@@ -188,8 +202,10 @@ impl RegexParser {
     }
 }
 
-impl Transform for RegexParser {
-    fn transform(&mut self, mut event: Event) -> Option<Event> {
+impl RegexParser {
+    /// Applies the first matching pattern, handing the event back untouched
+    /// as the error if none matches.
+    fn parse(&mut self, mut event: Event) -> Result<Event, Event> {
         let log = event.as_mut_log();
         let value = log.get(&self.field).map(|s| s.as_bytes());
         emit!(RegexEventProcessed);
@@ -200,11 +216,7 @@ impl Transform for RegexParser {
                 Some(id) => id,
                 None => {
                     emit!(RegexFailedMatch { value });
-                    if self.drop_failed {
-                        return None;
-                    } else {
-                        return Some(event);
-                    }
+                    return Err(event);
                 }
             };
 
@@ -212,7 +224,7 @@ impl Transform for RegexParser {
                 Some(capture_locs) => capture_locs,
                 None => {
                     error!(message = "Cannot find capture locations for pattern", %id, rate_limit_secs = 30);
-                    return None;
+                    return Err(event);
                 }
             };
 
@@ -227,7 +239,7 @@ impl Transform for RegexParser {
                             log.remove(target_field);
                         } else {
                             error!(message = "target field already exists", %target_field, rate_limit_secs = 30);
-                            return Some(event);
+                            return Ok(event);
                         }
                     }
                 }
@@ -257,7 +269,7 @@ impl Transform for RegexParser {
                 if self.drop_field {
                     log.remove(&self.field);
                 }
-                return Some(event);
+                return Ok(event);
             } else {
                 emit!(RegexFailedMatch { value });
             }
@@ -265,10 +277,32 @@ impl Transform for RegexParser {
             emit!(RegexMissingField { field: &self.field });
         }
 
-        if self.drop_failed {
-            None
+        Err(event)
+    }
+}
+
+impl Transform for RegexParser {
+    fn transform(&mut self, event: Event) -> Option<Event> {
+        match self.parse(event) {
+            Ok(event) => Some(event),
+            Err(_) if self.drop_failed => None,
+            Err(event) => Some(event),
+        }
+    }
+
+    fn transform_into_named(
+        &mut self,
+        output: &mut Vec<Event>,
+        named: &mut Vec<(String, Event)>,
+        event: Event,
+    ) {
+        if self.reroute_errors {
+            match self.parse(event) {
+                Ok(event) => output.push(event),
+                Err(event) => named.push((super::ERRORS_OUTPUT.into(), event)),
+            }
         } else {
-            Some(event)
+            self.transform_into(output, event)
         }
     }
 }
@@ -444,6 +478,33 @@ mod tests {
         assert!(log.is_none());
     }
 
+    #[test]
+    fn reroutes_event_if_no_match() {
+        let rt = crate::runtime::Runtime::single_threaded().unwrap();
+        let config = toml::from_str::<RegexParserConfig>(
+            r#"
+                patterns = ['^(?P<status>\d+)$']
+                reroute_errors = true
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.named_outputs(), vec!["errors"]);
+        let mut parser = config
+            .build(TransformContext::new_test(rt.executor()))
+            .unwrap();
+
+        let mut output = Vec::new();
+        let mut named = Vec::new();
+        parser.transform_into_named(&mut output, &mut named, Event::from("1234"));
+        parser.transform_into_named(&mut output, &mut named, Event::from("asdf"));
+
+        assert_eq!(output.len(), 1);
+        assert_eq!(output[0].as_log()[&"status".into()], "1234".into());
+        assert_eq!(named.len(), 1);
+        assert_eq!(named[0].0, "errors");
+        assert_eq!(named[0].1.as_log()[&"message".into()], "asdf".into());
+    }
+
     #[test]
     fn handles_valid_optional_capture() {
         let log = do_transform("1234", r#"['(?P<status>\d+)?']"#, "").unwrap();
@@ -9,54 +9,6 @@ use serde::{Deserialize, Serialize};
 
 //------------------------------------------------------------------------------
 
-#[derive(Serialize, Deserialize, Debug)]
-#[serde(deny_unknown_fields)]
-pub struct SwimlaneConfig {
-    #[serde(flatten)]
-    condition: AnyCondition,
-}
-
-#[typetag::serde(name = "swimlane")]
-impl TransformConfig for SwimlaneConfig {
-    fn build(&self, _ctx: TransformContext) -> crate::Result<Box<dyn Transform>> {
-        Ok(Box::new(Swimlane::new(self.condition.build()?)))
-    }
-
-    fn input_type(&self) -> DataType {
-        DataType::Log
-    }
-
-    fn output_type(&self) -> DataType {
-        DataType::Log
-    }
-
-    fn transform_type(&self) -> &'static str {
-        "swimlane"
-    }
-}
-
-pub struct Swimlane {
-    condition: Box<dyn Condition>,
-}
-
-impl Swimlane {
-    pub fn new(condition: Box<dyn Condition>) -> Self {
-        Self { condition }
-    }
-}
-
-impl Transform for Swimlane {
-    fn transform(&mut self, event: Event) -> Option<Event> {
-        if self.condition.check(&event) {
-            Some(event)
-        } else {
-            None
-        }
-    }
-}
-
-//------------------------------------------------------------------------------
-
 #[derive(Deserialize, Serialize, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct SwimlanesConfig {
@@ -70,21 +22,20 @@ inventory::submit! {
 #[typetag::serde(name = "swimlanes")]
 impl TransformConfig for SwimlanesConfig {
     fn build(&self, _ctx: TransformContext) -> crate::Result<Box<dyn Transform>> {
-        Err("this transform must be expanded".into())
-    }
-
-    fn expand(&mut self) -> crate::Result<Option<IndexMap<String, Box<dyn TransformConfig>>>> {
-        let mut map: IndexMap<String, Box<dyn TransformConfig>> = IndexMap::new();
-
-        while let Some((k, v)) = self.lanes.pop() {
-            map.insert(k.clone(), Box::new(SwimlaneConfig { condition: v }));
+        if self.lanes.is_empty() {
+            return Err("must specify at least one swimlane".into());
         }
 
-        if !map.is_empty() {
-            Ok(Some(map))
-        } else {
-            Err("must specify at least one swimlane".into())
-        }
+        let lanes = self
+            .lanes
+            .iter()
+            .map(|(name, condition)| Ok((name.clone(), condition.build()?)))
+            .collect::<crate::Result<_>>()?;
+        Ok(Box::new(Swimlanes { lanes }))
+    }
+
+    fn named_outputs(&self) -> Vec<String> {
+        self.lanes.keys().cloned().collect()
     }
 
     fn input_type(&self) -> DataType {
@@ -101,3 +52,68 @@ impl TransformConfig for SwimlanesConfig {
 }
 
 //------------------------------------------------------------------------------
+
+/// Sends each event to every lane whose condition it matches. Lanes are
+/// named outputs, so nothing goes to the default output.
+pub struct Swimlanes {
+    lanes: Vec<(String, Box<dyn Condition>)>,
+}
+
+impl Transform for Swimlanes {
+    fn transform(&mut self, _event: Event) -> Option<Event> {
+        None
+    }
+
+    fn transform_into_named(
+        &mut self,
+        _output: &mut Vec<Event>,
+        named: &mut Vec<(String, Event)>,
+        event: Event,
+    ) {
+        for (name, condition) in &self.lanes {
+            if condition.check(&event) {
+                named.push((name.clone(), event.clone()));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SwimlanesConfig;
+    use crate::{
+        event::Event,
+        topology::config::{TransformConfig, TransformContext},
+    };
+
+    #[test]
+    fn swimlanes_route_to_matching_lanes() {
+        let rt = crate::runtime::Runtime::single_threaded().unwrap();
+        let config = toml::from_str::<SwimlanesConfig>(
+            r#"
+            [lanes.first]
+            "message.eq" = "first"
+            [lanes.any]
+            type = "is_log"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.named_outputs(), vec!["first", "any"]);
+
+        let mut swimlanes = config
+            .build(TransformContext::new_test(rt.executor()))
+            .unwrap();
+
+        let mut output = Vec::new();
+        let mut named = Vec::new();
+        swimlanes.transform_into_named(&mut output, &mut named, Event::from("first"));
+        swimlanes.transform_into_named(&mut output, &mut named, Event::from("second"));
+
+        assert!(output.is_empty());
+        let lanes = named
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(lanes, vec!["first", "any", "any"]);
+    }
+}
@@ -0,0 +1,23 @@
+//! The protocol spoken between the `vector` sinks and sources.
+//!
+//! Both versions carry events as protobuf `EventWrapper` messages, each
+//! prefixed with its length on four bytes. Version 1 streams them over TCP,
+//! without acknowledgements. Version 2 posts batches of them, optionally gzip
+//! compressed, to `/v2/events` over HTTP. The source answers once the events
+//! are accepted into its pipeline, and with an error otherwise, so that the
+//! sink keeps the batch and retries it.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Derivative)]
+#[derivative(Default)]
+pub enum Version {
+    #[derivative(Default)]
+    #[serde(rename = "1")]
+    V1,
+    #[serde(rename = "2")]
+    V2,
+}
+
+/// The path batches are posted to with version 2.
+pub const EVENTS_PATH: &str = "v2/events";
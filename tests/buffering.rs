@@ -1,5 +1,3 @@
-#![cfg(feature = "leveldb")]
-
 use futures01::{Future, Sink};
 use prost::Message;
 use tempfile::tempdir;
@@ -491,6 +491,77 @@ fn disabled_healthcheck() {
     .unwrap();
 }
 
+#[cfg(all(
+    feature = "sources-stdin",
+    feature = "transforms-swimlanes",
+    feature = "sinks-http"
+))]
+#[test]
+fn named_output_input() {
+    let config = |input| {
+        format!(
+            r#"
+        [sources.in]
+        type = "stdin"
+
+        [transforms.lanes]
+        type = "swimlanes"
+        inputs = ["in"]
+        [transforms.lanes.lanes.errors]
+        "level.eq" = "error"
+
+        [sinks.out]
+        type = "http"
+        inputs = ["{}"]
+        uri = "https://localhost"
+        encoding = "json"
+        "#,
+            input
+        )
+    };
+
+    assert_eq!(load(&config("lanes.errors")).unwrap(), Vec::<String>::new());
+
+    assert_eq!(
+        load(&config("lanes.warnings")).unwrap_err(),
+        vec!["Input \"lanes.warnings\" for sink \"out\" doesn't exist."]
+    );
+}
+
+#[cfg(all(feature = "sources-stdin", feature = "sinks-http"))]
+#[test]
+fn dead_letter_input() {
+    let config = |dead_letter| {
+        format!(
+            r#"
+        [sources.in]
+        type = "stdin"
+
+        [sinks.out]
+        type = "http"
+        inputs = ["in"]
+        uri = "https://localhost"
+        encoding = "json"
+        dead_letter = {}
+
+        [sinks.retry]
+        type = "http"
+        inputs = ["out.dead_letter"]
+        uri = "https://localhost"
+        encoding = "json"
+        "#,
+            dead_letter
+        )
+    };
+
+    load(&config(true)).unwrap();
+
+    assert_eq!(
+        load(&config(false)).unwrap_err(),
+        vec!["Input \"out.dead_letter\" for sink \"retry\" doesn't exist."]
+    );
+}
+
 #[cfg(all(feature = "sources-stdin", feature = "sinks-http"))]
 #[test]
 fn parses_sink_no_request() {
@@ -671,3 +742,24 @@ fn parses_sink_full_es_aws() {
     )
     .unwrap();
 }
+
+#[cfg(all(feature = "sources-stdin", feature = "sinks-console"))]
+#[test]
+fn dead_letter_unsupported_sink() {
+    assert_eq!(
+        load(
+            r#"
+        [sources.in]
+        type = "stdin"
+
+        [sinks.out]
+        type = "console"
+        inputs = ["in"]
+        encoding = "json"
+        dead_letter = true
+        "#,
+        )
+        .unwrap_err(),
+        vec!["Sink \"out\" of type \"console\" doesn't support dead_letter."]
+    );
+}
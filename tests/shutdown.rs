@@ -259,7 +259,8 @@ fn timely_shutdown_statsd() {
     test_timely_shutdown(source_vector(
         r#"
     type = "statsd"
-    address = "${VECTOR_TEST_ADDRESS}""#,
+    address = "${VECTOR_TEST_ADDRESS}"
+    mode = "udp""#,
     ));
 }
 
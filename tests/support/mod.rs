@@ -16,6 +16,7 @@ use std::sync::{
     Arc, Mutex,
 };
 use tracing::{error, info};
+use vector::buffers::Acker;
 use vector::event::{self, metric::MetricValue, Event, Value};
 use vector::shutdown::ShutdownSignal;
 use vector::sinks::{util::StreamSink, Healthcheck, RouterSink};
@@ -45,6 +46,10 @@ pub fn sink_dead() -> MockSinkConfig<DeadSink<Event>> {
     MockSinkConfig::new(DeadSink::new(), false)
 }
 
+pub fn sink_rejecting() -> RejectingSinkConfig {
+    RejectingSinkConfig
+}
+
 pub fn source() -> (Sender<Event>, MockSourceConfig) {
     let (tx, rx) = futures01::sync::mpsc::channel(0);
     let source = MockSourceConfig::new(rx);
@@ -70,6 +75,8 @@ pub struct MockSourceConfig {
     event_counter: Option<Arc<AtomicUsize>>,
     #[serde(skip)]
     data_type: Option<DataType>,
+    #[serde(skip)]
+    acknowledgements: bool,
 }
 
 impl MockSourceConfig {
@@ -78,6 +85,7 @@ impl MockSourceConfig {
             receiver: Arc::new(Mutex::new(Some(receiver))),
             event_counter: None,
             data_type: Some(DataType::Any),
+            acknowledgements: false,
         }
     }
 
@@ -89,12 +97,17 @@ impl MockSourceConfig {
             receiver: Arc::new(Mutex::new(Some(receiver))),
             event_counter: Some(event_counter),
             data_type: Some(DataType::Any),
+            acknowledgements: false,
         }
     }
 
     pub fn set_data_type(&mut self, data_type: DataType) {
         self.data_type = Some(data_type)
     }
+
+    pub fn set_acknowledgements(&mut self, acknowledgements: bool) {
+        self.acknowledgements = acknowledgements
+    }
 }
 
 #[typetag::serde(name = "mock")]
@@ -134,6 +147,10 @@ impl SourceConfig for MockSourceConfig {
     fn source_type(&self) -> &'static str {
         "mock"
     }
+
+    fn acknowledgements(&self) -> bool {
+        self.acknowledgements
+    }
 }
 
 pub struct MockTransform {
@@ -288,6 +305,56 @@ where
     }
 }
 
+/// A sink whose downstream service rejects every event, sending them to its
+/// dead-letter output when it has one.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct RejectingSinkConfig;
+
+#[typetag::serialize(name = "rejecting")]
+impl SinkConfig for RejectingSinkConfig {
+    fn build(&self, cx: SinkContext) -> Result<(RouterSink, Healthcheck), vector::Error> {
+        Ok((
+            Box::new(RejectingSink(cx.acker())),
+            Box::new(future::ok(())),
+        ))
+    }
+
+    fn input_type(&self) -> DataType {
+        DataType::Any
+    }
+
+    fn sink_type(&self) -> &'static str {
+        "rejecting"
+    }
+
+    fn supports_dead_letter(&self) -> bool {
+        true
+    }
+
+    fn typetag_deserialize(&self) {
+        unimplemented!("not intended for use in real configs")
+    }
+}
+
+pub struct RejectingSink(Acker);
+
+impl Sink for RejectingSink {
+    type SinkItem = Event;
+    type SinkError = ();
+
+    fn start_send(
+        &mut self,
+        _item: Self::SinkItem,
+    ) -> futures01::StartSend<Self::SinkItem, Self::SinkError> {
+        self.0.reject(1, "rejected");
+        Ok(futures01::AsyncSink::Ready)
+    }
+
+    fn poll_complete(&mut self) -> futures01::Poll<(), Self::SinkError> {
+        Ok(futures01::Async::Ready(()))
+    }
+}
+
 /// Represents a sink that's never ready.
 /// Useful to simulate an upstream sink server that is down.
 #[derive(Debug, Clone)]
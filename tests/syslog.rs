@@ -6,7 +6,7 @@ use futures01::{Future, Sink, Stream};
 use rand::{thread_rng, Rng};
 use serde::Deserialize;
 use sinks::socket::SocketSinkConfig;
-use sinks::util::{encoding::EncodingConfig, Encoding};
+use sinks::util::encoding::{EncodingConfig, StandardEncodings};
 use std::{collections::HashMap, thread, time::Duration};
 #[cfg(unix)]
 use tokio01::codec::{FramedWrite, LinesCodec};
@@ -69,6 +69,60 @@ fn test_tcp_syslog() {
     assert_eq!(output_messages, input_messages);
 }
 
+#[test]
+fn test_tcp_syslog_octet_counting() {
+    let num_messages: usize = 1000;
+
+    let in_addr = next_addr();
+    let out_addr = next_addr();
+
+    let mut config = config::Config::empty();
+    config.add_source(
+        "in",
+        SyslogConfig::new(Mode::Tcp {
+            address: in_addr.into(),
+            tls: None,
+        }),
+    );
+    config.add_sink("out", &["in"], tcp_json_sink(out_addr.to_string()));
+
+    let mut rt = runtime::Runtime::new().unwrap();
+
+    let output_lines = receive(&out_addr);
+
+    let (topology, _crash) = topology::start(config, &mut rt, false).unwrap();
+    // Wait for server to accept traffic
+    wait_for_tcp(in_addr);
+
+    let input_messages: Vec<SyslogMessageRFC5424> = (0..num_messages)
+        .map(|i| SyslogMessageRFC5424::random(i, 30, 4, 3, 3))
+        .collect();
+
+    // `send_lines` adds a newline after each frame, which is tolerated.
+    let input_frames: Vec<String> = input_messages
+        .iter()
+        .map(|msg| {
+            let msg = msg.to_string();
+            format!("{} {}", msg.len(), msg)
+        })
+        .collect();
+
+    block_on(send_lines(in_addr, input_frames.into_iter())).unwrap();
+
+    // Shut down server
+    block_on(topology.stop()).unwrap();
+
+    shutdown_on_idle(rt);
+    let output_lines = output_lines.wait();
+    assert_eq!(output_lines.len(), num_messages);
+
+    let output_messages: Vec<SyslogMessageRFC5424> = output_lines
+        .iter()
+        .map(|s| serde_json::from_str(s).unwrap())
+        .collect();
+    assert_eq!(output_messages, input_messages);
+}
+
 #[test]
 fn test_udp_syslog() {
     let num_messages: usize = 1000;
@@ -330,5 +384,5 @@ fn encode_priority(severity: Severity, facility: Facility) -> u8 {
 }
 
 fn tcp_json_sink(address: String) -> SocketSinkConfig {
-    SocketSinkConfig::make_tcp_config(address, EncodingConfig::from(Encoding::Json), None)
+    SocketSinkConfig::make_tcp_config(address, EncodingConfig::from(StandardEncodings::Json), None)
 }
@@ -1,6 +1,8 @@
 mod support;
 
-use crate::support::{sink, sink_failing_healthcheck, source, transform, MockSourceConfig};
+use crate::support::{
+    sink, sink_failing_healthcheck, sink_rejecting, source, transform, MockSourceConfig,
+};
 use futures01::{
     future, future::Future, sink::Sink, stream::iter_ok, stream::Stream, sync::mpsc::SendError,
     sync::oneshot,
@@ -14,7 +16,7 @@ use std::{iter, thread};
 use vector::event::{self, Event};
 use vector::test_util::{runtime, shutdown_on_idle, trace_init};
 use vector::topology;
-use vector::topology::config::Config;
+use vector::topology::config::{Config, DataType};
 
 fn basic_config() -> Config {
     let mut config = Config::empty();
@@ -555,3 +557,70 @@ fn topology_healthcheck_run_for_changes_on_reload() {
             == false
     );
 }
+
+#[test]
+fn topology_dead_letter_sink_shuts_down() {
+    let mut rt = runtime();
+    let (in1, source1) = source();
+    let (out1, sink1) = sink(10);
+
+    let mut config = Config::empty();
+    config.add_source("in1", source1);
+    config.add_sink("rejecting", &["in1"], sink_rejecting());
+    config.sinks["rejecting"].dead_letter = true;
+    config.add_sink("out1", &["rejecting.dead_letter"], sink1);
+
+    let (topology, _crash) = topology::start(config, &mut rt, false).unwrap();
+
+    in1.send(Event::from("this")).wait().unwrap();
+
+    rt.block_on(topology.stop()).unwrap();
+
+    let res = out1.collect().wait().unwrap();
+
+    shutdown_on_idle(rt);
+    assert_eq!(res.len(), 1);
+    assert_eq!(into_message(res[0].clone()), "this");
+    assert_eq!(res[0].as_log()[&"sink".into()], "rejecting".into());
+}
+
+#[test]
+fn topology_transform_from_dead_letter() {
+    let mut rt = runtime();
+    let (in1, source1) = source();
+    let (out1, sink1) = sink(10);
+
+    let mut config = Config::empty();
+    config.add_source("in1", source1);
+    config.add_sink("rejecting", &["in1"], sink_rejecting());
+    config.sinks["rejecting"].dead_letter = true;
+    config.add_transform("t1", &["rejecting.dead_letter"], transform(" retried", 0.0));
+    config.add_sink("out1", &["t1"], sink1);
+
+    let (topology, _crash) = topology::start(config, &mut rt, false).unwrap();
+
+    in1.send(Event::from("this")).wait().unwrap();
+
+    rt.block_on(topology.stop()).unwrap();
+
+    let res = out1.map(into_message).collect().wait().unwrap();
+
+    shutdown_on_idle(rt);
+    assert_eq!(res, vec!["this retried"]);
+}
+
+#[test]
+fn topology_refuses_acknowledging_metric_sources() {
+    let (_in1, mut source1) = source();
+    source1.set_data_type(DataType::Metric);
+    source1.set_acknowledgements(true);
+
+    let mut config = Config::empty();
+    config.add_source("in1", source1);
+    config.add_sink("out1", &["in1"], sink(10).1);
+
+    assert_eq!(
+        topology::builder::check(&config).unwrap_err(),
+        vec!["Source \"in1\" of type \"mock\" can't acknowledge events, as metric events aren't tracked to delivery."]
+    );
+}